@@ -11,7 +11,8 @@ interface BalanceCardProps {
 
 export const BalanceCard: FC<BalanceCardProps> = ({ balanceResult }) => {
   const { balance, refresh, decrypt } = balanceResult;
-  const { isLoading, isDecrypted, decryptedLamports, error } = balance;
+  const { isLoading, isDecrypted, decryptedLamports, principalLamports, yieldLamports, error } =
+    balance;
 
   const handleRefresh = async () => {
     await refresh();
@@ -64,6 +65,23 @@ export const BalanceCard: FC<BalanceCardProps> = ({ balanceResult }) => {
         )}
       </div>
 
+      {isDecrypted && principalLamports !== null && yieldLamports !== null ? (
+        <div className="mb-4 grid grid-cols-2 gap-4 text-sm">
+          <div>
+            <div className="text-gray-500 dark:text-gray-400">Principal</div>
+            <div className="font-medium text-gray-900 dark:text-white">
+              {formatUSDC(principalLamports)} USDC
+            </div>
+          </div>
+          <div>
+            <div className="text-gray-500 dark:text-gray-400">Accrued yield</div>
+            <div className="font-medium text-gray-900 dark:text-white">
+              {formatUSDC(yieldLamports)} USDC
+            </div>
+          </div>
+        </div>
+      ) : null}
+
       {!isDecrypted ? (
         <button
           onClick={handleDecrypt}
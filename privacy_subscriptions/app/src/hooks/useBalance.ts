@@ -5,7 +5,7 @@ import { useWallet } from "@solana/wallet-adapter-react";
 import { Balance } from "@/types";
 import { useArcium } from "@/components/providers/ArciumProvider";
 import { fetchUserLedger } from "@/lib/transactions";
-import { decryptBalance } from "@/lib/arcium";
+import { decryptLedgerBalance } from "@/lib/arcium";
 
 export interface UseBalanceResult {
   balance: Balance;
@@ -21,14 +21,18 @@ export const useBalance = (): UseBalanceResult => {
   const [balance, setBalance] = useState<Balance>({
     lamports: BigInt(0),
     decryptedLamports: null,
+    principalLamports: null,
+    yieldLamports: null,
     isDecrypted: false,
     isLoading: false,
     error: null,
   });
 
-  // Store encrypted balance and nonce for later decryption
+  // Store encrypted balance parts and nonce for later decryption
   const [encryptedData, setEncryptedData] = useState<{
     encryptedBalance: number[] | null;
+    encryptedSubscriptionCount?: number[];
+    encryptedAccruedYield?: number[];
     nonce: bigint | null;
   }>({ encryptedBalance: null, nonce: null });
 
@@ -37,6 +41,8 @@ export const useBalance = (): UseBalanceResult => {
       setBalance({
         lamports: BigInt(0),
         decryptedLamports: null,
+        principalLamports: null,
+        yieldLamports: null,
         isDecrypted: false,
         isLoading: false,
         error: null,
@@ -70,6 +76,8 @@ export const useBalance = (): UseBalanceResult => {
         setBalance({
           lamports: BigInt(0),
           decryptedLamports: BigInt(0),
+          principalLamports: BigInt(0),
+          yieldLamports: BigInt(0),
           isDecrypted: true,
           isLoading: false,
           error: null,
@@ -82,6 +90,8 @@ export const useBalance = (): UseBalanceResult => {
       const nonce = BigInt(userLedger.nonce.toString());
       setEncryptedData({
         encryptedBalance: userLedger.encryptedBalance,
+        encryptedSubscriptionCount: userLedger.encryptedSubscriptionCount,
+        encryptedAccruedYield: userLedger.encryptedAccruedYield,
         nonce,
       });
 
@@ -89,6 +99,8 @@ export const useBalance = (): UseBalanceResult => {
       setBalance({
         lamports: BigInt(1), // Non-zero to indicate balance exists
         decryptedLamports: null,
+        principalLamports: null,
+        yieldLamports: null,
         isDecrypted: false,
         isLoading: false,
         error: null,
@@ -111,7 +123,12 @@ export const useBalance = (): UseBalanceResult => {
       return;
     }
 
-    if (!encryptedData.encryptedBalance || encryptedData.nonce === null) {
+    if (
+      !encryptedData.encryptedBalance ||
+      !encryptedData.encryptedSubscriptionCount ||
+      !encryptedData.encryptedAccruedYield ||
+      encryptedData.nonce === null
+    ) {
       // No encrypted data to decrypt
       return;
     }
@@ -127,17 +144,22 @@ export const useBalance = (): UseBalanceResult => {
         nonce >>= BigInt(8);
       }
 
-      // Decrypt the balance using Arcium cipher
-      const decrypted = decryptBalance(
+      // Decrypt principal and accrued yield using Arcium cipher
+      const { principal, accruedYield } = decryptLedgerBalance(
         arciumContext.cipher,
         encryptedData.encryptedBalance,
+        encryptedData.encryptedSubscriptionCount,
+        encryptedData.encryptedAccruedYield,
         nonceBytes
       );
+      const total = principal + accruedYield;
 
       setBalance((prev) => ({
         ...prev,
-        lamports: decrypted,
-        decryptedLamports: decrypted,
+        lamports: total,
+        decryptedLamports: total,
+        principalLamports: principal,
+        yieldLamports: accruedYield,
         isDecrypted: true,
         isLoading: false,
       }));
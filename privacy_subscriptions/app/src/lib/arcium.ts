@@ -189,6 +189,25 @@ export function decryptBalance(
   return decryptValue(cipher, encryptedBalance, nonce);
 }
 
+/**
+ * Decrypt a user ledger balance into its principal and accrued yield parts.
+ * Both share the ledger nonce, so the whole struct prefix is decrypted together.
+ */
+export function decryptLedgerBalance(
+  cipher: RescueCipher,
+  encryptedBalance: number[] | Uint8Array,
+  encryptedSubscriptionCount: number[] | Uint8Array,
+  encryptedAccruedYield: number[] | Uint8Array,
+  nonce: Uint8Array
+): { principal: bigint; accruedYield: bigint } {
+  const [principal, , accruedYield] = decryptValues(
+    cipher,
+    [encryptedBalance, encryptedSubscriptionCount, encryptedAccruedYield],
+    nonce
+  );
+  return { principal, accruedYield };
+}
+
 /**
  * Split a PublicKey into two u128 values (little-endian)
  */
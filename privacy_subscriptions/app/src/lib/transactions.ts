@@ -212,6 +212,7 @@ export async function fetchUserLedger(
   encryptionPubkey: number[];
   encryptedBalance: number[];
   encryptedSubscriptionCount: number[];
+  encryptedAccruedYield: number[];
   nonce: BN;
   lastUpdated: BN;
   bump: number;
@@ -226,6 +227,7 @@ export async function fetchUserLedger(
       encryptionPubkey: number[];
       encryptedBalance: number[];
       encryptedSubscriptionCount: number[];
+      encryptedAccruedYield: number[];
       nonce: BN;
       lastUpdated: BN;
       bump: number;
@@ -16,6 +16,10 @@ export interface Balance {
   lamports: bigint;
   // Decrypted balance in lamports (for display)
   decryptedLamports: bigint | null;
+  // Decrypted principal part of the balance
+  principalLamports: bigint | null;
+  // Decrypted accrued yield part of the balance (spent first by subscriptions)
+  yieldLamports: bigint | null;
   // Whether the balance has been decrypted
   isDecrypted: boolean;
   // Loading state
@@ -0,0 +1,1302 @@
+//! Rust client for the privacy_subscriptions program.
+//!
+//! The native counterpart of the TypeScript SDK (`sdk/`): PDA derivation for
+//! every seed, instruction builders, x25519 encryption keys, encryption of the
+//! `deposit` and `subscribe` inputs and decryption of `UserLedger`,
+//! `MerchantLedger` and `UserSubscription` state.
+//!
+//! Values are encrypted with [`RescueCipher`], a native port of Arcium's
+//! Rescue cipher keyed with [`EncryptionKeypair::shared_secret`], exactly as
+//! the TypeScript SDK builds a `RescueCipher` from the same shared secret.
+//! [`mxe_public_key`] reads the MXE key the secret is agreed with.
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AccountDeserialize, Id, InstructionData, ToAccountMetas};
+use arcium_anchor::prelude::{
+    comp_def_offset, Arcium, MXEAccount, ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+};
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+pub use privacy_subscriptions::{accounts, instruction, ID as PROGRAM_ID};
+pub use privacy_subscriptions::{
+    Merchant, MerchantLedger, ProtocolConfig, ProtocolPool, RevenueClaimed,
+    ScheduledPayoutExecuted, SubscriptionPlan, UserLedger, UserSubscription,
+    MERCHANT_LEDGER_STATE_VERSION, OUTFLOW_KIND_CLAIM, OUTFLOW_KIND_WITHDRAW,
+    USER_LEDGER_STATE_VERSION, USER_SUBSCRIPTION_STATE_VERSION,
+};
+pub use state_layouts::{MerchantLedgerState, UserLedgerState, UserSubscriptionState};
+
+use privacy_subscriptions::{
+    AUDIT_GRANT_SEED, CIRCUIT_VERSION_SEED, DISPUTE_SEED, MERCHANT_LEDGER_SEED, MERCHANT_SEED,
+    PAYOUT_SCHEDULE_SEED, PENDING_OUTFLOW_SEED, PLAN_PUBLIC_STATS_SEED, PLAN_STATS_SEED,
+    PRICE_FEED_SEED, PROTOCOL_CONFIG_SEED, PROTOCOL_POOL_SEED, REVENUE_SPLIT_SEED,
+    SCREENING_ATTESTATION_SEED, SEAT_ASSIGNMENT_SEED, SOLVENCY_ATTESTATION_SEED, SPENDING_CAP_SEED,
+    SUBSCRIPTION_PLAN_SEED, USER_LEDGER_SEED, USER_SUBSCRIPTION_SEED, VOUCHER_POOL_SEED,
+};
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("account data could not be decoded: {0}")]
+    AccountDecode(#[from] anchor_lang::error::Error),
+    #[error("account has no encrypted state yet")]
+    Uninitialized,
+    #[error("encrypted state is at layout {found}, expected {expected}; run migrate_account")]
+    StaleStateLayout { found: u8, expected: u8 },
+    #[error("ciphertext does not decrypt to a value; check the encryption key and MXE key")]
+    DecryptionFailed,
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Decode a program account, checking its discriminator
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    Ok(T::try_deserialize(&mut &data[..])?)
+}
+
+// ============================================================================
+// PDA Derivation
+// ============================================================================
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID).0
+}
+
+pub fn protocol_config() -> Pubkey {
+    find(&[PROTOCOL_CONFIG_SEED])
+}
+
+pub fn protocol_pool(mint: &Pubkey) -> Pubkey {
+    find(&[PROTOCOL_POOL_SEED, mint.as_ref()])
+}
+
+pub fn merchant(wallet: &Pubkey) -> Pubkey {
+    find(&[MERCHANT_SEED, wallet.as_ref()])
+}
+
+pub fn merchant_ledger(merchant: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[MERCHANT_LEDGER_SEED, merchant.as_ref(), mint.as_ref()])
+}
+
+pub fn subscription_plan(merchant: &Pubkey, plan_id: u64) -> Pubkey {
+    find(&[
+        SUBSCRIPTION_PLAN_SEED,
+        merchant.as_ref(),
+        &plan_id.to_le_bytes(),
+    ])
+}
+
+pub fn user_ledger(user: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[USER_LEDGER_SEED, user.as_ref(), mint.as_ref()])
+}
+
+pub fn user_subscription(user: &Pubkey, subscription_index: u64) -> Pubkey {
+    find(&[
+        USER_SUBSCRIPTION_SEED,
+        user.as_ref(),
+        &subscription_index.to_le_bytes(),
+    ])
+}
+
+pub fn dispute(user_subscription: &Pubkey, billing_period: u64) -> Pubkey {
+    find(&[
+        DISPUTE_SEED,
+        user_subscription.as_ref(),
+        &billing_period.to_le_bytes(),
+    ])
+}
+
+pub fn spending_cap(user: &Pubkey, merchant: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[
+        SPENDING_CAP_SEED,
+        user.as_ref(),
+        merchant.as_ref(),
+        mint.as_ref(),
+    ])
+}
+
+pub fn solvency_attestation(protocol_pool: &Pubkey) -> Pubkey {
+    find(&[SOLVENCY_ATTESTATION_SEED, protocol_pool.as_ref()])
+}
+
+pub fn voucher_pool(subscription_plan: &Pubkey, cycles: u32, billing_cycle_days: u32) -> Pubkey {
+    find(&[
+        VOUCHER_POOL_SEED,
+        subscription_plan.as_ref(),
+        &cycles.to_le_bytes(),
+        &billing_cycle_days.to_le_bytes(),
+    ])
+}
+
+pub fn seat_assignment(user_subscription: &Pubkey, member: &Pubkey) -> Pubkey {
+    find(&[
+        SEAT_ASSIGNMENT_SEED,
+        user_subscription.as_ref(),
+        member.as_ref(),
+    ])
+}
+
+pub fn revenue_split(merchant: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[REVENUE_SPLIT_SEED, merchant.as_ref(), mint.as_ref()])
+}
+
+pub fn payout_schedule(merchant: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[PAYOUT_SCHEDULE_SEED, merchant.as_ref(), mint.as_ref()])
+}
+
+pub fn plan_stats(subscription_plan: &Pubkey) -> Pubkey {
+    find(&[PLAN_STATS_SEED, subscription_plan.as_ref()])
+}
+
+pub fn plan_public_stats(subscription_plan: &Pubkey) -> Pubkey {
+    find(&[PLAN_PUBLIC_STATS_SEED, subscription_plan.as_ref()])
+}
+
+pub fn audit_grant(owner: &Pubkey, auditor: &Pubkey, target: &Pubkey) -> Pubkey {
+    find(&[
+        AUDIT_GRANT_SEED,
+        owner.as_ref(),
+        auditor.as_ref(),
+        target.as_ref(),
+    ])
+}
+
+pub fn screening_attestation(wallet: &Pubkey) -> Pubkey {
+    find(&[SCREENING_ATTESTATION_SEED, wallet.as_ref()])
+}
+
+/// `kind` is [`OUTFLOW_KIND_WITHDRAW`] or [`OUTFLOW_KIND_CLAIM`]
+pub fn pending_outflow(owner: &Pubkey, mint: &Pubkey, kind: u8) -> Pubkey {
+    find(&[PENDING_OUTFLOW_SEED, owner.as_ref(), mint.as_ref(), &[kind]])
+}
+
+pub fn price_feed(mint: &Pubkey, currency: &[u8; 8]) -> Pubkey {
+    find(&[PRICE_FEED_SEED, mint.as_ref(), currency])
+}
+
+pub fn circuit_version(version: u8) -> Pubkey {
+    find(&[CIRCUIT_VERSION_SEED, &[version]])
+}
+
+// ============================================================================
+// Arcium Accounts
+// ============================================================================
+
+/// Computation definition names, as registered by the `init_*_comp_def` instructions
+pub mod circuits {
+    pub const DEPOSIT: &str = "deposit_v2";
+    pub const WITHDRAW: &str = "withdraw_v2";
+    pub const SUBSCRIBE: &str = "subscribe_v2";
+    pub const UNSUBSCRIBE: &str = "unsubscribe_v2";
+    pub const PROCESS_PAYMENT: &str = "process_payment_v2";
+    pub const VERIFY_SUBSCRIPTION: &str = "verify_subscription_v2";
+    pub const CLAIM_REVENUE: &str = "claim_revenue_v2";
+    pub const REACTIVATE_SUBSCRIPTION: &str = "reactivate_subscription_v2";
+    pub const CANCEL_AT_PERIOD_END: &str = "cancel_at_period_end_v2";
+    pub const RESUME_SUBSCRIPTION: &str = "resume_subscription_v2";
+    pub const REFUND: &str = "refund_v2";
+    pub const OPEN_DISPUTE: &str = "open_dispute_v2";
+    pub const RESOLVE_DISPUTE_REFUND: &str = "resolve_dispute_refund_v2";
+    pub const RESOLVE_DISPUTE_RELEASE: &str = "resolve_dispute_release_v2";
+    pub const SET_SPENDING_CAP: &str = "set_spending_cap_v2";
+    pub const BALANCE_RUNWAY: &str = "balance_runway_v2";
+    pub const CHECK_SOLVENCY: &str = "check_solvency_v2";
+    pub const TRANSFER: &str = "transfer_v2";
+    pub const CLAIM_TRANSFERS: &str = "claim_transfers_v2";
+    pub const BUY_VOUCHER: &str = "buy_voucher_v2";
+    pub const REDEEM_VOUCHER: &str = "redeem_voucher_v2";
+    pub const UPDATE_SEATS: &str = "update_seats_v2";
+    pub const ASSIGN_SEAT: &str = "assign_seat_v2";
+    pub const SCHEDULED_PAYOUT: &str = "scheduled_payout_v2";
+    pub const PUBLISH_PLAN_STATS: &str = "publish_plan_stats_v2";
+    pub const DISCLOSE_USER_LEDGER: &str = "disclose_user_ledger_v2";
+    pub const DISCLOSE_MERCHANT_LEDGER: &str = "disclose_merchant_ledger_v2";
+    pub const DISCLOSE_SUBSCRIPTION: &str = "disclose_subscription_v2";
+    pub const DISTRIBUTE_YIELD: &str = "distribute_yield_v2";
+    pub const SETTLE_YIELD: &str = "settle_yield_v2";
+    pub const SET_PRINCIPAL_LOCK: &str = "set_principal_lock_v2";
+    pub const MIGRATE_USER_LEDGER: &str = "migrate_user_ledger_v2";
+    pub const MIGRATE_USER_SUBSCRIPTION: &str = "migrate_user_subscription_v2";
+    pub const MIGRATE_MERCHANT_LEDGER: &str = "migrate_merchant_ledger_v2";
+    pub const SEED_LIABILITIES: &str = "seed_liabilities_v2";
+}
+
+const SIGN_PDA_SEED: &[u8] = b"ArciumSignerAccount";
+const MXE_ACCOUNT_SEED: &[u8] = b"MXEAccount";
+const MEMPOOL_SEED: &[u8] = b"Mempool";
+const EXECPOOL_SEED: &[u8] = b"Execpool";
+const COMPUTATION_SEED: &[u8] = b"ComputationAccount";
+const COMP_DEF_SEED: &[u8] = b"ComputationDefinitionAccount";
+const CLUSTER_SEED: &[u8] = b"Cluster";
+
+fn find_arcium(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &Arcium::id()).0
+}
+
+/// The program's MXE account, which publishes the MXE's x25519 key
+pub fn mxe_account() -> Pubkey {
+    find_arcium(&[MXE_ACCOUNT_SEED, PROGRAM_ID.as_ref()])
+}
+
+/// The MXE's x25519 key from the data of [`mxe_account`], or `None` while
+/// the cluster is still generating it
+pub fn mxe_public_key(data: &[u8]) -> Result<Option<[u8; 32]>> {
+    Ok(decode_account::<MXEAccount>(data)?.x25519_pubkey())
+}
+
+/// A computation to queue: a fresh offset identifying it, and the offset of
+/// the cluster the MXE is assigned to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Computation {
+    pub offset: u64,
+    pub cluster_offset: u32,
+}
+
+impl Computation {
+    /// A computation with a random offset
+    pub fn new(cluster_offset: u32) -> Self {
+        Self {
+            offset: rand::thread_rng().next_u64(),
+            cluster_offset,
+        }
+    }
+}
+
+/// Accounts every instruction that queues a computation passes to Arcium
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArciumAccounts {
+    pub sign_pda_account: Pubkey,
+    pub mxe_account: Pubkey,
+    pub mempool_account: Pubkey,
+    pub executing_pool: Pubkey,
+    pub computation_account: Pubkey,
+    pub comp_def_account: Pubkey,
+    pub cluster_account: Pubkey,
+    pub pool_account: Pubkey,
+    pub clock_account: Pubkey,
+    pub arcium_program: Pubkey,
+}
+
+impl ArciumAccounts {
+    /// `circuit` is one of the [`circuits`] names
+    pub fn new(circuit: &str, computation: Computation) -> Self {
+        let cluster = computation.cluster_offset.to_le_bytes();
+        Self {
+            sign_pda_account: find(&[SIGN_PDA_SEED]),
+            mxe_account: mxe_account(),
+            mempool_account: find_arcium(&[MEMPOOL_SEED, &cluster]),
+            executing_pool: find_arcium(&[EXECPOOL_SEED, &cluster]),
+            computation_account: find_arcium(&[
+                COMPUTATION_SEED,
+                &cluster,
+                &computation.offset.to_le_bytes(),
+            ]),
+            comp_def_account: find_arcium(&[
+                COMP_DEF_SEED,
+                PROGRAM_ID.as_ref(),
+                &comp_def_offset(circuit).to_le_bytes(),
+            ]),
+            cluster_account: find_arcium(&[CLUSTER_SEED, &cluster]),
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            arcium_program: Arcium::id(),
+        }
+    }
+}
+
+// ============================================================================
+// Instruction Builders
+// ============================================================================
+
+/// Build any program instruction from its generated account and argument structs:
+///
+/// ```ignore
+/// let ix = instruction(
+///     accounts::SetPlanPriceCurrency { wallet, merchant, subscription_plan },
+///     instruction::SetPlanPriceCurrency { price_currency },
+/// );
+/// ```
+pub fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn register_merchant(
+    wallet: Pubkey,
+    mint: Pubkey,
+    name: String,
+    encryption_pubkey: [u8; 32],
+) -> Instruction {
+    let merchant = merchant(&wallet);
+    instruction(
+        accounts::RegisterMerchant {
+            wallet,
+            mint,
+            merchant,
+            merchant_ledger: merchant_ledger(&merchant, &mint),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::RegisterMerchant {
+            name,
+            encryption_pubkey,
+        },
+    )
+}
+
+/// Open a ledger for a further settlement mint of a registered merchant
+pub fn open_merchant_ledger(
+    wallet: Pubkey,
+    mint: Pubkey,
+    encryption_pubkey: [u8; 32],
+) -> Instruction {
+    let merchant = merchant(&wallet);
+    instruction(
+        accounts::OpenMerchantLedger {
+            wallet,
+            mint,
+            merchant,
+            merchant_ledger: merchant_ledger(&merchant, &mint),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::OpenMerchantLedger { encryption_pubkey },
+    )
+}
+
+pub fn create_subscription_plan(
+    wallet: Pubkey,
+    mint: Pubkey,
+    plan_id: u64,
+    name: String,
+    price: u64,
+    billing_cycle_days: u32,
+) -> Instruction {
+    let merchant = merchant(&wallet);
+    instruction(
+        accounts::CreateSubscriptionPlan {
+            wallet,
+            merchant,
+            mint,
+            subscription_plan: subscription_plan(&merchant, plan_id),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::CreateSubscriptionPlan {
+            plan_id,
+            name,
+            price,
+            billing_cycle_days,
+        },
+    )
+}
+
+/// Fields left as `None` are not changed
+#[derive(Clone, Debug, Default)]
+pub struct PlanUpdate {
+    pub name: Option<String>,
+    pub price: Option<u64>,
+    pub billing_cycle_days: Option<u32>,
+    pub is_active: Option<bool>,
+}
+
+pub fn update_subscription_plan(wallet: Pubkey, plan_id: u64, update: PlanUpdate) -> Instruction {
+    let merchant = merchant(&wallet);
+    instruction(
+        accounts::UpdateSubscriptionPlan {
+            wallet,
+            merchant,
+            subscription_plan: subscription_plan(&merchant, plan_id),
+        },
+        instruction::UpdateSubscriptionPlan {
+            name: update.name,
+            price: update.price,
+            billing_cycle_days: update.billing_cycle_days,
+            is_active: update.is_active,
+        },
+    )
+}
+
+/// Token accounts of a deposit or withdrawal. `pool_token_account` is the
+/// `ProtocolPool::token_account` of the mint.
+#[derive(Clone, Copy, Debug)]
+pub struct PoolTransfer {
+    pub mint: Pubkey,
+    pub pool_token_account: Pubkey,
+    pub owner_token_account: Pubkey,
+    /// Pass the owner's screening attestation (deposits into pools in
+    /// compliance mode; withdrawals are never screened)
+    pub screened: bool,
+}
+
+impl PoolTransfer {
+    fn screening_attestation(&self, owner: &Pubkey) -> Option<Pubkey> {
+        self.screened.then(|| screening_attestation(owner))
+    }
+}
+
+pub fn deposit(
+    user: Pubkey,
+    transfer: PoolTransfer,
+    amount: u64,
+    input: DepositInput,
+    computation: Computation,
+) -> Instruction {
+    let arcium = ArciumAccounts::new(circuits::DEPOSIT, computation);
+    let mint = transfer.mint;
+    instruction(
+        accounts::Deposit {
+            user,
+            mint,
+            protocol_pool: protocol_pool(&mint),
+            protocol_config: protocol_config(),
+            screening_attestation: transfer.screening_attestation(&user),
+            pool_token_account: transfer.pool_token_account,
+            user_token_account: transfer.owner_token_account,
+            user_ledger: user_ledger(&user, &mint),
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            arcium_program: arcium.arcium_program,
+        },
+        instruction::Deposit {
+            computation_offset: computation.offset,
+            amount,
+            encryption_pubkey: input.encryption_pubkey,
+            encrypted_amount: input.encrypted_amount,
+            encrypted_amount_nonce: input.encrypted_amount_nonce,
+        },
+    )
+}
+
+/// `pending` passes the queued large-outflow request, when one is due
+pub fn withdraw(
+    user: Pubkey,
+    transfer: PoolTransfer,
+    amount: u64,
+    encryption_pubkey: [u8; 32],
+    pending: bool,
+    computation: Computation,
+) -> Instruction {
+    let arcium = ArciumAccounts::new(circuits::WITHDRAW, computation);
+    let mint = transfer.mint;
+    instruction(
+        accounts::Withdraw {
+            user,
+            mint,
+            protocol_pool: protocol_pool(&mint),
+            pending_outflow: pending.then(|| pending_outflow(&user, &mint, OUTFLOW_KIND_WITHDRAW)),
+            pool_token_account: transfer.pool_token_account,
+            user_token_account: transfer.owner_token_account,
+            user_ledger: user_ledger(&user, &mint),
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            arcium_program: arcium.arcium_program,
+        },
+        instruction::Withdraw {
+            computation_offset: computation.offset,
+            amount,
+            encryption_pubkey,
+        },
+    )
+}
+
+/// `plan` is the subscription plan account. `price_account` is the oracle
+/// price account of the plan's approved feed and is required when the plan
+/// is priced in a reference currency.
+pub fn subscribe(
+    user: Pubkey,
+    mint: Pubkey,
+    plan: (Pubkey, &SubscriptionPlan),
+    subscription_index: u64,
+    input: SubscribeInput,
+    price_account: Option<Pubkey>,
+    computation: Computation,
+) -> Instruction {
+    let arcium = ArciumAccounts::new(circuits::SUBSCRIBE, computation);
+    let (plan_key, plan) = plan;
+    let priced_in_currency = plan.price_currency != [0u8; 8];
+    instruction(
+        accounts::Subscribe {
+            user,
+            mint,
+            subscription_plan: plan_key,
+            user_ledger: user_ledger(&user, &mint),
+            merchant_ledger: merchant_ledger(&plan.merchant, &mint),
+            user_subscription: user_subscription(&user, subscription_index),
+            spending_cap: spending_cap(&user, &plan.merchant, &mint),
+            plan_stats: plan_stats(&plan_key),
+            price_feed: priced_in_currency.then(|| price_feed(&mint, &plan.price_currency)),
+            price_account: price_account.filter(|_| priced_in_currency),
+            protocol_pool: protocol_pool(&mint),
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            system_program: anchor_lang::system_program::ID,
+            arcium_program: arcium.arcium_program,
+        },
+        instruction::Subscribe {
+            computation_offset: computation.offset,
+            subscription_index,
+            encrypted_plan: input.encrypted_plan,
+            encrypted_plan_nonce: input.encrypted_plan_nonce,
+            encrypted_price: input.encrypted_price,
+            encrypted_price_nonce: input.encrypted_price_nonce,
+            encrypted_billing_cycle: input.encrypted_billing_cycle,
+            encrypted_billing_cycle_nonce: input.encrypted_billing_cycle_nonce,
+            encrypted_quantity: input.encrypted_quantity,
+            encrypted_quantity_nonce: input.encrypted_quantity_nonce,
+        },
+    )
+}
+
+/// `pending` passes the queued large-outflow request, when one is due
+pub fn claim_revenue(
+    wallet: Pubkey,
+    mint: Pubkey,
+    pool_token_account: Pubkey,
+    merchant_token_account: Pubkey,
+    amount: u64,
+    pending: bool,
+    computation: Computation,
+) -> Instruction {
+    let arcium = ArciumAccounts::new(circuits::CLAIM_REVENUE, computation);
+    let merchant = merchant(&wallet);
+    instruction(
+        accounts::ClaimRevenue {
+            wallet,
+            mint,
+            merchant,
+            protocol_pool: protocol_pool(&mint),
+            pool_token_account,
+            pending_outflow: pending.then(|| pending_outflow(&wallet, &mint, OUTFLOW_KIND_CLAIM)),
+            merchant_token_account,
+            merchant_ledger: merchant_ledger(&merchant, &mint),
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            arcium_program: arcium.arcium_program,
+        },
+        instruction::ClaimRevenue {
+            computation_offset: computation.offset,
+            amount,
+        },
+    )
+}
+
+pub fn migrate_account(payer: Pubkey, account: Pubkey) -> Instruction {
+    instruction(
+        accounts::MigrateAccount {
+            payer,
+            account,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::MigrateAccount,
+    )
+}
+
+// ============================================================================
+// Encryption Keys
+// ============================================================================
+
+/// Message signed by the wallet to derive its encryption key.
+/// Do not change: existing ciphertexts are bound to the derived key.
+pub const ENCRYPTION_SIGNING_MESSAGE: &str =
+    "Subly Privacy Subscriptions - Encryption Key Derivation v1";
+
+/// x25519 key pair an `Enc<Shared, _>` value is encrypted to, together with the MXE key
+pub struct EncryptionKeypair {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl EncryptionKeypair {
+    pub fn generate() -> Self {
+        Self::from_secret_bytes(StaticSecret::random_from_rng(rand::rngs::OsRng).to_bytes())
+    }
+
+    pub fn from_secret_bytes(secret: [u8; 32]) -> Self {
+        let secret = StaticSecret::from(secret);
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    /// Same derivation as the TypeScript SDK: the secret key is the SHA-256
+    /// of the wallet's signature over [`ENCRYPTION_SIGNING_MESSAGE`], so the
+    /// key can be recovered from the wallet alone.
+    pub fn from_signature(signature: &[u8]) -> Self {
+        Self::from_secret_bytes(Sha256::digest(signature).into())
+    }
+
+    /// The `encryption_pubkey` registered on ledgers and passed to circuits
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public.to_bytes()
+    }
+
+    pub fn secret_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    /// Key of the Rescue cipher shared with the MXE
+    pub fn shared_secret(&self, mxe_public_key: &[u8; 32]) -> [u8; 32] {
+        self.secret
+            .diffie_hellman(&PublicKey::from(*mxe_public_key))
+            .to_bytes()
+    }
+}
+
+// ============================================================================
+// Rescue Cipher
+// ============================================================================
+
+/// Field elements in the Rescue key, and in each cipher block
+const RESCUE_KEY_COUNT: usize = 5;
+/// Rescue block cipher rounds for a 5-element state at 128-bit security
+const RESCUE_CIPHER_ROUNDS: usize = 10;
+/// Rescue-Prime sponge used to derive the key: rate 7, capacity 5, 256-bit security
+const RESCUE_HASH_RATE: usize = 7;
+const RESCUE_HASH_CAPACITY: usize = 5;
+const RESCUE_HASH_ROUNDS: usize = 8;
+/// S-box exponent: the smallest prime not dividing p - 1
+const RESCUE_ALPHA: u32 = 5;
+/// SHAKE256 seed of the block cipher's round constants
+const RESCUE_CIPHER_SEED: &[u8] = b"encrypt everything, compute anything";
+
+/// The Curve25519 base field 2^255 - 19 that `Enc<Shared, _>` values live in
+fn field_modulus() -> BigUint {
+    (BigUint::one() << 255u32) - BigUint::from(19u8)
+}
+
+/// Element of the Curve25519 base field
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Fp(BigUint);
+
+impl Fp {
+    fn new(value: BigUint) -> Self {
+        Fp(value % field_modulus())
+    }
+
+    fn from_u128(value: u128) -> Self {
+        Fp::new(BigUint::from(value))
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Fp::new(BigUint::from_bytes_le(bytes))
+    }
+
+    fn to_le_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        let digits = self.0.to_bytes_le();
+        bytes[..digits.len()].copy_from_slice(&digits);
+        bytes
+    }
+
+    fn add(&self, other: &Fp) -> Fp {
+        Fp::new(&self.0 + &other.0)
+    }
+
+    fn sub(&self, other: &Fp) -> Fp {
+        Fp::new(&self.0 + field_modulus() - &other.0)
+    }
+
+    fn mul(&self, other: &Fp) -> Fp {
+        Fp::new(&self.0 * &other.0)
+    }
+
+    fn pow(&self, exponent: &BigUint) -> Fp {
+        Fp(self.0.modpow(exponent, &field_modulus()))
+    }
+
+    fn invert(&self) -> Fp {
+        self.pow(&(field_modulus() - BigUint::from(2u8)))
+    }
+
+    /// Whether the element is at most `u128::MAX`, i.e. a value this program encrypts
+    fn to_u128(&self) -> Option<u128> {
+        self.0.to_u128()
+    }
+}
+
+/// Rescue permutation parameters: the cipher's round keys or the hash's round constants
+struct RescueDesc {
+    /// Exponents applied on even and odd rounds
+    round_exponents: [BigUint; 2],
+    mds: Vec<Vec<Fp>>,
+    round_keys: Vec<Vec<Fp>>,
+}
+
+impl RescueDesc {
+    /// Block cipher keyed with `key`; its round keys are the states of the
+    /// key schedule, which permutes the key under the sampled round constants
+    fn cipher(key: Vec<Fp>) -> Self {
+        let m = key.len();
+        let [alpha, alpha_inverse] = Self::exponents();
+        let constants = Self::cipher_constants(m);
+        let schedule = RescueDesc {
+            round_exponents: [alpha_inverse.clone(), alpha.clone()],
+            mds: Self::mds(m),
+            round_keys: constants,
+        };
+        let round_keys = schedule.permutation_states(&key);
+        RescueDesc {
+            round_keys,
+            ..schedule
+        }
+    }
+
+    /// Rescue-Prime permutation over a `m`-element sponge state
+    fn hash(m: usize, capacity: usize) -> Self {
+        let [alpha, alpha_inverse] = Self::exponents();
+        RescueDesc {
+            round_exponents: [alpha, alpha_inverse],
+            mds: Self::mds(m),
+            round_keys: Self::hash_constants(m, capacity),
+        }
+    }
+
+    fn exponents() -> [BigUint; 2] {
+        let alpha = BigUint::from(RESCUE_ALPHA);
+        let alpha_inverse = alpha
+            .modinv(&(field_modulus() - BigUint::one()))
+            .expect("alpha is coprime with p - 1");
+        [alpha, alpha_inverse]
+    }
+
+    /// Cauchy matrix 1 / (x_i - y_j) with x_i = i and y_j = -j
+    fn mds(m: usize) -> Vec<Vec<Fp>> {
+        (1..=m)
+            .map(|i| {
+                (1..=m)
+                    .map(|j| Fp::from_u128((i + j) as u128).invert())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Field elements read from a SHAKE256 stream, 16 bytes wider than the
+    /// modulus so they are close to uniform after reduction
+    fn sample(reader: &mut impl XofReader, count: usize) -> Vec<Fp> {
+        (0..count)
+            .map(|_| {
+                let mut bytes = [0u8; 48];
+                reader.read(&mut bytes);
+                Fp::from_le_bytes(&bytes)
+            })
+            .collect()
+    }
+
+    /// Round constants c_0 and c_(k+1) = M * c_k + v for random M, c_0 and v
+    fn cipher_constants(m: usize) -> Vec<Vec<Fp>> {
+        let mut hasher = Shake256::default();
+        hasher.update(RESCUE_CIPHER_SEED);
+        let mut reader = hasher.finalize_xof();
+        let samples = Self::sample(&mut reader, m * m + 2 * m);
+        let matrix: Vec<Vec<Fp>> = samples[..m * m].chunks(m).map(<[Fp]>::to_vec).collect();
+        let affine = &samples[m * m + m..];
+
+        let mut constants = vec![samples[m * m..m * m + m].to_vec()];
+        for _ in 0..2 * RESCUE_CIPHER_ROUNDS {
+            let previous = constants.last().expect("constants start non-empty");
+            let next = mat_mul(&matrix, previous)
+                .iter()
+                .zip(affine)
+                .map(|(value, term)| value.add(term))
+                .collect();
+            constants.push(next);
+        }
+        constants
+    }
+
+    /// Rescue-XLIX round constants, preceded by a zero key so the first
+    /// round matches the cipher's structure
+    fn hash_constants(m: usize, capacity: usize) -> Vec<Vec<Fp>> {
+        let mut hasher = Shake256::default();
+        let seed = format!("Rescue-XLIX({},{},{},256)", field_modulus(), m, capacity);
+        hasher.update(seed.as_bytes());
+        let mut reader = hasher.finalize_xof();
+        let samples = Self::sample(&mut reader, 2 * m * RESCUE_HASH_ROUNDS);
+
+        let mut constants = vec![vec![Fp::from_u128(0); m]];
+        constants.extend(samples.chunks(m).map(<[Fp]>::to_vec));
+        constants
+    }
+
+    /// Every intermediate state of the permutation, starting after the first key
+    fn permutation_states(&self, state: &[Fp]) -> Vec<Vec<Fp>> {
+        let mut state: Vec<Fp> = state
+            .iter()
+            .zip(&self.round_keys[0])
+            .map(|(x, k)| x.add(k))
+            .collect();
+        let mut states = vec![state.clone()];
+        for (round, key) in self.round_keys[1..].iter().enumerate() {
+            let exponent = &self.round_exponents[round % 2];
+            let powered: Vec<Fp> = state.iter().map(|x| x.pow(exponent)).collect();
+            state = mat_mul(&self.mds, &powered)
+                .iter()
+                .zip(key)
+                .map(|(x, k)| x.add(k))
+                .collect();
+            states.push(state.clone());
+        }
+        states
+    }
+
+    fn permute(&self, state: &[Fp]) -> Vec<Fp> {
+        self.permutation_states(state)
+            .pop()
+            .expect("permutation has at least one state")
+    }
+}
+
+fn mat_mul(matrix: &[Vec<Fp>], vector: &[Fp]) -> Vec<Fp> {
+    matrix
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(vector)
+                .fold(Fp::from_u128(0), |sum, (a, b)| sum.add(&a.mul(b)))
+        })
+        .collect()
+}
+
+/// Rescue-Prime hash of `message`, truncated to a Rescue key
+fn rescue_prime_digest(mut message: Vec<Fp>) -> Vec<Fp> {
+    let width = RESCUE_HASH_RATE + RESCUE_HASH_CAPACITY;
+    let desc = RescueDesc::hash(width, RESCUE_HASH_CAPACITY);
+    message.push(Fp::from_u128(1));
+    while !message.len().is_multiple_of(RESCUE_HASH_RATE) {
+        message.push(Fp::from_u128(0));
+    }
+    let mut state = vec![Fp::from_u128(0); width];
+    for chunk in message.chunks(RESCUE_HASH_RATE) {
+        for (value, input) in state.iter_mut().zip(chunk) {
+            *value = value.add(input);
+        }
+        state = desc.permute(&state);
+    }
+    state.truncate(RESCUE_KEY_COUNT);
+    state
+}
+
+/// Arcium's Rescue cipher in counter mode, keyed with an
+/// [`EncryptionKeypair::shared_secret`]. Same cipher as `RescueCipher` in
+/// `@arcium-hq/client`, so circuits can read what it encrypts as
+/// `Enc<Shared, _>`. Plaintexts are field elements; every value this program
+/// encrypts fits in a `u128`.
+pub struct RescueCipher {
+    desc: RescueDesc,
+}
+
+impl RescueCipher {
+    /// Derive the key from the x25519 shared secret (NIST SP 800-56C, one-step
+    /// KDF with counter 1 and the key length as fixed info)
+    pub fn new(shared_secret: &[u8; 32]) -> Self {
+        let key = rescue_prime_digest(vec![
+            Fp::from_u128(1),
+            Fp::from_le_bytes(shared_secret),
+            Fp::from_u128(RESCUE_KEY_COUNT as u128),
+        ]);
+        Self {
+            desc: RescueDesc::cipher(key),
+        }
+    }
+
+    /// Cipher shared between `keypair` and the MXE
+    pub fn from_keypair(keypair: &EncryptionKeypair, mxe_public_key: &[u8; 32]) -> Self {
+        Self::new(&keypair.shared_secret(mxe_public_key))
+    }
+
+    /// Keystream for `len` values: block i encrypts `[nonce, i, 0, 0, 0]`
+    fn keystream(&self, nonce: &[u8; 16], len: usize) -> Vec<Fp> {
+        let nonce = Fp::from_le_bytes(nonce);
+        (0..len.div_ceil(RESCUE_KEY_COUNT))
+            .flat_map(|block| {
+                let mut counter = vec![Fp::from_u128(0); RESCUE_KEY_COUNT];
+                counter[0] = nonce.clone();
+                counter[1] = Fp::from_u128(block as u128);
+                self.desc.permute(&counter)
+            })
+            .take(len)
+            .collect()
+    }
+
+    pub fn encrypt(&self, plaintexts: &[u128], nonce: &[u8; 16]) -> Vec<[u8; 32]> {
+        plaintexts
+            .iter()
+            .zip(self.keystream(nonce, plaintexts.len()))
+            .map(|(plaintext, mask)| Fp::from_u128(*plaintext).add(&mask).to_le_bytes())
+            .collect()
+    }
+
+    /// Fails if a value does not fit in a `u128`, which means a wrong key or nonce
+    pub fn decrypt(&self, ciphertexts: &[[u8; 32]], nonce: &[u8; 16]) -> Result<Vec<u128>> {
+        ciphertexts
+            .iter()
+            .zip(self.keystream(nonce, ciphertexts.len()))
+            .map(|(ciphertext, mask)| {
+                Fp::from_le_bytes(ciphertext)
+                    .sub(&mask)
+                    .to_u128()
+                    .ok_or(ClientError::DecryptionFailed)
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Encryption
+// ============================================================================
+
+/// Random nonce for a new encryption
+pub fn random_nonce() -> u128 {
+    let mut nonce = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    u128::from_le_bytes(nonce)
+}
+
+/// A public key as the two little-endian u128 halves circuits compare plans by
+pub fn pubkey_to_u128s(pubkey: &Pubkey) -> [u128; 2] {
+    let bytes = pubkey.to_bytes();
+    let mut first = [0u8; 16];
+    let mut second = [0u8; 16];
+    first.copy_from_slice(&bytes[..16]);
+    second.copy_from_slice(&bytes[16..]);
+    [u128::from_le_bytes(first), u128::from_le_bytes(second)]
+}
+
+pub fn u128s_to_pubkey(values: &[u128; 2]) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(&values[0].to_le_bytes());
+    bytes[16..].copy_from_slice(&values[1].to_le_bytes());
+    Pubkey::new_from_array(bytes)
+}
+
+fn encrypt_with_nonce<const N: usize>(
+    cipher: &RescueCipher,
+    plaintexts: [u128; N],
+) -> ([[u8; 32]; N], u128) {
+    let nonce = random_nonce();
+    let ciphertexts = cipher.encrypt(&plaintexts, &nonce.to_le_bytes());
+    let ciphertexts = ciphertexts
+        .try_into()
+        .expect("one ciphertext per plaintext");
+    (ciphertexts, nonce)
+}
+
+/// Encrypted `deposit` arguments
+#[derive(Clone, Copy, Debug)]
+pub struct DepositInput {
+    pub encryption_pubkey: [u8; 32],
+    pub encrypted_amount: [u8; 32],
+    pub encrypted_amount_nonce: u128,
+}
+
+pub fn encrypt_deposit(
+    cipher: &RescueCipher,
+    keypair: &EncryptionKeypair,
+    amount: u64,
+) -> DepositInput {
+    let ([encrypted_amount], encrypted_amount_nonce) = encrypt_with_nonce(cipher, [amount as u128]);
+    DepositInput {
+        encryption_pubkey: keypair.public_key(),
+        encrypted_amount,
+        encrypted_amount_nonce,
+    }
+}
+
+/// Encrypted `subscribe` arguments, each value under its own nonce
+#[derive(Clone, Copy, Debug)]
+pub struct SubscribeInput {
+    pub encrypted_plan: [[u8; 32]; 2],
+    pub encrypted_plan_nonce: u128,
+    pub encrypted_price: [u8; 32],
+    pub encrypted_price_nonce: u128,
+    pub encrypted_billing_cycle: [u8; 32],
+    pub encrypted_billing_cycle_nonce: u128,
+    pub encrypted_quantity: [u8; 32],
+    pub encrypted_quantity_nonce: u128,
+}
+
+/// `price` is the plan's per-seat price as published on the plan account
+/// (in its reference currency when it has one), and must match it exactly.
+pub fn encrypt_subscribe(
+    cipher: &RescueCipher,
+    plan: &Pubkey,
+    price: u64,
+    billing_cycle_days: u32,
+    quantity: u32,
+) -> SubscribeInput {
+    let (encrypted_plan, encrypted_plan_nonce) = encrypt_with_nonce(cipher, pubkey_to_u128s(plan));
+    let ([encrypted_price], encrypted_price_nonce) = encrypt_with_nonce(cipher, [price as u128]);
+    let ([encrypted_billing_cycle], encrypted_billing_cycle_nonce) =
+        encrypt_with_nonce(cipher, [billing_cycle_days as u128]);
+    let ([encrypted_quantity], encrypted_quantity_nonce) =
+        encrypt_with_nonce(cipher, [quantity as u128]);
+    SubscribeInput {
+        encrypted_plan,
+        encrypted_plan_nonce,
+        encrypted_price,
+        encrypted_price_nonce,
+        encrypted_billing_cycle,
+        encrypted_billing_cycle_nonce,
+        encrypted_quantity,
+        encrypted_quantity_nonce,
+    }
+}
+
+// ============================================================================
+// Decryption
+// ============================================================================
+
+fn decrypt_state<const N: usize>(
+    cipher: &RescueCipher,
+    ciphertexts: &[[u8; 32]; N],
+    nonce: u128,
+) -> Result<[u128; N]> {
+    if nonce == 0 {
+        return Err(ClientError::Uninitialized);
+    }
+    let plaintexts = cipher.decrypt(ciphertexts, &nonce.to_le_bytes())?;
+    Ok(plaintexts.try_into().expect("one plaintext per ciphertext"))
+}
+
+fn require_state_version(found: u8, expected: u8) -> Result<()> {
+    if found != expected {
+        return Err(ClientError::StaleStateLayout { found, expected });
+    }
+    Ok(())
+}
+
+/// Decrypt a user ledger with the cipher of its `encryption_pubkey`
+pub fn decrypt_user_ledger(cipher: &RescueCipher, ledger: &UserLedger) -> Result<UserLedgerState> {
+    require_state_version(ledger.state_version, USER_LEDGER_STATE_VERSION)?;
+    let plaintexts = decrypt_state(cipher, &ledger.state().to_ciphertexts(), ledger.nonce)?;
+    Ok(UserLedgerState::from_plaintexts(&plaintexts))
+}
+
+/// Decrypt a merchant ledger with the cipher of its `encryption_pubkey`
+pub fn decrypt_merchant_ledger(
+    cipher: &RescueCipher,
+    ledger: &MerchantLedger,
+) -> Result<MerchantLedgerState> {
+    require_state_version(ledger.state_version, MERCHANT_LEDGER_STATE_VERSION)?;
+    let plaintexts = decrypt_state(cipher, &ledger.state().to_ciphertexts(), ledger.nonce)?;
+    Ok(MerchantLedgerState::from_plaintexts(&plaintexts))
+}
+
+/// Decrypt a subscription with the cipher of its `encryption_pubkey`.
+/// The plan is recovered with [`u128s_to_pubkey`].
+pub fn decrypt_user_subscription(
+    cipher: &RescueCipher,
+    subscription: &UserSubscription,
+) -> Result<UserSubscriptionState> {
+    require_state_version(subscription.state_version, USER_SUBSCRIPTION_STATE_VERSION)?;
+    let plaintexts = decrypt_state(
+        cipher,
+        &subscription.state().to_ciphertexts(),
+        subscription.nonce,
+    )?;
+    Ok(UserSubscriptionState::from_plaintexts(&plaintexts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AnchorDeserialize, Discriminator};
+
+    /// Field element printed big-endian, as Arcis debug output shows it
+    fn field_bytes(hex: &str) -> [u8; 32] {
+        let mut bytes: [u8; 32] =
+            core::array::from_fn(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap());
+        bytes.reverse();
+        bytes
+    }
+
+    fn test_cipher() -> RescueCipher {
+        let mut shared_secret: [u8; 32] = core::array::from_fn(|i| i as u8 + 1);
+        shared_secret[31] &= 0x3f;
+        RescueCipher::new(&shared_secret)
+    }
+
+    fn test_nonce() -> [u8; 16] {
+        0x0102030405060708u128.to_le_bytes()
+    }
+
+    #[test]
+    fn rescue_cipher_matches_arcis() {
+        // Reference output of the Arcis compiler's RescueCipher for the same key and nonce
+        let ciphertexts = test_cipher().encrypt(&[42, 7, 0, 1, 2, 3], &test_nonce());
+        assert_eq!(
+            ciphertexts,
+            vec![
+                field_bytes("0ffd59636bc3c16fd5879a7912cd22638589f9e4a2698717fc9ad43b17bc6c95"),
+                field_bytes("23f77246c9bcb7df3e06b1964d55b2c60680d3158a4916fe902e2b3bf00b3c67"),
+                field_bytes("4a054bb18d7a3da613a9a64ad0bb6f9043d8358f9809a75c13007d434cc617df"),
+                field_bytes("122e3e5bde61aaab591b2c2038f1d98bac3319d97aacc9a580f3526f529a00b5"),
+                field_bytes("20e0797d2e61d874bcad2b55c7fa0ec3b84a1f2cd4e7f92c9b48e865861f8cb8"),
+                field_bytes("104a001eb6f786e01b65c856ff01f62c3aff9a57cb6c464c20f36d2f580349c7"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rescue_cipher_round_trips() {
+        let cipher = test_cipher();
+        let plaintexts = [u128::MAX, 0, 1_000_000, u64::MAX as u128];
+        let ciphertexts = cipher.encrypt(&plaintexts, &test_nonce());
+        assert_eq!(
+            cipher.decrypt(&ciphertexts, &test_nonce()).unwrap(),
+            plaintexts
+        );
+    }
+
+    #[test]
+    fn rescue_cipher_rejects_wrong_nonce() {
+        let cipher = test_cipher();
+        let ciphertexts = cipher.encrypt(&[42], &test_nonce());
+        assert!(matches!(
+            cipher.decrypt(&ciphertexts, &[0u8; 16]),
+            Err(ClientError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn shared_secret_is_symmetric() {
+        let user = EncryptionKeypair::generate();
+        let mxe = EncryptionKeypair::generate();
+        assert_eq!(
+            user.shared_secret(&mxe.public_key()),
+            mxe.shared_secret(&user.public_key())
+        );
+    }
+
+    #[test]
+    fn pubkey_halves_are_little_endian() {
+        let bytes: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let pubkey = Pubkey::new_from_array(bytes);
+        let [first, second] = pubkey_to_u128s(&pubkey);
+        assert_eq!(first, 0x0f0e0d0c0b0a09080706050403020100);
+        assert_eq!(second, 0x1f1e1d1c1b1a19181716151413121110);
+        assert_eq!(u128s_to_pubkey(&[first, second]), pubkey);
+    }
+
+    #[test]
+    fn pdas_use_program_seeds() {
+        let user = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let merchant_key = merchant(&user);
+        assert_eq!(
+            user_ledger(&user, &mint),
+            Pubkey::find_program_address(
+                &[b"user_ledger", user.as_ref(), mint.as_ref()],
+                &PROGRAM_ID
+            )
+            .0
+        );
+        assert_eq!(
+            subscription_plan(&merchant_key, 7),
+            Pubkey::find_program_address(
+                &[
+                    b"subscription_plan",
+                    merchant_key.as_ref(),
+                    &7u64.to_le_bytes()
+                ],
+                &PROGRAM_ID
+            )
+            .0
+        );
+        assert_ne!(
+            pending_outflow(&user, &mint, OUTFLOW_KIND_WITHDRAW),
+            pending_outflow(&user, &mint, OUTFLOW_KIND_CLAIM)
+        );
+    }
+
+    #[test]
+    fn deposit_encodes_arguments() {
+        let user = Pubkey::new_unique();
+        let keypair = EncryptionKeypair::generate();
+        let input = encrypt_deposit(&test_cipher(), &keypair, 500);
+        let transfer = PoolTransfer {
+            mint: Pubkey::new_unique(),
+            pool_token_account: Pubkey::new_unique(),
+            owner_token_account: Pubkey::new_unique(),
+            screened: false,
+        };
+        let computation = Computation::new(0);
+        let ix = deposit(user, transfer, 500, input, computation);
+
+        assert_eq!(ix.program_id, PROGRAM_ID);
+        assert_eq!(&ix.data[..8], instruction::Deposit::DISCRIMINATOR);
+        let args = instruction::Deposit::try_from_slice(&ix.data[8..]).unwrap();
+        assert_eq!(args.computation_offset, computation.offset);
+        assert_eq!(args.amount, 500);
+        assert_eq!(args.encryption_pubkey, keypair.public_key());
+        assert_eq!(args.encrypted_amount, input.encrypted_amount);
+        assert_eq!(args.encrypted_amount_nonce, input.encrypted_amount_nonce);
+        assert_eq!(ix.accounts[0].pubkey, user);
+        assert!(ix.accounts[0].is_signer);
+    }
+
+    #[test]
+    fn withdraw_passes_pending_outflow_only_when_due() {
+        let user = Pubkey::new_unique();
+        let transfer = PoolTransfer {
+            mint: Pubkey::new_unique(),
+            pool_token_account: Pubkey::new_unique(),
+            owner_token_account: Pubkey::new_unique(),
+            screened: false,
+        };
+        let pending = pending_outflow(&user, &transfer.mint, OUTFLOW_KIND_WITHDRAW);
+        let passes = |due| {
+            withdraw(user, transfer, 10, [0u8; 32], due, Computation::new(0))
+                .accounts
+                .iter()
+                .any(|meta| meta.pubkey == pending)
+        };
+        assert!(passes(true));
+        assert!(!passes(false));
+    }
+}
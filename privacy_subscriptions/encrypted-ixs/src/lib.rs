@@ -4,32 +4,357 @@ use arcis::*;
 mod circuits {
     use arcis::*;
 
+    /// Number of subscription slots accepted by the runway query
+    const RUNWAY_SLOTS: usize = 4;
+    /// Number of upcoming payments simulated by the runway query
+    const RUNWAY_STEPS: usize = 36;
+    /// Maximum number of seats on one subscription
+    const MAX_SEATS: u32 = 1000;
+    /// Longest spending cap window in days
+    const MAX_SPENDING_WINDOW_DAYS: u32 = 365;
+    /// Length of a plan analytics period (30 days)
+    const STATS_PERIOD_SECONDS: i64 = 30 * 86400;
+    /// Fixed-point scale of the pool yield index
+    const YIELD_INDEX_SCALE: u128 = 1_000_000_000_000;
+    /// Smallest period charge of a subscription that has no paid period yet
+    const NO_CHARGE: u64 = u64::MAX;
+    /// Number of outstanding vouchers a voucher pool can hold
+    const VOUCHER_POOL_SLOTS: usize = 16;
+
     // ========================================================================
     // Encrypted State Structures
     // ========================================================================
 
+    // The states the program stores or decodes (and their legacy layouts) are
+    // declared again in `state-layouts`, in the same field order; `repr(C)`
+    // lets `assert_layout!` check that order at compile time.
+
     /// User ledger state (encrypted)
+    #[repr(C)]
     pub struct UserLedgerState {
-        pub balance: u64,
+        /// Deposited and credited funds
+        pub principal: u64,
         pub subscription_count: u64,
+        /// Yield credited by settlements; spent before principal
+        pub accrued_yield: u64,
+        /// When set, subscription charges may only be paid from accrued yield
+        pub principal_locked: bool,
     }
 
     /// Merchant ledger state (encrypted)
+    #[repr(C)]
     pub struct MerchantLedgerState {
         pub balance: u64,
         pub total_claimed: u64,
+        /// Amount frozen by open disputes (excluded from balance and claims)
+        pub frozen: u64,
     }
 
     /// User subscription state (encrypted)
+    #[repr(C)]
     pub struct UserSubscriptionState {
         /// Encrypted plan public key as two u128 values (32 bytes total)
         pub plan: [u128; 2],
-        /// Subscription status (0=Active, 1=Cancelled, 2=Expired)
+        /// Subscription status (0=Active, 1=Cancelled, 2=Expired, 3=Cancelling, 4=CapExceeded)
         pub status: u8,
         /// Next payment date (unix timestamp)
         pub next_payment_date: i64,
         /// Start date (unix timestamp)
         pub start_date: i64,
+        /// Number of seats paid for (price is charged per seat)
+        pub quantity: u32,
+    }
+
+    /// Per-merchant spending cap state (encrypted, owned by the user key)
+    #[repr(C)]
+    pub struct SpendingCapState {
+        /// Maximum spend per window
+        pub cap: u64,
+        /// Window length in seconds
+        pub window_seconds: i64,
+        /// Start of the current window (unix timestamp)
+        pub window_start: i64,
+        /// Amount charged in the current window
+        pub spent: u64,
+    }
+
+    /// Per-plan analytics (encrypted, owned by the merchant key)
+    #[repr(C)]
+    pub struct PlanStatsState {
+        /// Subscriptions currently paying (Active or Cancelling)
+        pub active_subscribers: u64,
+        /// Monthly (30-day) recurring revenue across active subscriptions
+        pub mrr: u64,
+        /// Start of the current period (unix timestamp)
+        pub period_start: i64,
+        /// Subscriptions started in the current period
+        pub new_subscribers: u64,
+        /// Subscriptions ended in the current period
+        pub churned_subscribers: u64,
+        /// Subscriptions started in the previous period
+        pub previous_new_subscribers: u64,
+        /// Subscriptions ended in the previous period
+        pub previous_churned_subscribers: u64,
+    }
+
+    /// Pool yield accounting (encrypted, MXE-owned)
+    #[repr(C)]
+    pub struct PoolYieldState {
+        /// Cumulative yield per unit of settled user balance, scaled by YIELD_INDEX_SCALE
+        pub index: u128,
+        /// Sum of user balances as of their last settlement, the weight yield is spread over
+        pub user_liabilities: u64,
+    }
+
+    /// Charges recorded on a subscription, capping what a dispute can freeze (encrypted, MXE-owned)
+    #[repr(C)]
+    pub struct ChargeRecordState {
+        /// Charged for the latest paid billing period, seat changes included
+        pub last_charge: u64,
+        /// Smallest charge of any paid billing period
+        pub min_charge: u64,
+    }
+
+    /// Balance runway summary (encrypted to the user key)
+    #[repr(C)]
+    pub struct BalanceRunway {
+        /// Total committed spend per 30 days across the given subscriptions
+        pub monthly_spend: u64,
+        /// Date of the first payment the balance cannot cover (0 = none within the horizon)
+        pub first_failure_date: i64,
+    }
+
+    // ========================================================================
+    // Legacy State Layouts (inputs of the migration circuits)
+    // ========================================================================
+
+    /// UserLedgerState layout 1, before yield accounting
+    #[repr(C)]
+    pub struct UserLedgerStateV1 {
+        pub balance: u64,
+        pub subscription_count: u64,
+    }
+
+    /// MerchantLedgerState layout 1, before disputes
+    #[repr(C)]
+    pub struct MerchantLedgerStateV1 {
+        pub balance: u64,
+        pub total_claimed: u64,
+    }
+
+    /// UserSubscriptionState layout 1, before seats
+    #[repr(C)]
+    pub struct UserSubscriptionStateV1 {
+        pub plan: [u128; 2],
+        pub status: u8,
+        pub next_payment_date: i64,
+        pub start_date: i64,
+    }
+
+    // ========================================================================
+    // Helpers
+    // ========================================================================
+
+    /// Start a new spending window once the current one has elapsed.
+    /// A cap that has never been set is unlimited.
+    fn refresh_spending_window(
+        cap: SpendingCapState,
+        current_timestamp: i64,
+        cap_is_new: bool,
+    ) -> SpendingCapState {
+        let mut cap = cap;
+
+        if cap_is_new {
+            cap.cap = u64::MAX;
+            cap.window_seconds = 0;
+            cap.window_start = 0;
+            cap.spent = 0;
+        }
+
+        if current_timestamp >= cap.window_start + cap.window_seconds {
+            cap.window_start = current_timestamp;
+            cap.spent = 0;
+        }
+
+        cap
+    }
+
+    /// Check whether a charge fits in what is left of the current window
+    fn is_within_spending_cap(cap: &SpendingCapState, amount: u64) -> bool {
+        let remaining = cap.cap - cap.cap.min(cap.spent);
+        amount <= remaining
+    }
+
+    /// Start a new analytics period once the current one has elapsed.
+    /// The finished period's counters move to the previous-period fields.
+    fn refresh_stats_period(
+        stats: PlanStatsState,
+        current_timestamp: i64,
+        stats_is_new: bool,
+    ) -> PlanStatsState {
+        let mut stats = stats;
+
+        if stats_is_new {
+            stats.active_subscribers = 0;
+            stats.mrr = 0;
+            stats.period_start = current_timestamp;
+            stats.new_subscribers = 0;
+            stats.churned_subscribers = 0;
+            stats.previous_new_subscribers = 0;
+            stats.previous_churned_subscribers = 0;
+        }
+
+        if current_timestamp >= stats.period_start + STATS_PERIOD_SECONDS {
+            stats.previous_new_subscribers = stats.new_subscribers;
+            stats.previous_churned_subscribers = stats.churned_subscribers;
+            stats.new_subscribers = 0;
+            stats.churned_subscribers = 0;
+            stats.period_start = current_timestamp;
+        }
+
+        stats
+    }
+
+    /// Revenue of one subscription normalised to 30 days
+    fn monthly_revenue(plan_price: u64, quantity: u32, billing_cycle_days: u32) -> u64 {
+        plan_price * (quantity as u64) * 30 / (billing_cycle_days as u64)
+    }
+
+    /// Add a started (or restarted) subscription to the active totals
+    fn record_start(stats: PlanStatsState, revenue: u64, started: bool) -> PlanStatsState {
+        let mut stats = stats;
+
+        if started {
+            stats.active_subscribers += 1;
+            stats.mrr += revenue;
+            stats.new_subscribers += 1;
+        }
+
+        stats
+    }
+
+    /// Replace a subscription's revenue in the MRR after a seat change
+    fn record_seat_change(
+        stats: PlanStatsState,
+        old_revenue: u64,
+        new_revenue: u64,
+        changed: bool,
+    ) -> PlanStatsState {
+        let mut stats = stats;
+
+        if changed {
+            stats.mrr = stats.mrr - stats.mrr.min(old_revenue) + new_revenue;
+        }
+
+        stats
+    }
+
+    /// Remove an ended subscription from the active totals.
+    /// Totals never go below zero if the plan price changed since the subscription started.
+    fn record_churn(stats: PlanStatsState, revenue: u64, churned: bool) -> PlanStatsState {
+        let mut stats = stats;
+
+        if churned {
+            stats.active_subscribers = if stats.active_subscribers > 0 {
+                stats.active_subscribers - 1
+            } else {
+                0
+            };
+            stats.mrr -= stats.mrr.min(revenue);
+            stats.churned_subscribers += 1;
+        }
+
+        stats
+    }
+
+    /// Total balance of a ledger, principal plus accrued yield
+    fn total_balance(ledger: &UserLedgerState) -> u64 {
+        ledger.principal + ledger.accrued_yield
+    }
+
+    /// Funds a subscription charge can draw on; a locked principal is off limits
+    fn subscription_funds(ledger: &UserLedgerState) -> u64 {
+        if ledger.principal_locked {
+            ledger.accrued_yield
+        } else {
+            ledger.principal + ledger.accrued_yield
+        }
+    }
+
+    /// Debit a ledger from accrued yield first and from principal only for the remainder.
+    /// The caller must already have checked that the ledger covers the amount.
+    fn debit_yield_first(ledger: UserLedgerState, amount: u64) -> UserLedgerState {
+        let mut ledger = ledger;
+        let from_yield = if amount <= ledger.accrued_yield { amount } else { ledger.accrued_yield };
+
+        ledger.accrued_yield -= from_yield;
+        ledger.principal -= amount - from_yield;
+
+        ledger
+    }
+
+    /// Pool yield state a circuit starts from; a pool that never distributed starts empty
+    fn current_pool_yield(pool_yield: PoolYieldState, yield_is_new: bool) -> PoolYieldState {
+        let mut pool = pool_yield;
+
+        if yield_is_new {
+            pool.index = 0;
+            pool.user_liabilities = 0;
+        }
+
+        pool
+    }
+
+    /// Credit a ledger with the yield its balance earned since its checkpoint.
+    /// Harvested yield is counted in liabilities when it is distributed, so a
+    /// settlement only adds the credit to the pool's settled user balances and
+    /// the ledger's checkpoint moves to `pool.index`. Every circuit that changes
+    /// a user balance settles first, which keeps the balance constant between
+    /// two checkpoints.
+    fn settle_yield(
+        ledger: UserLedgerState,
+        checkpoint: u128,
+        pool: PoolYieldState,
+    ) -> (UserLedgerState, PoolYieldState) {
+        let mut ledger = ledger;
+        let mut pool = pool;
+        let owed = ((total_balance(&ledger) as u128) * (pool.index - checkpoint) / YIELD_INDEX_SCALE) as u64;
+        ledger.accrued_yield += owed;
+        pool.user_liabilities += owed;
+        (ledger, pool)
+    }
+
+    /// Remove an amount leaving user balances (a charge or a withdrawal) from the
+    /// pool's user liabilities. Saturates so an out-of-sync total cannot wrap around.
+    fn debit_user_liabilities(pool: PoolYieldState, amount: u64) -> PoolYieldState {
+        let mut pool = pool;
+        pool.user_liabilities -= pool.user_liabilities.min(amount);
+        pool
+    }
+
+    /// Charge record a circuit starts from. Subscriptions charged before charges were
+    /// recorded start from zero, so only periods they pay from now on can be disputed.
+    fn current_charges(charges: ChargeRecordState, charges_is_new: bool) -> ChargeRecordState {
+        let mut charges = charges;
+
+        if charges_is_new {
+            charges.last_charge = 0;
+            charges.min_charge = 0;
+        }
+
+        charges
+    }
+
+    /// Record the charge of a newly paid billing period
+    fn record_period_charge(charges: ChargeRecordState, amount: u64, charged: bool) -> ChargeRecordState {
+        let mut charges = charges;
+
+        if charged {
+            charges.last_charge = amount;
+            charges.min_charge = charges.min_charge.min(amount);
+        }
+
+        charges
     }
 
     // ========================================================================
@@ -37,106 +362,164 @@ mod circuits {
     // ========================================================================
 
     /// Deposit circuit: Add funds to user's encrypted balance
-    /// Input: user_ledger (encrypted), amount (encrypted), is_new (plaintext)
-    /// Output: updated user_ledger (encrypted)
+    /// Pending yield is settled first, so the deposit only earns from the current yield index on.
+    /// Input: user_ledger (encrypted), amount (encrypted), total_liabilities, pool_yield, yield_checkpoint (MXE), flags (plaintext)
+    /// Output: updated user_ledger (encrypted), total_liabilities, yield_checkpoint and pool_yield (MXE)
     #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     pub fn deposit_v2(
         user_ledger: Enc<Shared, UserLedgerState>,
         amount: Enc<Shared, u64>,
+        total_liabilities: Enc<Mxe, u64>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
         is_new: bool,
-    ) -> Enc<Shared, UserLedgerState> {
+        liabilities_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+    ) -> (Enc<Shared, UserLedgerState>, Enc<Mxe, u64>, Enc<Mxe, u128>, Enc<Mxe, PoolYieldState>) {
         let mut ledger = user_ledger.to_arcis();
         let deposit_amount = amount.to_arcis();
+        let liabilities = if liabilities_is_new { 0u64 } else { total_liabilities.to_arcis() };
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
 
         if is_new {
-            ledger.balance = 0;
+            ledger.principal = 0;
             ledger.subscription_count = 0;
+            ledger.accrued_yield = 0;
+            ledger.principal_locked = false;
         }
 
-        let new_balance = ledger.balance + deposit_amount;
-        let new_state = UserLedgerState {
-            balance: new_balance,
-            subscription_count: ledger.subscription_count,
-        };
+        let (mut new_state, mut pool) = settle_yield(ledger, checkpoint, pool);
+        new_state.principal += deposit_amount;
+        pool.user_liabilities += deposit_amount;
 
-        user_ledger.owner.from_arcis(new_state)
+        (
+            user_ledger.owner.from_arcis(new_state),
+            Mxe::get().from_arcis(liabilities + deposit_amount),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+        )
     }
 
     /// Withdraw circuit: Subtract funds from user's encrypted balance
-    /// Input: user_ledger (encrypted), amount (plaintext), is_new (plaintext)
-    /// Output: updated user_ledger (encrypted) and actual_amount (revealed)
+    /// Pending yield is settled first and can be withdrawn along with principal.
+    /// Input: user_ledger (encrypted), total_liabilities, pool_yield, yield_checkpoint (MXE), amount (plaintext), flags (plaintext)
+    /// Output: updated user_ledger (encrypted), total_liabilities, yield_checkpoint and pool_yield (MXE), actual_amount (revealed)
     #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     pub fn withdraw_v2(
         user_ledger: Enc<Shared, UserLedgerState>,
+        total_liabilities: Enc<Mxe, u64>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
         amount: u64,
         is_new: bool,
-    ) -> (Enc<Shared, UserLedgerState>, u64) {
+        liabilities_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+    ) -> (Enc<Shared, UserLedgerState>, Enc<Mxe, u64>, Enc<Mxe, u128>, Enc<Mxe, PoolYieldState>, u64) {
         let mut ledger = user_ledger.to_arcis();
+        let liabilities = if liabilities_is_new { 0u64 } else { total_liabilities.to_arcis() };
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
 
         if is_new {
-            ledger.balance = 0;
+            ledger.principal = 0;
             ledger.subscription_count = 0;
+            ledger.accrued_yield = 0;
+            ledger.principal_locked = false;
         }
+        let (ledger, pool) = settle_yield(ledger, checkpoint, pool);
 
-        // Check if user has sufficient balance
-        let has_balance = ledger.balance >= amount;
-
-        // Conditional subtraction: only subtract if has_balance is true
-        let new_balance = if has_balance {
-            ledger.balance - amount
-        } else {
-            ledger.balance
-        };
+        // Check if user has sufficient balance (the principal lock only applies to subscriptions)
+        let has_balance = total_balance(&ledger) >= amount;
 
         // Actual withdraw amount: if success, use amount, else 0
         let actual_amount = if has_balance { amount } else { 0u64 };
 
-        let new_state = UserLedgerState {
-            balance: new_balance,
-            subscription_count: ledger.subscription_count,
-        };
+        // Withdrawals also spend accrued yield before principal
+        let new_state = debit_yield_first(ledger, actual_amount);
+        let pool = debit_user_liabilities(pool, actual_amount);
 
         (
             user_ledger.owner.from_arcis(new_state),
+            // Saturate so an out-of-sync total cannot wrap around
+            Mxe::get().from_arcis(liabilities - liabilities.min(actual_amount)),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
             actual_amount.reveal(),
         )
     }
 
     /// Subscribe circuit: Create subscription and process initial payment
-    /// Input: user_ledger, merchant_ledger (encrypted), plan/price/cycle (encrypted), timestamps + plan metadata (plaintext)
-    /// Output: Updated ledgers + subscription state (encrypted)
+    /// Input: user_ledger, merchant_ledger, spending_cap, plan_stats (encrypted), plan/price/cycle/quantity (encrypted),
+    ///        pool_yield, yield_checkpoint (MXE), timestamps + plan metadata (plaintext)
+    /// Output: Updated ledgers + subscription state + spending_cap + plan_stats (encrypted), yield_checkpoint,
+    ///         pool_yield and the subscription's charge record (MXE)
+    /// `plan_price` is the listed price the user agreed to; `settlement_price` is what is charged
+    /// per seat in the ledger's mint (equal unless the plan is priced in a reference currency).
     #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     pub fn subscribe_v2(
         user_ledger: Enc<Shared, UserLedgerState>,
         merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        spending_cap: Enc<Shared, SpendingCapState>,
+        plan_stats: Enc<Shared, PlanStatsState>,
         plan: Enc<Shared, [u128; 2]>,
         price: Enc<Shared, u64>,
         billing_cycle_days: Enc<Shared, u32>,
+        quantity: Enc<Shared, u32>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
         current_timestamp: i64,
         plan_pubkey: [u128; 2],
         plan_price: u64,
+        settlement_price: u64,
         plan_billing_cycle_days: u32,
         user_is_new: bool,
         merchant_is_new: bool,
+        cap_is_new: bool,
+        stats_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
     ) -> (
         Enc<Shared, UserLedgerState>,
         Enc<Shared, MerchantLedgerState>,
         Enc<Shared, UserSubscriptionState>,
+        Enc<Shared, SpendingCapState>,
+        Enc<Shared, PlanStatsState>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+        Enc<Mxe, ChargeRecordState>,
     ) {
         let mut user = user_ledger.to_arcis();
         let mut merchant = merchant_ledger.to_arcis();
+        let cap = spending_cap.to_arcis();
+        let stats = plan_stats.to_arcis();
         let input_plan = plan.to_arcis();
         let input_price = price.to_arcis();
         let input_cycle = billing_cycle_days.to_arcis();
+        let seats = quantity.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
 
         if user_is_new {
-            user.balance = 0;
+            user.principal = 0;
             user.subscription_count = 0;
+            user.accrued_yield = 0;
+            user.principal_locked = false;
         }
+        let (user, pool) = settle_yield(user, checkpoint, pool);
 
         if merchant_is_new {
             merchant.balance = 0;
             merchant.total_claimed = 0;
+            merchant.frozen = 0;
         }
 
         // Validate encrypted inputs against the provided plan metadata
@@ -144,21 +527,27 @@ mod circuits {
             (input_plan[0] == plan_pubkey[0]) & (input_plan[1] == plan_pubkey[1]);
         let is_price_match = input_price == plan_price;
         let is_cycle_match = input_cycle == plan_billing_cycle_days;
-        let is_valid_plan = is_plan_match & is_price_match & is_cycle_match;
+        // Arcis has no `RangeInclusive::contains`
+        #[allow(clippy::manual_range_contains)]
+        let is_valid_seats = (seats >= 1) & (seats <= MAX_SEATS);
+        let is_valid_plan = is_plan_match & is_price_match & is_cycle_match & is_valid_seats;
 
-        // Check if user has sufficient balance for initial payment (use plaintext plan price)
-        let has_balance = user.balance >= plan_price;
+        // The plan price is per seat
+        let amount_due = settlement_price * (seats as u64);
 
-        // Calculate new balances (only if has_balance)
-        let can_subscribe = is_valid_plan & has_balance;
-        let new_user_bal = if can_subscribe {
-            user.balance - plan_price
-        } else {
-            user.balance
-        };
+        // Check if user has sufficient funds for initial payment (yield, then unlocked principal)
+        let has_balance = subscription_funds(&user) >= amount_due;
+
+        // Check the user's spending cap for this merchant
+        let cap = refresh_spending_window(cap, current_timestamp, cap_is_new);
+        let within_cap = is_within_spending_cap(&cap, amount_due);
+
+        // Calculate new balances (only if has_balance and within the cap)
+        let can_subscribe = is_valid_plan & has_balance & within_cap;
+        let charged = if can_subscribe { amount_due } else { 0u64 };
 
         let new_merchant_bal = if can_subscribe {
-            merchant.balance + plan_price
+            merchant.balance + amount_due
         } else {
             merchant.balance
         };
@@ -174,17 +563,24 @@ mod circuits {
         let cycle_seconds = (plan_billing_cycle_days as i64) * seconds_per_day;
         let next_payment = current_timestamp + cycle_seconds;
 
-        // Status: 0 = Active, 1 = Cancelled
-        let status: u8 = if can_subscribe { 0 } else { 1 };
-
-        let user_state = UserLedgerState {
-            balance: new_user_bal,
-            subscription_count: new_subscription_count,
+        // Status: 0 = Active, 1 = Cancelled, 4 = CapExceeded
+        let is_cap_exceeded = is_valid_plan & has_balance & !within_cap;
+        let status: u8 = if can_subscribe {
+            0
+        } else if is_cap_exceeded {
+            4
+        } else {
+            1
         };
 
+        let mut user_state = debit_yield_first(user, charged);
+        user_state.subscription_count = new_subscription_count;
+        let pool = debit_user_liabilities(pool, charged);
+
         let merchant_state = MerchantLedgerState {
             balance: new_merchant_bal,
             total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen,
         };
 
         let subscription_state = UserSubscriptionState {
@@ -192,6 +588,28 @@ mod circuits {
             status,
             next_payment_date: if can_subscribe { next_payment } else { 0 },
             start_date: if can_subscribe { current_timestamp } else { 0 },
+            quantity: seats,
+        };
+
+        let cap_state = SpendingCapState {
+            cap: cap.cap,
+            window_seconds: cap.window_seconds,
+            window_start: cap.window_start,
+            spent: if can_subscribe { cap.spent + amount_due } else { cap.spent },
+        };
+
+        // Count the new subscriber in the plan analytics
+        let stats = refresh_stats_period(stats, current_timestamp, stats_is_new);
+        let stats = record_start(
+            stats,
+            monthly_revenue(plan_price, seats, plan_billing_cycle_days),
+            can_subscribe,
+        );
+
+        // The first period is the only one charged so far
+        let charges = ChargeRecordState {
+            last_charge: charged,
+            min_charge: charged,
         };
 
         let subscription_owner = Shared::new(user_ledger.owner.public_key);
@@ -200,58 +618,118 @@ mod circuits {
             user_ledger.owner.from_arcis(user_state),
             merchant_ledger.owner.from_arcis(merchant_state),
             subscription_owner.from_arcis(subscription_state),
+            spending_cap.owner.from_arcis(cap_state),
+            plan_stats.owner.from_arcis(stats),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+            Mxe::get().from_arcis(charges),
         )
     }
 
     /// Unsubscribe circuit: Cancel subscription
-    /// Input: subscription (encrypted)
-    /// Output: updated subscription (encrypted) - always set to Cancelled (1)
+    /// Input: subscription + plan_stats (encrypted), timestamp + plan metadata (plaintext)
+    /// Output: updated subscription (encrypted) - always set to Cancelled (1) - and plan_stats (encrypted)
     #[instruction]
     pub fn unsubscribe_v2(
         subscription: Enc<Shared, UserSubscriptionState>,
-    ) -> Enc<Shared, UserSubscriptionState> {
+        plan_stats: Enc<Shared, PlanStatsState>,
+        current_timestamp: i64,
+        plan_price: u64,
+        billing_cycle_days: u32,
+        plan_pubkey: [u128; 2],
+        stats_is_new: bool,
+    ) -> (Enc<Shared, UserSubscriptionState>, Enc<Shared, PlanStatsState>) {
         let mut sub = subscription.to_arcis();
+        let stats = plan_stats.to_arcis();
+
+        // Only a paying subscription on this plan counts as churn
+        let is_plan_match =
+            (sub.plan[0] == plan_pubkey[0]) & (sub.plan[1] == plan_pubkey[1]);
+        let was_active = (sub.status == 0) | (sub.status == 3);
+        let stats = refresh_stats_period(stats, current_timestamp, stats_is_new);
+        let stats = record_churn(
+            stats,
+            monthly_revenue(plan_price, sub.quantity, billing_cycle_days),
+            was_active & is_plan_match,
+        );
+
         sub.status = 1;
         sub.next_payment_date = 0;
-        subscription.owner.from_arcis(sub)
+        (
+            subscription.owner.from_arcis(sub),
+            plan_stats.owner.from_arcis(stats),
+        )
     }
 
     /// ProcessPayment circuit: Process recurring subscription payment
-    /// Input: ledgers + subscription (encrypted), timestamps + plan metadata (plaintext)
-    /// Output: Updated ledgers + subscription (encrypted)
+    /// Input: ledgers + subscription + spending_cap + plan_stats (encrypted), pool_yield, yield_checkpoint,
+    ///        charge record (MXE), timestamps + plan metadata (plaintext)
+    /// Output: Updated ledgers + subscription + spending_cap + plan_stats (encrypted), yield_checkpoint,
+    ///         pool_yield and charge record (MXE)
+    /// Charges `settlement_price` per seat; analytics keep using the listed `plan_price`.
     #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     pub fn process_payment_v2(
         user_ledger: Enc<Shared, UserLedgerState>,
         merchant_ledger: Enc<Shared, MerchantLedgerState>,
         subscription: Enc<Shared, UserSubscriptionState>,
+        spending_cap: Enc<Shared, SpendingCapState>,
+        plan_stats: Enc<Shared, PlanStatsState>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        charges: Enc<Mxe, ChargeRecordState>,
         current_timestamp: i64,
         plan_price: u64,
+        settlement_price: u64,
         billing_cycle_days: u32,
         plan_pubkey: [u128; 2],
         user_is_new: bool,
         merchant_is_new: bool,
+        cap_is_new: bool,
+        stats_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+        charges_is_new: bool,
     ) -> (
         Enc<Shared, UserLedgerState>,
         Enc<Shared, MerchantLedgerState>,
         Enc<Shared, UserSubscriptionState>,
+        Enc<Shared, SpendingCapState>,
+        Enc<Shared, PlanStatsState>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+        Enc<Mxe, ChargeRecordState>,
     ) {
         let mut user = user_ledger.to_arcis();
         let mut merchant = merchant_ledger.to_arcis();
         let mut sub = subscription.to_arcis();
+        let cap = spending_cap.to_arcis();
+        let stats = plan_stats.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let charges = current_charges(charges.to_arcis(), charges_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
 
         if user_is_new {
-            user.balance = 0;
+            user.principal = 0;
             user.subscription_count = 0;
+            user.accrued_yield = 0;
+            user.principal_locked = false;
         }
+        let (user, pool) = settle_yield(user, checkpoint, pool);
 
         if merchant_is_new {
             merchant.balance = 0;
             merchant.total_claimed = 0;
+            merchant.frozen = 0;
         }
 
         // Check if subscription is Active (status == 0)
         let is_active = sub.status == 0;
 
+        // Check if subscription is Cancelling at period end (status == 3)
+        let is_cancelling = sub.status == 3;
+
         // Check if payment is due (next_payment_date <= current_timestamp)
         let is_due = sub.next_payment_date <= current_timestamp;
 
@@ -262,21 +740,24 @@ mod circuits {
         // Should we process this payment?
         let should_process = is_active && is_due && is_plan_match;
 
-        // Check if user has sufficient balance
-        let has_balance = user.balance >= plan_price;
+        // The plan price is per seat
+        let amount_due = settlement_price * (sub.quantity as u64);
+
+        // Check if user has sufficient funds (yield, then unlocked principal)
+        let has_balance = subscription_funds(&user) >= amount_due;
+
+        // Check the user's spending cap for this merchant
+        let cap = refresh_spending_window(cap, current_timestamp, cap_is_new);
+        let within_cap = is_within_spending_cap(&cap, amount_due);
 
         // Can we actually process the payment?
-        let can_pay = should_process && has_balance;
+        let can_pay = should_process && has_balance && within_cap;
 
         // Calculate new balances
-        let new_user_bal = if can_pay {
-            user.balance - plan_price
-        } else {
-            user.balance
-        };
+        let charged = if can_pay { amount_due } else { 0u64 };
 
         let new_merchant_bal = if can_pay {
-            merchant.balance + plan_price
+            merchant.balance + amount_due
         } else {
             merchant.balance
         };
@@ -286,6 +767,18 @@ mod circuits {
             sub.status = 1u8; // Cancelled due to insufficient balance
         }
 
+        // Refused by the user's spending cap (distinct from a low-balance cancel)
+        if should_process && has_balance && !within_cap {
+            sub.status = 4u8;
+        }
+
+        // A pending cancellation takes effect once the paid-through date is reached
+        let ends_cancellation = is_cancelling && is_due && is_plan_match;
+        if ends_cancellation {
+            sub.status = 1u8;
+            sub.next_payment_date = 0;
+        }
+
         // Calculate next payment date
         let seconds_per_day: i64 = 86400;
         let cycle_seconds = (billing_cycle_days as i64) * seconds_per_day;
@@ -299,20 +792,41 @@ mod circuits {
             sub.next_payment_date = base_date + cycle_seconds;
         }
 
-        let user_state = UserLedgerState {
-            balance: new_user_bal,
-            subscription_count: user.subscription_count,
-        };
+        let charges = record_period_charge(charges, amount_due, can_pay);
+        let user_state = debit_yield_first(user, charged);
+        let pool = debit_user_liabilities(pool, charged);
 
         let merchant_state = MerchantLedgerState {
             balance: new_merchant_bal,
             total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen,
+        };
+
+        let cap_state = SpendingCapState {
+            cap: cap.cap,
+            window_seconds: cap.window_seconds,
+            window_start: cap.window_start,
+            spent: if can_pay { cap.spent + amount_due } else { cap.spent },
         };
 
+        // A failed renewal or a completed cancellation ends the subscription
+        let churned = (should_process && !can_pay) || ends_cancellation;
+        let stats = refresh_stats_period(stats, current_timestamp, stats_is_new);
+        let stats = record_churn(
+            stats,
+            monthly_revenue(plan_price, sub.quantity, billing_cycle_days),
+            churned,
+        );
+
         (
             user_ledger.owner.from_arcis(user_state),
             merchant_ledger.owner.from_arcis(merchant_state),
             subscription.owner.from_arcis(sub),
+            spending_cap.owner.from_arcis(cap_state),
+            plan_stats.owner.from_arcis(stats),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+            Mxe::get().from_arcis(charges),
         )
     }
 
@@ -327,34 +841,42 @@ mod circuits {
         let sub = subscription.to_arcis();
 
         // Subscription is valid if:
-        // 1. Status is Active (0)
-        // 2. Not past the grace period (next_payment_date + some buffer)
+        // 1. Status is Active (0) and not past the grace period (next_payment_date + some buffer), or
+        // 2. Status is Cancelling (3) and still within the paid-through period
         let is_active = sub.status == 0;
+        let is_cancelling = sub.status == 3;
 
         // Allow some grace period (e.g., 3 days = 259200 seconds)
         let grace_period: i64 = 259200;
         let grace_deadline = sub.next_payment_date + grace_period;
         let not_expired = current_timestamp <= grace_deadline;
 
-        let is_valid = is_active && not_expired;
+        // No grace period once the user has asked to cancel
+        let is_paid_through = current_timestamp < sub.next_payment_date;
+
+        let is_valid = (is_active && not_expired) || (is_cancelling && is_paid_through);
 
         is_valid.reveal()
     }
 
     /// ClaimRevenue circuit: Merchant withdraws accumulated revenue
-    /// Input: merchant_ledger (encrypted), amount (plaintext), is_new (plaintext)
-    /// Output: updated merchant_ledger (encrypted) and actual_amount (revealed)
+    /// Input: merchant_ledger (encrypted), total_liabilities (MXE), amount (plaintext), flags (plaintext)
+    /// Output: updated merchant_ledger (encrypted), total_liabilities (MXE) and actual_amount (revealed)
     #[instruction]
     pub fn claim_revenue_v2(
         merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        total_liabilities: Enc<Mxe, u64>,
         amount: u64,
         is_new: bool,
-    ) -> (Enc<Shared, MerchantLedgerState>, u64) {
+        liabilities_is_new: bool,
+    ) -> (Enc<Shared, MerchantLedgerState>, Enc<Mxe, u64>, u64) {
         let mut merchant = merchant_ledger.to_arcis();
+        let liabilities = if liabilities_is_new { 0u64 } else { total_liabilities.to_arcis() };
 
         if is_new {
             merchant.balance = 0;
             merchant.total_claimed = 0;
+            merchant.frozen = 0;
         }
 
         // Check if merchant has sufficient balance
@@ -377,11 +899,1354 @@ mod circuits {
             } else {
                 merchant.total_claimed
             },
+            frozen: merchant.frozen,
         };
 
         (
             merchant_ledger.owner.from_arcis(new_state),
+            // Saturate so an out-of-sync total cannot wrap around
+            Mxe::get().from_arcis(liabilities - liabilities.min(actual_amount)),
             actual_amount.reveal(),
         )
     }
-}
+
+    /// ReactivateSubscription circuit: Re-activate a cancelled subscription in place
+    /// Input: ledgers + subscription + spending_cap + plan_stats (encrypted), pool_yield, yield_checkpoint,
+    ///        charge record (MXE), timestamps + plan metadata (plaintext)
+    /// Output: Updated ledgers + subscription + spending_cap + plan_stats (encrypted), yield_checkpoint,
+    ///         pool_yield and charge record (MXE)
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn reactivate_subscription_v2(
+        user_ledger: Enc<Shared, UserLedgerState>,
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        subscription: Enc<Shared, UserSubscriptionState>,
+        spending_cap: Enc<Shared, SpendingCapState>,
+        plan_stats: Enc<Shared, PlanStatsState>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        charges: Enc<Mxe, ChargeRecordState>,
+        current_timestamp: i64,
+        plan_price: u64,
+        billing_cycle_days: u32,
+        plan_pubkey: [u128; 2],
+        user_is_new: bool,
+        merchant_is_new: bool,
+        cap_is_new: bool,
+        stats_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+        charges_is_new: bool,
+    ) -> (
+        Enc<Shared, UserLedgerState>,
+        Enc<Shared, MerchantLedgerState>,
+        Enc<Shared, UserSubscriptionState>,
+        Enc<Shared, SpendingCapState>,
+        Enc<Shared, PlanStatsState>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+        Enc<Mxe, ChargeRecordState>,
+    ) {
+        let mut user = user_ledger.to_arcis();
+        let mut merchant = merchant_ledger.to_arcis();
+        let mut sub = subscription.to_arcis();
+        let cap = spending_cap.to_arcis();
+        let stats = plan_stats.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let charges = current_charges(charges.to_arcis(), charges_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
+
+        if user_is_new {
+            user.principal = 0;
+            user.subscription_count = 0;
+            user.accrued_yield = 0;
+            user.principal_locked = false;
+        }
+        let (user, pool) = settle_yield(user, checkpoint, pool);
+
+        if merchant_is_new {
+            merchant.balance = 0;
+            merchant.total_claimed = 0;
+            merchant.frozen = 0;
+        }
+
+        // Only a Cancelled (1) or CapExceeded (4) subscription can be reactivated
+        let is_cancelled = (sub.status == 1) | (sub.status == 4);
+
+        // Ensure the subscription plan matches the current plan account
+        let is_plan_match =
+            (sub.plan[0] == plan_pubkey[0]) & (sub.plan[1] == plan_pubkey[1]);
+
+        // The plan price is per seat
+        let amount_due = plan_price * (sub.quantity as u64);
+
+        // Check if user has sufficient funds for the current price (yield, then unlocked principal)
+        let has_balance = subscription_funds(&user) >= amount_due;
+
+        // The reactivation charge counts against the user's spending cap like a renewal
+        let cap = refresh_spending_window(cap, current_timestamp, cap_is_new);
+        let within_cap = is_within_spending_cap(&cap, amount_due);
+
+        let can_reactivate = is_cancelled & is_plan_match & has_balance & within_cap;
+        let charged = if can_reactivate { amount_due } else { 0u64 };
+
+        let new_merchant_bal = if can_reactivate {
+            merchant.balance + amount_due
+        } else {
+            merchant.balance
+        };
+
+        // Start a fresh billing cycle from now
+        let seconds_per_day: i64 = 86400;
+        let cycle_seconds = (billing_cycle_days as i64) * seconds_per_day;
+
+        if can_reactivate {
+            sub.status = 0u8;
+            sub.next_payment_date = current_timestamp + cycle_seconds;
+        }
+
+        let charges = record_period_charge(charges, amount_due, can_reactivate);
+        let user_state = debit_yield_first(user, charged);
+        let pool = debit_user_liabilities(pool, charged);
+
+        let merchant_state = MerchantLedgerState {
+            balance: new_merchant_bal,
+            total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen,
+        };
+
+        let cap_state = SpendingCapState {
+            cap: cap.cap,
+            window_seconds: cap.window_seconds,
+            window_start: cap.window_start,
+            spent: if can_reactivate { cap.spent + amount_due } else { cap.spent },
+        };
+
+        // A reactivated subscription counts as a new subscriber again
+        let stats = refresh_stats_period(stats, current_timestamp, stats_is_new);
+        let stats = record_start(
+            stats,
+            monthly_revenue(plan_price, sub.quantity, billing_cycle_days),
+            can_reactivate,
+        );
+
+        (
+            user_ledger.owner.from_arcis(user_state),
+            merchant_ledger.owner.from_arcis(merchant_state),
+            subscription.owner.from_arcis(sub),
+            spending_cap.owner.from_arcis(cap_state),
+            plan_stats.owner.from_arcis(stats),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+            Mxe::get().from_arcis(charges),
+        )
+    }
+
+    /// CancelAtPeriodEnd circuit: Schedule cancellation at the end of the paid cycle
+    /// Input: subscription (encrypted)
+    /// Output: updated subscription (encrypted) - Active (0) becomes Cancelling (3)
+    #[instruction]
+    pub fn cancel_at_period_end_v2(
+        subscription: Enc<Shared, UserSubscriptionState>,
+    ) -> Enc<Shared, UserSubscriptionState> {
+        let mut sub = subscription.to_arcis();
+
+        // next_payment_date is kept as the paid-through date
+        if sub.status == 0 {
+            sub.status = 3u8;
+        }
+
+        subscription.owner.from_arcis(sub)
+    }
+
+    /// ResumeSubscription circuit: Undo a pending cancellation
+    /// Input: subscription (encrypted), current_timestamp (plaintext)
+    /// Output: updated subscription (encrypted) - Cancelling (3) becomes Active (0)
+    #[instruction]
+    pub fn resume_subscription_v2(
+        subscription: Enc<Shared, UserSubscriptionState>,
+        current_timestamp: i64,
+    ) -> Enc<Shared, UserSubscriptionState> {
+        let mut sub = subscription.to_arcis();
+
+        // Only possible before the paid-through date is reached
+        let can_resume = (sub.status == 3) & (current_timestamp < sub.next_payment_date);
+        if can_resume {
+            sub.status = 0u8;
+        }
+
+        subscription.owner.from_arcis(sub)
+    }
+
+    /// Refund circuit: Merchant returns funds from its ledger to a subscriber's ledger
+    /// Input: ledgers + subscription + plan_stats (encrypted), amount (encrypted to merchant key),
+    ///        pool_yield, yield_checkpoint (MXE), timestamp + plan metadata + flags (plaintext)
+    /// Output: Updated ledgers + subscription + plan_stats (encrypted), yield_checkpoint and pool_yield (MXE)
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn refund_v2(
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        user_ledger: Enc<Shared, UserLedgerState>,
+        subscription: Enc<Shared, UserSubscriptionState>,
+        plan_stats: Enc<Shared, PlanStatsState>,
+        amount: Enc<Shared, u64>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        current_timestamp: i64,
+        plan_pubkey: [u128; 2],
+        plan_price: u64,
+        billing_cycle_days: u32,
+        cancel_subscription: bool,
+        merchant_is_new: bool,
+        user_is_new: bool,
+        stats_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+    ) -> (
+        Enc<Shared, MerchantLedgerState>,
+        Enc<Shared, UserLedgerState>,
+        Enc<Shared, UserSubscriptionState>,
+        Enc<Shared, PlanStatsState>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+    ) {
+        let mut merchant = merchant_ledger.to_arcis();
+        let mut user = user_ledger.to_arcis();
+        let mut sub = subscription.to_arcis();
+        let stats = plan_stats.to_arcis();
+        let refund_amount = amount.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
+
+        if merchant_is_new {
+            merchant.balance = 0;
+            merchant.total_claimed = 0;
+            merchant.frozen = 0;
+        }
+
+        if user_is_new {
+            user.principal = 0;
+            user.subscription_count = 0;
+            user.accrued_yield = 0;
+            user.principal_locked = false;
+        }
+        let (user, pool) = settle_yield(user, checkpoint, pool);
+
+        // Refunds are only allowed against a subscription to one of the merchant's plans
+        let is_plan_match =
+            (sub.plan[0] == plan_pubkey[0]) & (sub.plan[1] == plan_pubkey[1]);
+
+        // Check if merchant has sufficient balance
+        let has_balance = merchant.balance >= refund_amount;
+
+        let can_refund = is_plan_match & has_balance;
+
+        let new_merchant_bal = if can_refund {
+            merchant.balance - refund_amount
+        } else {
+            merchant.balance
+        };
+
+        let new_user_principal = if can_refund {
+            user.principal + refund_amount
+        } else {
+            user.principal
+        };
+        let mut pool = pool;
+        if can_refund {
+            pool.user_liabilities += refund_amount;
+        }
+
+        // Cancelling a paying subscription counts as churn, as in unsubscribe
+        let was_active = (sub.status == 0) | (sub.status == 3);
+        let cancels = can_refund & cancel_subscription;
+        let stats = refresh_stats_period(stats, current_timestamp, stats_is_new);
+        let stats = record_churn(
+            stats,
+            monthly_revenue(plan_price, sub.quantity, billing_cycle_days),
+            cancels & was_active,
+        );
+
+        if cancels {
+            sub.status = 1u8;
+            sub.next_payment_date = 0;
+        }
+
+        let merchant_state = MerchantLedgerState {
+            balance: new_merchant_bal,
+            total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen,
+        };
+
+        let user_state = UserLedgerState {
+            principal: new_user_principal,
+            subscription_count: user.subscription_count,
+            accrued_yield: user.accrued_yield,
+            principal_locked: user.principal_locked,
+        };
+
+        (
+            merchant_ledger.owner.from_arcis(merchant_state),
+            user_ledger.owner.from_arcis(user_state),
+            subscription.owner.from_arcis(sub),
+            plan_stats.owner.from_arcis(stats),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+        )
+    }
+
+    /// OpenDispute circuit: Freeze a disputed charge inside the merchant ledger
+    /// Input: merchant_ledger + subscription (encrypted), amount (encrypted to user key),
+    ///        charge record (MXE), plan metadata and disputed period (plaintext)
+    /// Output: Updated merchant_ledger (encrypted), frozen amount (encrypted to the MXE)
+    ///         and whether anything was frozen (revealed)
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn open_dispute_v2(
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        subscription: Enc<Shared, UserSubscriptionState>,
+        amount: Enc<Shared, u64>,
+        charges: Enc<Mxe, ChargeRecordState>,
+        plan_pubkey: [u128; 2],
+        billing_cycle_days: u32,
+        billing_period: u64,
+        merchant_is_new: bool,
+        charges_is_new: bool,
+    ) -> (Enc<Shared, MerchantLedgerState>, Enc<Mxe, u64>, bool) {
+        let mut merchant = merchant_ledger.to_arcis();
+        let sub = subscription.to_arcis();
+        let disputed_amount = amount.to_arcis();
+        let charges = current_charges(charges.to_arcis(), charges_is_new);
+
+        if merchant_is_new {
+            merchant.balance = 0;
+            merchant.total_claimed = 0;
+            merchant.frozen = 0;
+        }
+
+        // The subscription must belong to the plan
+        let is_plan_match =
+            (sub.plan[0] == plan_pubkey[0]) & (sub.plan[1] == plan_pubkey[1]);
+
+        // Only a period that was charged can be disputed: period k starts at
+        // start_date + k cycles and was paid once next_payment_date moved past it.
+        // A failed subscribe leaves start_date at 0.
+        let seconds_per_day: i64 = 86400;
+        let cycle_seconds = (billing_cycle_days as i64) * seconds_per_day;
+        let period_start = sub.start_date + (billing_period as i64) * cycle_seconds;
+        let was_charged = (sub.start_date != 0) & (period_start < sub.next_payment_date);
+
+        // The amount cannot exceed what was charged for the period: the latest period's
+        // charge is recorded, earlier ones are capped by the smallest recorded charge
+        let is_latest_period = period_start + cycle_seconds == sub.next_payment_date;
+        let period_charge = if is_latest_period { charges.last_charge } else { charges.min_charge };
+        let is_within_charge = disputed_amount <= period_charge;
+
+        // Revenue that was already claimed cannot be frozen
+        let has_balance = merchant.balance >= disputed_amount;
+
+        let can_freeze = is_plan_match
+            & is_within_charge
+            & was_charged
+            & has_balance
+            & (disputed_amount > 0);
+        let frozen_amount = if can_freeze { disputed_amount } else { 0u64 };
+
+        let merchant_state = MerchantLedgerState {
+            balance: merchant.balance - frozen_amount,
+            total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen + frozen_amount,
+        };
+
+        (
+            merchant_ledger.owner.from_arcis(merchant_state),
+            Mxe::get().from_arcis(frozen_amount),
+            can_freeze.reveal(),
+        )
+    }
+
+    /// ResolveDisputeRefund circuit: Arbiter returns the frozen amount to the user
+    /// Input: ledgers (encrypted), disputed_amount, pool_yield, yield_checkpoint (MXE), flags (plaintext)
+    /// Output: Updated ledgers (encrypted), yield_checkpoint and pool_yield (MXE)
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn resolve_dispute_refund_v2(
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        user_ledger: Enc<Shared, UserLedgerState>,
+        disputed_amount: Enc<Mxe, u64>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        user_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+    ) -> (
+        Enc<Shared, MerchantLedgerState>,
+        Enc<Shared, UserLedgerState>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+    ) {
+        let merchant = merchant_ledger.to_arcis();
+        let mut user = user_ledger.to_arcis();
+        let amount = disputed_amount.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
+
+        if user_is_new {
+            user.principal = 0;
+            user.subscription_count = 0;
+            user.accrued_yield = 0;
+            user.principal_locked = false;
+        }
+        let (user, pool) = settle_yield(user, checkpoint, pool);
+
+        let released = if merchant.frozen >= amount { amount } else { merchant.frozen };
+
+        let merchant_state = MerchantLedgerState {
+            balance: merchant.balance,
+            total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen - released,
+        };
+
+        let user_state = UserLedgerState {
+            principal: user.principal + released,
+            subscription_count: user.subscription_count,
+            accrued_yield: user.accrued_yield,
+            principal_locked: user.principal_locked,
+        };
+        let mut pool = pool;
+        pool.user_liabilities += released;
+
+        (
+            merchant_ledger.owner.from_arcis(merchant_state),
+            user_ledger.owner.from_arcis(user_state),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+        )
+    }
+
+    /// ResolveDisputeRelease circuit: Arbiter releases the frozen amount to the merchant
+    /// Input: merchant_ledger (encrypted), disputed_amount (encrypted to the MXE)
+    /// Output: Updated merchant_ledger (encrypted)
+    #[instruction]
+    pub fn resolve_dispute_release_v2(
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        disputed_amount: Enc<Mxe, u64>,
+    ) -> Enc<Shared, MerchantLedgerState> {
+        let merchant = merchant_ledger.to_arcis();
+        let amount = disputed_amount.to_arcis();
+
+        let released = if merchant.frozen >= amount { amount } else { merchant.frozen };
+
+        let merchant_state = MerchantLedgerState {
+            balance: merchant.balance + released,
+            total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen - released,
+        };
+
+        merchant_ledger.owner.from_arcis(merchant_state)
+    }
+
+    /// SetSpendingCap circuit: Set the user's spending cap for one merchant
+    /// Input: spending_cap (encrypted), cap/window_days (encrypted), current_timestamp + is_new (plaintext)
+    /// Output: updated spending_cap (encrypted) and whether the window was valid (revealed)
+    /// A window outside 1..=MAX_SPENDING_WINDOW_DAYS leaves the cap untouched.
+    #[instruction]
+    pub fn set_spending_cap_v2(
+        spending_cap: Enc<Shared, SpendingCapState>,
+        cap: Enc<Shared, u64>,
+        window_days: Enc<Shared, u32>,
+        current_timestamp: i64,
+        is_new: bool,
+    ) -> (Enc<Shared, SpendingCapState>, bool) {
+        let mut state = spending_cap.to_arcis();
+        let new_cap = cap.to_arcis();
+        let new_window_days = window_days.to_arcis();
+
+        if is_new {
+            state.cap = u64::MAX;
+            state.window_seconds = 0;
+            state.window_start = current_timestamp;
+            state.spent = 0;
+        }
+
+        // A zero-length window restarts on every charge and would cap nothing.
+        // Arcis has no `RangeInclusive::contains`, so the bounds are compared directly.
+        #[allow(clippy::manual_range_contains)]
+        let is_valid_window = (new_window_days >= 1) & (new_window_days <= MAX_SPENDING_WINDOW_DAYS);
+
+        // The running window is kept so that changing the cap cannot reset what was spent
+        let seconds_per_day: i64 = 86400;
+        if is_valid_window {
+            state.cap = new_cap;
+            state.window_seconds = (new_window_days as i64) * seconds_per_day;
+        }
+
+        (spending_cap.owner.from_arcis(state), is_valid_window.reveal())
+    }
+
+    /// Balance runway circuit: Estimate how long the user's balance lasts
+    ///
+    /// Takes up to RUNWAY_SLOTS subscriptions together with the plaintext
+    /// terms of the plans they reference. A slot is only counted when it is
+    /// used, Active, and its encrypted plan matches the supplied plan pubkey.
+    /// Upcoming payments are replayed in date order against the balance to
+    /// find the first one that would fail.
+    #[instruction]
+    // Circuit parameters are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments)]
+    pub fn balance_runway_v2(
+        user_ledger: Enc<Shared, UserLedgerState>,
+        subscription_0: Enc<Shared, UserSubscriptionState>,
+        subscription_1: Enc<Shared, UserSubscriptionState>,
+        subscription_2: Enc<Shared, UserSubscriptionState>,
+        subscription_3: Enc<Shared, UserSubscriptionState>,
+        plan_pubkeys: [[u128; 2]; RUNWAY_SLOTS],
+        plan_prices: [u64; RUNWAY_SLOTS],
+        plan_billing_cycle_days: [u32; RUNWAY_SLOTS],
+        slot_used: [bool; RUNWAY_SLOTS],
+    ) -> Enc<Shared, BalanceRunway> {
+        let ledger = user_ledger.to_arcis();
+        let subscriptions = [
+            subscription_0.to_arcis(),
+            subscription_1.to_arcis(),
+            subscription_2.to_arcis(),
+            subscription_3.to_arcis(),
+        ];
+
+        let seconds_per_day: i64 = 86400;
+        let mut active = [false; RUNWAY_SLOTS];
+        let mut due_dates = [0i64; RUNWAY_SLOTS];
+        let mut cycle_seconds = [0i64; RUNWAY_SLOTS];
+        let mut charges = [0u64; RUNWAY_SLOTS];
+        let mut monthly_spend: u64 = 0;
+
+        for i in 0..RUNWAY_SLOTS {
+            let sub = &subscriptions[i];
+            // Unused slots carry a zero cycle; clamp so the division stays defined
+            let cycle_days = if plan_billing_cycle_days[i] == 0 { 1 } else { plan_billing_cycle_days[i] };
+            let is_active = slot_used[i]
+                && sub.status == 0
+                && sub.plan[0] == plan_pubkeys[i][0]
+                && sub.plan[1] == plan_pubkeys[i][1];
+
+            active[i] = is_active;
+            due_dates[i] = sub.next_payment_date;
+            cycle_seconds[i] = (cycle_days as i64) * seconds_per_day;
+            charges[i] = plan_prices[i] * (sub.quantity as u64);
+
+            if is_active {
+                monthly_spend += charges[i] * 30 / (cycle_days as u64);
+            }
+        }
+
+        // Renewals can only draw on yield and, unless locked, principal
+        let mut balance = subscription_funds(&ledger);
+        let mut failed = false;
+        let mut first_failure_date: i64 = 0;
+
+        for _ in 0..RUNWAY_STEPS {
+            let mut earliest = i64::MAX;
+            for i in 0..RUNWAY_SLOTS {
+                if active[i] && due_dates[i] < earliest {
+                    earliest = due_dates[i];
+                }
+            }
+
+            // Charge exactly one payment per step, the earliest one due
+            let mut charged = false;
+            for i in 0..RUNWAY_SLOTS {
+                if !charged && active[i] && due_dates[i] == earliest {
+                    charged = true;
+                    if !failed {
+                        if balance >= charges[i] {
+                            balance -= charges[i];
+                        } else {
+                            failed = true;
+                            first_failure_date = due_dates[i];
+                        }
+                    }
+                    due_dates[i] += cycle_seconds[i];
+                }
+            }
+        }
+
+        let result = BalanceRunway {
+            monthly_spend,
+            first_failure_date,
+        };
+
+        user_ledger.owner.from_arcis(result)
+    }
+
+    /// Solvency check circuit: Compare total liabilities against the pool balance
+    /// Input: total_liabilities (MXE), pool_balance (plaintext), liabilities_is_new (plaintext)
+    /// Output: whether liabilities <= pool_balance (revealed); nothing else is disclosed
+    #[instruction]
+    pub fn check_solvency_v2(
+        total_liabilities: Enc<Mxe, u64>,
+        pool_balance: u64,
+        liabilities_is_new: bool,
+    ) -> bool {
+        let liabilities = if liabilities_is_new { 0u64 } else { total_liabilities.to_arcis() };
+
+        (liabilities <= pool_balance).reveal()
+    }
+
+    /// Liabilities seeding circuit: Start tracking liabilities for a pool created before they existed
+    /// Input: amount held by the pool (plaintext)
+    /// Output: total_liabilities and pool_yield (MXE)
+    /// User and merchant balances cannot be told apart in the pool balance, so all of it
+    /// starts out as user balances. Yield on the merchant part is counted in liabilities
+    /// without ever being settled: a seeded pool's liabilities err on the high side, and
+    /// users are still never credited more than was harvested.
+    #[instruction]
+    pub fn seed_liabilities_v2(amount: u64) -> (Enc<Mxe, u64>, Enc<Mxe, PoolYieldState>) {
+        let pool = PoolYieldState {
+            index: 0,
+            user_liabilities: amount,
+        };
+
+        (Mxe::get().from_arcis(amount), Mxe::get().from_arcis(pool))
+    }
+
+    /// Transfer circuit: Move an encrypted amount from a user ledger into another ledger's pending transfers
+    /// Input: sender_ledger (encrypted), amount (encrypted, sender key), pool_yield, sender yield checkpoint
+    ///        and the recipient's pending transfers (MXE), flags (plaintext)
+    /// Output: updated sender_ledger (encrypted), sender yield checkpoint, pool_yield and pending transfers (MXE)
+    /// Incoming funds wait until the recipient claims them, so a transfer never needs the recipient's key.
+    /// An insufficient sender balance leaves both ledgers unchanged, apart from settled yield.
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn transfer_v2(
+        sender_ledger: Enc<Shared, UserLedgerState>,
+        amount: Enc<Shared, u64>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        sender_checkpoint: Enc<Mxe, u128>,
+        recipient_pending: Enc<Mxe, u64>,
+        sender_is_new: bool,
+        yield_is_new: bool,
+        sender_checkpoint_is_new: bool,
+        pending_is_new: bool,
+    ) -> (
+        Enc<Shared, UserLedgerState>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+        Enc<Mxe, u64>,
+    ) {
+        let mut sender = sender_ledger.to_arcis();
+        let transfer_amount = amount.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let sender_from = if sender_checkpoint_is_new { pool.index } else { sender_checkpoint.to_arcis() };
+        let pending = if pending_is_new { 0u64 } else { recipient_pending.to_arcis() };
+
+        if sender_is_new {
+            sender.principal = 0;
+            sender.subscription_count = 0;
+            sender.accrued_yield = 0;
+            sender.principal_locked = false;
+        }
+        let (sender, pool) = settle_yield(sender, sender_from, pool);
+
+        let has_balance = total_balance(&sender) >= transfer_amount;
+        let actual_amount = if has_balance { transfer_amount } else { 0u64 };
+
+        // Pending transfers are still owed to users, so user liabilities only change by the settled yield
+        let new_sender = debit_yield_first(sender, actual_amount);
+
+        (
+            sender_ledger.owner.from_arcis(new_sender),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+            Mxe::get().from_arcis(pending + actual_amount),
+        )
+    }
+
+    /// Claim transfers circuit: Move a ledger's pending transfers into its principal
+    /// Input: user_ledger (encrypted), pending transfers, pool_yield, yield_checkpoint (MXE), flags (plaintext)
+    /// Output: updated user_ledger (encrypted), emptied pending transfers, yield_checkpoint and pool_yield (MXE)
+    /// Pending transfers earn no yield, so the ledger settles before the claimed funds arrive.
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn claim_transfers_v2(
+        user_ledger: Enc<Shared, UserLedgerState>,
+        pending: Enc<Mxe, u64>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        is_new: bool,
+        pending_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+    ) -> (
+        Enc<Shared, UserLedgerState>,
+        Enc<Mxe, u64>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+    ) {
+        let mut ledger = user_ledger.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
+        let incoming = if pending_is_new { 0u64 } else { pending.to_arcis() };
+
+        if is_new {
+            ledger.principal = 0;
+            ledger.subscription_count = 0;
+            ledger.accrued_yield = 0;
+            ledger.principal_locked = false;
+        }
+        let (mut ledger, pool) = settle_yield(ledger, checkpoint, pool);
+
+        // Claimed funds arrive as principal; they were counted in user liabilities since the transfer
+        ledger.principal += incoming;
+
+        (
+            user_ledger.owner.from_arcis(ledger),
+            Mxe::get().from_arcis(0u64),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+        )
+    }
+
+    /// Buy voucher circuit: Prepay a plan from the buyer's ledger as a gift voucher
+    /// Input: buyer_ledger, merchant_ledger (encrypted), voucher_pool (MXE), secret_hash (encrypted, buyer key),
+    ///        pool_yield, yield_checkpoint (MXE), amount and flags (plaintext)
+    /// Output: updated ledgers (encrypted), voucher_pool, yield_checkpoint and pool_yield (MXE), issued (revealed)
+    /// The merchant is paid at purchase; the buyer is only charged if the balance
+    /// covers it and the pool has a free slot. A zero hash marks a free slot and is refused.
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn buy_voucher_v2(
+        buyer_ledger: Enc<Shared, UserLedgerState>,
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        voucher_pool: Enc<Mxe, [u128; VOUCHER_POOL_SLOTS]>,
+        secret_hash: Enc<Shared, u128>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        amount: u64,
+        buyer_is_new: bool,
+        merchant_is_new: bool,
+        vouchers_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+    ) -> (
+        Enc<Shared, UserLedgerState>,
+        Enc<Shared, MerchantLedgerState>,
+        Enc<Mxe, [u128; VOUCHER_POOL_SLOTS]>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+        bool,
+    ) {
+        let mut buyer = buyer_ledger.to_arcis();
+        let mut merchant = merchant_ledger.to_arcis();
+        let mut slots = voucher_pool.to_arcis();
+        let hash = secret_hash.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
+
+        if buyer_is_new {
+            buyer.principal = 0;
+            buyer.subscription_count = 0;
+            buyer.accrued_yield = 0;
+            buyer.principal_locked = false;
+        }
+        let (buyer, pool) = settle_yield(buyer, checkpoint, pool);
+
+        if merchant_is_new {
+            merchant.balance = 0;
+            merchant.total_claimed = 0;
+            merchant.frozen = 0;
+        }
+
+        if vouchers_is_new {
+            slots = [0u128; VOUCHER_POOL_SLOTS];
+        }
+
+        let can_issue = (total_balance(&buyer) >= amount) & (hash != 0);
+
+        // Place the voucher in the first free slot
+        let mut issued = false;
+        for slot in slots.iter_mut() {
+            if can_issue & !issued & (*slot == 0) {
+                *slot = hash;
+                issued = true;
+            }
+        }
+
+        let charged = if issued { amount } else { 0u64 };
+        let buyer_state = debit_yield_first(buyer, charged);
+        let pool = debit_user_liabilities(pool, charged);
+
+        let merchant_state = MerchantLedgerState {
+            balance: merchant.balance + charged,
+            total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen,
+        };
+
+        (
+            buyer_ledger.owner.from_arcis(buyer_state),
+            merchant_ledger.owner.from_arcis(merchant_state),
+            Mxe::get().from_arcis(slots),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+            issued.reveal(),
+        )
+    }
+
+    /// Redeem voucher circuit: Turn a pooled voucher into a prepaid subscription
+    /// Input: voucher_pool (MXE), recipient_ledger + plan_stats (encrypted), secret_hash (encrypted, recipient key),
+    ///        pool terms, plan price and flags (plaintext)
+    /// Output: voucher_pool (MXE), recipient_ledger, subscription and plan_stats (encrypted),
+    ///         charge record (MXE), redeemed (revealed)
+    /// Which slot matched is never revealed, so a redemption cannot be tied to a purchase.
+    /// The billing cycle length and cycle count are the pool's, fixed when its vouchers were bought.
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn redeem_voucher_v2(
+        voucher_pool: Enc<Mxe, [u128; VOUCHER_POOL_SLOTS]>,
+        recipient_ledger: Enc<Shared, UserLedgerState>,
+        plan_stats: Enc<Shared, PlanStatsState>,
+        secret_hash: Enc<Shared, u128>,
+        current_timestamp: i64,
+        plan_pubkey: [u128; 2],
+        plan_price: u64,
+        billing_cycle_days: u32,
+        cycles: u32,
+        recipient_is_new: bool,
+        stats_is_new: bool,
+    ) -> (
+        Enc<Mxe, [u128; VOUCHER_POOL_SLOTS]>,
+        Enc<Shared, UserLedgerState>,
+        Enc<Shared, UserSubscriptionState>,
+        Enc<Shared, PlanStatsState>,
+        Enc<Mxe, ChargeRecordState>,
+        bool,
+    ) {
+        let mut slots = voucher_pool.to_arcis();
+        let mut recipient = recipient_ledger.to_arcis();
+        let stats = plan_stats.to_arcis();
+        let hash = secret_hash.to_arcis();
+
+        if recipient_is_new {
+            recipient.principal = 0;
+            recipient.subscription_count = 0;
+            recipient.accrued_yield = 0;
+            recipient.principal_locked = false;
+        }
+
+        // Free slots hold zero, which no redemption may claim
+        let mut redeemed = false;
+        for slot in slots.iter_mut() {
+            if (hash != 0) & !redeemed & (*slot == hash) {
+                *slot = 0;
+                redeemed = true;
+            }
+        }
+
+        // The voucher prepays `cycles` billing periods
+        let seconds_per_day: i64 = 86400;
+        let prepaid_seconds = (billing_cycle_days as i64) * (cycles as i64) * seconds_per_day;
+
+        let recipient_state = UserLedgerState {
+            principal: recipient.principal,
+            subscription_count: if redeemed {
+                recipient.subscription_count + 1
+            } else {
+                recipient.subscription_count
+            },
+            accrued_yield: recipient.accrued_yield,
+            principal_locked: recipient.principal_locked,
+        };
+
+        // Status: 0 = Active, 1 = Cancelled (secret did not match)
+        let subscription_state = UserSubscriptionState {
+            plan: plan_pubkey,
+            status: if redeemed { 0 } else { 1 },
+            next_payment_date: if redeemed { current_timestamp + prepaid_seconds } else { 0 },
+            start_date: if redeemed { current_timestamp } else { 0 },
+            quantity: 1,
+        };
+
+        let stats = refresh_stats_period(stats, current_timestamp, stats_is_new);
+        let stats = record_start(
+            stats,
+            monthly_revenue(plan_price, 1, billing_cycle_days),
+            redeemed,
+        );
+
+        // The prepaid periods were paid by the buyer, so no period is charged yet
+        let charges = ChargeRecordState {
+            last_charge: 0,
+            min_charge: NO_CHARGE,
+        };
+
+        let subscription_owner = Shared::new(recipient_ledger.owner.public_key);
+
+        (
+            Mxe::get().from_arcis(slots),
+            recipient_ledger.owner.from_arcis(recipient_state),
+            subscription_owner.from_arcis(subscription_state),
+            plan_stats.owner.from_arcis(stats),
+            Mxe::get().from_arcis(charges),
+            redeemed.reveal(),
+        )
+    }
+
+    /// UpdateSeats circuit: Change the seat count of an active subscription with proration
+    /// Input: ledgers + subscription + spending_cap + plan_stats (encrypted), new_quantity (encrypted),
+    ///        pool_yield, yield_checkpoint, charge record (MXE), timestamps + plan metadata + assigned seat count (plaintext)
+    /// Output: Updated ledgers + subscription + spending_cap + plan_stats (encrypted), yield_checkpoint,
+    ///         pool_yield and charge record (MXE)
+    /// Added seats are charged for the rest of the current period; removed seats
+    /// are credited back for the same remainder. Invalid changes leave all state untouched.
+    #[instruction]
+    // Circuit parameters and outputs are the MPC interface the program's ArgBuilder mirrors
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn update_seats_v2(
+        user_ledger: Enc<Shared, UserLedgerState>,
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        subscription: Enc<Shared, UserSubscriptionState>,
+        spending_cap: Enc<Shared, SpendingCapState>,
+        plan_stats: Enc<Shared, PlanStatsState>,
+        new_quantity: Enc<Shared, u32>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        charges: Enc<Mxe, ChargeRecordState>,
+        current_timestamp: i64,
+        plan_price: u64,
+        billing_cycle_days: u32,
+        plan_pubkey: [u128; 2],
+        seats_assigned: u32,
+        user_is_new: bool,
+        merchant_is_new: bool,
+        cap_is_new: bool,
+        stats_is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+        charges_is_new: bool,
+    ) -> (
+        Enc<Shared, UserLedgerState>,
+        Enc<Shared, MerchantLedgerState>,
+        Enc<Shared, UserSubscriptionState>,
+        Enc<Shared, SpendingCapState>,
+        Enc<Shared, PlanStatsState>,
+        Enc<Mxe, u128>,
+        Enc<Mxe, PoolYieldState>,
+        Enc<Mxe, ChargeRecordState>,
+    ) {
+        let mut user = user_ledger.to_arcis();
+        let mut merchant = merchant_ledger.to_arcis();
+        let mut sub = subscription.to_arcis();
+        let cap = spending_cap.to_arcis();
+        let stats = plan_stats.to_arcis();
+        let seats = new_quantity.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let mut charges = current_charges(charges.to_arcis(), charges_is_new);
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
+
+        if user_is_new {
+            user.principal = 0;
+            user.subscription_count = 0;
+            user.accrued_yield = 0;
+            user.principal_locked = false;
+        }
+        let (user, pool) = settle_yield(user, checkpoint, pool);
+
+        if merchant_is_new {
+            merchant.balance = 0;
+            merchant.total_claimed = 0;
+            merchant.frozen = 0;
+        }
+
+        let is_active = sub.status == 0;
+        let is_plan_match =
+            (sub.plan[0] == plan_pubkey[0]) & (sub.plan[1] == plan_pubkey[1]);
+
+        // Seats already handed to members cannot be removed
+        #[allow(clippy::manual_range_contains)]
+        let is_valid_seats = (seats >= 1) & (seats <= MAX_SEATS) & (seats >= seats_assigned);
+
+        // Prorate over the time left until the next payment
+        let seconds_per_day: i64 = 86400;
+        let cycle_seconds = (billing_cycle_days as i64) * seconds_per_day;
+        let until_due = sub.next_payment_date - current_timestamp;
+        let remaining = if until_due <= 0 {
+            0
+        } else if until_due > cycle_seconds {
+            cycle_seconds
+        } else {
+            until_due
+        };
+
+        let is_increase = seats > sub.quantity;
+        let seat_delta = if is_increase { seats - sub.quantity } else { sub.quantity - seats };
+        let prorated = ((plan_price as u128) * (seat_delta as u128) * (remaining as u128)
+            / (cycle_seconds as u128)) as u64;
+
+        // Added seats are a charge: check balance and the spending cap
+        let cap = refresh_spending_window(cap, current_timestamp, cap_is_new);
+        let can_charge = (subscription_funds(&user) >= prorated) & is_within_spending_cap(&cap, prorated);
+
+        // Removed seats are a credit: the merchant must still hold the revenue
+        let can_credit = merchant.balance >= prorated;
+
+        let can_update = is_active
+            & is_plan_match
+            & is_valid_seats
+            & (if is_increase { can_charge } else { can_credit });
+
+        let charged = if can_update & is_increase { prorated } else { 0u64 };
+        let credited = if can_update & !is_increase { prorated } else { 0u64 };
+
+        let stats = refresh_stats_period(stats, current_timestamp, stats_is_new);
+        let stats = record_seat_change(
+            stats,
+            monthly_revenue(plan_price, sub.quantity, billing_cycle_days),
+            monthly_revenue(plan_price, seats, billing_cycle_days),
+            can_update,
+        );
+
+        if can_update {
+            sub.quantity = seats;
+        }
+
+        // Charges spend yield first; credits for removed seats return as principal
+        let mut user_state = debit_yield_first(user, charged);
+        user_state.principal += credited;
+        let mut pool = debit_user_liabilities(pool, charged);
+        pool.user_liabilities += credited;
+
+        // The current period now cost what was charged for it plus or minus the change
+        charges.last_charge = charges.last_charge + charged - charges.last_charge.min(credited);
+        if (credited > 0) & (charges.min_charge != NO_CHARGE) {
+            charges.min_charge = charges.min_charge.min(charges.last_charge);
+        }
+
+        let merchant_state = MerchantLedgerState {
+            balance: merchant.balance + charged - credited,
+            total_claimed: merchant.total_claimed,
+            frozen: merchant.frozen,
+        };
+
+        let cap_state = SpendingCapState {
+            cap: cap.cap,
+            window_seconds: cap.window_seconds,
+            window_start: cap.window_start,
+            spent: cap.spent + charged,
+        };
+
+        (
+            user_ledger.owner.from_arcis(user_state),
+            merchant_ledger.owner.from_arcis(merchant_state),
+            subscription.owner.from_arcis(sub),
+            spending_cap.owner.from_arcis(cap_state),
+            plan_stats.owner.from_arcis(stats),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+            Mxe::get().from_arcis(charges),
+        )
+    }
+
+    /// AssignSeat circuit: Check that a subscription has a free seat
+    /// Input: subscription (encrypted), plan_pubkey and seat index being reserved (plaintext)
+    /// Output: whether the seat can be assigned (bool, revealed)
+    #[instruction]
+    pub fn assign_seat_v2(
+        subscription: Enc<Shared, UserSubscriptionState>,
+        plan_pubkey: [u128; 2],
+        seat_index: u32,
+    ) -> bool {
+        let sub = subscription.to_arcis();
+
+        // Active or Cancelling subscriptions still grant access
+        let grants_access = (sub.status == 0) | (sub.status == 3);
+        let is_plan_match =
+            (sub.plan[0] == plan_pubkey[0]) & (sub.plan[1] == plan_pubkey[1]);
+        let has_free_seat = seat_index < sub.quantity;
+
+        (grants_access & is_plan_match & has_free_seat).reveal()
+    }
+
+    /// Scheduled payout: claim the merchant's balance, up to max_amount, once it reaches the threshold
+    #[instruction]
+    pub fn scheduled_payout_v2(
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+        total_liabilities: Enc<Mxe, u64>,
+        min_threshold: u64,
+        max_amount: u64,
+        is_new: bool,
+        liabilities_is_new: bool,
+    ) -> (Enc<Shared, MerchantLedgerState>, Enc<Mxe, u64>, u64) {
+        let mut merchant = merchant_ledger.to_arcis();
+        let liabilities = if liabilities_is_new { 0u64 } else { total_liabilities.to_arcis() };
+
+        if is_new {
+            merchant.balance = 0;
+            merchant.total_claimed = 0;
+            merchant.frozen = 0;
+        }
+
+        // Frozen funds are already excluded from balance, so the whole balance is claimable
+        let meets_threshold = merchant.balance >= min_threshold && merchant.balance > 0;
+        let capped = if merchant.balance > max_amount { max_amount } else { merchant.balance };
+        let actual_amount = if meets_threshold { capped } else { 0u64 };
+
+        let new_state = MerchantLedgerState {
+            balance: merchant.balance - actual_amount,
+            total_claimed: merchant.total_claimed + actual_amount,
+            frozen: merchant.frozen,
+        };
+
+        (
+            merchant_ledger.owner.from_arcis(new_state),
+            // Saturate so an out-of-sync total cannot wrap around
+            Mxe::get().from_arcis(liabilities - liabilities.min(actual_amount)),
+            actual_amount.reveal(),
+        )
+    }
+
+    /// Public plan statistics: reveal the active subscriber count only when it is at least k
+    /// Input: plan_stats (encrypted, merchant key), k (plaintext)
+    /// Output: revealed subscriber count, or 0 for the "fewer than k" bucket
+    #[instruction]
+    pub fn publish_plan_stats_v2(
+        plan_stats: Enc<Shared, PlanStatsState>,
+        k: u64,
+    ) -> u64 {
+        let stats = plan_stats.to_arcis();
+
+        let count = if stats.active_subscribers >= k { stats.active_subscribers } else { 0 };
+        count.reveal()
+    }
+
+    /// Disclose a user ledger to an auditor by re-encrypting it to the auditor key
+    /// Input: auditor (receiver key), user_ledger (encrypted, owner key)
+    /// Output: user_ledger (encrypted to the auditor key)
+    #[instruction]
+    pub fn disclose_user_ledger_v2(
+        auditor: Shared,
+        user_ledger: Enc<Shared, UserLedgerState>,
+    ) -> Enc<Shared, UserLedgerState> {
+        auditor.from_arcis(user_ledger.to_arcis())
+    }
+
+    /// Disclose a merchant ledger to an auditor by re-encrypting it to the auditor key
+    /// Input: auditor (receiver key), merchant_ledger (encrypted, owner key)
+    /// Output: merchant_ledger (encrypted to the auditor key)
+    #[instruction]
+    pub fn disclose_merchant_ledger_v2(
+        auditor: Shared,
+        merchant_ledger: Enc<Shared, MerchantLedgerState>,
+    ) -> Enc<Shared, MerchantLedgerState> {
+        auditor.from_arcis(merchant_ledger.to_arcis())
+    }
+
+    /// Disclose a subscription record to an auditor by re-encrypting it to the auditor key
+    /// Input: auditor (receiver key), subscription (encrypted, owner key)
+    /// Output: subscription (encrypted to the auditor key)
+    #[instruction]
+    pub fn disclose_subscription_v2(
+        auditor: Shared,
+        subscription: Enc<Shared, UserSubscriptionState>,
+    ) -> Enc<Shared, UserSubscriptionState> {
+        auditor.from_arcis(subscription.to_arcis())
+    }
+
+    /// Yield distribution: spread harvested strategy yield over user balances pro rata
+    /// Input: pool_yield, total_liabilities (MXE), accrued (plaintext), flags (plaintext)
+    /// Output: updated pool_yield and total_liabilities (MXE); ledgers collect their share when they settle
+    #[instruction]
+    pub fn distribute_yield_v2(
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        total_liabilities: Enc<Mxe, u64>,
+        accrued: u64,
+        yield_is_new: bool,
+        liabilities_is_new: bool,
+    ) -> (Enc<Mxe, PoolYieldState>, Enc<Mxe, u64>) {
+        let mut pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        let liabilities = if liabilities_is_new { 0u64 } else { total_liabilities.to_arcis() };
+
+        // Only user balances earn yield, so the index is spread over settled user
+        // balances and merchant balances do not dilute it. What the users are owed
+        // joins liabilities up front, rounded up so it never falls short of what
+        // their settlements credit. With no user balances the index stays put and
+        // the yield stays outside liabilities as pool surplus.
+        let has_users = pool.user_liabilities > 0;
+        let divisor = if has_users { pool.user_liabilities } else { 1u64 };
+        let index_delta = if has_users {
+            (accrued as u128) * YIELD_INDEX_SCALE / (divisor as u128)
+        } else {
+            0u128
+        };
+        // Arcis lowers `div_ceil` differently from Rust, so round up by hand
+        #[allow(clippy::manual_div_ceil)]
+        let owed = (((pool.user_liabilities as u128) * index_delta + YIELD_INDEX_SCALE - 1)
+            / YIELD_INDEX_SCALE) as u64;
+
+        pool.index += index_delta;
+
+        (
+            Mxe::get().from_arcis(pool),
+            Mxe::get().from_arcis(liabilities + owed),
+        )
+    }
+
+    /// Yield settlement: credit a user ledger with its pro-rata share of distributed yield
+    /// Distributed yield is already in liabilities, so only the ledger, its checkpoint
+    /// and the pool's settled user balances change.
+    /// Input: user_ledger (encrypted), pool_yield, yield_checkpoint (MXE), flags (plaintext)
+    /// Output: updated user_ledger (encrypted), yield_checkpoint and pool_yield (MXE)
+    #[instruction]
+    pub fn settle_yield_v2(
+        user_ledger: Enc<Shared, UserLedgerState>,
+        pool_yield: Enc<Mxe, PoolYieldState>,
+        yield_checkpoint: Enc<Mxe, u128>,
+        is_new: bool,
+        yield_is_new: bool,
+        checkpoint_is_new: bool,
+    ) -> (Enc<Shared, UserLedgerState>, Enc<Mxe, u128>, Enc<Mxe, PoolYieldState>) {
+        let mut ledger = user_ledger.to_arcis();
+        let pool = current_pool_yield(pool_yield.to_arcis(), yield_is_new);
+        // A ledger that has never settled starts earning from the current index
+        let checkpoint = if checkpoint_is_new { pool.index } else { yield_checkpoint.to_arcis() };
+
+        if is_new {
+            ledger.principal = 0;
+            ledger.subscription_count = 0;
+            ledger.accrued_yield = 0;
+            ledger.principal_locked = false;
+        }
+        let (ledger, pool) = settle_yield(ledger, checkpoint, pool);
+
+        (
+            user_ledger.owner.from_arcis(ledger),
+            Mxe::get().from_arcis(pool.index),
+            Mxe::get().from_arcis(pool),
+        )
+    }
+
+    /// Principal lock: choose whether subscriptions may spend principal or only accrued yield
+    /// Input: user_ledger (encrypted), locked (encrypted, user key), is_new (plaintext)
+    /// Output: updated user_ledger (encrypted)
+    #[instruction]
+    pub fn set_principal_lock_v2(
+        user_ledger: Enc<Shared, UserLedgerState>,
+        locked: Enc<Shared, bool>,
+        is_new: bool,
+    ) -> Enc<Shared, UserLedgerState> {
+        let mut ledger = user_ledger.to_arcis();
+
+        if is_new {
+            ledger.principal = 0;
+            ledger.subscription_count = 0;
+            ledger.accrued_yield = 0;
+            ledger.principal_locked = false;
+        }
+
+        ledger.principal_locked = locked.to_arcis();
+
+        user_ledger.owner.from_arcis(ledger)
+    }
+
+    /// Ledger migration: UserLedgerState layout 1 -> layout 2
+    /// The old balance becomes principal; nothing has accrued and principal stays spendable.
+    #[instruction]
+    pub fn migrate_user_ledger_v2(
+        user_ledger: Enc<Shared, UserLedgerStateV1>,
+    ) -> Enc<Shared, UserLedgerState> {
+        let old = user_ledger.to_arcis();
+
+        let new_state = UserLedgerState {
+            principal: old.balance,
+            subscription_count: old.subscription_count,
+            accrued_yield: 0,
+            principal_locked: false,
+        };
+
+        user_ledger.owner.from_arcis(new_state)
+    }
+
+    /// Merchant ledger migration: MerchantLedgerState layout 1 -> layout 2
+    /// Ledgers written before disputes have nothing frozen.
+    #[instruction]
+    pub fn migrate_merchant_ledger_v2(
+        merchant_ledger: Enc<Shared, MerchantLedgerStateV1>,
+    ) -> Enc<Shared, MerchantLedgerState> {
+        let old = merchant_ledger.to_arcis();
+
+        let new_state = MerchantLedgerState {
+            balance: old.balance,
+            total_claimed: old.total_claimed,
+            frozen: 0,
+        };
+
+        merchant_ledger.owner.from_arcis(new_state)
+    }
+
+    /// Subscription migration: UserSubscriptionState layout 1 -> layout 2
+    /// Subscriptions created before seats pay for a single seat.
+    #[instruction]
+    pub fn migrate_user_subscription_v2(
+        subscription: Enc<Shared, UserSubscriptionStateV1>,
+    ) -> Enc<Shared, UserSubscriptionState> {
+        let old = subscription.to_arcis();
+
+        let new_state = UserSubscriptionState {
+            plan: old.plan,
+            status: old.status,
+            next_payment_date: old.next_payment_date,
+            start_date: old.start_date,
+            quantity: 1,
+        };
+
+        subscription.owner.from_arcis(new_state)
+    }
+}
+
+// ============================================================================
+// Shared State Layouts
+// ============================================================================
+
+// The program encodes arguments and decodes outputs with the layouts in
+// `state-layouts`; a field added, removed or retyped on one side only fails to
+// compile here, and a reordered field fails the generated test.
+state_layouts::assert_layout!(circuits::UserLedgerState => state_layouts::UserLedgerState {
+    principal, subscription_count, accrued_yield, principal_locked,
+});
+state_layouts::assert_layout!(circuits::MerchantLedgerState => state_layouts::MerchantLedgerState {
+    balance, total_claimed, frozen,
+});
+state_layouts::assert_layout!(circuits::UserSubscriptionState => state_layouts::UserSubscriptionState {
+    plan, status, next_payment_date, start_date, quantity,
+});
+state_layouts::assert_layout!(circuits::SpendingCapState => state_layouts::SpendingCapState {
+    cap, window_seconds, window_start, spent,
+});
+state_layouts::assert_layout!(circuits::PlanStatsState => state_layouts::PlanStatsState {
+    active_subscribers, mrr, period_start, new_subscribers, churned_subscribers,
+    previous_new_subscribers, previous_churned_subscribers,
+});
+state_layouts::assert_layout!(circuits::PoolYieldState => state_layouts::PoolYieldState {
+    index, user_liabilities,
+});
+state_layouts::assert_layout!(circuits::ChargeRecordState => state_layouts::ChargeRecordState {
+    last_charge, min_charge,
+});
+state_layouts::assert_layout!(circuits::BalanceRunway => state_layouts::BalanceRunway {
+    monthly_spend, first_failure_date,
+});
+state_layouts::assert_layout!(circuits::UserLedgerStateV1 => state_layouts::UserLedgerStateV1 {
+    balance, subscription_count,
+});
+state_layouts::assert_layout!(circuits::MerchantLedgerStateV1 => state_layouts::MerchantLedgerStateV1 {
+    balance, total_claimed,
+});
+state_layouts::assert_layout!(circuits::UserSubscriptionStateV1 => state_layouts::UserSubscriptionStateV1 {
+    plan, status, next_payment_date, start_date,
+});
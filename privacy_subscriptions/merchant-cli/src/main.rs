@@ -0,0 +1,856 @@
+//! Merchant command-line tool for the privacy_subscriptions program.
+//!
+//! Registers the merchant, manages the x25519 key its ledgers are encrypted
+//! to, creates and updates plans, decrypts the merchant ledger, claims revenue
+//! and exports revenue statements. Everything is read from a TOML config file
+//! (see `subly-merchant.example.toml`).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use anchor_spl::associated_token::get_associated_token_address;
+use anyhow::{anyhow, bail, Context as _, Result};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use clap::{Parser, Subcommand, ValueEnum};
+use privacy_subscriptions_client::{
+    self as client, Computation, EncryptionKeypair, Merchant, MerchantLedger, MerchantLedgerState,
+    PlanUpdate, ProtocolPool, RescueCipher, RevenueClaimed, ScheduledPayoutExecuted,
+    SubscriptionPlan, ENCRYPTION_SIGNING_MESSAGE, OUTFLOW_KIND_CLAIM, PROGRAM_ID,
+};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::{RpcProgramAccountsConfig, RpcTransactionConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status_client_types::UiTransactionEncoding;
+
+// ============================================================================
+// Command Line
+// ============================================================================
+
+#[derive(Parser)]
+#[command(
+    name = "subly-merchant",
+    about = "Merchant operations for Subly privacy subscriptions"
+)]
+struct Cli {
+    /// Config file
+    #[arg(long, short, global = true, default_value = "subly-merchant.toml")]
+    config: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Register the wallet as a merchant with a ledger in one mint
+    Register {
+        /// Merchant name shown to subscribers
+        #[arg(long)]
+        name: String,
+        /// Mint alias from the config, or a mint address
+        #[arg(long)]
+        mint: Option<String>,
+    },
+    /// Open a ledger in a further settlement mint
+    OpenLedger {
+        #[arg(long)]
+        mint: Option<String>,
+    },
+    /// Manage the x25519 key the merchant ledgers are encrypted to
+    #[command(subcommand)]
+    Keys(KeysCommand),
+    /// Create, update and list subscription plans
+    #[command(subcommand)]
+    Plans(PlansCommand),
+    /// Decrypt and show the merchant ledger
+    Balance {
+        #[arg(long)]
+        mint: Option<String>,
+    },
+    /// Claim revenue to the wallet's associated token account
+    Claim {
+        /// Amount in base units of the mint
+        #[arg(long)]
+        amount: u64,
+        #[arg(long)]
+        mint: Option<String>,
+    },
+    /// Export a revenue statement: ledger totals, plans and paid-out claims and payouts
+    Statement {
+        #[arg(long)]
+        mint: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Only list payouts at or after this unix timestamp
+        #[arg(long)]
+        since: Option<i64>,
+        /// Only list payouts before this unix timestamp
+        #[arg(long)]
+        until: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// Write a new random key to the configured key file
+    Generate {
+        /// Replace an existing key file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Derive the key from the wallet, as the dashboard does, and write it to the key file
+    Derive {
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show the public key and check it against the on-chain ledgers
+    Show,
+}
+
+#[derive(Subcommand)]
+enum PlansCommand {
+    /// List the merchant's plans
+    List,
+    /// Create a plan
+    Create {
+        #[arg(long)]
+        id: u64,
+        #[arg(long)]
+        name: String,
+        /// Per-seat price in base units of the mint
+        #[arg(long)]
+        price: u64,
+        #[arg(long)]
+        cycle_days: u32,
+        #[arg(long)]
+        mint: Option<String>,
+    },
+    /// Update a plan; omitted fields are unchanged
+    Update {
+        #[arg(long)]
+        id: u64,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        price: Option<u64>,
+        #[arg(long)]
+        cycle_days: Option<u32>,
+        #[arg(long)]
+        active: Option<bool>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+// ============================================================================
+// Config
+// ============================================================================
+
+#[derive(Deserialize)]
+struct Config {
+    /// RPC endpoint, e.g. http://127.0.0.1:8899 for a local validator
+    rpc_url: String,
+    /// Merchant wallet keypair
+    wallet: PathBuf,
+    /// Hex x25519 secret key the merchant ledgers are encrypted to
+    encryption_key: PathBuf,
+    /// Offset of the Arcium cluster the MXE is assigned to
+    #[serde(default)]
+    cluster_offset: u32,
+    /// Hex MXE x25519 public key; read from the MXE account when unset
+    mxe_public_key: Option<String>,
+    /// Mint used when `--mint` is omitted
+    default_mint: Option<String>,
+    /// Mint aliases
+    #[serde(default)]
+    mints: BTreeMap<String, String>,
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn parse_hex32(value: &str, what: &str) -> Result<[u8; 32]> {
+    hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("{what} must be 32 bytes of hex"))
+}
+
+// ============================================================================
+// Context
+// ============================================================================
+
+struct Context {
+    config: Config,
+    rpc: RpcClient,
+    wallet: Keypair,
+}
+
+impl Context {
+    fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config {}", path.display()))?;
+        let config: Config =
+            toml::from_str(&text).with_context(|| format!("invalid config {}", path.display()))?;
+        let wallet_path = expand_home(&config.wallet);
+        let wallet = read_keypair_file(&wallet_path)
+            .map_err(|err| anyhow!("failed to read wallet {}: {err}", wallet_path.display()))?;
+        let rpc =
+            RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+        Ok(Self {
+            config,
+            rpc,
+            wallet,
+        })
+    }
+
+    /// Resolve a mint alias or address, falling back to the default mint
+    fn mint(&self, mint: Option<&str>) -> Result<Pubkey> {
+        let name = match mint.or(self.config.default_mint.as_deref()) {
+            Some(name) => name,
+            None if self.config.mints.len() == 1 => self.config.mints.keys().next().unwrap(),
+            None => bail!("pass --mint or set default_mint in the config"),
+        };
+        let address = self.config.mints.get(name).map_or(name, String::as_str);
+        Pubkey::from_str(address).with_context(|| format!("unknown mint `{name}`"))
+    }
+
+    fn merchant(&self) -> Pubkey {
+        client::merchant(&self.wallet.pubkey())
+    }
+
+    fn key_path(&self) -> PathBuf {
+        expand_home(&self.config.encryption_key)
+    }
+
+    fn encryption_key(&self) -> Result<EncryptionKeypair> {
+        let path = self.key_path();
+        let secret = fs::read_to_string(&path).with_context(|| {
+            format!(
+                "failed to read encryption key {}; run `keys generate` or `keys derive`",
+                path.display()
+            )
+        })?;
+        Ok(EncryptionKeypair::from_secret_bytes(parse_hex32(
+            &secret,
+            "encryption key",
+        )?))
+    }
+
+    fn write_encryption_key(&self, keypair: &EncryptionKeypair, force: bool) -> Result<()> {
+        let path = self.key_path();
+        if path.exists() && !force {
+            bail!(
+                "{} already exists; pass --force to replace it",
+                path.display()
+            );
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        writeln!(
+            options.open(&path)?,
+            "{}",
+            hex::encode(keypair.secret_bytes())
+        )?;
+        println!("Wrote {}", path.display());
+        println!("Public key: {}", hex::encode(keypair.public_key()));
+        Ok(())
+    }
+
+    fn mxe_public_key(&self) -> Result<[u8; 32]> {
+        if let Some(key) = &self.config.mxe_public_key {
+            return parse_hex32(key, "mxe_public_key");
+        }
+        let address = client::mxe_account();
+        let data = self
+            .rpc
+            .get_account_data(&address)
+            .with_context(|| format!("failed to fetch MXE account {address}"))?;
+        client::mxe_public_key(&data)?.context("MXE public key is not set yet")
+    }
+
+    fn cipher(&self) -> Result<RescueCipher> {
+        Ok(RescueCipher::from_keypair(
+            &self.encryption_key()?,
+            &self.mxe_public_key()?,
+        ))
+    }
+
+    fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let data = self
+            .rpc
+            .get_account_data(address)
+            .with_context(|| format!("failed to fetch account {address}"))?;
+        Ok(client::decode_account(&data)?)
+    }
+
+    fn exists(&self, address: &Pubkey) -> Result<bool> {
+        Ok(self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())?
+            .value
+            .is_some())
+    }
+
+    fn send(&self, instruction: Instruction) -> Result<Signature> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.wallet.pubkey()),
+            &[&self.wallet],
+            blockhash,
+        );
+        let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+        println!("Signature: {signature}");
+        Ok(signature)
+    }
+
+    fn ledger_state(&self, mint: &Pubkey) -> Result<MerchantLedgerState> {
+        let ledger: MerchantLedger =
+            self.fetch(&client::merchant_ledger(&self.merchant(), mint))?;
+        let cipher = self.cipher()?;
+        Ok(client::decrypt_merchant_ledger(&cipher, &ledger)?)
+    }
+
+    fn plans(&self) -> Result<Vec<(Pubkey, SubscriptionPlan)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    0,
+                    SubscriptionPlan::DISCRIMINATOR.to_vec(),
+                )),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    8,
+                    self.merchant().to_bytes().to_vec(),
+                )),
+            ]),
+            ..Default::default()
+        };
+        let mut plans = self
+            .rpc
+            .get_program_accounts_with_config(&PROGRAM_ID, config)?
+            .into_iter()
+            .map(|(address, account)| Ok((address, client::decode_account(&account.data)?)))
+            .collect::<Result<Vec<(Pubkey, SubscriptionPlan)>>>()?;
+        plans.sort_by_key(|(_, plan)| plan.plan_id);
+        Ok(plans)
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+fn register(ctx: &Context, name: String, mint: Option<&str>) -> Result<()> {
+    let mint = ctx.mint(mint)?;
+    let encryption_pubkey = ctx.encryption_key()?.public_key();
+    ctx.send(client::register_merchant(
+        ctx.wallet.pubkey(),
+        mint,
+        name,
+        encryption_pubkey,
+    ))?;
+    println!("Merchant: {}", ctx.merchant());
+    Ok(())
+}
+
+fn open_ledger(ctx: &Context, mint: Option<&str>) -> Result<()> {
+    let mint = ctx.mint(mint)?;
+    let encryption_pubkey = ctx.encryption_key()?.public_key();
+    ctx.send(client::open_merchant_ledger(
+        ctx.wallet.pubkey(),
+        mint,
+        encryption_pubkey,
+    ))?;
+    println!(
+        "Ledger: {}",
+        client::merchant_ledger(&ctx.merchant(), &mint)
+    );
+    Ok(())
+}
+
+fn keys(ctx: &Context, command: KeysCommand) -> Result<()> {
+    match command {
+        KeysCommand::Generate { force } => {
+            ctx.write_encryption_key(&EncryptionKeypair::generate(), force)
+        }
+        KeysCommand::Derive { force } => {
+            let signature = ctx
+                .wallet
+                .sign_message(ENCRYPTION_SIGNING_MESSAGE.as_bytes());
+            ctx.write_encryption_key(
+                &EncryptionKeypair::from_signature(signature.as_ref()),
+                force,
+            )
+        }
+        KeysCommand::Show => {
+            let public_key = ctx.encryption_key()?.public_key();
+            println!("Public key: {}", hex::encode(public_key));
+            for alias in ctx.config.mints.keys() {
+                let ledger = client::merchant_ledger(&ctx.merchant(), &ctx.mint(Some(alias))?);
+                let status = if !ctx.exists(&ledger)? {
+                    "no ledger"
+                } else if ctx.fetch::<MerchantLedger>(&ledger)?.encryption_pubkey == public_key {
+                    "matches ledger"
+                } else {
+                    "DIFFERS from ledger"
+                };
+                println!("  {alias}: {status}");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn plan_name(plan: &SubscriptionPlan) -> String {
+    String::from_utf8_lossy(&plan.name)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn plans(ctx: &Context, command: PlansCommand) -> Result<()> {
+    match command {
+        PlansCommand::List => {
+            for (address, plan) in ctx.plans()? {
+                println!(
+                    "{:>4}  {:<24} price {:>12} every {:>3} days  {}  {}",
+                    plan.plan_id,
+                    plan_name(&plan),
+                    plan.price,
+                    plan.billing_cycle_days,
+                    if plan.is_active {
+                        "active  "
+                    } else {
+                        "inactive"
+                    },
+                    address,
+                );
+            }
+            Ok(())
+        }
+        PlansCommand::Create {
+            id,
+            name,
+            price,
+            cycle_days,
+            mint,
+        } => {
+            let mint = ctx.mint(mint.as_deref())?;
+            ctx.send(client::create_subscription_plan(
+                ctx.wallet.pubkey(),
+                mint,
+                id,
+                name,
+                price,
+                cycle_days,
+            ))?;
+            println!("Plan: {}", client::subscription_plan(&ctx.merchant(), id));
+            Ok(())
+        }
+        PlansCommand::Update {
+            id,
+            name,
+            price,
+            cycle_days,
+            active,
+        } => {
+            let update = PlanUpdate {
+                name,
+                price,
+                billing_cycle_days: cycle_days,
+                is_active: active,
+            };
+            ctx.send(client::update_subscription_plan(
+                ctx.wallet.pubkey(),
+                id,
+                update,
+            ))?;
+            Ok(())
+        }
+    }
+}
+
+fn balance(ctx: &Context, mint: Option<&str>) -> Result<()> {
+    let mint = ctx.mint(mint)?;
+    let state = ctx.ledger_state(&mint)?;
+    println!("Mint:          {mint}");
+    println!("Balance:       {}", state.balance);
+    println!("Total claimed: {}", state.total_claimed);
+    println!("Frozen:        {}", state.frozen);
+    Ok(())
+}
+
+fn claim(ctx: &Context, amount: u64, mint: Option<&str>) -> Result<()> {
+    let mint = ctx.mint(mint)?;
+    let wallet = ctx.wallet.pubkey();
+    let pool: ProtocolPool = ctx.fetch(&client::protocol_pool(&mint))?;
+    // A queued large claim is passed once it exists
+    let pending = ctx.exists(&client::pending_outflow(&wallet, &mint, OUTFLOW_KIND_CLAIM))?;
+    ctx.send(client::claim_revenue(
+        wallet,
+        mint,
+        pool.token_account,
+        get_associated_token_address(&wallet, &mint),
+        amount,
+        pending,
+        Computation::new(ctx.config.cluster_offset),
+    ))?;
+    println!("Claim queued; the balance updates once the computation finalizes");
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Statement {
+    merchant: String,
+    merchant_name: String,
+    mint: String,
+    generated_at: i64,
+    balance: u64,
+    total_claimed: u64,
+    frozen: u64,
+    plans: Vec<StatementPlan>,
+    payouts: Vec<StatementPayout>,
+}
+
+#[derive(Serialize)]
+struct StatementPlan {
+    plan_id: u64,
+    name: String,
+    price: u64,
+    billing_cycle_days: u32,
+    is_active: bool,
+}
+
+/// What paid a [`StatementPayout`] out
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PayoutKind {
+    /// `claim_revenue` to the merchant's token account
+    Claim,
+    /// `claim_revenue_split` to the revenue split's recipients
+    SplitClaim,
+    /// `execute_scheduled_payout` to the payout schedule's destination
+    Scheduled,
+}
+
+impl PayoutKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            PayoutKind::Claim => "claim",
+            PayoutKind::SplitClaim => "split_claim",
+            PayoutKind::Scheduled => "scheduled",
+        }
+    }
+}
+
+/// Tokens a finalized computation moved out of the ledger, as reported by its
+/// callback. Rejected or aborted requests paid nothing and are not listed.
+#[derive(Serialize)]
+struct StatementPayout {
+    signature: String,
+    block_time: Option<i64>,
+    kind: PayoutKind,
+    amount: u64,
+}
+
+/// Decode an Anchor event logged as `Program data: <base64>`
+fn decode_event<T: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<T> {
+    let payload = data.strip_prefix(T::DISCRIMINATOR)?;
+    T::try_from_slice(payload).ok()
+}
+
+/// Payouts reported by the callbacks that updated the ledger, oldest first
+fn payouts(
+    ctx: &Context,
+    ledger: &Pubkey,
+    mint: &Pubkey,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Result<Vec<StatementPayout>> {
+    let merchant = ctx.merchant();
+    let mut payouts = Vec::new();
+    let mut before = None;
+    'pages: loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until: None,
+            limit: None,
+            commitment: Some(ctx.rpc.commitment()),
+        };
+        let page = ctx
+            .rpc
+            .get_signatures_for_address_with_config(ledger, config)?;
+        let Some(last) = page.last() else { break };
+        before = Some(Signature::from_str(&last.signature)?);
+
+        for status in &page {
+            // Signatures come newest first
+            let block_time = status.block_time;
+            if since
+                .zip(block_time)
+                .is_some_and(|(since, time)| time < since)
+            {
+                break 'pages;
+            }
+            if status.err.is_some()
+                || until
+                    .zip(block_time)
+                    .is_some_and(|(until, time)| time >= until)
+            {
+                continue;
+            }
+            let signature = Signature::from_str(&status.signature)?;
+            let config = RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(ctx.rpc.commitment()),
+                max_supported_transaction_version: Some(0),
+            };
+            let encoded = ctx.rpc.get_transaction_with_config(&signature, config)?;
+            let logs: Option<Vec<String>> = encoded
+                .transaction
+                .meta
+                .and_then(|meta| meta.log_messages.into());
+            for line in logs.unwrap_or_default() {
+                let Some(data) = line
+                    .strip_prefix("Program data: ")
+                    .and_then(|data| BASE64_STANDARD.decode(data).ok())
+                else {
+                    continue;
+                };
+                let payout = if let Some(event) = decode_event::<RevenueClaimed>(&data) {
+                    let kind = if event.split {
+                        PayoutKind::SplitClaim
+                    } else {
+                        PayoutKind::Claim
+                    };
+                    (event.merchant == merchant && event.mint == *mint)
+                        .then_some((kind, event.amount))
+                } else if let Some(event) = decode_event::<ScheduledPayoutExecuted>(&data) {
+                    (event.merchant == merchant && event.mint == *mint)
+                        .then_some((PayoutKind::Scheduled, event.amount))
+                } else {
+                    None
+                };
+                if let Some((kind, amount)) = payout.filter(|(_, amount)| *amount > 0) {
+                    payouts.push(StatementPayout {
+                        signature: status.signature.clone(),
+                        block_time,
+                        kind,
+                        amount,
+                    });
+                }
+            }
+        }
+    }
+    payouts.reverse();
+    Ok(payouts)
+}
+
+fn statement(
+    ctx: &Context,
+    mint: Option<&str>,
+    format: Format,
+    output: Option<&Path>,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Result<()> {
+    let mint = ctx.mint(mint)?;
+    let merchant: Merchant = ctx.fetch(&ctx.merchant())?;
+    let ledger = client::merchant_ledger(&ctx.merchant(), &mint);
+    let state = ctx.ledger_state(&mint)?;
+    let statement = Statement {
+        merchant: ctx.merchant().to_string(),
+        merchant_name: String::from_utf8_lossy(&merchant.name)
+            .trim_end_matches('\0')
+            .to_string(),
+        mint: mint.to_string(),
+        generated_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+        balance: state.balance,
+        total_claimed: state.total_claimed,
+        frozen: state.frozen,
+        plans: ctx
+            .plans()?
+            .into_iter()
+            .filter(|(_, plan)| plan.mint == mint)
+            .map(|(_, plan)| StatementPlan {
+                plan_id: plan.plan_id,
+                name: plan_name(&plan),
+                price: plan.price,
+                billing_cycle_days: plan.billing_cycle_days,
+                is_active: plan.is_active,
+            })
+            .collect(),
+        payouts: payouts(ctx, &ledger, &mint, since, until)?,
+    };
+
+    let text = match format {
+        Format::Json => serde_json::to_string_pretty(&statement)? + "\n",
+        Format::Csv => statement_csv(&statement),
+    };
+    match output {
+        Some(path) => {
+            fs::write(path, text)?;
+            println!("Wrote {}", path.display());
+        }
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+/// One row per ledger total and per payout
+fn statement_csv(statement: &Statement) -> String {
+    let mut csv = String::from("kind,time,reference,amount,status\n");
+    for (kind, amount) in [
+        ("balance", statement.balance),
+        ("total_claimed", statement.total_claimed),
+        ("frozen", statement.frozen),
+    ] {
+        csv += &format!(
+            "{kind},{},{},{amount},\n",
+            statement.generated_at, statement.mint
+        );
+    }
+    for payout in &statement.payouts {
+        csv += &format!(
+            "{},{},{},{},paid\n",
+            payout.kind.as_str(),
+            payout
+                .block_time
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+            payout.signature,
+            payout.amount,
+        );
+    }
+    csv
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let ctx = Context::load(&cli.config)?;
+    match cli.command {
+        Command::Register { name, mint } => register(&ctx, name, mint.as_deref()),
+        Command::OpenLedger { mint } => open_ledger(&ctx, mint.as_deref()),
+        Command::Keys(command) => keys(&ctx, command),
+        Command::Plans(command) => plans(&ctx, command),
+        Command::Balance { mint } => balance(&ctx, mint.as_deref()),
+        Command::Claim { amount, mint } => claim(&ctx, amount, mint.as_deref()),
+        Command::Statement {
+            mint,
+            format,
+            output,
+            since,
+            until,
+        } => statement(
+            &ctx,
+            mint.as_deref(),
+            format,
+            output.as_deref(),
+            since,
+            until,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorSerialize;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn example_config_parses() {
+        let config: Config =
+            toml::from_str(include_str!("../subly-merchant.example.toml")).unwrap();
+        assert_eq!(config.default_mint.as_deref(), Some("usdc"));
+        assert!(config.mints.contains_key("usdc"));
+        assert!(config.mxe_public_key.is_none());
+    }
+
+    #[test]
+    fn parse_hex32_requires_32_bytes() {
+        assert_eq!(
+            parse_hex32(&format!(" {} ", "ab".repeat(32)), "key").unwrap(),
+            [0xab; 32]
+        );
+        assert!(parse_hex32(&"ab".repeat(31), "key").is_err());
+        assert!(parse_hex32(&"zz".repeat(32), "key").is_err());
+    }
+
+    #[test]
+    fn decode_event_checks_discriminator() {
+        let event = RevenueClaimed {
+            merchant: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount: 250,
+            split: true,
+        };
+        let mut data = RevenueClaimed::DISCRIMINATOR.to_vec();
+        event.serialize(&mut data).unwrap();
+
+        let decoded = decode_event::<RevenueClaimed>(&data).unwrap();
+        assert_eq!(decoded.amount, 250);
+        assert!(decoded.split);
+        assert!(decode_event::<ScheduledPayoutExecuted>(&data).is_none());
+    }
+
+    #[test]
+    fn statement_csv_lists_totals_then_payouts() {
+        let statement = Statement {
+            merchant: String::new(),
+            merchant_name: String::new(),
+            mint: "mint".into(),
+            generated_at: 1_700_000_000,
+            balance: 900,
+            total_claimed: 100,
+            frozen: 0,
+            plans: Vec::new(),
+            payouts: vec![StatementPayout {
+                signature: "sig".into(),
+                block_time: None,
+                kind: PayoutKind::SplitClaim,
+                amount: 100,
+            }],
+        };
+        assert_eq!(
+            statement_csv(&statement),
+            "kind,time,reference,amount,status\n\
+             balance,1700000000,mint,900,\n\
+             total_claimed,1700000000,mint,100,\n\
+             frozen,1700000000,mint,0,\n\
+             split_claim,,sig,100,paid\n"
+        );
+    }
+}
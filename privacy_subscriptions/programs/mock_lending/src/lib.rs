@@ -0,0 +1,260 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+declare_id!("6e7UAYCv5zQVRp4eFrik1uuVz4h1q1nGGs55aLEg56Kv");
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+pub const RESERVE_SEED: &[u8] = b"reserve";
+pub const POSITION_SEED: &[u8] = b"position";
+
+// ============================================================================
+// Program Module
+// ============================================================================
+
+/// Minimal lending market for localnet tests.
+/// Interest is not computed from rates: `accrue_interest` pays tokens into a
+/// position directly, so tests control exactly how much yield a depositor earns.
+#[program]
+pub mod mock_lending {
+    use super::*;
+
+    /// Create the reserve for a mint
+    pub fn initialize_reserve(ctx: Context<InitializeReserve>) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.mint = ctx.accounts.mint.key();
+        reserve.vault = ctx.accounts.vault.key();
+        reserve.total_deposits = 0;
+        reserve.bump = ctx.bumps.reserve;
+
+        Ok(())
+    }
+
+    /// Deposit tokens into the owner's position
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, LendingError::InvalidAmount);
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.source.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        );
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+        let position = &mut ctx.accounts.position;
+        if position.owner == Pubkey::default() {
+            position.reserve = ctx.accounts.reserve.key();
+            position.owner = ctx.accounts.owner.key();
+            position.amount = 0;
+            position.bump = ctx.bumps.position;
+        }
+        position.amount += amount;
+        ctx.accounts.reserve.total_deposits += amount;
+
+        Ok(())
+    }
+
+    /// Withdraw tokens (principal or interest) from the owner's position
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        require!(amount > 0, LendingError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.position.amount,
+            LendingError::InsufficientPosition
+        );
+
+        let reserve = &ctx.accounts.reserve;
+        let signer_seeds: &[&[u8]] = &[RESERVE_SEED, reserve.mint.as_ref(), &[reserve.bump]];
+        let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.reserve.to_account_info(),
+            },
+            signer,
+        );
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+        ctx.accounts.position.amount -= amount;
+        ctx.accounts.reserve.total_deposits -= amount;
+
+        Ok(())
+    }
+
+    /// Pay interest into a position (funded by the caller)
+    pub fn accrue_interest(ctx: Context<AccrueInterest>, amount: u64) -> Result<()> {
+        require!(amount > 0, LendingError::InvalidAmount);
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.funder_token_account.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: ctx.accounts.funder.to_account_info(),
+            },
+        );
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+        ctx.accounts.position.amount += amount;
+        ctx.accounts.reserve.total_deposits += amount;
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+/// Lending reserve for one mint
+/// PDA Seeds: ["reserve", mint]
+#[account]
+pub struct Reserve {
+    /// Token mint
+    pub mint: Pubkey,
+    /// Token account holding deposits (owned by the reserve PDA)
+    pub vault: Pubkey,
+    /// Sum of all position amounts
+    pub total_deposits: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl Reserve {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+/// A depositor's position, principal plus accrued interest
+/// PDA Seeds: ["position", reserve, owner]
+#[account]
+pub struct Position {
+    /// Reserve this position belongs to
+    pub reserve: Pubkey,
+    /// Depositor (may be a PDA of the calling program)
+    pub owner: Pubkey,
+    /// Withdrawable amount
+    pub amount: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl Position {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+// ============================================================================
+// Context Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeReserve<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        space = Reserve::SIZE,
+        seeds = [RESERVE_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub reserve: Account<'info, Reserve>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = reserve,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [RESERVE_SEED, reserve.mint.as_ref()],
+        bump = reserve.bump,
+    )]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut, address = reserve.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = Position::SIZE,
+        seeds = [POSITION_SEED, reserve.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut, token::mint = reserve.mint)]
+    pub source: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [RESERVE_SEED, reserve.mint.as_ref()],
+        bump = reserve.bump,
+    )]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut, address = reserve.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, reserve.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut, token::mint = reserve.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueInterest<'info> {
+    pub funder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [RESERVE_SEED, reserve.mint.as_ref()],
+        bump = reserve.bump,
+    )]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut, address = reserve.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, reserve.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut, token::mint = reserve.mint)]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+#[error_code]
+pub enum LendingError {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientPosition,
+}
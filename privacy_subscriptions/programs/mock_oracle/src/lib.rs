@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+declare_id!("H69Wp6tJEPHR2FtSqwEZ77Zdm2ncPNnmuMhtR27CR1vf");
+
+// ============================================================================
+// Program Module
+// ============================================================================
+
+/// Minimal price oracle for localnet tests.
+/// A feed is written directly by its authority, including its publish time,
+/// so tests can exercise stale and low-confidence prices deterministically.
+#[program]
+pub mod mock_oracle {
+    use super::*;
+
+    /// Create a price feed owned by the signer
+    pub fn initialize_feed(
+        ctx: Context<InitializeFeed>,
+        price: i64,
+        conf: u64,
+        expo: i32,
+    ) -> Result<()> {
+        let feed = &mut ctx.accounts.feed;
+        feed.authority = ctx.accounts.authority.key();
+        feed.price = price;
+        feed.conf = conf;
+        feed.expo = expo;
+        feed.publish_time = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Publish a new price. `publish_time` defaults to the current clock.
+    pub fn set_price(
+        ctx: Context<SetPrice>,
+        price: i64,
+        conf: u64,
+        publish_time: Option<i64>,
+    ) -> Result<()> {
+        let feed = &mut ctx.accounts.feed;
+        feed.price = price;
+        feed.conf = conf;
+        feed.publish_time = match publish_time {
+            Some(publish_time) => publish_time,
+            None => Clock::get()?.unix_timestamp,
+        };
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+/// Price of one whole token in a reference currency: `price * 10^expo`,
+/// with a one-sided confidence interval `conf` in the same units.
+#[account]
+pub struct PriceFeed {
+    /// Signer allowed to publish prices
+    pub authority: Pubkey,
+    /// Price mantissa
+    pub price: i64,
+    /// Confidence interval mantissa
+    pub conf: u64,
+    /// Decimal exponent applied to `price` and `conf`
+    pub expo: i32,
+    /// Unix timestamp of the last publish
+    pub publish_time: i64,
+}
+
+impl PriceFeed {
+    pub const SIZE: usize = 8 + 32 + 8 + 8 + 4 + 8;
+}
+
+// ============================================================================
+// Context Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(init, payer = authority, space = PriceFeed::SIZE)]
+    pub feed: Account<'info, PriceFeed>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub feed: Account<'info, PriceFeed>,
+}
@@ -3,6 +3,13 @@ use anchor_spl::token::{Mint, Token, TokenAccount};
 use arcium_anchor::prelude::*;
 use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
 use arcium_macros::circuit_hash;
+use state_layouts::{
+    ArgBuilderExt, BalanceRunwayCiphertexts, ChargeRecordStateCiphertexts, MerchantLedgerStateCiphertexts,
+    MerchantLedgerStateV1Ciphertexts, PlanStatsStateCiphertexts, PoolYieldStateCiphertexts,
+    SpendingCapStateCiphertexts, UserLedgerStateCiphertexts,
+    UserLedgerStateV1Ciphertexts, UserSubscriptionStateCiphertexts,
+    UserSubscriptionStateV1Ciphertexts,
+};
 
 declare_id!("Hwmvq4rJ1P6bxHD5G6KvzteuXdMtMzpwZTT7AJb3wSa9");
 
@@ -17,12 +24,85 @@ pub const MERCHANT_LEDGER_SEED: &[u8] = b"merchant_ledger";
 pub const SUBSCRIPTION_PLAN_SEED: &[u8] = b"subscription_plan";
 pub const USER_LEDGER_SEED: &[u8] = b"user_ledger";
 pub const USER_SUBSCRIPTION_SEED: &[u8] = b"user_subscription";
+pub const DISPUTE_SEED: &[u8] = b"dispute";
+pub const SPENDING_CAP_SEED: &[u8] = b"spending_cap";
+pub const SOLVENCY_ATTESTATION_SEED: &[u8] = b"solvency_attestation";
+pub const VOUCHER_POOL_SEED: &[u8] = b"voucher_pool";
+pub const SEAT_ASSIGNMENT_SEED: &[u8] = b"seat_assignment";
+pub const REVENUE_SPLIT_SEED: &[u8] = b"revenue_split";
+pub const PAYOUT_SCHEDULE_SEED: &[u8] = b"payout_schedule";
+pub const PLAN_STATS_SEED: &[u8] = b"plan_stats";
+pub const PLAN_PUBLIC_STATS_SEED: &[u8] = b"plan_public_stats";
+pub const AUDIT_GRANT_SEED: &[u8] = b"audit_grant";
+pub const SCREENING_ATTESTATION_SEED: &[u8] = b"screening_attestation";
+pub const PENDING_OUTFLOW_SEED: &[u8] = b"pending_outflow";
+pub const PRICE_FEED_SEED: &[u8] = b"price_feed";
+pub const CIRCUIT_VERSION_SEED: &[u8] = b"circuit_version";
 
 pub const MAX_NAME_LENGTH: usize = 64;
 pub const MAX_PLAN_NAME_LENGTH: usize = 32;
-pub const MAX_FEE_RATE_BPS: u16 = 10000; // 100%
+/// Basis points in one whole (100%)
+pub const BPS_DENOMINATOR: u16 = 10000;
+pub const MAX_FEE_RATE_BPS: u16 = BPS_DENOMINATOR; // 100%
 pub const MIN_BILLING_CYCLE_DAYS: u32 = 1;
 pub const MAX_BILLING_CYCLE_DAYS: u32 = 365;
+/// Subscription slots accepted by the balance runway query (must match the circuit)
+pub const MAX_RUNWAY_SUBSCRIPTIONS: usize = 4;
+/// Billing cycles a single gift voucher can prepay
+pub const MAX_VOUCHER_CYCLES: u32 = 24;
+/// Outstanding vouchers one voucher pool can hold (must match the circuit)
+pub const VOUCHER_POOL_SLOTS: usize = 16;
+/// How long a queued purchase or redemption holds a voucher pool before another can be queued
+pub const VOUCHER_POOL_LOCK_TIMEOUT_SECONDS: i64 = 600;
+
+// Dispute status (public)
+pub const DISPUTE_STATUS_PENDING: u8 = 0;
+pub const DISPUTE_STATUS_OPEN: u8 = 1;
+pub const DISPUTE_STATUS_REFUNDED: u8 = 2;
+pub const DISPUTE_STATUS_RELEASED: u8 = 3;
+pub const DISPUTE_STATUS_REJECTED: u8 = 4;
+/// Disputes a subscription can have pending or open at once
+pub const MAX_OPEN_DISPUTES: u8 = 3;
+/// Highest billing period index a dispute can name
+pub const MAX_DISPUTE_BILLING_PERIOD: u64 = 10_000;
+
+// Seat assignment status (public)
+pub const SEAT_STATUS_PENDING: u8 = 0;
+pub const SEAT_STATUS_ACTIVE: u8 = 1;
+pub const SEAT_STATUS_REJECTED: u8 = 2;
+
+// Pending outflow kind (public)
+pub const OUTFLOW_KIND_WITHDRAW: u8 = 0;
+pub const OUTFLOW_KIND_CLAIM: u8 = 1;
+/// Recipients a merchant revenue split can pay
+pub const MAX_SPLIT_RECIPIENTS: usize = 8;
+/// Basis points a revenue split's shares must add up to
+pub const SPLIT_TOTAL_BPS: u16 = 10000;
+pub const MIN_PAYOUT_CADENCE_DAYS: u32 = 1;
+pub const MAX_PAYOUT_CADENCE_DAYS: u32 = 365;
+/// Smallest subscriber count published exactly; smaller counts show as "fewer than k"
+pub const PUBLIC_STATS_MIN_SUBSCRIBERS: u64 = 10;
+/// Minimum time between public plan stats refreshes
+pub const PUBLIC_STATS_REFRESH_INTERVAL_SECONDS: i64 = 3600;
+/// Screening authorities the protocol config can list
+pub const MAX_SCREENING_AUTHORITIES: usize = 4;
+/// Minimum time between two yield distributions for a pool
+pub const YIELD_DISTRIBUTION_INTERVAL_SECONDS: i64 = 86400;
+/// How long a computation may hold a pool's liabilities lock before another can take it
+pub const LIABILITIES_LOCK_TIMEOUT_SECONDS: i64 = 600;
+/// Plan currency code meaning the price is quoted in the plan's own mint
+pub const MINT_PRICE_CURRENCY: [u8; 8] = [0u8; 8];
+/// Decimals of prices quoted in a reference currency (1_000_000 = 1.00)
+pub const REFERENCE_PRICE_DECIMALS: u32 = 6;
+/// Circuit version the computation definitions are built from (the `_v2` circuit suffix)
+pub const CIRCUIT_VERSION: u8 = 2;
+pub const MAX_CIRCUIT_SOURCE_URL_LENGTH: usize = 128;
+/// UserLedgerState layout written by the current circuits
+pub const USER_LEDGER_STATE_VERSION: u8 = 2;
+/// MerchantLedgerState layout written by the current circuits
+pub const MERCHANT_LEDGER_STATE_VERSION: u8 = 2;
+/// UserSubscriptionState layout written by the current circuits
+pub const USER_SUBSCRIPTION_STATE_VERSION: u8 = 2;
 
 // ============================================================================
 // Arcium Computation Definition Offsets
@@ -35,6 +115,34 @@ const COMP_DEF_OFFSET_UNSUBSCRIBE: u32 = comp_def_offset("unsubscribe_v2");
 const COMP_DEF_OFFSET_PROCESS_PAYMENT: u32 = comp_def_offset("process_payment_v2");
 const COMP_DEF_OFFSET_VERIFY_SUBSCRIPTION: u32 = comp_def_offset("verify_subscription_v2");
 const COMP_DEF_OFFSET_CLAIM_REVENUE: u32 = comp_def_offset("claim_revenue_v2");
+const COMP_DEF_OFFSET_REACTIVATE_SUBSCRIPTION: u32 = comp_def_offset("reactivate_subscription_v2");
+const COMP_DEF_OFFSET_CANCEL_AT_PERIOD_END: u32 = comp_def_offset("cancel_at_period_end_v2");
+const COMP_DEF_OFFSET_RESUME_SUBSCRIPTION: u32 = comp_def_offset("resume_subscription_v2");
+const COMP_DEF_OFFSET_REFUND: u32 = comp_def_offset("refund_v2");
+const COMP_DEF_OFFSET_OPEN_DISPUTE: u32 = comp_def_offset("open_dispute_v2");
+const COMP_DEF_OFFSET_RESOLVE_DISPUTE_REFUND: u32 = comp_def_offset("resolve_dispute_refund_v2");
+const COMP_DEF_OFFSET_RESOLVE_DISPUTE_RELEASE: u32 = comp_def_offset("resolve_dispute_release_v2");
+const COMP_DEF_OFFSET_SET_SPENDING_CAP: u32 = comp_def_offset("set_spending_cap_v2");
+const COMP_DEF_OFFSET_BALANCE_RUNWAY: u32 = comp_def_offset("balance_runway_v2");
+const COMP_DEF_OFFSET_CHECK_SOLVENCY: u32 = comp_def_offset("check_solvency_v2");
+const COMP_DEF_OFFSET_TRANSFER: u32 = comp_def_offset("transfer_v2");
+const COMP_DEF_OFFSET_CLAIM_TRANSFERS: u32 = comp_def_offset("claim_transfers_v2");
+const COMP_DEF_OFFSET_BUY_VOUCHER: u32 = comp_def_offset("buy_voucher_v2");
+const COMP_DEF_OFFSET_REDEEM_VOUCHER: u32 = comp_def_offset("redeem_voucher_v2");
+const COMP_DEF_OFFSET_UPDATE_SEATS: u32 = comp_def_offset("update_seats_v2");
+const COMP_DEF_OFFSET_ASSIGN_SEAT: u32 = comp_def_offset("assign_seat_v2");
+const COMP_DEF_OFFSET_SCHEDULED_PAYOUT: u32 = comp_def_offset("scheduled_payout_v2");
+const COMP_DEF_OFFSET_PUBLISH_PLAN_STATS: u32 = comp_def_offset("publish_plan_stats_v2");
+const COMP_DEF_OFFSET_DISCLOSE_USER_LEDGER: u32 = comp_def_offset("disclose_user_ledger_v2");
+const COMP_DEF_OFFSET_DISCLOSE_MERCHANT_LEDGER: u32 = comp_def_offset("disclose_merchant_ledger_v2");
+const COMP_DEF_OFFSET_DISCLOSE_SUBSCRIPTION: u32 = comp_def_offset("disclose_subscription_v2");
+const COMP_DEF_OFFSET_DISTRIBUTE_YIELD: u32 = comp_def_offset("distribute_yield_v2");
+const COMP_DEF_OFFSET_SETTLE_YIELD: u32 = comp_def_offset("settle_yield_v2");
+const COMP_DEF_OFFSET_SET_PRINCIPAL_LOCK: u32 = comp_def_offset("set_principal_lock_v2");
+const COMP_DEF_OFFSET_MIGRATE_USER_LEDGER: u32 = comp_def_offset("migrate_user_ledger_v2");
+const COMP_DEF_OFFSET_MIGRATE_USER_SUBSCRIPTION: u32 = comp_def_offset("migrate_user_subscription_v2");
+const COMP_DEF_OFFSET_MIGRATE_MERCHANT_LEDGER: u32 = comp_def_offset("migrate_merchant_ledger_v2");
+const COMP_DEF_OFFSET_SEED_LIABILITIES: u32 = comp_def_offset("seed_liabilities_v2");
 
 // ============================================================================
 // Helpers
@@ -61,6 +169,675 @@ fn pubkey_to_u128s(pubkey: &Pubkey) -> [u128; 2] {
     [first, second]
 }
 
+/// URL of a circuit published under a registered circuit version
+fn circuit_source_url(circuit_version: &CircuitVersion, circuit: &str) -> String {
+    let len = circuit_version
+        .source_base_url
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(MAX_CIRCUIT_SOURCE_URL_LENGTH);
+    let base_url = String::from_utf8_lossy(&circuit_version.source_base_url[..len]);
+    format!("{}/{}.arcis", base_url, circuit)
+}
+
+/// Refuse a ledger the circuits cannot read: one still waiting for its owner's key
+/// (created by a transfer) or one whose ciphertexts use an older UserLedgerState layout.
+/// Ledgers never written by a circuit (nonce 0) are reset and adopt the current layout.
+fn require_current_ledger_layout(ledger: &UserLedger) -> Result<()> {
+    require!(
+        !is_zero_pubkey(&ledger.encryption_pubkey),
+        ErrorCode::InvalidEncryptionKey
+    );
+    require!(
+        ledger.nonce == 0 || ledger.state_version == USER_LEDGER_STATE_VERSION,
+        ErrorCode::StaleStateVersion
+    );
+    Ok(())
+}
+
+/// Refuse a merchant ledger whose ciphertexts use an older MerchantLedgerState layout
+fn require_current_merchant_ledger_layout(ledger: &MerchantLedger) -> Result<()> {
+    require!(
+        ledger.nonce == 0 || ledger.state_version == MERCHANT_LEDGER_STATE_VERSION,
+        ErrorCode::StaleStateVersion
+    );
+    Ok(())
+}
+
+/// Refuse a subscription whose ciphertexts use an older UserSubscriptionState layout
+fn require_current_subscription_layout(subscription: &UserSubscription) -> Result<()> {
+    require!(
+        subscription.nonce == 0 || subscription.state_version == USER_SUBSCRIPTION_STATE_VERSION,
+        ErrorCode::StaleStateVersion
+    );
+    Ok(())
+}
+
+/// Mint a subscription is billed in. Reference-currency plans bill in the mint
+/// chosen at subscribe time; older subscriptions without one use the plan's mint.
+fn subscription_payment_mint(plan: &SubscriptionPlan, subscription: &UserSubscription) -> Pubkey {
+    if plan.price_currency == MINT_PRICE_CURRENCY || subscription.payment_mint == Pubkey::default() {
+        plan.mint
+    } else {
+        subscription.payment_mint
+    }
+}
+
+/// Whether a computation currently holds the pool's liabilities lock
+fn is_liabilities_locked(pool: &ProtocolPool, now: i64) -> bool {
+    pool.liabilities_lock != Pubkey::default()
+        && now < pool.liabilities_locked_at + LIABILITIES_LOCK_TIMEOUT_SECONDS
+}
+
+/// Whether a queued purchase or redemption still holds the voucher pool
+fn is_voucher_pool_locked(voucher_pool: &VoucherPool, now: i64) -> bool {
+    voucher_pool.lock != Pubkey::default()
+        && now < voucher_pool.locked_at + VOUCHER_POOL_LOCK_TIMEOUT_SECONDS
+}
+
+/// Take the voucher pool's lock for `computation`. The encrypted slots are read
+/// when a purchase or redemption is queued and overwritten by its callback.
+fn lock_voucher_pool(voucher_pool: &mut VoucherPool, computation: Pubkey, now: i64) -> Result<()> {
+    require!(!is_voucher_pool_locked(voucher_pool, now), ErrorCode::VoucherPoolBusy);
+    voucher_pool.lock = computation;
+    voucher_pool.locked_at = now;
+    Ok(())
+}
+
+/// Release the voucher pool's lock in a callback. A callback that lost the lock
+/// to a timeout must not overwrite the slots written after it.
+fn unlock_voucher_pool(voucher_pool: &mut VoucherPool, computation: Pubkey) -> Result<()> {
+    require!(voucher_pool.lock == computation, ErrorCode::StaleVoucherPoolUpdate);
+    voucher_pool.lock = Pubkey::default();
+    Ok(())
+}
+
+/// Take the pool's liabilities lock for `computation`. Total liabilities and the
+/// yield state are read when a computation is queued and overwritten by its
+/// callback, so only one computation updating them may be in flight per pool.
+fn lock_pool_liabilities(pool: &mut ProtocolPool, computation: Pubkey, now: i64) -> Result<()> {
+    require!(pool.liabilities_seeded, ErrorCode::LiabilitiesNotSeeded);
+    require!(!is_liabilities_locked(pool, now), ErrorCode::LiabilitiesBusy);
+    pool.liabilities_lock = computation;
+    pool.liabilities_locked_at = now;
+    Ok(())
+}
+
+/// Release the liabilities lock in a callback. A callback that lost its lock to a
+/// timeout must not overwrite the state written after it.
+fn unlock_pool_liabilities(pool: &mut ProtocolPool, computation: Pubkey) -> Result<()> {
+    require!(pool.liabilities_lock == computation, ErrorCode::StaleLiabilitiesUpdate);
+    pool.liabilities_lock = Pubkey::default();
+    Ok(())
+}
+
+/// Fill in a freshly created UserLedger. The encryption key stays unset until the
+/// ledger's owner supplies it, so whoever pays for the account cannot choose it.
+fn init_user_ledger_if_new(user_ledger: &mut UserLedger, user: Pubkey, mint: Pubkey, bump: u8) {
+    if user_ledger.user == Pubkey::default() {
+        user_ledger.user = user;
+        user_ledger.mint = mint;
+        user_ledger.encryption_pubkey = [0u8; 32];
+        user_ledger.encrypted_balance = [0u8; 32];
+        user_ledger.encrypted_subscription_count = [0u8; 32];
+        user_ledger.encrypted_accrued_yield = [0u8; 32];
+        user_ledger.encrypted_principal_locked = [0u8; 32];
+        user_ledger.state_version = USER_LEDGER_STATE_VERSION;
+        user_ledger.nonce = 0;
+        user_ledger.bump = bump;
+        user_ledger.account_version = UserLedger::VERSION;
+        user_ledger.encrypted_yield_checkpoint = [0u8; 32];
+        user_ledger.yield_checkpoint_nonce = 0;
+        user_ledger.yield_epoch = 0;
+        user_ledger.encrypted_pending_transfers = [0u8; 32];
+        user_ledger.pending_transfers_nonce = 0;
+    }
+}
+
+/// Set the key of a ledger its owner is signing for. A ledger created by a transfer
+/// takes the owner's key; any other ledger must already use it.
+fn set_ledger_key(user_ledger: &mut UserLedger, encryption_pubkey: [u8; 32]) -> Result<()> {
+    if is_zero_pubkey(&user_ledger.encryption_pubkey) {
+        user_ledger.encryption_pubkey = encryption_pubkey;
+    } else {
+        require!(
+            user_ledger.encryption_pubkey == encryption_pubkey,
+            ErrorCode::EncryptionKeyMismatch
+        );
+    }
+    Ok(())
+}
+
+/// Fill in a freshly created SpendingCap (an unset cap is unlimited in the circuits)
+fn init_spending_cap_if_new(
+    spending_cap: &mut SpendingCap,
+    user: Pubkey,
+    merchant: Pubkey,
+    mint: Pubkey,
+    encryption_pubkey: [u8; 32],
+    bump: u8,
+) {
+    if spending_cap.user == Pubkey::default() {
+        spending_cap.user = user;
+        spending_cap.merchant = merchant;
+        spending_cap.mint = mint;
+        spending_cap.encryption_pubkey = encryption_pubkey;
+        spending_cap.encrypted_cap = [0u8; 32];
+        spending_cap.encrypted_window_seconds = [0u8; 32];
+        spending_cap.encrypted_window_start = [0u8; 32];
+        spending_cap.encrypted_spent = [0u8; 32];
+        spending_cap.nonce = 0;
+        spending_cap.bump = bump;
+    }
+}
+
+/// Fill in a freshly created PlanStats, encrypted to the merchant ledger key
+fn init_plan_stats_if_new(
+    plan_stats: &mut PlanStats,
+    subscription_plan: Pubkey,
+    merchant: Pubkey,
+    encryption_pubkey: [u8; 32],
+    bump: u8,
+) {
+    if plan_stats.subscription_plan == Pubkey::default() {
+        plan_stats.subscription_plan = subscription_plan;
+        plan_stats.merchant = merchant;
+        plan_stats.encryption_pubkey = encryption_pubkey;
+        plan_stats.encrypted_active_subscribers = [0u8; 32];
+        plan_stats.encrypted_mrr = [0u8; 32];
+        plan_stats.encrypted_period_start = [0u8; 32];
+        plan_stats.encrypted_new_subscribers = [0u8; 32];
+        plan_stats.encrypted_churned_subscribers = [0u8; 32];
+        plan_stats.encrypted_previous_new_subscribers = [0u8; 32];
+        plan_stats.encrypted_previous_churned_subscribers = [0u8; 32];
+        plan_stats.nonce = 0;
+        plan_stats.bump = bump;
+    }
+}
+
+/// Append the pool's encrypted PoolYieldState (Enc<Mxe>) to an ArgBuilder
+fn pool_yield_args(args: ArgBuilder, protocol_pool: &ProtocolPool) -> ArgBuilder {
+    args.plaintext_u128(protocol_pool.yield_nonce)
+        .encrypted_state(&protocol_pool.yield_state())
+}
+
+/// Append a ledger's encrypted yield checkpoint (Enc<Mxe, u128>) to an ArgBuilder
+fn yield_checkpoint_args(args: ArgBuilder, user_ledger: &UserLedger) -> ArgBuilder {
+    args.plaintext_u128(user_ledger.yield_checkpoint_nonce)
+        .encrypted::<u128>(&user_ledger.encrypted_yield_checkpoint)  // yield_checkpoint
+}
+
+/// Append a ledger's encrypted pending transfers (Enc<Mxe, u64>) to an ArgBuilder
+fn pending_transfers_args(args: ArgBuilder, user_ledger: &UserLedger) -> ArgBuilder {
+    args.plaintext_u128(user_ledger.pending_transfers_nonce)
+        .encrypted::<u64>(&user_ledger.encrypted_pending_transfers)  // pending_transfers
+}
+
+/// Append a subscription's encrypted charge record (Enc<Mxe, ChargeRecordState>) to an ArgBuilder
+fn charge_record_args(args: ArgBuilder, user_subscription: &UserSubscription) -> ArgBuilder {
+    args.plaintext_u128(user_subscription.charges_nonce)
+        .encrypted_state(&user_subscription.charges())
+}
+
+/// Split `amount` by basis-point shares, rounding each share down.
+/// The rounding dust goes to the first share so payouts always sum to `amount`.
+fn split_by_bps(amount: u64, shares_bps: &[u16]) -> Vec<u64> {
+    let mut payouts: Vec<u64> = shares_bps
+        .iter()
+        .map(|bps| ((amount as u128) * (*bps as u128) / (SPLIT_TOTAL_BPS as u128)) as u64)
+        .collect();
+    let distributed: u64 = payouts.iter().sum();
+    if let Some(first) = payouts.first_mut() {
+        *first += amount - distributed;
+    }
+    payouts
+}
+
+/// Check that an audit grant covers `target` and may be used by `requester` now
+fn check_audit_grant(audit_grant: &AuditGrant, requester: Pubkey, target: Pubkey) -> Result<()> {
+    require!(audit_grant.target == target, ErrorCode::Unauthorized);
+    require!(
+        requester == audit_grant.owner || requester == audit_grant.auditor,
+        ErrorCode::Unauthorized
+    );
+    require!(
+        Clock::get()?.unix_timestamp < audit_grant.expires_at,
+        ErrorCode::AuditGrantExpired
+    );
+    Ok(())
+}
+
+fn is_screening_authority(protocol_config: &ProtocolConfig, key: &Pubkey) -> bool {
+    protocol_config.screening_authorities[..protocol_config.screening_authority_count as usize]
+        .contains(key)
+}
+
+/// Amount that can still leave the pool in the current outflow epoch
+fn outflow_budget_remaining(protocol_pool: &ProtocolPool, now: i64) -> u64 {
+    if protocol_pool.outflow_epoch_budget == 0 {
+        return u64::MAX;
+    }
+    if now >= protocol_pool.outflow_epoch_start + protocol_pool.outflow_epoch_seconds {
+        return protocol_pool.outflow_epoch_budget;
+    }
+    protocol_pool.outflow_epoch_budget.saturating_sub(protocol_pool.outflow_epoch_spent)
+}
+
+/// Charge a token outflow against the pool's epoch budget
+fn consume_outflow_budget(protocol_pool: &mut ProtocolPool, amount: u64, now: i64) -> Result<()> {
+    if protocol_pool.outflow_epoch_budget == 0 {
+        return Ok(());
+    }
+    require!(
+        amount <= outflow_budget_remaining(protocol_pool, now),
+        ErrorCode::OutflowBudgetExceeded
+    );
+    if now >= protocol_pool.outflow_epoch_start + protocol_pool.outflow_epoch_seconds {
+        protocol_pool.outflow_epoch_start = now;
+        protocol_pool.outflow_epoch_spent = 0;
+    }
+    protocol_pool.outflow_epoch_spent += amount;
+    Ok(())
+}
+
+/// Check the outflow limits when a withdrawal or claim is queued.
+/// Amounts above the large-outflow threshold must match a matured request, which is
+/// tied to `computation` and only used up once its callback pays the outflow.
+fn check_outflow_limits(
+    protocol_pool: &ProtocolPool,
+    pending_outflow: Option<&mut PendingOutflow>,
+    amount: u64,
+    computation: Pubkey,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        amount <= outflow_budget_remaining(protocol_pool, now),
+        ErrorCode::OutflowBudgetExceeded
+    );
+
+    if protocol_pool.large_outflow_threshold == 0 || amount <= protocol_pool.large_outflow_threshold {
+        return Ok(());
+    }
+    let pending_outflow = pending_outflow.ok_or(ErrorCode::OutflowRequestRequired)?;
+    require!(pending_outflow.amount == amount, ErrorCode::OutflowRequestMismatch);
+    require!(now >= pending_outflow.executable_at, ErrorCode::OutflowRequestLocked);
+    // The liabilities lock keeps a second computation from being queued while this
+    // one is in flight, so a previous tie belongs to a callback that never landed
+    pending_outflow.pending_computation = computation;
+
+    Ok(())
+}
+
+/// Use up a large-outflow request in the callback that paid it. A request tied to
+/// another computation, or a callback that paid nothing, leaves it in place.
+fn consume_pending_outflow(
+    pending_outflow: Option<&mut PendingOutflow>,
+    computation: Pubkey,
+    actual_amount: u64,
+) {
+    if let Some(pending_outflow) = pending_outflow {
+        if pending_outflow.pending_computation != computation {
+            return;
+        }
+        pending_outflow.pending_computation = Pubkey::default();
+        if actual_amount > 0 {
+            pending_outflow.amount = 0;
+        }
+    }
+}
+
+/// Callback slot for an optional account; the program id stands in for `None`
+fn optional_callback_account(pubkey: Option<Pubkey>) -> CallbackAccount {
+    CallbackAccount {
+        pubkey: pubkey.unwrap_or(crate::ID),
+        is_writable: pubkey.is_some(),
+    }
+}
+
+/// Enforce screening of the wallet funds are credited to when the pool is in
+/// compliance mode. Withdrawals are never screened, so a revocation, an expiry
+/// or compliance mode being switched on never strands an existing balance.
+fn check_screening(
+    protocol_pool: &ProtocolPool,
+    protocol_config: &ProtocolConfig,
+    screening_attestation: Option<&ScreeningAttestation>,
+) -> Result<()> {
+    if !protocol_pool.compliance_mode {
+        return Ok(());
+    }
+
+    let attestation = screening_attestation.ok_or(ErrorCode::ScreeningRequired)?;
+    require!(!attestation.is_revoked, ErrorCode::ScreeningRevoked);
+    require!(
+        is_screening_authority(protocol_config, &attestation.issuer),
+        ErrorCode::UnknownScreeningAuthority
+    );
+    require!(
+        Clock::get()?.unix_timestamp < attestation.expires_at,
+        ErrorCode::ScreeningExpired
+    );
+
+    Ok(())
+}
+
+// ============================================================================
+// Lending Strategies
+// ============================================================================
+
+/// Adapter over a lending program that idle pool liquidity can be parked in.
+/// The pool PDA owns the lending position, so calls are signed with the pool seeds.
+pub trait LendingStrategy {
+    /// Move `amount` from the pool token account into the lending position
+    fn deposit(&self, amount: u64, signer: &[&[&[u8]]]) -> Result<()>;
+    /// Move `amount` from the lending position back into the pool token account
+    fn withdraw(&self, amount: u64, signer: &[&[&[u8]]]) -> Result<()>;
+    /// Current value of the position, principal plus accrued interest
+    fn position_value(&self) -> Result<u64>;
+}
+
+/// Accounts a lending strategy adapter operates on
+pub struct StrategyAccounts<'info> {
+    pub payer: AccountInfo<'info>,
+    pub protocol_pool: AccountInfo<'info>,
+    pub pool_token_account: AccountInfo<'info>,
+    pub lending_program: AccountInfo<'info>,
+    pub reserve: AccountInfo<'info>,
+    pub vault: AccountInfo<'info>,
+    pub position: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Whether this build has an adapter for `program`
+fn is_supported_lending_program(program: &Pubkey) -> bool {
+    #[cfg(feature = "mock-programs")]
+    if *program == mock_lending::ID {
+        return true;
+    }
+    let _ = program;
+    false
+}
+
+/// Build the adapter for the lending program in `accounts`
+fn lending_strategy<'info>(accounts: StrategyAccounts<'info>) -> Result<Box<dyn LendingStrategy + 'info>> {
+    #[cfg(feature = "mock-programs")]
+    if accounts.lending_program.key() == mock_lending::ID {
+        return Ok(Box::new(MockLendingStrategy(accounts)));
+    }
+    let _ = accounts;
+    err!(ErrorCode::UnsupportedLendingStrategy)
+}
+
+/// Strategy backed by the mock lending program.
+/// Only compiled with the `mock-programs` feature, which localnet and test builds enable.
+#[cfg(feature = "mock-programs")]
+pub struct MockLendingStrategy<'info>(pub StrategyAccounts<'info>);
+
+#[cfg(feature = "mock-programs")]
+impl<'info> LendingStrategy for MockLendingStrategy<'info> {
+    fn deposit(&self, amount: u64, signer: &[&[&[u8]]]) -> Result<()> {
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.0.lending_program.clone(),
+            mock_lending::cpi::accounts::Deposit {
+                payer: self.0.payer.clone(),
+                owner: self.0.protocol_pool.clone(),
+                reserve: self.0.reserve.clone(),
+                vault: self.0.vault.clone(),
+                position: self.0.position.clone(),
+                source: self.0.pool_token_account.clone(),
+                token_program: self.0.token_program.clone(),
+                system_program: self.0.system_program.clone(),
+            },
+            signer,
+        );
+        mock_lending::cpi::deposit(cpi_ctx, amount)
+    }
+
+    fn withdraw(&self, amount: u64, signer: &[&[&[u8]]]) -> Result<()> {
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.0.lending_program.clone(),
+            mock_lending::cpi::accounts::Withdraw {
+                owner: self.0.protocol_pool.clone(),
+                reserve: self.0.reserve.clone(),
+                vault: self.0.vault.clone(),
+                position: self.0.position.clone(),
+                destination: self.0.pool_token_account.clone(),
+                token_program: self.0.token_program.clone(),
+            },
+            signer,
+        );
+        mock_lending::cpi::withdraw(cpi_ctx, amount)
+    }
+
+    fn position_value(&self) -> Result<u64> {
+        // No position until the first allocation
+        if self.0.position.data_is_empty() {
+            return Ok(0);
+        }
+        require!(
+            *self.0.position.owner == mock_lending::ID,
+            ErrorCode::InvalidStrategyAccounts
+        );
+        let position = mock_lending::Position::try_deserialize(&mut &self.0.position.try_borrow_data()?[..])?;
+        require!(
+            position.owner == self.0.protocol_pool.key() && position.reserve == self.0.reserve.key(),
+            ErrorCode::InvalidStrategyAccounts
+        );
+        Ok(position.amount)
+    }
+}
+
+// ============================================================================
+// Price Oracles
+// ============================================================================
+
+/// Oracle price of one whole token in a reference currency: `price * 10^expo`
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+/// Pyth Solana Receiver program, which owns verified `PriceUpdateV2` accounts
+/// (including the sponsored feed accounts maintained by the Pyth push oracle)
+pub mod pyth_receiver {
+    anchor_lang::declare_id!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+}
+
+/// Anchor discriminator of the Pyth receiver's `PriceUpdateV2` account
+const PYTH_PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Whether this build can read prices published by `program`
+fn is_supported_price_oracle(program: &Pubkey) -> bool {
+    #[cfg(feature = "mock-programs")]
+    if *program == mock_oracle::ID {
+        return true;
+    }
+    *program == pyth_receiver::ID
+}
+
+/// Read the current price from a feed's oracle account
+pub fn read_oracle_price(feed: &PriceFeedConfig, price_account: &AccountInfo) -> Result<OraclePrice> {
+    require!(
+        price_account.key() == feed.price_account && *price_account.owner == feed.oracle_program,
+        ErrorCode::InvalidPriceAccount
+    );
+    if feed.oracle_program == pyth_receiver::ID {
+        return read_pyth_price(&price_account.try_borrow_data()?, &feed.feed_id);
+    }
+    #[cfg(feature = "mock-programs")]
+    if feed.oracle_program == mock_oracle::ID {
+        let account = mock_oracle::PriceFeed::try_deserialize(&mut &price_account.try_borrow_data()?[..])?;
+        return Ok(OraclePrice {
+            price: account.price,
+            conf: account.conf,
+            expo: account.expo,
+            publish_time: account.publish_time,
+        });
+    }
+    err!(ErrorCode::UnsupportedPriceOracle)
+}
+
+/// Decode a Pyth `PriceUpdateV2` account:
+/// discriminator, write_authority, verification_level, price_message, posted_slot.
+/// Only fully verified updates for the approved feed id are accepted.
+fn read_pyth_price(data: &[u8], feed_id: &[u8; 32]) -> Result<OraclePrice> {
+    require!(
+        data.len() >= 8 && data[..8] == PYTH_PRICE_UPDATE_DISCRIMINATOR,
+        ErrorCode::InvalidPriceAccount
+    );
+    // verification_level is Borsh-encoded: 0 = Partial { num_signatures: u8 }, 1 = Full
+    let level = 8 + 32;
+    require!(data.get(level) == Some(&1), ErrorCode::UnverifiedOraclePrice);
+
+    // price_message: feed_id [u8; 32], price i64, conf u64, exponent i32, publish_time i64, ...
+    let message = level + 1;
+    let field = |offset: usize, len: usize| -> Result<&[u8]> {
+        data.get(message + offset..message + offset + len)
+            .ok_or_else(|| error!(ErrorCode::InvalidPriceAccount))
+    };
+    require!(field(0, 32)? == feed_id, ErrorCode::PriceFeedIdMismatch);
+    Ok(OraclePrice {
+        price: i64::from_le_bytes(field(32, 8)?.try_into().unwrap()),
+        conf: u64::from_le_bytes(field(40, 8)?.try_into().unwrap()),
+        expo: i32::from_le_bytes(field(48, 4)?.try_into().unwrap()),
+        publish_time: i64::from_le_bytes(field(52, 8)?.try_into().unwrap()),
+    })
+}
+
+/// Per-seat amount a plan charges in `mint`.
+/// Plans priced in their own mint pass through unchanged; reference-currency
+/// plans are converted at the oracle price, rounding up in the merchant's favour.
+pub fn settlement_price(
+    plan: &SubscriptionPlan,
+    mint: &Mint,
+    price_feed: Option<&PriceFeedConfig>,
+    price_account: Option<&AccountInfo>,
+    current_timestamp: i64,
+) -> Result<u64> {
+    if plan.price_currency == MINT_PRICE_CURRENCY {
+        return Ok(plan.price);
+    }
+    let (Some(feed), Some(price_account)) = (price_feed, price_account) else {
+        return err!(ErrorCode::MissingPriceFeed);
+    };
+
+    let quote = read_oracle_price(feed, price_account)?;
+    require!(quote.price > 0, ErrorCode::InvalidOraclePrice);
+    require!(
+        current_timestamp.saturating_sub(quote.publish_time) <= feed.max_staleness_seconds as i64,
+        ErrorCode::StaleOraclePrice
+    );
+    let price = quote.price as u128;
+    require!(
+        (quote.conf as u128) * (BPS_DENOMINATOR as u128) <= price * (feed.max_confidence_bps as u128),
+        ErrorCode::OraclePriceTooUncertain
+    );
+
+    // amount = plan.price / 10^REFERENCE_PRICE_DECIMALS / (price * 10^expo) * 10^mint.decimals
+    let scale = |exp: u32| 10u128.checked_pow(exp).ok_or(ErrorCode::ArithmeticOverflow);
+    let mut numerator = (plan.price as u128)
+        .checked_mul(scale(mint.decimals as u32)?)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    let mut denominator = price
+        .checked_mul(scale(REFERENCE_PRICE_DECIMALS)?)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    if quote.expo < 0 {
+        numerator = numerator
+            .checked_mul(scale(quote.expo.unsigned_abs())?)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    } else {
+        denominator = denominator
+            .checked_mul(scale(quote.expo as u32)?)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    let amount = numerator.div_ceil(denominator);
+    u64::try_from(amount).map_err(|_| error!(ErrorCode::ArithmeticOverflow))
+}
+
+// ============================================================================
+// Account Versioning
+// ============================================================================
+
+/// Accounts `migrate_account` can grow to their current layout in place.
+/// Fields added after an account type first shipped go after its `bump` and
+/// must be valid when zero (unseeded pool liabilities, a `state_version` of 0
+/// for ciphertexts written before versioning, ...), so the bytes a realloc
+/// adds already hold the right values; only the version is stamped.
+pub trait VersionedAccount: AccountSerialize + AccountDeserialize + Discriminator {
+    /// Serialized size of the current layout, including the discriminator
+    const CURRENT_SIZE: usize;
+    /// Current layout version
+    const CURRENT_VERSION: u8;
+    fn account_version(&self) -> u8;
+    fn set_account_version(&mut self, version: u8);
+}
+
+macro_rules! impl_versioned_account {
+    ($($account:ident),* $(,)?) => {$(
+        impl VersionedAccount for $account {
+            const CURRENT_SIZE: usize = $account::SIZE;
+            const CURRENT_VERSION: u8 = $account::VERSION;
+
+            fn account_version(&self) -> u8 {
+                self.account_version
+            }
+
+            fn set_account_version(&mut self, version: u8) {
+                self.account_version = version;
+            }
+        }
+    )*};
+}
+
+impl_versioned_account!(
+    ProtocolConfig,
+    ProtocolPool,
+    Merchant,
+    MerchantLedger,
+    SubscriptionPlan,
+    UserLedger,
+    UserSubscription,
+);
+
+/// Realloc `account` to `T`'s current size and stamp the current version.
+/// The payer tops up the rent-exempt minimum for the added bytes.
+fn migrate_versioned_account<'info, T: VersionedAccount>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if account.data_len() < T::CURRENT_SIZE {
+        let rent_exempt = Rent::get()?.minimum_balance(T::CURRENT_SIZE);
+        let top_up = rent_exempt.saturating_sub(account.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.clone(),
+                        to: account.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        account.resize(T::CURRENT_SIZE)?;
+    }
+
+    let mut state = T::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    require!(
+        state.account_version() < T::CURRENT_VERSION,
+        ErrorCode::AccountAlreadyCurrent
+    );
+    state.set_account_version(T::CURRENT_VERSION);
+    let mut data = account.try_borrow_mut_data()?;
+    state.try_serialize(&mut &mut data[..])?;
+
+    Ok(())
+}
+
 // ============================================================================
 // Program Module
 // ============================================================================
@@ -88,6 +865,165 @@ pub mod privacy_subscriptions {
         protocol_config.fee_rate_bps = fee_rate_bps;
         protocol_config.is_paused = false;
         protocol_config.bump = ctx.bumps.protocol_config;
+        protocol_config.account_version = ProtocolConfig::VERSION;
+        protocol_config.arbiter = ctx.accounts.authority.key();
+        protocol_config.screening_authorities = [Pubkey::default(); MAX_SCREENING_AUTHORITIES];
+        protocol_config.screening_authority_count = 0;
+
+        Ok(())
+    }
+
+    /// Appoint the arbiter that resolves disputes
+    pub fn set_arbiter(ctx: Context<SetArbiter>, arbiter: Pubkey) -> Result<()> {
+        ctx.accounts.protocol_config.arbiter = arbiter;
+
+        Ok(())
+    }
+
+    /// List an authority allowed to issue screening attestations
+    pub fn add_screening_authority(
+        ctx: Context<ManageScreeningAuthorities>,
+        screening_authority: Pubkey,
+    ) -> Result<()> {
+        let protocol_config = &mut ctx.accounts.protocol_config;
+        require!(
+            !is_screening_authority(protocol_config, &screening_authority),
+            ErrorCode::ScreeningAuthorityExists
+        );
+        let count = protocol_config.screening_authority_count as usize;
+        require!(count < MAX_SCREENING_AUTHORITIES, ErrorCode::TooManyScreeningAuthorities);
+
+        protocol_config.screening_authorities[count] = screening_authority;
+        protocol_config.screening_authority_count += 1;
+
+        Ok(())
+    }
+
+    /// Delist a screening authority; its attestations stop admitting deposits
+    pub fn remove_screening_authority(
+        ctx: Context<ManageScreeningAuthorities>,
+        screening_authority: Pubkey,
+    ) -> Result<()> {
+        let protocol_config = &mut ctx.accounts.protocol_config;
+        let count = protocol_config.screening_authority_count as usize;
+        let index = protocol_config.screening_authorities[..count]
+            .iter()
+            .position(|key| *key == screening_authority)
+            .ok_or(ErrorCode::UnknownScreeningAuthority)?;
+
+        protocol_config.screening_authorities[index] = protocol_config.screening_authorities[count - 1];
+        protocol_config.screening_authorities[count - 1] = Pubkey::default();
+        protocol_config.screening_authority_count -= 1;
+
+        Ok(())
+    }
+
+    /// Turn screening requirements on or off for a pool
+    pub fn set_compliance_mode(ctx: Context<SetComplianceMode>, compliance_mode: bool) -> Result<()> {
+        ctx.accounts.protocol_pool.compliance_mode = compliance_mode;
+
+        Ok(())
+    }
+
+    /// Configure a pool's per-epoch outflow budget and large-outflow time lock
+    pub fn set_outflow_limits(
+        ctx: Context<SetOutflowLimits>,
+        epoch_seconds: i64,
+        epoch_budget: u64,
+        large_outflow_threshold: u64,
+        large_outflow_delay_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            epoch_seconds > 0 && large_outflow_delay_seconds >= 0,
+            ErrorCode::InvalidOutflowLimits
+        );
+
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.outflow_epoch_seconds = epoch_seconds;
+        protocol_pool.outflow_epoch_budget = epoch_budget;
+        protocol_pool.large_outflow_threshold = large_outflow_threshold;
+        protocol_pool.large_outflow_delay_seconds = large_outflow_delay_seconds;
+
+        Ok(())
+    }
+
+    /// Record a large withdrawal or claim; it can be executed once the pool's delay has passed
+    pub fn request_large_outflow(
+        ctx: Context<RequestLargeOutflow>,
+        kind: u8,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            kind == OUTFLOW_KIND_WITHDRAW || kind == OUTFLOW_KIND_CLAIM,
+            ErrorCode::InvalidOutflowKind
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let delay = ctx.accounts.protocol_pool.large_outflow_delay_seconds;
+        let pending_outflow = &mut ctx.accounts.pending_outflow;
+        require!(pending_outflow.amount == 0, ErrorCode::OutflowRequestPending);
+
+        pending_outflow.owner = ctx.accounts.owner.key();
+        pending_outflow.mint = ctx.accounts.mint.key();
+        pending_outflow.kind = kind;
+        pending_outflow.amount = amount;
+        pending_outflow.requested_at = now;
+        pending_outflow.executable_at = now + delay;
+        pending_outflow.bump = ctx.bumps.pending_outflow;
+        pending_outflow.pending_computation = Pubkey::default();
+
+        Ok(())
+    }
+
+    /// Cancel a large outflow request (closes the request account)
+    pub fn cancel_large_outflow(_ctx: Context<CancelLargeOutflow>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Issue or renew a wallet's screening attestation (listed screening authority only)
+    pub fn issue_screening_attestation(
+        ctx: Context<IssueScreeningAttestation>,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            is_screening_authority(&ctx.accounts.protocol_config, &ctx.accounts.screening_authority.key()),
+            ErrorCode::UnknownScreeningAuthority
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, ErrorCode::InvalidExpiry);
+
+        // Only the authority holding an attestation may renew it or lift its
+        // revocation, unless that authority has since been delisted
+        let attestation = &mut ctx.accounts.screening_attestation;
+        if attestation.wallet != Pubkey::default() {
+            require!(
+                attestation.issuer == ctx.accounts.screening_authority.key()
+                    || !is_screening_authority(&ctx.accounts.protocol_config, &attestation.issuer),
+                ErrorCode::ScreeningAttestationHeld
+            );
+        }
+        attestation.wallet = ctx.accounts.wallet.key();
+        attestation.issuer = ctx.accounts.screening_authority.key();
+        attestation.issued_at = now;
+        attestation.expires_at = expires_at;
+        attestation.is_revoked = false;
+        attestation.bump = ctx.bumps.screening_attestation;
+
+        Ok(())
+    }
+
+    /// Revoke a wallet's screening attestation (listed screening authority only).
+    /// The revoking authority becomes the attestation's holder, so only it can lift
+    /// the revocation. The wallet can still withdraw its existing balance.
+    pub fn revoke_screening_attestation(ctx: Context<RevokeScreeningAttestation>) -> Result<()> {
+        require!(
+            is_screening_authority(&ctx.accounts.protocol_config, &ctx.accounts.screening_authority.key()),
+            ErrorCode::UnknownScreeningAuthority
+        );
+        let attestation = &mut ctx.accounts.screening_attestation;
+        attestation.is_revoked = true;
+        attestation.issuer = ctx.accounts.screening_authority.key();
 
         Ok(())
     }
@@ -98,6 +1034,26 @@ pub mod privacy_subscriptions {
         protocol_pool.mint = ctx.accounts.mint.key();
         protocol_pool.token_account = ctx.accounts.pool_token_account.key();
         protocol_pool.bump = ctx.bumps.protocol_pool;
+        protocol_pool.encrypted_total_liabilities = [0u8; 32];
+        protocol_pool.liabilities_nonce = 0;
+        protocol_pool.compliance_mode = false;
+        protocol_pool.outflow_epoch_seconds = 0;
+        protocol_pool.outflow_epoch_budget = 0;
+        protocol_pool.outflow_epoch_start = 0;
+        protocol_pool.outflow_epoch_spent = 0;
+        protocol_pool.large_outflow_threshold = 0;
+        protocol_pool.large_outflow_delay_seconds = 0;
+        protocol_pool.strategy_program = Pubkey::default();
+        protocol_pool.strategy_allocated = 0;
+        protocol_pool.encrypted_yield_state = [[0u8; 32]; PoolYieldStateCiphertexts::LEN];
+        protocol_pool.yield_nonce = 0;
+        protocol_pool.yield_epoch = 0;
+        protocol_pool.last_yield_distribution_at = 0;
+        // A fresh pool holds no balances, so the zero liabilities are accurate
+        protocol_pool.liabilities_seeded = true;
+        protocol_pool.liabilities_lock = Pubkey::default();
+        protocol_pool.liabilities_locked_at = 0;
+        protocol_pool.account_version = ProtocolPool::VERSION;
 
         Ok(())
     }
@@ -127,6 +1083,7 @@ pub mod privacy_subscriptions {
         merchant.is_active = true;
         merchant.registered_at = Clock::get()?.unix_timestamp;
         merchant.bump = ctx.bumps.merchant;
+        merchant.account_version = Merchant::VERSION;
 
         // Initialize MerchantLedger with encrypted zero balance
         let merchant_ledger = &mut ctx.accounts.merchant_ledger;
@@ -135,8 +1092,38 @@ pub mod privacy_subscriptions {
         merchant_ledger.encryption_pubkey = encryption_pubkey;
         merchant_ledger.encrypted_balance = [0u8; 32];
         merchant_ledger.encrypted_total_claimed = [0u8; 32];
+        merchant_ledger.encrypted_frozen = [0u8; 32];
+        merchant_ledger.nonce = 0;
+        merchant_ledger.bump = ctx.bumps.merchant_ledger;
+        merchant_ledger.state_version = MERCHANT_LEDGER_STATE_VERSION;
+        merchant_ledger.account_version = MerchantLedger::VERSION;
+
+        Ok(())
+    }
+
+    /// Open a ledger in an additional mint, so reference-currency plans can be
+    /// settled in it
+    pub fn open_merchant_ledger(
+        ctx: Context<OpenMerchantLedger>,
+        encryption_pubkey: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            !is_zero_pubkey(&encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(ctx.accounts.merchant.is_active, ErrorCode::MerchantNotActive);
+
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.merchant = ctx.accounts.merchant.key();
+        merchant_ledger.mint = ctx.accounts.mint.key();
+        merchant_ledger.encryption_pubkey = encryption_pubkey;
+        merchant_ledger.encrypted_balance = [0u8; 32];
+        merchant_ledger.encrypted_total_claimed = [0u8; 32];
+        merchant_ledger.encrypted_frozen = [0u8; 32];
         merchant_ledger.nonce = 0;
         merchant_ledger.bump = ctx.bumps.merchant_ledger;
+        merchant_ledger.state_version = MERCHANT_LEDGER_STATE_VERSION;
+        merchant_ledger.account_version = MerchantLedger::VERSION;
 
         Ok(())
     }
@@ -152,8 +1139,7 @@ pub mod privacy_subscriptions {
         require!(name.len() <= MAX_PLAN_NAME_LENGTH, ErrorCode::NameTooLong);
         require!(price > 0, ErrorCode::InvalidPrice);
         require!(
-            billing_cycle_days >= MIN_BILLING_CYCLE_DAYS
-                && billing_cycle_days <= MAX_BILLING_CYCLE_DAYS,
+            (MIN_BILLING_CYCLE_DAYS..=MAX_BILLING_CYCLE_DAYS).contains(&billing_cycle_days),
             ErrorCode::InvalidBillingCycle
         );
         require!(ctx.accounts.merchant.is_active, ErrorCode::MerchantNotActive);
@@ -175,6 +1161,8 @@ pub mod privacy_subscriptions {
         plan.is_active = true;
         plan.created_at = Clock::get()?.unix_timestamp;
         plan.bump = ctx.bumps.subscription_plan;
+        plan.account_version = SubscriptionPlan::VERSION;
+        plan.price_currency = MINT_PRICE_CURRENCY;
 
         Ok(())
     }
@@ -207,8 +1195,7 @@ pub mod privacy_subscriptions {
 
         if let Some(new_billing_cycle_days) = billing_cycle_days {
             require!(
-                new_billing_cycle_days >= MIN_BILLING_CYCLE_DAYS
-                    && new_billing_cycle_days <= MAX_BILLING_CYCLE_DAYS,
+                (MIN_BILLING_CYCLE_DAYS..=MAX_BILLING_CYCLE_DAYS).contains(&new_billing_cycle_days),
                 ErrorCode::InvalidBillingCycle
             );
             plan.billing_cycle_days = new_billing_cycle_days;
@@ -221,29 +1208,370 @@ pub mod privacy_subscriptions {
         Ok(())
     }
 
-    // ========================================================================
-    // Phase 2: Encrypted Instructions - Computation Definition Initialization
-    // ========================================================================
+    /// Quote a plan's price in a reference currency (e.g. `b"USD\0\0\0\0\0"`, with
+    /// REFERENCE_PRICE_DECIMALS decimals) or, with MINT_PRICE_CURRENCY, in its own mint.
+    /// Reference-currency plans can be paid in any mint with an approved price feed,
+    /// and one must exist for the plan's own mint. `price` is in the new currency.
+    pub fn set_plan_price_currency(
+        ctx: Context<SetPlanPriceCurrency>,
+        price_currency: [u8; 8],
+        price: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.merchant.is_active, ErrorCode::MerchantNotActive);
+        require!(price > 0, ErrorCode::InvalidPrice);
+        require!(
+            price_currency == MINT_PRICE_CURRENCY || ctx.accounts.price_feed.is_some(),
+            ErrorCode::MissingPriceFeed
+        );
+
+        let plan = &mut ctx.accounts.subscription_plan;
+        plan.price_currency = price_currency;
+        plan.price = price;
 
-    pub fn init_deposit_comp_def(ctx: Context<InitDepositCompDef>) -> Result<()> {
-        init_comp_def(
-            ctx.accounts,
-            Some(CircuitSource::OffChain(OffChainCircuitSource {
-                source: "https://raw.githubusercontent.com/SublyFi/circuits/main/deposit_v2.arcis".to_string(),
-                hash: circuit_hash!("deposit_v2"),
-            })),
-            None,
-        )?;
         Ok(())
     }
 
-    pub fn init_withdraw_comp_def(ctx: Context<InitWithdrawCompDef>) -> Result<()> {
-        init_comp_def(
-            ctx.accounts,
-            Some(CircuitSource::OffChain(OffChainCircuitSource {
-                source: "https://raw.githubusercontent.com/SublyFi/circuits/main/withdraw_v2.arcis".to_string(),
-                hash: circuit_hash!("withdraw_v2"),
-            })),
+    /// Release a member's seat (payer only)
+    pub fn unassign_seat(ctx: Context<UnassignSeat>) -> Result<()> {
+        require!(
+            ctx.accounts.seat_assignment.status != SEAT_STATUS_PENDING,
+            ErrorCode::SeatPending
+        );
+
+        // Rejected assignments already gave their reservation back
+        if ctx.accounts.seat_assignment.status == SEAT_STATUS_ACTIVE {
+            let user_subscription = &mut ctx.accounts.user_subscription;
+            user_subscription.seats_assigned = user_subscription.seats_assigned.saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    /// Configure how claimed revenue is split between payees.
+    /// Recipient token accounts are passed as remaining_accounts, in the same order as `shares_bps`.
+    pub fn set_revenue_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetRevenueSplit<'info>>,
+        shares_bps: Vec<u16>,
+    ) -> Result<()> {
+        require!(ctx.accounts.merchant.is_active, ErrorCode::MerchantNotActive);
+
+        let recipients = ctx.remaining_accounts;
+        require!(
+            !shares_bps.is_empty()
+                && shares_bps.len() <= MAX_SPLIT_RECIPIENTS
+                && recipients.len() == shares_bps.len(),
+            ErrorCode::InvalidRevenueSplit
+        );
+        require!(shares_bps.iter().all(|bps| *bps > 0), ErrorCode::InvalidRevenueSplit);
+        let total_bps: u32 = shares_bps.iter().map(|bps| *bps as u32).sum();
+        require!(total_bps == SPLIT_TOTAL_BPS as u32, ErrorCode::InvalidRevenueSplit);
+
+        let mint = ctx.accounts.mint.key();
+        let revenue_split = &mut ctx.accounts.revenue_split;
+        revenue_split.recipients = [Pubkey::default(); MAX_SPLIT_RECIPIENTS];
+        revenue_split.shares_bps = [0u16; MAX_SPLIT_RECIPIENTS];
+        for (i, recipient) in recipients.iter().enumerate() {
+            let token_account = Account::<TokenAccount>::try_from(recipient)?;
+            require!(token_account.mint == mint, ErrorCode::InvalidMint);
+            require!(
+                !revenue_split.recipients[..i].contains(&recipient.key()),
+                ErrorCode::InvalidRevenueSplit
+            );
+            revenue_split.recipients[i] = recipient.key();
+            revenue_split.shares_bps[i] = shares_bps[i];
+        }
+
+        revenue_split.merchant = ctx.accounts.merchant.key();
+        revenue_split.mint = mint;
+        revenue_split.recipient_count = shares_bps.len() as u8;
+        revenue_split.bump = ctx.bumps.revenue_split;
+
+        Ok(())
+    }
+
+    /// Configure automatic payouts of the merchant's balance for a mint
+    pub fn set_payout_schedule(
+        ctx: Context<SetPayoutSchedule>,
+        cadence_days: u32,
+        min_threshold: u64,
+        is_active: bool,
+    ) -> Result<()> {
+        require!(ctx.accounts.merchant.is_active, ErrorCode::MerchantNotActive);
+        require!(
+            (MIN_PAYOUT_CADENCE_DAYS..=MAX_PAYOUT_CADENCE_DAYS).contains(&cadence_days),
+            ErrorCode::InvalidPayoutCadence
+        );
+        require!(min_threshold > 0, ErrorCode::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let payout_schedule = &mut ctx.accounts.payout_schedule;
+        let is_new = payout_schedule.merchant == Pubkey::default();
+
+        payout_schedule.merchant = ctx.accounts.merchant.key();
+        payout_schedule.mint = ctx.accounts.mint.key();
+        payout_schedule.destination = ctx.accounts.destination_token_account.key();
+        payout_schedule.cadence_days = cadence_days;
+        payout_schedule.min_threshold = min_threshold;
+        payout_schedule.is_active = is_active;
+        if is_new {
+            payout_schedule.next_payout_at = now + (cadence_days as i64) * 86400;
+            payout_schedule.last_payout_at = 0;
+            payout_schedule.last_payout_amount = 0;
+        }
+        payout_schedule.bump = ctx.bumps.payout_schedule;
+
+        Ok(())
+    }
+
+    /// Grant an auditor read access to one of the caller's encrypted accounts
+    /// (user ledger, merchant ledger or subscription) until `expires_at`
+    pub fn grant_audit_access(
+        ctx: Context<GrantAuditAccess>,
+        auditor_encryption_pubkey: [u8; 32],
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            !is_zero_pubkey(&auditor_encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, ErrorCode::InvalidExpiry);
+
+        let audit_grant = &mut ctx.accounts.audit_grant;
+        audit_grant.owner = ctx.accounts.owner.key();
+        audit_grant.auditor = ctx.accounts.auditor.key();
+        audit_grant.target = ctx.accounts.target.key();
+        audit_grant.auditor_encryption_pubkey = auditor_encryption_pubkey;
+        audit_grant.granted_at = now;
+        audit_grant.expires_at = expires_at;
+        audit_grant.bump = ctx.bumps.audit_grant;
+
+        Ok(())
+    }
+
+    /// Revoke an auditor grant (closes the grant account)
+    pub fn revoke_audit_access(_ctx: Context<RevokeAuditAccess>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Set where a circuit version's `.arcis` files are published (authority only).
+    /// Computation definitions read the source of CIRCUIT_VERSION when initialized.
+    pub fn register_circuit_version(
+        ctx: Context<RegisterCircuitVersion>,
+        version: u8,
+        source_base_url: String,
+    ) -> Result<()> {
+        let source_base_url = source_base_url.trim_end_matches('/');
+        require!(
+            !source_base_url.is_empty() && source_base_url.len() <= MAX_CIRCUIT_SOURCE_URL_LENGTH,
+            ErrorCode::InvalidCircuitSource
+        );
+
+        let mut url_bytes = [0u8; MAX_CIRCUIT_SOURCE_URL_LENGTH];
+        url_bytes[..source_base_url.len()].copy_from_slice(source_base_url.as_bytes());
+
+        let circuit_version = &mut ctx.accounts.circuit_version;
+        circuit_version.version = version;
+        circuit_version.source_base_url = url_bytes;
+        circuit_version.updated_at = Clock::get()?.unix_timestamp;
+        circuit_version.bump = ctx.bumps.circuit_version;
+
+        Ok(())
+    }
+
+    /// Grow an account created by an older program version to its current layout.
+    /// Permissionless: added fields take their zero defaults, and the payer only
+    /// covers the extra rent.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        let mut discriminator = [0u8; 8];
+        {
+            let data = account.try_borrow_data()?;
+            require!(data.len() >= 8, ErrorCode::UnsupportedAccountMigration);
+            discriminator.copy_from_slice(&data[..8]);
+        }
+
+        match discriminator.as_slice() {
+            d if d == ProtocolConfig::DISCRIMINATOR => {
+                migrate_versioned_account::<ProtocolConfig>(&account, &payer, &system_program)
+            }
+            d if d == ProtocolPool::DISCRIMINATOR => {
+                migrate_versioned_account::<ProtocolPool>(&account, &payer, &system_program)
+            }
+            d if d == Merchant::DISCRIMINATOR => {
+                migrate_versioned_account::<Merchant>(&account, &payer, &system_program)
+            }
+            d if d == MerchantLedger::DISCRIMINATOR => {
+                migrate_versioned_account::<MerchantLedger>(&account, &payer, &system_program)
+            }
+            d if d == SubscriptionPlan::DISCRIMINATOR => {
+                migrate_versioned_account::<SubscriptionPlan>(&account, &payer, &system_program)
+            }
+            d if d == UserLedger::DISCRIMINATOR => {
+                migrate_versioned_account::<UserLedger>(&account, &payer, &system_program)
+            }
+            d if d == UserSubscription::DISCRIMINATOR => {
+                migrate_versioned_account::<UserSubscription>(&account, &payer, &system_program)
+            }
+            _ => err!(ErrorCode::UnsupportedAccountMigration),
+        }
+    }
+
+    /// Approve the oracle price account used to convert reference-currency plan
+    /// prices into a mint (authority only)
+    pub fn set_price_feed(
+        ctx: Context<SetPriceFeed>,
+        currency: [u8; 8],
+        feed_id: [u8; 32],
+        max_staleness_seconds: u32,
+        max_confidence_bps: u16,
+    ) -> Result<()> {
+        require!(currency != MINT_PRICE_CURRENCY, ErrorCode::InvalidPriceCurrency);
+        require!(
+            is_supported_price_oracle(&ctx.accounts.oracle_program.key()),
+            ErrorCode::UnsupportedPriceOracle
+        );
+        require!(
+            *ctx.accounts.price_account.owner == ctx.accounts.oracle_program.key(),
+            ErrorCode::InvalidPriceAccount
+        );
+        require!(
+            max_staleness_seconds > 0
+                && max_confidence_bps > 0
+                && max_confidence_bps <= BPS_DENOMINATOR,
+            ErrorCode::InvalidPriceFeedConfig
+        );
+
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.mint = ctx.accounts.mint.key();
+        price_feed.currency = currency;
+        price_feed.oracle_program = ctx.accounts.oracle_program.key();
+        price_feed.price_account = ctx.accounts.price_account.key();
+        price_feed.feed_id = feed_id;
+        price_feed.max_staleness_seconds = max_staleness_seconds;
+        price_feed.max_confidence_bps = max_confidence_bps;
+        price_feed.bump = ctx.bumps.price_feed;
+
+        // The price account must already publish the configured feed
+        read_oracle_price(price_feed, &ctx.accounts.price_account)?;
+
+        Ok(())
+    }
+
+    /// Choose the lending program a pool's idle liquidity can be allocated to
+    pub fn set_lending_strategy(ctx: Context<SetLendingStrategy>) -> Result<()> {
+        require!(
+            is_supported_lending_program(&ctx.accounts.lending_program.key()),
+            ErrorCode::UnsupportedLendingStrategy
+        );
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        require!(protocol_pool.strategy_allocated == 0, ErrorCode::StrategyInUse);
+
+        protocol_pool.strategy_program = ctx.accounts.lending_program.key();
+
+        Ok(())
+    }
+
+    /// Move idle pool liquidity into the lending strategy (authority only)
+    pub fn allocate_to_strategy(ctx: Context<ManageStrategyAllocation>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.pool_token_account.amount,
+            ErrorCode::InsufficientPoolLiquidity
+        );
+
+        let strategy = lending_strategy(StrategyAccounts {
+            payer: ctx.accounts.authority.to_account_info(),
+            protocol_pool: ctx.accounts.protocol_pool.to_account_info(),
+            pool_token_account: ctx.accounts.pool_token_account.to_account_info(),
+            lending_program: ctx.accounts.lending_program.to_account_info(),
+            reserve: ctx.accounts.lending_reserve.to_account_info(),
+            vault: ctx.accounts.lending_vault.to_account_info(),
+            position: ctx.accounts.lending_position.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        })?;
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let signer_seeds: &[&[u8]] = &[
+            PROTOCOL_POOL_SEED,
+            protocol_pool.mint.as_ref(),
+            &[protocol_pool.bump],
+        ];
+        strategy.deposit(amount, &[signer_seeds])?;
+
+        ctx.accounts.protocol_pool.strategy_allocated += amount;
+
+        emit!(StrategyAllocationChanged {
+            pool: ctx.accounts.protocol_pool.key(),
+            strategy_allocated: ctx.accounts.protocol_pool.strategy_allocated,
+        });
+
+        Ok(())
+    }
+
+    /// Return allocated principal from the lending strategy to the pool (authority only)
+    pub fn deallocate_from_strategy(ctx: Context<ManageStrategyAllocation>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.protocol_pool.strategy_allocated,
+            ErrorCode::ExceedsStrategyAllocation
+        );
+
+        let strategy = lending_strategy(StrategyAccounts {
+            payer: ctx.accounts.authority.to_account_info(),
+            protocol_pool: ctx.accounts.protocol_pool.to_account_info(),
+            pool_token_account: ctx.accounts.pool_token_account.to_account_info(),
+            lending_program: ctx.accounts.lending_program.to_account_info(),
+            reserve: ctx.accounts.lending_reserve.to_account_info(),
+            vault: ctx.accounts.lending_vault.to_account_info(),
+            position: ctx.accounts.lending_position.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        })?;
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let signer_seeds: &[&[u8]] = &[
+            PROTOCOL_POOL_SEED,
+            protocol_pool.mint.as_ref(),
+            &[protocol_pool.bump],
+        ];
+        strategy.withdraw(amount, &[signer_seeds])?;
+
+        ctx.accounts.protocol_pool.strategy_allocated -= amount;
+
+        emit!(StrategyAllocationChanged {
+            pool: ctx.accounts.protocol_pool.key(),
+            strategy_allocated: ctx.accounts.protocol_pool.strategy_allocated,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Phase 2: Encrypted Instructions - Computation Definition Initialization
+    // ========================================================================
+
+    pub fn init_deposit_comp_def(ctx: Context<InitDepositCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "deposit_v2"),
+                hash: circuit_hash!("deposit_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_withdraw_comp_def(ctx: Context<InitWithdrawCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "withdraw_v2"),
+                hash: circuit_hash!("withdraw_v2"),
+            })),
             None,
         )?;
         Ok(())
@@ -253,7 +1581,7 @@ pub mod privacy_subscriptions {
         init_comp_def(
             ctx.accounts,
             Some(CircuitSource::OffChain(OffChainCircuitSource {
-                source: "https://raw.githubusercontent.com/SublyFi/circuits/main/subscribe_v2.arcis".to_string(),
+                source: circuit_source_url(&ctx.accounts.circuit_version, "subscribe_v2"),
                 hash: circuit_hash!("subscribe_v2"),
             })),
             None,
@@ -265,7 +1593,7 @@ pub mod privacy_subscriptions {
         init_comp_def(
             ctx.accounts,
             Some(CircuitSource::OffChain(OffChainCircuitSource {
-                source: "https://raw.githubusercontent.com/SublyFi/circuits/main/unsubscribe_v2.arcis".to_string(),
+                source: circuit_source_url(&ctx.accounts.circuit_version, "unsubscribe_v2"),
                 hash: circuit_hash!("unsubscribe_v2"),
             })),
             None,
@@ -277,7 +1605,7 @@ pub mod privacy_subscriptions {
         init_comp_def(
             ctx.accounts,
             Some(CircuitSource::OffChain(OffChainCircuitSource {
-                source: "https://raw.githubusercontent.com/SublyFi/circuits/main/process_payment_v2.arcis".to_string(),
+                source: circuit_source_url(&ctx.accounts.circuit_version, "process_payment_v2"),
                 hash: circuit_hash!("process_payment_v2"),
             })),
             None,
@@ -289,7 +1617,7 @@ pub mod privacy_subscriptions {
         init_comp_def(
             ctx.accounts,
             Some(CircuitSource::OffChain(OffChainCircuitSource {
-                source: "https://raw.githubusercontent.com/SublyFi/circuits/main/verify_subscription_v2.arcis".to_string(),
+                source: circuit_source_url(&ctx.accounts.circuit_version, "verify_subscription_v2"),
                 hash: circuit_hash!("verify_subscription_v2"),
             })),
             None,
@@ -301,7 +1629,7 @@ pub mod privacy_subscriptions {
         init_comp_def(
             ctx.accounts,
             Some(CircuitSource::OffChain(OffChainCircuitSource {
-                source: "https://raw.githubusercontent.com/SublyFi/circuits/main/claim_revenue_v2.arcis".to_string(),
+                source: circuit_source_url(&ctx.accounts.circuit_version, "claim_revenue_v2"),
                 hash: circuit_hash!("claim_revenue_v2"),
             })),
             None,
@@ -309,129 +1637,438 @@ pub mod privacy_subscriptions {
         Ok(())
     }
 
-    // ========================================================================
-    // Phase 2: Encrypted Instructions - Queue Phase
-    // ========================================================================
+    pub fn init_reactivate_subscription_comp_def(ctx: Context<InitReactivateSubscriptionCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "reactivate_subscription_v2"),
+                hash: circuit_hash!("reactivate_subscription_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
 
-    /// Deposit tokens to user's encrypted ledger
-    pub fn deposit(
-        ctx: Context<Deposit>,
-        computation_offset: u64,
-        amount: u64,
-        encryption_pubkey: [u8; 32],
-        encrypted_amount: [u8; 32],
-        encrypted_amount_nonce: u128,
-    ) -> Result<()> {
-        require!(
-            !is_zero_pubkey(&encryption_pubkey),
-            ErrorCode::InvalidEncryptionKey
-        );
-        require!(amount > 0, ErrorCode::InvalidAmount);
-        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+    pub fn init_cancel_at_period_end_comp_def(ctx: Context<InitCancelAtPeriodEndCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "cancel_at_period_end_v2"),
+                hash: circuit_hash!("cancel_at_period_end_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
 
-        // Transfer tokens from user to pool
-        let cpi_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token::Transfer {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                to: ctx.accounts.pool_token_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        );
-        anchor_spl::token::transfer(cpi_ctx, amount)?;
+    pub fn init_resume_subscription_comp_def(ctx: Context<InitResumeSubscriptionCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "resume_subscription_v2"),
+                hash: circuit_hash!("resume_subscription_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
 
-        // Initialize user ledger if it's new
-        let user_ledger = &mut ctx.accounts.user_ledger;
-        if user_ledger.user == Pubkey::default() {
-            user_ledger.user = ctx.accounts.user.key();
-            user_ledger.mint = ctx.accounts.mint.key();
-            user_ledger.encryption_pubkey = encryption_pubkey;
-            user_ledger.encrypted_balance = [0u8; 32];
-            user_ledger.encrypted_subscription_count = [0u8; 32];
-            user_ledger.nonce = 0;
-            user_ledger.bump = ctx.bumps.user_ledger;
-        } else {
-            require!(
-                user_ledger.encryption_pubkey == encryption_pubkey,
-                ErrorCode::EncryptionKeyMismatch
-            );
-        }
-        user_ledger.last_updated = Clock::get()?.unix_timestamp;
+    pub fn init_refund_comp_def(ctx: Context<InitRefundCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "refund_v2"),
+                hash: circuit_hash!("refund_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
 
-        let user_is_new = user_ledger.nonce == 0;
+    pub fn init_open_dispute_comp_def(ctx: Context<InitOpenDisputeCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "open_dispute_v2"),
+                hash: circuit_hash!("open_dispute_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
 
-        // Queue computation to Arcium
-        // ArgBuilder order must match Arcis circuit's deposit parameters:
-        //   1. user_ledger (Enc<Shared, UserLedgerState>)
-        //   2. amount (Enc<Shared, u64>)
-        //   3. is_new (plaintext)
-        let args = ArgBuilder::new()
-            .x25519_pubkey(user_ledger.encryption_pubkey)
-            .plaintext_u128(user_ledger.nonce)
-            .encrypted_u64(user_ledger.encrypted_balance)               // balance
-            .encrypted_u64(user_ledger.encrypted_subscription_count)    // subscription_count
-            .x25519_pubkey(user_ledger.encryption_pubkey)
-            .plaintext_u128(encrypted_amount_nonce)
-            .encrypted_u64(encrypted_amount)                            // amount
-            .plaintext_bool(user_is_new)                                // is_new
-            .build();
+    pub fn init_resolve_dispute_refund_comp_def(ctx: Context<InitResolveDisputeRefundCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "resolve_dispute_refund_v2"),
+                hash: circuit_hash!("resolve_dispute_refund_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
 
-        queue_computation(
+    pub fn init_resolve_dispute_release_comp_def(ctx: Context<InitResolveDisputeReleaseCompDef>) -> Result<()> {
+        init_comp_def(
             ctx.accounts,
-            computation_offset,
-            args,
-            vec![DepositV2Callback::callback_ix(
-                computation_offset,
-                &ctx.accounts.mxe_account,
-                &[CallbackAccount {
-                    pubkey: ctx.accounts.user_ledger.key(),
-                    is_writable: true,
-                }],
-            )?],
-            1,
-            0,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "resolve_dispute_release_v2"),
+                hash: circuit_hash!("resolve_dispute_release_v2"),
+            })),
+            None,
         )?;
+        Ok(())
+    }
 
+    pub fn init_set_spending_cap_comp_def(ctx: Context<InitSetSpendingCapCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "set_spending_cap_v2"),
+                hash: circuit_hash!("set_spending_cap_v2"),
+            })),
+            None,
+        )?;
         Ok(())
     }
 
-    /// Withdraw tokens from user's encrypted ledger
-    pub fn withdraw(
-        ctx: Context<Withdraw>,
-        computation_offset: u64,
-        amount: u64,
-        encryption_pubkey: [u8; 32],
-    ) -> Result<()> {
-        require!(amount > 0, ErrorCode::InvalidAmount);
-        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+    pub fn init_balance_runway_comp_def(ctx: Context<InitBalanceRunwayCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "balance_runway_v2"),
+                hash: circuit_hash!("balance_runway_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
 
-        let user_ledger = &mut ctx.accounts.user_ledger;
-        require!(
-            user_ledger.encryption_pubkey == encryption_pubkey,
-            ErrorCode::EncryptionKeyMismatch
+    pub fn init_check_solvency_comp_def(ctx: Context<InitCheckSolvencyCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "check_solvency_v2"),
+                hash: circuit_hash!("check_solvency_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_transfer_comp_def(ctx: Context<InitTransferCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "transfer_v2"),
+                hash: circuit_hash!("transfer_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_claim_transfers_comp_def(ctx: Context<InitClaimTransfersCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "claim_transfers_v2"),
+                hash: circuit_hash!("claim_transfers_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_buy_voucher_comp_def(ctx: Context<InitBuyVoucherCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "buy_voucher_v2"),
+                hash: circuit_hash!("buy_voucher_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_redeem_voucher_comp_def(ctx: Context<InitRedeemVoucherCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "redeem_voucher_v2"),
+                hash: circuit_hash!("redeem_voucher_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_update_seats_comp_def(ctx: Context<InitUpdateSeatsCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "update_seats_v2"),
+                hash: circuit_hash!("update_seats_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_assign_seat_comp_def(ctx: Context<InitAssignSeatCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "assign_seat_v2"),
+                hash: circuit_hash!("assign_seat_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_scheduled_payout_comp_def(ctx: Context<InitScheduledPayoutCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "scheduled_payout_v2"),
+                hash: circuit_hash!("scheduled_payout_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_publish_plan_stats_comp_def(ctx: Context<InitPublishPlanStatsCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "publish_plan_stats_v2"),
+                hash: circuit_hash!("publish_plan_stats_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_disclose_user_ledger_comp_def(ctx: Context<InitDiscloseUserLedgerCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "disclose_user_ledger_v2"),
+                hash: circuit_hash!("disclose_user_ledger_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_disclose_merchant_ledger_comp_def(ctx: Context<InitDiscloseMerchantLedgerCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "disclose_merchant_ledger_v2"),
+                hash: circuit_hash!("disclose_merchant_ledger_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_disclose_subscription_comp_def(ctx: Context<InitDiscloseSubscriptionCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "disclose_subscription_v2"),
+                hash: circuit_hash!("disclose_subscription_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_distribute_yield_comp_def(ctx: Context<InitDistributeYieldCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "distribute_yield_v2"),
+                hash: circuit_hash!("distribute_yield_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_settle_yield_comp_def(ctx: Context<InitSettleYieldCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "settle_yield_v2"),
+                hash: circuit_hash!("settle_yield_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_set_principal_lock_comp_def(ctx: Context<InitSetPrincipalLockCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "set_principal_lock_v2"),
+                hash: circuit_hash!("set_principal_lock_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_migrate_user_ledger_comp_def(ctx: Context<InitMigrateUserLedgerCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "migrate_user_ledger_v2"),
+                hash: circuit_hash!("migrate_user_ledger_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_migrate_user_subscription_comp_def(ctx: Context<InitMigrateUserSubscriptionCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "migrate_user_subscription_v2"),
+                hash: circuit_hash!("migrate_user_subscription_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_migrate_merchant_ledger_comp_def(ctx: Context<InitMigrateMerchantLedgerCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "migrate_merchant_ledger_v2"),
+                hash: circuit_hash!("migrate_merchant_ledger_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn init_seed_liabilities_comp_def(ctx: Context<InitSeedLiabilitiesCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: circuit_source_url(&ctx.accounts.circuit_version, "seed_liabilities_v2"),
+                hash: circuit_hash!("seed_liabilities_v2"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    // ========================================================================
+    // Phase 2: Encrypted Instructions - Queue Phase
+    // ========================================================================
+
+    /// Deposit tokens to user's encrypted ledger
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        computation_offset: u64,
+        amount: u64,
+        encryption_pubkey: [u8; 32],
+        encrypted_amount: [u8; 32],
+        encrypted_amount_nonce: u128,
+    ) -> Result<()> {
+        require!(
+            !is_zero_pubkey(&encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        check_screening(
+            &ctx.accounts.protocol_pool,
+            &ctx.accounts.protocol_config,
+            ctx.accounts.screening_attestation.as_deref(),
+        )?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Transfer tokens from user to pool
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.pool_token_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+        // Initialize user ledger if it's new
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        init_user_ledger_if_new(
+            user_ledger,
+            ctx.accounts.user.key(),
+            ctx.accounts.mint.key(),
+            ctx.bumps.user_ledger,
         );
+        set_ledger_key(user_ledger, encryption_pubkey)?;
         user_ledger.last_updated = Clock::get()?.unix_timestamp;
+        // Every balance change settles pending yield, which counts as this epoch's settlement
+        user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
 
         let user_is_new = user_ledger.nonce == 0;
+        let checkpoint_is_new = user_ledger.yield_checkpoint_nonce == 0;
 
-        // ArgBuilder order must match Arcis circuit's withdraw parameters:
+        require_current_ledger_layout(user_ledger)?;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let liabilities_is_new = protocol_pool.liabilities_nonce == 0;
+        let yield_is_new = protocol_pool.yield_nonce == 0;
+
+        // Queue computation to Arcium
+        // ArgBuilder order must match Arcis circuit's deposit parameters:
         //   1. user_ledger (Enc<Shared, UserLedgerState>)
-        //   2. amount (plaintext)
-        //   3. is_new (plaintext)
+        //   2. amount (Enc<Shared, u64>)
+        //   3. total_liabilities (Enc<Mxe, u64>)
+        //   4. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   5. yield_checkpoint (Enc<Mxe, u128>)
+        //   6. is_new (plaintext)
+        //   7. liabilities_is_new (plaintext)
+        //   8. yield_is_new (plaintext)
+        //   9. checkpoint_is_new (plaintext)
         let args = ArgBuilder::new()
             .x25519_pubkey(user_ledger.encryption_pubkey)
             .plaintext_u128(user_ledger.nonce)
-            .encrypted_u64(user_ledger.encrypted_balance)               // balance
-            .encrypted_u64(user_ledger.encrypted_subscription_count)    // subscription_count
-            .plaintext_u64(amount)                                      // amount
+            .encrypted_state(&user_ledger.state())
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_amount_nonce)
+            .encrypted::<u64>(&encrypted_amount)                            // amount
+            .plaintext_u128(protocol_pool.liabilities_nonce)
+            .encrypted::<u64>(&protocol_pool.encrypted_total_liabilities);  // total_liabilities
+        let args = pool_yield_args(args, protocol_pool);
+        let args = yield_checkpoint_args(args, user_ledger)
             .plaintext_bool(user_is_new)                                // is_new
+            .plaintext_bool(liabilities_is_new)                         // liabilities_is_new
+            .plaintext_bool(yield_is_new)                               // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                          // checkpoint_is_new
             .build();
 
         queue_computation(
             ctx.accounts,
             computation_offset,
             args,
-            vec![WithdrawV2Callback::callback_ix(
+            vec![DepositV2Callback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
                 &[
@@ -441,14 +2078,6 @@ pub mod privacy_subscriptions {
                     },
                     CallbackAccount {
                         pubkey: ctx.accounts.protocol_pool.key(),
-                        is_writable: false,
-                    },
-                    CallbackAccount {
-                        pubkey: ctx.accounts.pool_token_account.key(),
-                        is_writable: true,
-                    },
-                    CallbackAccount {
-                        pubkey: ctx.accounts.user_token_account.key(),
                         is_writable: true,
                     },
                 ],
@@ -460,91 +2089,268 @@ pub mod privacy_subscriptions {
         Ok(())
     }
 
-    /// Subscribe to a plan
-    pub fn subscribe(
-        ctx: Context<Subscribe>,
+    /// Withdraw tokens from user's encrypted ledger
+    pub fn withdraw(
+        ctx: Context<Withdraw>,
         computation_offset: u64,
-        subscription_index: u64,
-        encrypted_plan: [[u8; 32]; 2],
-        encrypted_plan_nonce: u128,
-        encrypted_price: [u8; 32],
-        encrypted_price_nonce: u128,
-        encrypted_billing_cycle: [u8; 32],
-        encrypted_billing_cycle_nonce: u128,
+        amount: u64,
+        encryption_pubkey: [u8; 32],
     ) -> Result<()> {
-        require!(ctx.accounts.subscription_plan.is_active, ErrorCode::PlanNotActive);
-        require!(
-            !is_zero_pubkey(&ctx.accounts.user_ledger.encryption_pubkey),
-            ErrorCode::InvalidEncryptionKey
-        );
-        require!(
-            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
-            ErrorCode::InvalidEncryptionKey
-        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        check_outflow_limits(
+            &ctx.accounts.protocol_pool,
+            ctx.accounts.pending_outflow.as_deref_mut(),
+            amount,
+            ctx.accounts.computation_account.key(),
+        )?;
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        // Initialize user subscription PDA
-        let user_subscription = &mut ctx.accounts.user_subscription;
-        user_subscription.user = ctx.accounts.user.key();
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        require!(
+            user_ledger.encryption_pubkey == encryption_pubkey,
+            ErrorCode::EncryptionKeyMismatch
+        );
+        user_ledger.last_updated = Clock::get()?.unix_timestamp;
+        user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        let user_is_new = user_ledger.nonce == 0;
+        let checkpoint_is_new = user_ledger.yield_checkpoint_nonce == 0;
+
+        require_current_ledger_layout(user_ledger)?;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let liabilities_is_new = protocol_pool.liabilities_nonce == 0;
+        let yield_is_new = protocol_pool.yield_nonce == 0;
+
+        // ArgBuilder order must match Arcis circuit's withdraw parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerState>)
+        //   2. total_liabilities (Enc<Mxe, u64>)
+        //   3. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   4. yield_checkpoint (Enc<Mxe, u128>)
+        //   5. amount (plaintext)
+        //   6. is_new (plaintext)
+        //   7. liabilities_is_new (plaintext)
+        //   8. yield_is_new (plaintext)
+        //   9. checkpoint_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(user_ledger.nonce)
+            .encrypted_state(&user_ledger.state())
+            .plaintext_u128(protocol_pool.liabilities_nonce)
+            .encrypted::<u64>(&protocol_pool.encrypted_total_liabilities);  // total_liabilities
+        let args = pool_yield_args(args, protocol_pool);
+        let args = yield_checkpoint_args(args, user_ledger)
+            .plaintext_u64(amount)                                      // amount
+            .plaintext_bool(user_is_new)                                // is_new
+            .plaintext_bool(liabilities_is_new)                         // liabilities_is_new
+            .plaintext_bool(yield_is_new)                               // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                          // checkpoint_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![WithdrawV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.pool_token_account.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_token_account.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.token_program.key(),
+                        is_writable: false,
+                    },
+                    optional_callback_account(
+                        ctx.accounts.pending_outflow.as_ref().map(|pending| pending.key()),
+                    ),
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Subscribe to a plan
+    // Each encrypted input is a ciphertext plus its nonce, as the IDL exposes them
+    #[allow(clippy::too_many_arguments)]
+    pub fn subscribe(
+        ctx: Context<Subscribe>,
+        computation_offset: u64,
+        subscription_index: u64,
+        encrypted_plan: [[u8; 32]; 2],
+        encrypted_plan_nonce: u128,
+        encrypted_price: [u8; 32],
+        encrypted_price_nonce: u128,
+        encrypted_billing_cycle: [u8; 32],
+        encrypted_billing_cycle_nonce: u128,
+        encrypted_quantity: [u8; 32],
+        encrypted_quantity_nonce: u128,
+    ) -> Result<()> {
+        require!(ctx.accounts.subscription_plan.is_active, ErrorCode::PlanNotActive);
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Initialize user subscription PDA
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.user = ctx.accounts.user.key();
         user_subscription.subscription_index = subscription_index;
         user_subscription.encryption_pubkey = ctx.accounts.user_ledger.encryption_pubkey;
         user_subscription.encrypted_plan = [[0u8; 32]; 2];
         user_subscription.encrypted_status = [0u8; 32];
         user_subscription.encrypted_next_payment_date = [0u8; 32];
         user_subscription.encrypted_start_date = [0u8; 32];
+        user_subscription.encrypted_quantity = [0u8; 32];
+        user_subscription.state_version = USER_SUBSCRIPTION_STATE_VERSION;
         user_subscription.nonce = 0;
         user_subscription.bump = ctx.bumps.user_subscription;
+        user_subscription.account_version = UserSubscription::VERSION;
+        user_subscription.open_disputes = 0;
+        user_subscription.seats_assigned = 0;
+        user_subscription.payment_mint = ctx.accounts.mint.key();
+        user_subscription.encrypted_charges = [[0u8; 32]; ChargeRecordStateCiphertexts::LEN];
+        user_subscription.charges_nonce = 0;
+        user_subscription.prepaid_cycles = 0;
+
+        init_spending_cap_if_new(
+            &mut ctx.accounts.spending_cap,
+            ctx.accounts.user.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.mint.key(),
+            ctx.accounts.user_ledger.encryption_pubkey,
+            ctx.bumps.spending_cap,
+        );
+
+        init_plan_stats_if_new(
+            &mut ctx.accounts.plan_stats,
+            ctx.accounts.subscription_plan.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.merchant_ledger.encryption_pubkey,
+            ctx.bumps.plan_stats,
+        );
 
         let user_ledger = &mut ctx.accounts.user_ledger;
         user_ledger.last_updated = Clock::get()?.unix_timestamp;
+        user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
 
         let current_timestamp = Clock::get()?.unix_timestamp;
         let billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
         let price = ctx.accounts.subscription_plan.price;
+        let charge_price = settlement_price(
+            &ctx.accounts.subscription_plan,
+            &ctx.accounts.mint,
+            ctx.accounts.price_feed.as_deref().map(|feed| &**feed),
+            ctx.accounts.price_account.as_deref(),
+            current_timestamp,
+        )?;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
 
         let user_is_new = user_ledger.nonce == 0;
         let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
+        let cap_is_new = ctx.accounts.spending_cap.nonce == 0;
+        let stats_is_new = ctx.accounts.plan_stats.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let checkpoint_is_new = user_ledger.yield_checkpoint_nonce == 0;
 
         let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
 
+        require_current_ledger_layout(user_ledger)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
         // ArgBuilder order must match Arcis circuit's subscribe parameters:
         //   1. user_ledger (Enc<Shared, UserLedgerState>)
         //   2. merchant_ledger (Enc<Shared, MerchantLedgerState>)
-        //   3. plan (Enc<Shared, [u128; 2]>)
-        //   4. price (Enc<Shared, u64>)
-        //   5. billing_cycle_days (Enc<Shared, u32>)
-        //   6. current_timestamp (plaintext)
-        //   7. plan_pubkey (plaintext [u128; 2])
-        //   8. plan_price (plaintext)
-        //   9. plan_billing_cycle_days (plaintext)
-        //  10. user_is_new (plaintext)
-        //  11. merchant_is_new (plaintext)
+        //   3. spending_cap (Enc<Shared, SpendingCapState>)
+        //   4. plan_stats (Enc<Shared, PlanStatsState>)
+        //   5. plan (Enc<Shared, [u128; 2]>)
+        //   6. price (Enc<Shared, u64>)
+        //   7. billing_cycle_days (Enc<Shared, u32>)
+        //   8. quantity (Enc<Shared, u32>)
+        //   9. pool_yield (Enc<Mxe, PoolYieldState>)
+        //  10. yield_checkpoint (Enc<Mxe, u128>)
+        //  11. current_timestamp (plaintext)
+        //  12. plan_pubkey (plaintext [u128; 2])
+        //  13. plan_price (plaintext, per seat)
+        //  14. settlement_price (plaintext, per seat in the ledger's mint)
+        //  15. plan_billing_cycle_days (plaintext)
+        //  16. user_is_new (plaintext)
+        //  17. merchant_is_new (plaintext)
+        //  18. cap_is_new (plaintext)
+        //  19. stats_is_new (plaintext)
+        //  20. yield_is_new (plaintext)
+        //  21. checkpoint_is_new (plaintext)
         let args = ArgBuilder::new()
             .x25519_pubkey(user_ledger.encryption_pubkey)
             .plaintext_u128(user_ledger.nonce)
-            .encrypted_u64(user_ledger.encrypted_balance)                       // user_balance
-            .encrypted_u64(user_ledger.encrypted_subscription_count)            // subscription_count
+            .encrypted_state(&user_ledger.state())
             .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
             .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
-            .encrypted_u64(ctx.accounts.merchant_ledger.encrypted_balance)      // merchant_balance
-            .encrypted_u64(ctx.accounts.merchant_ledger.encrypted_total_claimed)// total_claimed
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .x25519_pubkey(ctx.accounts.spending_cap.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.spending_cap.nonce)
+            .encrypted_state(&ctx.accounts.spending_cap.state())
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state())
             .x25519_pubkey(user_ledger.encryption_pubkey)
             .plaintext_u128(encrypted_plan_nonce)
-            .encrypted_u128(encrypted_plan[0])                                  // plan_part1
-            .encrypted_u128(encrypted_plan[1])                                  // plan_part2
+            .encrypted::<[u128; 2]>(&encrypted_plan)                          // plan
             .x25519_pubkey(user_ledger.encryption_pubkey)
             .plaintext_u128(encrypted_price_nonce)
-            .encrypted_u64(encrypted_price)                                     // price
+            .encrypted::<u64>(&encrypted_price)                                     // price
             .x25519_pubkey(user_ledger.encryption_pubkey)
             .plaintext_u128(encrypted_billing_cycle_nonce)
-            .encrypted_u32(encrypted_billing_cycle)                             // billing_cycle_days
+            .encrypted::<u32>(&encrypted_billing_cycle)                             // billing_cycle_days
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_quantity_nonce)
+            .encrypted::<u32>(&encrypted_quantity);                                 // quantity
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, user_ledger)
             .plaintext_i64(current_timestamp)                                   // current_timestamp
             .plaintext_u128(plan_bytes[0])                                      // plan_pubkey_part1
             .plaintext_u128(plan_bytes[1])                                      // plan_pubkey_part2
             .plaintext_u64(price)                                               // plan_price
+            .plaintext_u64(charge_price)                                        // settlement_price
             .plaintext_u32(billing_cycle_days)                                  // plan_billing_cycle_days
             .plaintext_bool(user_is_new)                                        // user_is_new
             .plaintext_bool(merchant_is_new)                                    // merchant_is_new
+            .plaintext_bool(cap_is_new)                                         // cap_is_new
+            .plaintext_bool(stats_is_new)                                       // stats_is_new
+            .plaintext_bool(yield_is_new)                                       // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                                  // checkpoint_is_new
             .build();
 
         queue_computation(
@@ -567,6 +2373,18 @@ pub mod privacy_subscriptions {
                         pubkey: ctx.accounts.user_subscription.key(),
                         is_writable: true,
                     },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.spending_cap.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.plan_stats.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
                 ],
             )?],
             1,
@@ -586,17 +2404,48 @@ pub mod privacy_subscriptions {
             !is_zero_pubkey(&ctx.accounts.user_subscription.encryption_pubkey),
             ErrorCode::InvalidEncryptionKey
         );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        init_plan_stats_if_new(
+            &mut ctx.accounts.plan_stats,
+            ctx.accounts.subscription_plan.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.merchant_ledger.encryption_pubkey,
+            ctx.bumps.plan_stats,
+        );
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+        let plan_price = ctx.accounts.subscription_plan.price;
+        let billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
+        let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
+        let stats_is_new = ctx.accounts.plan_stats.nonce == 0;
+
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
 
         // ArgBuilder order must match Arcis circuit's unsubscribe parameters:
         //   1. subscription (Enc<Shared, UserSubscriptionState>)
+        //   2. plan_stats (Enc<Shared, PlanStatsState>)
+        //   3. current_timestamp (plaintext)
+        //   4. plan_price (plaintext)
+        //   5. billing_cycle_days (plaintext)
+        //   6. plan_pubkey (plaintext [u128; 2])
+        //   7. stats_is_new (plaintext)
         let args = ArgBuilder::new()
             .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
             .plaintext_u128(ctx.accounts.user_subscription.nonce)
-            .encrypted_u128(ctx.accounts.user_subscription.encrypted_plan[0])
-            .encrypted_u128(ctx.accounts.user_subscription.encrypted_plan[1])
-            .encrypted_u8(ctx.accounts.user_subscription.encrypted_status)
-            .encrypted_i64(ctx.accounts.user_subscription.encrypted_next_payment_date)
-            .encrypted_i64(ctx.accounts.user_subscription.encrypted_start_date)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state())
+            .plaintext_i64(current_timestamp)                                               // current_timestamp
+            .plaintext_u64(plan_price)                                                      // plan_price
+            .plaintext_u32(billing_cycle_days)                                              // billing_cycle_days
+            .plaintext_u128(plan_bytes[0])                                                  // plan_pubkey_part1
+            .plaintext_u128(plan_bytes[1])                                                  // plan_pubkey_part2
+            .plaintext_bool(stats_is_new)                                                   // stats_is_new
             .build();
 
         queue_computation(
@@ -606,10 +2455,16 @@ pub mod privacy_subscriptions {
             vec![UnsubscribeV2Callback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[CallbackAccount {
-                    pubkey: ctx.accounts.user_subscription.key(),
-                    is_writable: true,
-                }],
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.plan_stats.key(),
+                        is_writable: true,
+                    },
+                ],
             )?],
             1,
             0,
@@ -642,46 +2497,97 @@ pub mod privacy_subscriptions {
             ErrorCode::EncryptionKeyMismatch
         );
 
+        init_plan_stats_if_new(
+            &mut ctx.accounts.plan_stats,
+            ctx.accounts.subscription_plan.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.merchant_ledger.encryption_pubkey,
+            ctx.bumps.plan_stats,
+        );
+
         let current_timestamp = Clock::get()?.unix_timestamp;
         let plan_price = ctx.accounts.subscription_plan.price;
+        let charge_price = settlement_price(
+            &ctx.accounts.subscription_plan,
+            &ctx.accounts.mint,
+            ctx.accounts.price_feed.as_deref().map(|feed| &**feed),
+            ctx.accounts.price_account.as_deref(),
+            current_timestamp,
+        )?;
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
         let billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
         let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
         let user_is_new = ctx.accounts.user_ledger.nonce == 0;
         let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
+        let cap_is_new = ctx.accounts.spending_cap.nonce == 0;
+        let stats_is_new = ctx.accounts.plan_stats.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let checkpoint_is_new = ctx.accounts.user_ledger.yield_checkpoint_nonce == 0;
+        let charges_is_new = ctx.accounts.user_subscription.charges_nonce == 0;
+        ctx.accounts.user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        require_current_ledger_layout(&ctx.accounts.user_ledger)?;
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
 
         // ArgBuilder order must match Arcis circuit's process_payment parameters:
         //   1. user_ledger (Enc<Shared, UserLedgerState>)
         //   2. merchant_ledger (Enc<Shared, MerchantLedgerState>)
         //   3. subscription (Enc<Shared, UserSubscriptionState>)
-        //   4. current_timestamp (plaintext)
-        //   5. plan_price (plaintext)
-        //   6. billing_cycle_days (plaintext)
-        //   7. plan_pubkey (plaintext [u128; 2])
-        //   8. user_is_new (plaintext)
-        //   9. merchant_is_new (plaintext)
+        //   4. spending_cap (Enc<Shared, SpendingCapState>)
+        //   5. plan_stats (Enc<Shared, PlanStatsState>)
+        //   6. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   7. yield_checkpoint (Enc<Mxe, u128>)
+        //   8. charges (Enc<Mxe, ChargeRecordState>)
+        //   9. current_timestamp (plaintext)
+        //  10. plan_price (plaintext)
+        //  11. settlement_price (plaintext, per seat in the ledger's mint)
+        //  12. billing_cycle_days (plaintext)
+        //  13. plan_pubkey (plaintext [u128; 2])
+        //  14. user_is_new (plaintext)
+        //  15. merchant_is_new (plaintext)
+        //  16. cap_is_new (plaintext)
+        //  17. stats_is_new (plaintext)
+        //  18. yield_is_new (plaintext)
+        //  19. checkpoint_is_new (plaintext)
+        //  20. charges_is_new (plaintext)
         let args = ArgBuilder::new()
             .x25519_pubkey(ctx.accounts.user_ledger.encryption_pubkey)
             .plaintext_u128(ctx.accounts.user_ledger.nonce)
-            .encrypted_u64(ctx.accounts.user_ledger.encrypted_balance)               // user_balance
-            .encrypted_u64(ctx.accounts.user_ledger.encrypted_subscription_count)    // subscription_count
+            .encrypted_state(&ctx.accounts.user_ledger.state())
             .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
             .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
-            .encrypted_u64(ctx.accounts.merchant_ledger.encrypted_balance)           // merchant_balance
-            .encrypted_u64(ctx.accounts.merchant_ledger.encrypted_total_claimed)     // total_claimed
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
             .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
             .plaintext_u128(ctx.accounts.user_subscription.nonce)
-            .encrypted_u128(ctx.accounts.user_subscription.encrypted_plan[0])
-            .encrypted_u128(ctx.accounts.user_subscription.encrypted_plan[1])
-            .encrypted_u8(ctx.accounts.user_subscription.encrypted_status)
-            .encrypted_i64(ctx.accounts.user_subscription.encrypted_next_payment_date)
-            .encrypted_i64(ctx.accounts.user_subscription.encrypted_start_date)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .x25519_pubkey(ctx.accounts.spending_cap.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.spending_cap.nonce)
+            .encrypted_state(&ctx.accounts.spending_cap.state())
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state());
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, &ctx.accounts.user_ledger);
+        let args = charge_record_args(args, &ctx.accounts.user_subscription)
             .plaintext_i64(current_timestamp)                                       // current_timestamp
             .plaintext_u64(plan_price)                                              // plan_price
+            .plaintext_u64(charge_price)                                            // settlement_price
             .plaintext_u32(billing_cycle_days)                                      // billing_cycle_days
             .plaintext_u128(plan_bytes[0])                                          // plan_pubkey_part1
             .plaintext_u128(plan_bytes[1])                                          // plan_pubkey_part2
             .plaintext_bool(user_is_new)                                            // user_is_new
             .plaintext_bool(merchant_is_new)                                        // merchant_is_new
+            .plaintext_bool(cap_is_new)                                             // cap_is_new
+            .plaintext_bool(stats_is_new)                                           // stats_is_new
+            .plaintext_bool(yield_is_new)                                           // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                                      // checkpoint_is_new
+            .plaintext_bool(charges_is_new)                                         // charges_is_new
             .build();
 
         queue_computation(
@@ -704,6 +2610,18 @@ pub mod privacy_subscriptions {
                         pubkey: ctx.accounts.user_subscription.key(),
                         is_writable: true,
                     },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.spending_cap.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.plan_stats.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
                 ],
             )?],
             1,
@@ -726,17 +2644,15 @@ pub mod privacy_subscriptions {
 
         let current_timestamp = Clock::get()?.unix_timestamp;
 
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+
         // ArgBuilder order must match Arcis circuit's verify_subscription parameters:
         //   1. subscription (Enc<Shared, UserSubscriptionState>)
         //   2. current_timestamp (plaintext)
         let args = ArgBuilder::new()
             .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
             .plaintext_u128(ctx.accounts.user_subscription.nonce)
-            .encrypted_u128(ctx.accounts.user_subscription.encrypted_plan[0])
-            .encrypted_u128(ctx.accounts.user_subscription.encrypted_plan[1])
-            .encrypted_u8(ctx.accounts.user_subscription.encrypted_status)
-            .encrypted_i64(ctx.accounts.user_subscription.encrypted_next_payment_date)
-            .encrypted_i64(ctx.accounts.user_subscription.encrypted_start_date)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
             .plaintext_i64(current_timestamp)
             .build();
 
@@ -768,21 +2684,40 @@ pub mod privacy_subscriptions {
             !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
             ErrorCode::InvalidEncryptionKey
         );
+        check_outflow_limits(
+            &ctx.accounts.protocol_pool,
+            ctx.accounts.pending_outflow.as_deref_mut(),
+            amount,
+            ctx.accounts.computation_account.key(),
+        )?;
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
         let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
 
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        let liabilities_is_new = ctx.accounts.protocol_pool.liabilities_nonce == 0;
+
         // ArgBuilder order must match Arcis circuit's claim_revenue parameters:
         //   1. merchant_ledger (Enc<Shared, MerchantLedgerState>)
-        //   2. amount (plaintext)
-        //   3. is_new (plaintext)
+        //   2. total_liabilities (Enc<Mxe, u64>)
+        //   3. amount (plaintext)
+        //   4. is_new (plaintext)
+        //   5. liabilities_is_new (plaintext)
         let args = ArgBuilder::new()
             .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
             .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
-            .encrypted_u64(ctx.accounts.merchant_ledger.encrypted_balance)          // balance
-            .encrypted_u64(ctx.accounts.merchant_ledger.encrypted_total_claimed)    // total_claimed
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .plaintext_u128(ctx.accounts.protocol_pool.liabilities_nonce)
+            .encrypted::<u64>(&ctx.accounts.protocol_pool.encrypted_total_liabilities)  // total_liabilities
             .plaintext_u64(amount)                                                  // amount
             .plaintext_bool(merchant_is_new)                                        // is_new
+            .plaintext_bool(liabilities_is_new)                                     // liabilities_is_new
             .build();
 
         queue_computation(
@@ -799,7 +2734,7 @@ pub mod privacy_subscriptions {
                     },
                     CallbackAccount {
                         pubkey: ctx.accounts.protocol_pool.key(),
-                        is_writable: false,
+                        is_writable: true,
                     },
                     CallbackAccount {
                         pubkey: ctx.accounts.pool_token_account.key(),
@@ -809,6 +2744,13 @@ pub mod privacy_subscriptions {
                         pubkey: ctx.accounts.merchant_token_account.key(),
                         is_writable: true,
                     },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.token_program.key(),
+                        is_writable: false,
+                    },
+                    optional_callback_account(
+                        ctx.accounts.pending_outflow.as_ref().map(|pending| pending.key()),
+                    ),
                 ],
             )?],
             1,
@@ -818,1126 +2760,9038 @@ pub mod privacy_subscriptions {
         Ok(())
     }
 
-    // ========================================================================
-    // Phase 2: Encrypted Instructions - Callback Phase
-    // ========================================================================
-
-    const SHARED_ENCRYPTED_BASE_SIZE: usize = 32 + 16;
-    const SHARED_ENCRYPTED_SIZE_2: usize = SHARED_ENCRYPTED_BASE_SIZE + (32 * 2);
-    const SHARED_ENCRYPTED_SIZE_5: usize = SHARED_ENCRYPTED_BASE_SIZE + (32 * 5);
+    /// Claim revenue for merchant and pay it out across the merchant's revenue split.
+    /// Recipient token accounts are passed as remaining_accounts, in configuration order.
+    pub fn claim_revenue_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRevenueSplit<'info>>,
+        computation_offset: u64,
+        amount: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.merchant.is_active, ErrorCode::MerchantNotActive);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        check_outflow_limits(
+            &ctx.accounts.protocol_pool,
+            ctx.accounts.pending_outflow.as_deref_mut(),
+            amount,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-    #[derive(AnchorSerialize, AnchorDeserialize)]
-    pub struct WithdrawResult {
-        pub field_0: SharedEncryptedStruct<2>,
-        pub field_1: u64,
-    }
+        let revenue_split = &ctx.accounts.revenue_split;
+        let recipient_count = revenue_split.recipient_count as usize;
+        let recipients = ctx.remaining_accounts;
+        require!(
+            recipients.len() == recipient_count
+                && recipients
+                    .iter()
+                    .zip(revenue_split.recipients.iter())
+                    .all(|(account, expected)| account.key() == *expected),
+            ErrorCode::InvalidSplitRecipients
+        );
 
-    impl HasSize for WithdrawResult {
-        const SIZE: usize = SHARED_ENCRYPTED_SIZE_2 + 8;
-    }
+        let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
 
-    #[derive(AnchorSerialize, AnchorDeserialize)]
-    pub struct ClaimRevenueResult {
-        pub field_0: SharedEncryptedStruct<2>,
-        pub field_1: u64,
-    }
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
 
-    impl HasSize for ClaimRevenueResult {
-        const SIZE: usize = SHARED_ENCRYPTED_SIZE_2 + 8;
-    }
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        let liabilities_is_new = ctx.accounts.protocol_pool.liabilities_nonce == 0;
 
-    #[derive(AnchorSerialize, AnchorDeserialize)]
-    pub struct SubscribeResult {
-        pub field_0: SharedEncryptedStruct<2>,
-        pub field_1: SharedEncryptedStruct<2>,
-        pub field_2: SharedEncryptedStruct<5>,
-    }
+        // ArgBuilder order must match Arcis circuit's claim_revenue parameters:
+        //   1. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   2. total_liabilities (Enc<Mxe, u64>)
+        //   3. amount (plaintext)
+        //   4. is_new (plaintext)
+        //   5. liabilities_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .plaintext_u128(ctx.accounts.protocol_pool.liabilities_nonce)
+            .encrypted::<u64>(&ctx.accounts.protocol_pool.encrypted_total_liabilities)  // total_liabilities
+            .plaintext_u64(amount)                                                  // amount
+            .plaintext_bool(merchant_is_new)                                        // is_new
+            .plaintext_bool(liabilities_is_new)                                     // liabilities_is_new
+            .build();
 
-    impl HasSize for SubscribeResult {
-        const SIZE: usize = (SHARED_ENCRYPTED_SIZE_2 * 2) + SHARED_ENCRYPTED_SIZE_5;
-    }
+        // Callback accounts follow ClaimRevenueV2Callback's field order, with the
+        // first recipient in the merchant_token_account slot. The split account, the
+        // accounts its transfers use and every recipient are appended after them as
+        // the callback's remaining accounts.
+        let mut callback_accounts = vec![
+            CallbackAccount {
+                pubkey: ctx.accounts.merchant_ledger.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.protocol_pool.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.pool_token_account.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: recipients[0].key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.token_program.key(),
+                is_writable: false,
+            },
+            optional_callback_account(
+                ctx.accounts.pending_outflow.as_ref().map(|pending| pending.key()),
+            ),
+            CallbackAccount {
+                pubkey: ctx.accounts.revenue_split.key(),
+                is_writable: false,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.pool_token_account.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.protocol_pool.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.token_program.key(),
+                is_writable: false,
+            },
+        ];
+        for recipient in recipients {
+            callback_accounts.push(CallbackAccount {
+                pubkey: recipient.key(),
+                is_writable: true,
+            });
+        }
 
-    #[derive(AnchorSerialize, AnchorDeserialize)]
-    pub struct ProcessPaymentResult {
-        pub field_0: SharedEncryptedStruct<2>,
-        pub field_1: SharedEncryptedStruct<2>,
-        pub field_2: SharedEncryptedStruct<5>,
-    }
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ClaimRevenueV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
 
-    impl HasSize for ProcessPaymentResult {
-        const SIZE: usize = (SHARED_ENCRYPTED_SIZE_2 * 2) + SHARED_ENCRYPTED_SIZE_5;
+        Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "deposit_v2")]
-    pub fn deposit_v2_callback(
-        ctx: Context<DepositV2Callback>,
-        output: SignedComputationOutputs<DepositV2Output>,
+    /// Pay out the merchant's whole balance on schedule (permissionless).
+    /// Nothing is paid if the encrypted balance is below the schedule's threshold.
+    /// A payout never exceeds the pool's large-outflow threshold or what is left of
+    /// its epoch budget; larger balances leave through time-locked claims.
+    pub fn execute_scheduled_payout(
+        ctx: Context<ExecuteScheduledPayout>,
+        computation_offset: u64,
     ) -> Result<()> {
-        let o = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(DepositV2Output { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+        require!(ctx.accounts.merchant.is_active, ErrorCode::MerchantNotActive);
+        require!(ctx.accounts.payout_schedule.is_active, ErrorCode::PayoutScheduleInactive);
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        let user_ledger = &mut ctx.accounts.user_ledger;
-        user_ledger.encrypted_balance = o.ciphertexts[0];
-        user_ledger.encrypted_subscription_count = o.ciphertexts[1];
-        user_ledger.nonce = o.nonce;
+        // Advance the schedule before queueing so one cadence window queues at most once
+        let now = Clock::get()?.unix_timestamp;
+        let payout_schedule = &mut ctx.accounts.payout_schedule;
+        require!(now >= payout_schedule.next_payout_at, ErrorCode::PayoutNotDue);
+        payout_schedule.next_payout_at = now + (payout_schedule.cadence_days as i64) * 86400;
+        let min_threshold = payout_schedule.min_threshold;
+
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let mut max_amount = outflow_budget_remaining(protocol_pool, now);
+        if protocol_pool.large_outflow_threshold > 0 {
+            max_amount = max_amount.min(protocol_pool.large_outflow_threshold);
+        }
 
-        Ok(())
-    }
+        let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
 
-    #[arcium_callback(encrypted_ix = "withdraw_v2", auto_serialize = false)]
-    pub fn withdraw_v2_callback(
-        ctx: Context<WithdrawV2Callback>,
-        output: SignedComputationOutputs<WithdrawResult>,
-    ) -> Result<()> {
-        let WithdrawResult { field_0: o, field_1: actual_amount } = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(result) => result,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
 
-        // Update user ledger with new encrypted balance
-        let user_ledger = &mut ctx.accounts.user_ledger;
-        user_ledger.encrypted_balance = o.ciphertexts[0];
-        user_ledger.encrypted_subscription_count = o.ciphertexts[1];
-        user_ledger.nonce = o.nonce;
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        let liabilities_is_new = ctx.accounts.protocol_pool.liabilities_nonce == 0;
 
-        // Transfer actual amount from pool to user if approved by MPC
-        if actual_amount > 0 {
-            let protocol_pool = &ctx.accounts.protocol_pool;
-            let signer_seeds: &[&[u8]] = &[
-                PROTOCOL_POOL_SEED,
-                protocol_pool.mint.as_ref(),
-                &[protocol_pool.bump],
-            ];
-            let signer = &[signer_seeds];
+        // ArgBuilder order must match Arcis circuit's scheduled_payout parameters:
+        //   1. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   2. total_liabilities (Enc<Mxe, u64>)
+        //   3. min_threshold (plaintext)
+        //   4. max_amount (plaintext)
+        //   5. is_new (plaintext)
+        //   6. liabilities_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .plaintext_u128(ctx.accounts.protocol_pool.liabilities_nonce)
+            .encrypted::<u64>(&ctx.accounts.protocol_pool.encrypted_total_liabilities)  // total_liabilities
+            .plaintext_u64(min_threshold)                                           // min_threshold
+            .plaintext_u64(max_amount)                                              // max_amount
+            .plaintext_bool(merchant_is_new)                                        // is_new
+            .plaintext_bool(liabilities_is_new)                                     // liabilities_is_new
+            .build();
 
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: ctx.accounts.pool_token_account.to_account_info(),
-                    to: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.protocol_pool.to_account_info(),
-                },
-                signer,
-            );
-            anchor_spl::token::transfer(cpi_ctx, actual_amount)?;
-        }
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ScheduledPayoutV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.pool_token_account.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.destination_token_account.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.payout_schedule.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.token_program.key(),
+                        is_writable: false,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
 
         Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "subscribe_v2", auto_serialize = false)]
-    pub fn subscribe_v2_callback(
-        ctx: Context<SubscribeV2Callback>,
-        output: SignedComputationOutputs<SubscribeResult>,
+    /// Harvest the lending strategy's accrued interest into the pool and
+    /// spread it over the pool's liabilities (permissionless, rate limited)
+    pub fn distribute_yield(
+        ctx: Context<DistributeYield>,
+        computation_offset: u64,
     ) -> Result<()> {
-        let SubscribeResult { field_0: user_out, field_1: merchant_out, field_2: sub_out } = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(result) => result,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        // Update user ledger
-        let user_ledger = &mut ctx.accounts.user_ledger;
-        user_ledger.encrypted_balance = user_out.ciphertexts[0];
-        user_ledger.encrypted_subscription_count = user_out.ciphertexts[1];
-        user_ledger.nonce = user_out.nonce;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= ctx.accounts.protocol_pool.last_yield_distribution_at + YIELD_DISTRIBUTION_INTERVAL_SECONDS,
+            ErrorCode::YieldDistributionTooSoon
+        );
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            now,
+        )?;
 
-        // Update merchant ledger
-        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
-        merchant_ledger.encrypted_balance = merchant_out.ciphertexts[0];
-        merchant_ledger.encrypted_total_claimed = merchant_out.ciphertexts[1];
-        merchant_ledger.nonce = merchant_out.nonce;
+        let strategy = lending_strategy(StrategyAccounts {
+            payer: ctx.accounts.payer.to_account_info(),
+            protocol_pool: ctx.accounts.protocol_pool.to_account_info(),
+            pool_token_account: ctx.accounts.pool_token_account.to_account_info(),
+            lending_program: ctx.accounts.lending_program.to_account_info(),
+            reserve: ctx.accounts.lending_reserve.to_account_info(),
+            vault: ctx.accounts.lending_vault.to_account_info(),
+            position: ctx.accounts.lending_position.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        })?;
+        // Everything above the allocated principal is interest
+        let accrued = strategy
+            .position_value()?
+            .saturating_sub(ctx.accounts.protocol_pool.strategy_allocated);
+        require!(accrued > 0, ErrorCode::NoYieldAccrued);
+
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let signer_seeds: &[&[u8]] = &[
+            PROTOCOL_POOL_SEED,
+            protocol_pool.mint.as_ref(),
+            &[protocol_pool.bump],
+        ];
+        strategy.withdraw(accrued, &[signer_seeds])?;
 
-        // Update user subscription
-        let user_subscription = &mut ctx.accounts.user_subscription;
-        user_subscription.encrypted_plan[0] = sub_out.ciphertexts[0];
-        user_subscription.encrypted_plan[1] = sub_out.ciphertexts[1];
-        user_subscription.encrypted_status = sub_out.ciphertexts[2];
-        user_subscription.encrypted_next_payment_date = sub_out.ciphertexts[3];
-        user_subscription.encrypted_start_date = sub_out.ciphertexts[4];
-        user_subscription.nonce = sub_out.nonce;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.last_yield_distribution_at = now;
+        let yield_is_new = protocol_pool.yield_nonce == 0;
+        let liabilities_is_new = protocol_pool.liabilities_nonce == 0;
+
+        // ArgBuilder order must match Arcis circuit's distribute_yield parameters:
+        //   1. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   2. total_liabilities (Enc<Mxe, u64>)
+        //   3. accrued (plaintext)
+        //   4. yield_is_new (plaintext)
+        //   5. liabilities_is_new (plaintext)
+        let args = pool_yield_args(ArgBuilder::new(), protocol_pool)
+            .plaintext_u128(protocol_pool.liabilities_nonce)
+            .encrypted::<u64>(&protocol_pool.encrypted_total_liabilities)   // total_liabilities
+            .plaintext_u64(accrued)                                     // accrued
+            .plaintext_bool(yield_is_new)                               // yield_is_new
+            .plaintext_bool(liabilities_is_new)                         // liabilities_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![DistributeYieldV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.protocol_pool.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
 
         Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "unsubscribe_v2")]
-    pub fn unsubscribe_v2_callback(
-        ctx: Context<UnsubscribeV2Callback>,
-        output: SignedComputationOutputs<UnsubscribeV2Output>,
+    /// Credit a user ledger with its share of the yield distributed since its
+    /// last settlement (permissionless, at most once per yield epoch)
+    pub fn settle_yield(
+        ctx: Context<SettleYield>,
+        computation_offset: u64,
     ) -> Result<()> {
-        let o = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(UnsubscribeV2Output { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-        let user_subscription = &mut ctx.accounts.user_subscription;
-        user_subscription.encrypted_plan[0] = o.ciphertexts[0];
-        user_subscription.encrypted_plan[1] = o.ciphertexts[1];
-        user_subscription.encrypted_status = o.ciphertexts[2];
-        user_subscription.encrypted_next_payment_date = o.ciphertexts[3];
-        user_subscription.encrypted_start_date = o.ciphertexts[4];
-        user_subscription.nonce = o.nonce;
+        // Advance the ledger's epoch before queueing so an epoch settles at most once
+        let yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        require!(user_ledger.yield_epoch < yield_epoch, ErrorCode::YieldAlreadySettled);
+        user_ledger.yield_epoch = yield_epoch;
 
-        Ok(())
-    }
+        let user_is_new = user_ledger.nonce == 0;
+        let checkpoint_is_new = user_ledger.yield_checkpoint_nonce == 0;
 
-    #[arcium_callback(encrypted_ix = "process_payment_v2", auto_serialize = false)]
-    pub fn process_payment_v2_callback(
-        ctx: Context<ProcessPaymentV2Callback>,
-        output: SignedComputationOutputs<ProcessPaymentResult>,
-    ) -> Result<()> {
-        let ProcessPaymentResult { field_0: user_out, field_1: merchant_out, field_2: sub_out } = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(result) => result,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+        require_current_ledger_layout(user_ledger)?;
 
-        // Update user ledger
-        let user_ledger = &mut ctx.accounts.user_ledger;
-        user_ledger.encrypted_balance = user_out.ciphertexts[0];
-        user_ledger.encrypted_subscription_count = user_out.ciphertexts[1];
-        user_ledger.nonce = user_out.nonce;
+        // Settling moves the owed yield into the pool's user liabilities, so it takes the pool lock
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let yield_is_new = protocol_pool.yield_nonce == 0;
 
-        // Update merchant ledger
-        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
-        merchant_ledger.encrypted_balance = merchant_out.ciphertexts[0];
-        merchant_ledger.encrypted_total_claimed = merchant_out.ciphertexts[1];
-        merchant_ledger.nonce = merchant_out.nonce;
+        // ArgBuilder order must match Arcis circuit's settle_yield parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerState>)
+        //   2. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   3. yield_checkpoint (Enc<Mxe, u128>)
+        //   4. is_new (plaintext)
+        //   5. yield_is_new (plaintext)
+        //   6. checkpoint_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(user_ledger.nonce)
+            .encrypted_state(&user_ledger.state());
+        let args = pool_yield_args(args, protocol_pool);
+        let args = yield_checkpoint_args(args, user_ledger)
+            .plaintext_bool(user_is_new)                                // is_new
+            .plaintext_bool(yield_is_new)                               // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                          // checkpoint_is_new
+            .build();
 
-        // Update user subscription
-        let user_subscription = &mut ctx.accounts.user_subscription;
-        user_subscription.encrypted_plan[0] = sub_out.ciphertexts[0];
-        user_subscription.encrypted_plan[1] = sub_out.ciphertexts[1];
-        user_subscription.encrypted_status = sub_out.ciphertexts[2];
-        user_subscription.encrypted_next_payment_date = sub_out.ciphertexts[3];
-        user_subscription.encrypted_start_date = sub_out.ciphertexts[4];
-        user_subscription.nonce = sub_out.nonce;
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SettleYieldV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
 
         Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "verify_subscription_v2")]
-    pub fn verify_subscription_v2_callback(
-        ctx: Context<VerifySubscriptionV2Callback>,
-        output: SignedComputationOutputs<VerifySubscriptionV2Output>,
+    /// Forbid (or allow again) subscription charges from spending principal.
+    /// While locked, subscriptions are paid from accrued yield only.
+    pub fn set_principal_lock(
+        ctx: Context<SetPrincipalLock>,
+        computation_offset: u64,
+        encrypted_locked: [u8; 32],
+        encrypted_locked_nonce: u128,
     ) -> Result<()> {
-        // VerifySubscriptionV2Output.field_0 is a revealed bool (not encrypted)
-        let o = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(VerifySubscriptionV2Output { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let user_ledger = &ctx.accounts.user_ledger;
+        require!(
+            !is_zero_pubkey(&user_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
 
-        // Emit the revealed boolean result
-        emit!(SubscriptionVerified {
-            is_valid: o,
-        });
+        let is_new = user_ledger.nonce == 0;
+
+        require_current_ledger_layout(user_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's set_principal_lock parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerState>)
+        //   2. locked (Enc<Shared, bool>)
+        //   3. is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(user_ledger.nonce)
+            .encrypted_state(&user_ledger.state())
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_locked_nonce)
+            .encrypted::<bool>(&encrypted_locked)                           // locked
+            .plaintext_bool(is_new)                                     // is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SetPrincipalLockV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.user_ledger.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
 
         Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "claim_revenue_v2", auto_serialize = false)]
-    pub fn claim_revenue_v2_callback(
-        ctx: Context<ClaimRevenueV2Callback>,
-        output: SignedComputationOutputs<ClaimRevenueResult>,
+    /// Rewrite a ledger's ciphertexts from an older UserLedgerState layout into the
+    /// current one (permissionless; the ledger stays encrypted to its owner)
+    pub fn migrate_user_ledger(
+        ctx: Context<MigrateUserLedger>,
+        computation_offset: u64,
     ) -> Result<()> {
-        let ClaimRevenueResult { field_0: o, field_1: actual_amount } = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(result) => result,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
-
-        // Update merchant ledger
-        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
-        merchant_ledger.encrypted_balance = o.ciphertexts[0];
-        merchant_ledger.encrypted_total_claimed = o.ciphertexts[1];
-        merchant_ledger.nonce = o.nonce;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let user_ledger = &ctx.accounts.user_ledger;
+        require!(
+            user_ledger.nonce != 0 && user_ledger.state_version < USER_LEDGER_STATE_VERSION,
+            ErrorCode::StateAlreadyCurrent
+        );
 
-        // Transfer actual amount from pool to merchant if approved by MPC
-        if actual_amount > 0 {
-            let protocol_pool = &ctx.accounts.protocol_pool;
-            let signer_seeds: &[&[u8]] = &[
-                PROTOCOL_POOL_SEED,
-                protocol_pool.mint.as_ref(),
-                &[protocol_pool.bump],
-            ];
-            let signer = &[signer_seeds];
+        // ArgBuilder order must match Arcis circuit's migrate_user_ledger parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerStateV1>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(user_ledger.nonce)
+            .encrypted_state(&user_ledger.state_v1())
+            .build();
 
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: ctx.accounts.pool_token_account.to_account_info(),
-                    to: ctx.accounts.merchant_token_account.to_account_info(),
-                    authority: ctx.accounts.protocol_pool.to_account_info(),
-                },
-                signer,
-            );
-            anchor_spl::token::transfer(cpi_ctx, actual_amount)?;
-        }
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![MigrateUserLedgerV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.user_ledger.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
 
         Ok(())
     }
-}
 
-// ============================================================================
-// Account Structures
-// ============================================================================
+    /// Rewrite a subscription's ciphertexts from an older UserSubscriptionState
+    /// layout into the current one (permissionless)
+    pub fn migrate_user_subscription(
+        ctx: Context<MigrateUserSubscription>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let user_subscription = &ctx.accounts.user_subscription;
+        require!(
+            user_subscription.nonce != 0
+                && user_subscription.state_version < USER_SUBSCRIPTION_STATE_VERSION,
+            ErrorCode::StateAlreadyCurrent
+        );
 
-/// Protocol configuration account
-/// PDA Seeds: ["protocol_config"]
-#[account]
-pub struct ProtocolConfig {
-    /// Protocol administrator
-    pub authority: Pubkey,
-    /// Fee rate in basis points (100 = 1%)
-    pub fee_rate_bps: u16,
-    /// Protocol pause flag
-    pub is_paused: bool,
-    /// PDA bump
-    pub bump: u8,
-}
+        // ArgBuilder order must match Arcis circuit's migrate_user_subscription parameters:
+        //   1. subscription (Enc<Shared, UserSubscriptionStateV1>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(user_subscription.encryption_pubkey)
+            .plaintext_u128(user_subscription.nonce)
+            .encrypted_state(&user_subscription.state_v1())
+            .build();
 
-impl ProtocolConfig {
-    pub const SIZE: usize = 8 + 32 + 2 + 1 + 1;
-}
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![MigrateUserSubscriptionV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.user_subscription.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
 
-/// Protocol token pool account
-/// PDA Seeds: ["protocol_pool", mint]
-#[account]
-pub struct ProtocolPool {
-    /// Token mint
-    pub mint: Pubkey,
-    /// Pool's token account
-    pub token_account: Pubkey,
-    /// PDA bump
-    pub bump: u8,
-}
+        Ok(())
+    }
 
-impl ProtocolPool {
-    pub const SIZE: usize = 8 + 32 + 32 + 1;
-}
+    /// Rewrite a merchant ledger's ciphertexts from an older MerchantLedgerState
+    /// layout into the current one (permissionless)
+    pub fn migrate_merchant_ledger(
+        ctx: Context<MigrateMerchantLedger>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let merchant_ledger = &ctx.accounts.merchant_ledger;
+        require!(
+            merchant_ledger.nonce != 0
+                && merchant_ledger.state_version < MERCHANT_LEDGER_STATE_VERSION,
+            ErrorCode::StateAlreadyCurrent
+        );
 
-/// Merchant account
-/// PDA Seeds: ["merchant", wallet]
-#[account]
-pub struct Merchant {
-    /// Merchant wallet address
-    pub wallet: Pubkey,
-    /// Merchant name (fixed-size)
-    pub name: [u8; MAX_NAME_LENGTH],
-    /// Active flag
-    pub is_active: bool,
-    /// Registration timestamp
-    pub registered_at: i64,
-    /// PDA bump
-    pub bump: u8,
-}
+        // ArgBuilder order must match Arcis circuit's migrate_merchant_ledger parameters:
+        //   1. merchant_ledger (Enc<Shared, MerchantLedgerStateV1>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(merchant_ledger.encryption_pubkey)
+            .plaintext_u128(merchant_ledger.nonce)
+            .encrypted_state(&merchant_ledger.state_v1())
+            .build();
 
-impl Merchant {
-    pub const SIZE: usize = 8 + 32 + MAX_NAME_LENGTH + 1 + 8 + 1;
-}
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![MigrateMerchantLedgerV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.merchant_ledger.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
 
-/// Merchant ledger for encrypted balance tracking
-/// PDA Seeds: ["merchant_ledger", merchant, mint]
-#[account]
-pub struct MerchantLedger {
-    /// Associated merchant
-    pub merchant: Pubkey,
-    /// Token mint
-    pub mint: Pubkey,
-    /// X25519 encryption public key (used for Enc<Shared, T>)
-    pub encryption_pubkey: [u8; 32],
-    /// Encrypted balance (Enc<Shared, u64>)
-    pub encrypted_balance: [u8; 32],
-    /// Encrypted total claimed (Enc<Shared, u64>)
-    pub encrypted_total_claimed: [u8; 32],
-    /// Nonce for encryption
-    pub nonce: u128,
-    /// PDA bump
-    pub bump: u8,
-}
+        Ok(())
+    }
 
-impl MerchantLedger {
-    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 16 + 1;
-}
+    /// Start tracking liabilities for a pool created before they were recorded.
+    /// Such pools never charged fees, so everything they hold is owed to users
+    /// and merchants (authority only, once per pool)
+    pub fn seed_pool_liabilities(
+        ctx: Context<SeedPoolLiabilities>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
-/// Subscription plan account
-/// PDA Seeds: ["subscription_plan", merchant, plan_id.to_le_bytes()]
-#[account]
-pub struct SubscriptionPlan {
-    /// Associated merchant
-    pub merchant: Pubkey,
-    /// Plan ID (unique per merchant)
-    pub plan_id: u64,
-    /// Plan name (fixed-size)
-    pub name: [u8; MAX_PLAN_NAME_LENGTH],
-    /// Payment token mint
-    pub mint: Pubkey,
-    /// Subscription price
-    pub price: u64,
-    /// Billing cycle in days
-    pub billing_cycle_days: u32,
-    /// Active flag
-    pub is_active: bool,
-    /// Creation timestamp
-    pub created_at: i64,
-    /// PDA bump
-    pub bump: u8,
-}
+        let now = Clock::get()?.unix_timestamp;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        require!(!protocol_pool.liabilities_seeded, ErrorCode::LiabilitiesAlreadySeeded);
+        require!(!is_liabilities_locked(protocol_pool, now), ErrorCode::LiabilitiesBusy);
+        protocol_pool.liabilities_lock = ctx.accounts.computation_account.key();
+        protocol_pool.liabilities_locked_at = now;
 
-impl SubscriptionPlan {
-    pub const SIZE: usize = 8 + 32 + 8 + MAX_PLAN_NAME_LENGTH + 32 + 8 + 4 + 1 + 8 + 1;
-}
+        let amount =
+            ctx.accounts.pool_token_account.amount + ctx.accounts.protocol_pool.strategy_allocated;
 
-/// User ledger for encrypted balance tracking
-/// PDA Seeds: ["user_ledger", user, mint]
-#[account]
-pub struct UserLedger {
-    /// User wallet
-    pub user: Pubkey,
-    /// Token mint
-    pub mint: Pubkey,
-    /// X25519 encryption public key (used for Enc<Shared, T>)
-    pub encryption_pubkey: [u8; 32],
-    /// Encrypted balance (Enc<Shared, u64>)
-    pub encrypted_balance: [u8; 32],
-    /// Encrypted subscription count (Enc<Shared, u64>)
-    pub encrypted_subscription_count: [u8; 32],
-    /// Nonce for encryption
-    pub nonce: u128,
-    /// Last update timestamp
-    pub last_updated: i64,
-    /// PDA bump
-    pub bump: u8,
-}
+        // ArgBuilder order must match Arcis circuit's seed_liabilities parameters:
+        //   1. amount (plaintext)
+        let args = ArgBuilder::new()
+            .plaintext_u64(amount)
+            .build();
 
-impl UserLedger {
-    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 16 + 8 + 1;
-}
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SeedLiabilitiesV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.protocol_pool.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
 
-/// User subscription account
-/// PDA Seeds: ["user_subscription", user, subscription_index.to_le_bytes()]
-#[account]
-pub struct UserSubscription {
-    /// User wallet
-    pub user: Pubkey,
-    /// Subscription index (unique per user)
-    pub subscription_index: u64,
-    /// X25519 encryption public key (used for Enc<Shared, T>)
-    pub encryption_pubkey: [u8; 32],
-    /// Encrypted plan public key (Enc<Shared, [u128; 2]>)
-    pub encrypted_plan: [[u8; 32]; 2],
-    /// Encrypted status (Enc<Shared, u8>)
-    pub encrypted_status: [u8; 32],
-    /// Encrypted next payment date (Enc<Shared, i64>)
-    pub encrypted_next_payment_date: [u8; 32],
-    /// Encrypted start date (Enc<Shared, i64>)
-    pub encrypted_start_date: [u8; 32],
-    /// Nonce for encryption
-    pub nonce: u128,
-    /// PDA bump
-    pub bump: u8,
-}
+        Ok(())
+    }
 
-impl UserSubscription {
-    pub const SIZE: usize = 8 + 32 + 8 + 32 + (32 * 2) + 32 + 32 + 32 + 16 + 1;
+    /// Reactivate a cancelled subscription, reusing its existing PDA
+    pub fn reactivate_subscription(
+        ctx: Context<ReactivateSubscription>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.subscription_plan.is_active, ErrorCode::PlanNotActive);
+        require!(
+            ctx.accounts.subscription_plan.price_currency == MINT_PRICE_CURRENCY,
+            ErrorCode::ReferencePricedPlan
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            ctx.accounts.user_subscription.encryption_pubkey
+                == ctx.accounts.user_ledger.encryption_pubkey,
+            ErrorCode::EncryptionKeyMismatch
+        );
+
+        init_plan_stats_if_new(
+            &mut ctx.accounts.plan_stats,
+            ctx.accounts.subscription_plan.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.merchant_ledger.encryption_pubkey,
+            ctx.bumps.plan_stats,
+        );
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+        ctx.accounts.user_ledger.last_updated = current_timestamp;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let plan_price = ctx.accounts.subscription_plan.price;
+        let billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
+        let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
+        let user_is_new = ctx.accounts.user_ledger.nonce == 0;
+        let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
+        let cap_is_new = ctx.accounts.spending_cap.nonce == 0;
+        let stats_is_new = ctx.accounts.plan_stats.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let checkpoint_is_new = ctx.accounts.user_ledger.yield_checkpoint_nonce == 0;
+        let charges_is_new = ctx.accounts.user_subscription.charges_nonce == 0;
+        ctx.accounts.user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        require_current_ledger_layout(&ctx.accounts.user_ledger)?;
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's reactivate_subscription parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerState>)
+        //   2. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   3. subscription (Enc<Shared, UserSubscriptionState>)
+        //   4. spending_cap (Enc<Shared, SpendingCapState>)
+        //   5. plan_stats (Enc<Shared, PlanStatsState>)
+        //   6. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   7. yield_checkpoint (Enc<Mxe, u128>)
+        //   8. charges (Enc<Mxe, ChargeRecordState>)
+        //   9. current_timestamp (plaintext)
+        //  10. plan_price (plaintext)
+        //  11. billing_cycle_days (plaintext)
+        //  12. plan_pubkey (plaintext [u128; 2])
+        //  13. user_is_new (plaintext)
+        //  14. merchant_is_new (plaintext)
+        //  15. cap_is_new (plaintext)
+        //  16. stats_is_new (plaintext)
+        //  17. yield_is_new (plaintext)
+        //  18. checkpoint_is_new (plaintext)
+        //  19. charges_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.user_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_ledger.nonce)
+            .encrypted_state(&ctx.accounts.user_ledger.state())
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .x25519_pubkey(ctx.accounts.spending_cap.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.spending_cap.nonce)
+            .encrypted_state(&ctx.accounts.spending_cap.state())
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state());
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, &ctx.accounts.user_ledger);
+        let args = charge_record_args(args, &ctx.accounts.user_subscription)
+            .plaintext_i64(current_timestamp)                                       // current_timestamp
+            .plaintext_u64(plan_price)                                              // plan_price
+            .plaintext_u32(billing_cycle_days)                                      // billing_cycle_days
+            .plaintext_u128(plan_bytes[0])                                          // plan_pubkey_part1
+            .plaintext_u128(plan_bytes[1])                                          // plan_pubkey_part2
+            .plaintext_bool(user_is_new)                                            // user_is_new
+            .plaintext_bool(merchant_is_new)                                        // merchant_is_new
+            .plaintext_bool(cap_is_new)                                             // cap_is_new
+            .plaintext_bool(stats_is_new)                                           // stats_is_new
+            .plaintext_bool(yield_is_new)                                           // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                                      // checkpoint_is_new
+            .plaintext_bool(charges_is_new)                                         // charges_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ReactivateSubscriptionV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.spending_cap.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.plan_stats.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Cancel a subscription at the end of the current paid cycle
+    pub fn cancel_at_period_end(
+        ctx: Context<CancelAtPeriodEnd>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_subscription.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+
+        // ArgBuilder order must match Arcis circuit's cancel_at_period_end parameters:
+        //   1. subscription (Enc<Shared, UserSubscriptionState>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CancelAtPeriodEndV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.user_subscription.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Undo a pending cancellation before the paid-through date
+    pub fn resume_subscription(
+        ctx: Context<ResumeSubscription>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_subscription.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+
+        // ArgBuilder order must match Arcis circuit's resume_subscription parameters:
+        //   1. subscription (Enc<Shared, UserSubscriptionState>)
+        //   2. current_timestamp (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .plaintext_i64(current_timestamp)
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ResumeSubscriptionV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.user_subscription.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Refund a subscriber from the merchant's encrypted ledger
+    pub fn refund(
+        ctx: Context<Refund>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 32],
+        encrypted_amount_nonce: u128,
+        cancel_subscription: bool,
+    ) -> Result<()> {
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            ctx.accounts.user_subscription.encryption_pubkey
+                == ctx.accounts.user_ledger.encryption_pubkey,
+            ErrorCode::EncryptionKeyMismatch
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        init_plan_stats_if_new(
+            &mut ctx.accounts.plan_stats,
+            ctx.accounts.subscription_plan.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.merchant_ledger.encryption_pubkey,
+            ctx.bumps.plan_stats,
+        );
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+        let plan_price = ctx.accounts.subscription_plan.price;
+        let billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
+        let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
+        let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
+        let user_is_new = ctx.accounts.user_ledger.nonce == 0;
+        let stats_is_new = ctx.accounts.plan_stats.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let checkpoint_is_new = ctx.accounts.user_ledger.yield_checkpoint_nonce == 0;
+        ctx.accounts.user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        require_current_ledger_layout(&ctx.accounts.user_ledger)?;
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's refund parameters:
+        //   1. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   2. user_ledger (Enc<Shared, UserLedgerState>)
+        //   3. subscription (Enc<Shared, UserSubscriptionState>)
+        //   4. plan_stats (Enc<Shared, PlanStatsState>)
+        //   5. amount (Enc<Shared, u64>, encrypted to the merchant key)
+        //   6. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   7. yield_checkpoint (Enc<Mxe, u128>)
+        //   8. current_timestamp (plaintext)
+        //   9. plan_pubkey (plaintext [u128; 2])
+        //  10. plan_price (plaintext)
+        //  11. billing_cycle_days (plaintext)
+        //  12. cancel_subscription (plaintext)
+        //  13. merchant_is_new (plaintext)
+        //  14. user_is_new (plaintext)
+        //  15. stats_is_new (plaintext)
+        //  16. yield_is_new (plaintext)
+        //  17. checkpoint_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .x25519_pubkey(ctx.accounts.user_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_ledger.nonce)
+            .encrypted_state(&ctx.accounts.user_ledger.state())
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state())
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_amount_nonce)
+            .encrypted::<u64>(&encrypted_amount);                                       // amount
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, &ctx.accounts.user_ledger)
+            .plaintext_i64(current_timestamp)                                       // current_timestamp
+            .plaintext_u128(plan_bytes[0])                                          // plan_pubkey_part1
+            .plaintext_u128(plan_bytes[1])                                          // plan_pubkey_part2
+            .plaintext_u64(plan_price)                                              // plan_price
+            .plaintext_u32(billing_cycle_days)                                      // billing_cycle_days
+            .plaintext_bool(cancel_subscription)                                    // cancel_subscription
+            .plaintext_bool(merchant_is_new)                                        // merchant_is_new
+            .plaintext_bool(user_is_new)                                            // user_is_new
+            .plaintext_bool(stats_is_new)                                           // stats_is_new
+            .plaintext_bool(yield_is_new)                                           // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                                      // checkpoint_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RefundV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.plan_stats.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Open a dispute against one billing period of a subscription
+    pub fn open_dispute(
+        ctx: Context<OpenDispute>,
+        computation_offset: u64,
+        billing_period: u64,
+        encrypted_amount: [u8; 32],
+        encrypted_amount_nonce: u128,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.subscription_plan.price_currency == MINT_PRICE_CURRENCY,
+            ErrorCode::ReferencePricedPlan
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_subscription.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            billing_period <= MAX_DISPUTE_BILLING_PERIOD,
+            ErrorCode::InvalidBillingPeriod
+        );
+        require!(
+            billing_period >= ctx.accounts.user_subscription.prepaid_cycles as u64,
+            ErrorCode::VoucherPrepaidPeriod
+        );
+        require!(
+            ctx.accounts.user_subscription.open_disputes < MAX_OPEN_DISPUTES,
+            ErrorCode::TooManyOpenDisputes
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Pending disputes count towards the cap until the callback rejects or a resolution closes them
+        ctx.accounts.user_subscription.open_disputes += 1;
+
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.user_subscription = ctx.accounts.user_subscription.key();
+        dispute.user = ctx.accounts.user.key();
+        dispute.merchant = ctx.accounts.subscription_plan.merchant;
+        dispute.subscription_plan = ctx.accounts.subscription_plan.key();
+        dispute.mint = ctx.accounts.mint.key();
+        dispute.billing_period = billing_period;
+        dispute.status = DISPUTE_STATUS_PENDING;
+        dispute.encrypted_amount = [0u8; 32];
+        dispute.nonce = 0;
+        dispute.opened_at = Clock::get()?.unix_timestamp;
+        dispute.resolved_at = 0;
+        dispute.bump = ctx.bumps.dispute;
+
+        let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
+        let billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
+        let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
+        let charges_is_new = ctx.accounts.user_subscription.charges_nonce == 0;
+
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's open_dispute parameters:
+        //   1. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   2. subscription (Enc<Shared, UserSubscriptionState>)
+        //   3. amount (Enc<Shared, u64>, encrypted to the user key)
+        //   4. charges (Enc<Mxe, ChargeRecordState>)
+        //   5. plan_pubkey (plaintext [u128; 2])
+        //   6. billing_cycle_days (plaintext)
+        //   7. billing_period (plaintext)
+        //   8. merchant_is_new (plaintext)
+        //   9. charges_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(encrypted_amount_nonce)
+            .encrypted::<u64>(&encrypted_amount);                                       // amount
+        let args = charge_record_args(args, &ctx.accounts.user_subscription)
+            .plaintext_u128(plan_bytes[0])                                          // plan_pubkey_part1
+            .plaintext_u128(plan_bytes[1])                                          // plan_pubkey_part2
+            .plaintext_u32(billing_cycle_days)                                      // billing_cycle_days
+            .plaintext_u64(billing_period)                                          // billing_period
+            .plaintext_bool(merchant_is_new)                                        // merchant_is_new
+            .plaintext_bool(charges_is_new)                                         // charges_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![OpenDisputeV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.dispute.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Resolve a dispute by refunding the frozen amount to the user (arbiter only)
+    pub fn resolve_dispute_refund(
+        ctx: Context<ResolveDisputeRefund>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.dispute.status == DISPUTE_STATUS_OPEN,
+            ErrorCode::DisputeNotOpen
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let user_is_new = ctx.accounts.user_ledger.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let checkpoint_is_new = ctx.accounts.user_ledger.yield_checkpoint_nonce == 0;
+        ctx.accounts.user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        require_current_ledger_layout(&ctx.accounts.user_ledger)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's resolve_dispute_refund parameters:
+        //   1. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   2. user_ledger (Enc<Shared, UserLedgerState>)
+        //   3. disputed_amount (Enc<Mxe, u64>)
+        //   4. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   5. yield_checkpoint (Enc<Mxe, u128>)
+        //   6. user_is_new (plaintext)
+        //   7. yield_is_new (plaintext)
+        //   8. checkpoint_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .x25519_pubkey(ctx.accounts.user_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_ledger.nonce)
+            .encrypted_state(&ctx.accounts.user_ledger.state())
+            .plaintext_u128(ctx.accounts.dispute.nonce)
+            .encrypted::<u64>(&ctx.accounts.dispute.encrypted_amount);                   // disputed_amount
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, &ctx.accounts.user_ledger)
+            .plaintext_bool(user_is_new)                                            // user_is_new
+            .plaintext_bool(yield_is_new)                                           // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                                      // checkpoint_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ResolveDisputeRefundV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.dispute.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Resolve a dispute by releasing the frozen amount to the merchant (arbiter only)
+    pub fn resolve_dispute_release(
+        ctx: Context<ResolveDisputeRelease>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.dispute.status == DISPUTE_STATUS_OPEN,
+            ErrorCode::DisputeNotOpen
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's resolve_dispute_release parameters:
+        //   1. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   2. disputed_amount (Enc<Mxe, u64>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .plaintext_u128(ctx.accounts.dispute.nonce)
+            .encrypted::<u64>(&ctx.accounts.dispute.encrypted_amount)                    // disputed_amount
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ResolveDisputeReleaseV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.dispute.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Set (or change) the user's spending cap for a merchant
+    pub fn set_spending_cap(
+        ctx: Context<SetSpendingCap>,
+        computation_offset: u64,
+        encrypted_cap: [u8; 32],
+        encrypted_cap_nonce: u128,
+        encrypted_window_days: [u8; 32],
+        encrypted_window_days_nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let encryption_pubkey = ctx.accounts.user_ledger.encryption_pubkey;
+        require!(
+            !is_zero_pubkey(&encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        let user = ctx.accounts.user.key();
+        let merchant = ctx.accounts.merchant.key();
+        let mint = ctx.accounts.mint.key();
+        init_spending_cap_if_new(
+            &mut ctx.accounts.spending_cap,
+            user,
+            merchant,
+            mint,
+            encryption_pubkey,
+            ctx.bumps.spending_cap,
+        );
+
+        let is_new = ctx.accounts.spending_cap.nonce == 0;
+        let current_timestamp = Clock::get()?.unix_timestamp;
+
+        // ArgBuilder order must match Arcis circuit's set_spending_cap parameters:
+        //   1. spending_cap (Enc<Shared, SpendingCapState>)
+        //   2. cap (Enc<Shared, u64>)
+        //   3. window_days (Enc<Shared, u32>)
+        //   4. current_timestamp (i64)
+        //   5. is_new (bool)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(ctx.accounts.spending_cap.nonce)
+            .encrypted_state(&ctx.accounts.spending_cap.state())
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encrypted_cap_nonce)
+            .encrypted::<u64>(&encrypted_cap)                                               // new cap
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encrypted_window_days_nonce)
+            .encrypted::<u32>(&encrypted_window_days)                                       // window_days
+            .plaintext_i64(current_timestamp)                                           // current_timestamp
+            .plaintext_bool(is_new)                                                     // is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SetSpendingCapV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.spending_cap.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Query how long the user's balance lasts across their subscriptions
+    ///
+    /// remaining_accounts: up to MAX_RUNWAY_SUBSCRIPTIONS pairs of
+    /// (user_subscription, subscription_plan). The result is emitted
+    /// encrypted to the user's key.
+    pub fn query_balance_runway<'info>(
+        ctx: Context<'_, '_, 'info, 'info, QueryBalanceRunway<'info>>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let encryption_pubkey = ctx.accounts.user_ledger.encryption_pubkey;
+        require!(
+            !is_zero_pubkey(&encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        let remaining = ctx.remaining_accounts;
+        require!(
+            remaining.len() % 2 == 0 && remaining.len() / 2 <= MAX_RUNWAY_SUBSCRIPTIONS,
+            ErrorCode::InvalidRunwayAccounts
+        );
+
+        let user = ctx.accounts.user.key();
+        let mint = ctx.accounts.user_ledger.mint;
+        let mut slots: Vec<(Account<'info, UserSubscription>, Account<'info, SubscriptionPlan>)> =
+            Vec::with_capacity(MAX_RUNWAY_SUBSCRIPTIONS);
+        for pair in remaining.chunks(2) {
+            let user_subscription = Account::<UserSubscription>::try_from(&pair[0])?;
+            let subscription_plan = Account::<SubscriptionPlan>::try_from(&pair[1])?;
+            require!(user_subscription.user == user, ErrorCode::Unauthorized);
+            require_current_subscription_layout(&user_subscription)?;
+            require!(subscription_plan.mint == mint, ErrorCode::InvalidMint);
+            require!(
+                subscription_plan.price_currency == MINT_PRICE_CURRENCY,
+                ErrorCode::ReferencePricedPlan
+            );
+            slots.push((user_subscription, subscription_plan));
+        }
+
+        require_current_ledger_layout(&ctx.accounts.user_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's balance_runway parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerState>)
+        //   2-5. subscription_0..3 (Enc<Shared, UserSubscriptionState>)
+        //   6. plan_pubkeys (plaintext [[u128; 2]; 4])
+        //   7. plan_prices (plaintext [u64; 4])
+        //   8. plan_billing_cycle_days (plaintext [u32; 4])
+        //   9. slot_used (plaintext [bool; 4])
+        // Unused slots are zero-filled and masked out by slot_used.
+        let mut args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_ledger.nonce)
+            .encrypted_state(&ctx.accounts.user_ledger.state());
+        for i in 0..MAX_RUNWAY_SUBSCRIPTIONS {
+            args = match slots.get(i) {
+                Some((sub, _)) => args
+                    .x25519_pubkey(sub.encryption_pubkey)
+                    .plaintext_u128(sub.nonce)
+                    .encrypted_state(&sub.state()),
+                None => args
+                    .x25519_pubkey(encryption_pubkey)
+                    .plaintext_u128(0)
+                    .encrypted_state(&UserSubscriptionStateCiphertexts::default()),
+            };
+        }
+        for i in 0..MAX_RUNWAY_SUBSCRIPTIONS {
+            let plan_bytes = match slots.get(i) {
+                Some((_, plan)) => pubkey_to_u128s(&plan.key()),
+                None => [0u128; 2],
+            };
+            args = args.plaintext_u128(plan_bytes[0]).plaintext_u128(plan_bytes[1]);
+        }
+        for i in 0..MAX_RUNWAY_SUBSCRIPTIONS {
+            args = args.plaintext_u64(slots.get(i).map_or(0, |(_, plan)| plan.price));
+        }
+        for i in 0..MAX_RUNWAY_SUBSCRIPTIONS {
+            args = args.plaintext_u32(slots.get(i).map_or(0, |(_, plan)| plan.billing_cycle_days));
+        }
+        for i in 0..MAX_RUNWAY_SUBSCRIPTIONS {
+            args = args.plaintext_bool(i < slots.len());
+        }
+        let args = args.build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![BalanceRunwayV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.user_ledger.key(),
+                    is_writable: false,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Check that the pool's token balance covers its total liabilities (permissionless)
+    pub fn check_solvency(
+        ctx: Context<CheckSolvency>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // An unseeded or in-flight liabilities ciphertext does not match the token balance
+        require!(ctx.accounts.protocol_pool.liabilities_seeded, ErrorCode::LiabilitiesNotSeeded);
+        require!(
+            !is_liabilities_locked(&ctx.accounts.protocol_pool, Clock::get()?.unix_timestamp),
+            ErrorCode::LiabilitiesBusy
+        );
+
+        // Liquidity allocated to the lending strategy still backs the liabilities
+        let token_balance =
+            ctx.accounts.pool_token_account.amount + ctx.accounts.protocol_pool.strategy_allocated;
+        let protocol_pool = &ctx.accounts.protocol_pool;
+        let liabilities_is_new = protocol_pool.liabilities_nonce == 0;
+
+        // Remember which computation the attestation is waiting for, so a stale
+        // callback from an earlier check cannot overwrite a newer result
+        let attestation = &mut ctx.accounts.solvency_attestation;
+        if attestation.pool == Pubkey::default() {
+            attestation.pool = protocol_pool.key();
+            attestation.is_solvent = false;
+            attestation.token_balance = 0;
+            attestation.attested_at = 0;
+            attestation.bump = ctx.bumps.solvency_attestation;
+        }
+        attestation.pending_computation = ctx.accounts.computation_account.key();
+        attestation.pending_token_balance = token_balance;
+
+        // ArgBuilder order must match Arcis circuit's check_solvency parameters:
+        //   1. total_liabilities (Enc<Mxe, u64>)
+        //   2. pool_balance (plaintext)
+        //   3. liabilities_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .plaintext_u128(protocol_pool.liabilities_nonce)
+            .encrypted::<u64>(&protocol_pool.encrypted_total_liabilities)   // total_liabilities
+            .plaintext_u64(token_balance)                               // pool_balance
+            .plaintext_bool(liabilities_is_new)                         // liabilities_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckSolvencyV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.solvency_attestation.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Refresh a plan's public subscriber count (permissionless, rate limited)
+    pub fn publish_plan_stats(
+        ctx: Context<PublishPlanStats>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        require!(ctx.accounts.plan_stats.nonce != 0, ErrorCode::PlanStatsUnavailable);
+
+        let now = Clock::get()?.unix_timestamp;
+        let public_stats = &mut ctx.accounts.plan_public_stats;
+        if public_stats.subscription_plan == Pubkey::default() {
+            public_stats.subscription_plan = ctx.accounts.plan_stats.subscription_plan;
+            public_stats.k = PUBLIC_STATS_MIN_SUBSCRIBERS;
+            public_stats.subscriber_count = 0;
+            public_stats.is_below_threshold = true;
+            public_stats.updated_at = 0;
+            public_stats.last_requested_at = 0;
+            public_stats.bump = ctx.bumps.plan_public_stats;
+        }
+        require!(
+            now >= public_stats.last_requested_at + PUBLIC_STATS_REFRESH_INTERVAL_SECONDS,
+            ErrorCode::RefreshTooSoon
+        );
+        public_stats.last_requested_at = now;
+        // Only the latest refresh may write its result
+        public_stats.pending_computation = ctx.accounts.computation_account.key();
+
+        // ArgBuilder order must match Arcis circuit's publish_plan_stats parameters:
+        //   1. plan_stats (Enc<Shared, PlanStatsState>)
+        //   2. k (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state())
+            .plaintext_u64(PUBLIC_STATS_MIN_SUBSCRIBERS)                                    // k
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PublishPlanStatsV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.plan_public_stats.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Re-encrypt a user ledger to the auditor key of an audit grant
+    pub fn disclose_user_ledger(
+        ctx: Context<DiscloseUserLedger>,
+        computation_offset: u64,
+        auditor_nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        check_audit_grant(
+            &ctx.accounts.audit_grant,
+            ctx.accounts.requester.key(),
+            ctx.accounts.user_ledger.key(),
+        )?;
+        require!(
+            ctx.accounts.user_ledger.user == ctx.accounts.audit_grant.owner,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        require_current_ledger_layout(&ctx.accounts.user_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's disclose_user_ledger parameters:
+        //   1. auditor (Shared)
+        //   2. user_ledger (Enc<Shared, UserLedgerState>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.audit_grant.auditor_encryption_pubkey)
+            .plaintext_u128(auditor_nonce)
+            .x25519_pubkey(ctx.accounts.user_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_ledger.nonce)
+            .encrypted_state(&ctx.accounts.user_ledger.state())
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![DiscloseUserLedgerV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.audit_grant.key(),
+                    is_writable: false,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Re-encrypt a merchant ledger to the auditor key of an audit grant
+    pub fn disclose_merchant_ledger(
+        ctx: Context<DiscloseMerchantLedger>,
+        computation_offset: u64,
+        auditor_nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        check_audit_grant(
+            &ctx.accounts.audit_grant,
+            ctx.accounts.requester.key(),
+            ctx.accounts.merchant_ledger.key(),
+        )?;
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's disclose_merchant_ledger parameters:
+        //   1. auditor (Shared)
+        //   2. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.audit_grant.auditor_encryption_pubkey)
+            .plaintext_u128(auditor_nonce)
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![DiscloseMerchantLedgerV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.audit_grant.key(),
+                    is_writable: false,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Re-encrypt a subscription record to the auditor key of an audit grant
+    pub fn disclose_subscription(
+        ctx: Context<DiscloseSubscription>,
+        computation_offset: u64,
+        auditor_nonce: u128,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        check_audit_grant(
+            &ctx.accounts.audit_grant,
+            ctx.accounts.requester.key(),
+            ctx.accounts.user_subscription.key(),
+        )?;
+        require!(
+            ctx.accounts.user_subscription.user == ctx.accounts.audit_grant.owner,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_subscription.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+
+        // ArgBuilder order must match Arcis circuit's disclose_subscription parameters:
+        //   1. auditor (Shared)
+        //   2. subscription (Enc<Shared, UserSubscriptionState>)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.audit_grant.auditor_encryption_pubkey)
+            .plaintext_u128(auditor_nonce)
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![DiscloseSubscriptionV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.audit_grant.key(),
+                    is_writable: false,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Transfer an encrypted amount to another user's ledger (no token movement).
+    /// The recipient's ledger is created on demand; the amount waits in its pending
+    /// transfers until the recipient claims it, setting their own key on the first claim.
+    pub fn transfer(
+        ctx: Context<Transfer>,
+        computation_offset: u64,
+        encrypted_amount: [u8; 32],
+        encrypted_amount_nonce: u128,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.recipient.key() != ctx.accounts.sender.key(),
+            ErrorCode::InvalidRecipient
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.sender_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        // Transfers credit the recipient like a deposit would
+        check_screening(
+            &ctx.accounts.protocol_pool,
+            &ctx.accounts.protocol_config,
+            ctx.accounts.recipient_screening_attestation.as_deref(),
+        )?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        // Initialize the recipient ledger if it's new; only the recipient can set its key
+        init_user_ledger_if_new(
+            &mut ctx.accounts.recipient_ledger,
+            ctx.accounts.recipient.key(),
+            ctx.accounts.mint.key(),
+            ctx.bumps.recipient_ledger,
+        );
+        ctx.accounts.recipient_ledger.last_updated = now;
+        ctx.accounts.sender_ledger.last_updated = now;
+        // The transfer settles the sender's pending yield, which counts as this epoch's settlement
+        ctx.accounts.sender_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let sender_ledger = &ctx.accounts.sender_ledger;
+        let recipient_ledger = &ctx.accounts.recipient_ledger;
+        let sender_is_new = sender_ledger.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let sender_checkpoint_is_new = sender_ledger.yield_checkpoint_nonce == 0;
+        let pending_is_new = recipient_ledger.pending_transfers_nonce == 0;
+
+        require_current_ledger_layout(sender_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's transfer parameters:
+        //   1. sender_ledger (Enc<Shared, UserLedgerState>)
+        //   2. amount (Enc<Shared, u64>, encrypted to the sender key)
+        //   3. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   4. sender_checkpoint (Enc<Mxe, u128>)
+        //   5. recipient_pending (Enc<Mxe, u64>)
+        //   6. sender_is_new (plaintext)
+        //   7. yield_is_new (plaintext)
+        //   8. sender_checkpoint_is_new (plaintext)
+        //   9. pending_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(sender_ledger.encryption_pubkey)
+            .plaintext_u128(sender_ledger.nonce)
+            .encrypted_state(&sender_ledger.state())
+            .x25519_pubkey(sender_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_amount_nonce)
+            .encrypted::<u64>(&encrypted_amount);                               // amount
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, sender_ledger);
+        let args = pending_transfers_args(args, recipient_ledger)
+            .plaintext_bool(sender_is_new)                                  // sender_is_new
+            .plaintext_bool(yield_is_new)                                   // yield_is_new
+            .plaintext_bool(sender_checkpoint_is_new)                       // sender_checkpoint_is_new
+            .plaintext_bool(pending_is_new)                                 // pending_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![TransferV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.sender_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.recipient_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Move the transfers received by the signer's ledger into its balance. A ledger
+    /// created by a transfer takes the signer's encryption key on its first claim.
+    pub fn claim_transfers(
+        ctx: Context<ClaimTransfers>,
+        computation_offset: u64,
+        encryption_pubkey: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            !is_zero_pubkey(&encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        set_ledger_key(user_ledger, encryption_pubkey)?;
+        user_ledger.last_updated = Clock::get()?.unix_timestamp;
+        // Claiming settles pending yield, which counts as this epoch's settlement
+        user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        let user_is_new = user_ledger.nonce == 0;
+        let pending_is_new = user_ledger.pending_transfers_nonce == 0;
+        let checkpoint_is_new = user_ledger.yield_checkpoint_nonce == 0;
+
+        require_current_ledger_layout(user_ledger)?;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        let user_ledger = &ctx.accounts.user_ledger;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+
+        // ArgBuilder order must match Arcis circuit's claim_transfers parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerState>)
+        //   2. pending (Enc<Mxe, u64>)
+        //   3. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   4. yield_checkpoint (Enc<Mxe, u128>)
+        //   5. is_new (plaintext)
+        //   6. pending_is_new (plaintext)
+        //   7. yield_is_new (plaintext)
+        //   8. checkpoint_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(user_ledger.encryption_pubkey)
+            .plaintext_u128(user_ledger.nonce)
+            .encrypted_state(&user_ledger.state());
+        let args = pending_transfers_args(args, user_ledger);
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, user_ledger)
+            .plaintext_bool(user_is_new)                                // is_new
+            .plaintext_bool(pending_is_new)                             // pending_is_new
+            .plaintext_bool(yield_is_new)                               // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                          // checkpoint_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ClaimTransfersV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Buy a gift voucher prepaying `cycles` billing periods of a plan
+    ///
+    /// The voucher's secret hash goes into the pool for the plan, `cycles` and the
+    /// plan's current billing cycle length, which fix its terms. Anyone holding
+    /// the secret can redeem it.
+    pub fn buy_voucher(
+        ctx: Context<BuyVoucher>,
+        computation_offset: u64,
+        cycles: u32,
+        encrypted_secret_hash: [u8; 32],
+        encrypted_secret_hash_nonce: u128,
+    ) -> Result<()> {
+        require!(ctx.accounts.subscription_plan.is_active, ErrorCode::PlanNotActive);
+        require!(
+            ctx.accounts.subscription_plan.price_currency == MINT_PRICE_CURRENCY,
+            ErrorCode::ReferencePricedPlan
+        );
+        require!(
+            (1..=MAX_VOUCHER_CYCLES).contains(&cycles),
+            ErrorCode::InvalidVoucherCycles
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.buyer_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let amount = ctx
+            .accounts
+            .subscription_plan
+            .price
+            .checked_mul(cycles as u64)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let voucher_pool = &mut ctx.accounts.voucher_pool;
+        if voucher_pool.subscription_plan == Pubkey::default() {
+            voucher_pool.subscription_plan = ctx.accounts.subscription_plan.key();
+            voucher_pool.cycles = cycles;
+            voucher_pool.billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
+            voucher_pool.encrypted_slots = [[0u8; 32]; VOUCHER_POOL_SLOTS];
+            voucher_pool.nonce = 0;
+            voucher_pool.outstanding = 0;
+            voucher_pool.lock = Pubkey::default();
+            voucher_pool.locked_at = 0;
+            voucher_pool.bump = ctx.bumps.voucher_pool;
+        }
+        require!(
+            (voucher_pool.outstanding as usize) < VOUCHER_POOL_SLOTS,
+            ErrorCode::VoucherPoolFull
+        );
+        lock_voucher_pool(voucher_pool, ctx.accounts.computation_account.key(), now)?;
+
+        let buyer_ledger = &mut ctx.accounts.buyer_ledger;
+        buyer_ledger.last_updated = now;
+        buyer_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let buyer_is_new = buyer_ledger.nonce == 0;
+        let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
+        let vouchers_is_new = ctx.accounts.voucher_pool.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let checkpoint_is_new = buyer_ledger.yield_checkpoint_nonce == 0;
+
+        require_current_ledger_layout(buyer_ledger)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's buy_voucher parameters:
+        //   1. buyer_ledger (Enc<Shared, UserLedgerState>)
+        //   2. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   3. voucher_pool (Enc<Mxe, [u128; VOUCHER_POOL_SLOTS]>)
+        //   4. secret_hash (Enc<Shared, u128>, buyer key)
+        //   5. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   6. yield_checkpoint (Enc<Mxe, u128>)
+        //   7. amount (plaintext)
+        //   8. buyer_is_new (plaintext)
+        //   9. merchant_is_new (plaintext)
+        //  10. vouchers_is_new (plaintext)
+        //  11. yield_is_new (plaintext)
+        //  12. checkpoint_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(buyer_ledger.encryption_pubkey)
+            .plaintext_u128(buyer_ledger.nonce)
+            .encrypted_state(&buyer_ledger.state())
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .plaintext_u128(ctx.accounts.voucher_pool.nonce)
+            .encrypted::<[u128; VOUCHER_POOL_SLOTS]>(&ctx.accounts.voucher_pool.encrypted_slots) // voucher_pool
+            .x25519_pubkey(buyer_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_secret_hash_nonce)
+            .encrypted::<u128>(&encrypted_secret_hash);                         // secret_hash
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, buyer_ledger)
+            .plaintext_u64(amount)                                              // amount
+            .plaintext_bool(buyer_is_new)                                       // buyer_is_new
+            .plaintext_bool(merchant_is_new)                                    // merchant_is_new
+            .plaintext_bool(vouchers_is_new)                                    // vouchers_is_new
+            .plaintext_bool(yield_is_new)                                       // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                                  // checkpoint_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![BuyVoucherV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.buyer_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.voucher_pool.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Redeem a gift voucher into a new prepaid subscription
+    ///
+    /// Only the voucher pool is named; the MPC finds and clears the matching slot
+    /// without revealing which purchase it came from.
+    pub fn redeem_voucher(
+        ctx: Context<RedeemVoucher>,
+        computation_offset: u64,
+        subscription_index: u64,
+        encrypted_secret_hash: [u8; 32],
+        encrypted_secret_hash_nonce: u128,
+    ) -> Result<()> {
+        require!(ctx.accounts.subscription_plan.is_active, ErrorCode::PlanNotActive);
+        require!(
+            ctx.accounts.voucher_pool.outstanding > 0,
+            ErrorCode::NoOutstandingVouchers
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            !is_zero_pubkey(&ctx.accounts.recipient_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Initialize user subscription PDA
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.user = ctx.accounts.recipient.key();
+        user_subscription.subscription_index = subscription_index;
+        user_subscription.encryption_pubkey = ctx.accounts.recipient_ledger.encryption_pubkey;
+        user_subscription.encrypted_plan = [[0u8; 32]; 2];
+        user_subscription.encrypted_status = [0u8; 32];
+        user_subscription.encrypted_next_payment_date = [0u8; 32];
+        user_subscription.encrypted_start_date = [0u8; 32];
+        user_subscription.encrypted_quantity = [0u8; 32];
+        user_subscription.state_version = USER_SUBSCRIPTION_STATE_VERSION;
+        user_subscription.nonce = 0;
+        user_subscription.bump = ctx.bumps.user_subscription;
+        user_subscription.account_version = UserSubscription::VERSION;
+        user_subscription.open_disputes = 0;
+        user_subscription.seats_assigned = 0;
+        user_subscription.encrypted_charges = [[0u8; 32]; ChargeRecordStateCiphertexts::LEN];
+        user_subscription.charges_nonce = 0;
+        user_subscription.prepaid_cycles = ctx.accounts.voucher_pool.cycles;
+
+        // Renewals after the prepaid cycles are charged against this cap
+        init_spending_cap_if_new(
+            &mut ctx.accounts.spending_cap,
+            ctx.accounts.recipient.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.mint.key(),
+            ctx.accounts.recipient_ledger.encryption_pubkey,
+            ctx.bumps.spending_cap,
+        );
+
+        init_plan_stats_if_new(
+            &mut ctx.accounts.plan_stats,
+            ctx.accounts.subscription_plan.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.merchant_ledger.encryption_pubkey,
+            ctx.bumps.plan_stats,
+        );
+
+        lock_voucher_pool(
+            &mut ctx.accounts.voucher_pool,
+            ctx.accounts.computation_account.key(),
+            now,
+        )?;
+
+        let recipient_ledger = &mut ctx.accounts.recipient_ledger;
+        recipient_ledger.last_updated = now;
+
+        let current_timestamp = now;
+        let recipient_is_new = recipient_ledger.nonce == 0;
+        let stats_is_new = ctx.accounts.plan_stats.nonce == 0;
+        let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
+
+        require_current_ledger_layout(recipient_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's redeem_voucher parameters:
+        //   1. voucher_pool (Enc<Mxe, [u128; VOUCHER_POOL_SLOTS]>)
+        //   2. recipient_ledger (Enc<Shared, UserLedgerState>)
+        //   3. plan_stats (Enc<Shared, PlanStatsState>)
+        //   4. secret_hash (Enc<Shared, u128>, recipient key)
+        //   5. current_timestamp (plaintext)
+        //   6. plan_pubkey (plaintext [u128; 2])
+        //   7. plan_price (plaintext, for analytics)
+        //   8. billing_cycle_days (plaintext, fixed at purchase)
+        //   9. cycles (plaintext)
+        //  10. recipient_is_new (plaintext)
+        //  11. stats_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.voucher_pool.nonce)
+            .encrypted::<[u128; VOUCHER_POOL_SLOTS]>(&ctx.accounts.voucher_pool.encrypted_slots) // voucher_pool
+            .x25519_pubkey(recipient_ledger.encryption_pubkey)
+            .plaintext_u128(recipient_ledger.nonce)
+            .encrypted_state(&recipient_ledger.state())
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state())
+            .x25519_pubkey(recipient_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_secret_hash_nonce)
+            .encrypted::<u128>(&encrypted_secret_hash)                          // secret_hash
+            .plaintext_i64(current_timestamp)                                   // current_timestamp
+            .plaintext_u128(plan_bytes[0])                                      // plan_pubkey_part1
+            .plaintext_u128(plan_bytes[1])                                      // plan_pubkey_part2
+            .plaintext_u64(ctx.accounts.subscription_plan.price)                // plan_price
+            .plaintext_u32(ctx.accounts.voucher_pool.billing_cycle_days)        // billing_cycle_days
+            .plaintext_u32(ctx.accounts.voucher_pool.cycles)                    // cycles
+            .plaintext_bool(recipient_is_new)                                   // recipient_is_new
+            .plaintext_bool(stats_is_new)                                       // stats_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RedeemVoucherV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.voucher_pool.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.recipient_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.plan_stats.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Add or remove seats on a subscription, prorated to the next payment date
+    pub fn update_seats(
+        ctx: Context<UpdateSeats>,
+        computation_offset: u64,
+        encrypted_quantity: [u8; 32],
+        encrypted_quantity_nonce: u128,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.subscription_plan.price_currency == MINT_PRICE_CURRENCY,
+            ErrorCode::ReferencePricedPlan
+        );
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.merchant_ledger.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+        require!(
+            ctx.accounts.user_subscription.encryption_pubkey
+                == ctx.accounts.user_ledger.encryption_pubkey,
+            ErrorCode::EncryptionKeyMismatch
+        );
+
+        init_plan_stats_if_new(
+            &mut ctx.accounts.plan_stats,
+            ctx.accounts.subscription_plan.key(),
+            ctx.accounts.subscription_plan.merchant,
+            ctx.accounts.merchant_ledger.encryption_pubkey,
+            ctx.bumps.plan_stats,
+        );
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+        lock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let plan_price = ctx.accounts.subscription_plan.price;
+        let billing_cycle_days = ctx.accounts.subscription_plan.billing_cycle_days;
+        let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
+        let seats_assigned = ctx.accounts.user_subscription.seats_assigned;
+        let user_is_new = ctx.accounts.user_ledger.nonce == 0;
+        let merchant_is_new = ctx.accounts.merchant_ledger.nonce == 0;
+        let cap_is_new = ctx.accounts.spending_cap.nonce == 0;
+        let stats_is_new = ctx.accounts.plan_stats.nonce == 0;
+        let yield_is_new = ctx.accounts.protocol_pool.yield_nonce == 0;
+        let checkpoint_is_new = ctx.accounts.user_ledger.yield_checkpoint_nonce == 0;
+        let charges_is_new = ctx.accounts.user_subscription.charges_nonce == 0;
+        ctx.accounts.user_ledger.yield_epoch = ctx.accounts.protocol_pool.yield_epoch;
+
+        require_current_ledger_layout(&ctx.accounts.user_ledger)?;
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+        require_current_merchant_ledger_layout(&ctx.accounts.merchant_ledger)?;
+
+        // ArgBuilder order must match Arcis circuit's update_seats parameters:
+        //   1. user_ledger (Enc<Shared, UserLedgerState>)
+        //   2. merchant_ledger (Enc<Shared, MerchantLedgerState>)
+        //   3. subscription (Enc<Shared, UserSubscriptionState>)
+        //   4. spending_cap (Enc<Shared, SpendingCapState>)
+        //   5. plan_stats (Enc<Shared, PlanStatsState>)
+        //   6. new_quantity (Enc<Shared, u32>)
+        //   7. pool_yield (Enc<Mxe, PoolYieldState>)
+        //   8. yield_checkpoint (Enc<Mxe, u128>)
+        //   9. charges (Enc<Mxe, ChargeRecordState>)
+        //  10. current_timestamp (plaintext)
+        //  11. plan_price (plaintext, per seat)
+        //  12. billing_cycle_days (plaintext)
+        //  13. plan_pubkey (plaintext [u128; 2])
+        //  14. seats_assigned (plaintext)
+        //  15. user_is_new (plaintext)
+        //  16. merchant_is_new (plaintext)
+        //  17. cap_is_new (plaintext)
+        //  18. stats_is_new (plaintext)
+        //  19. yield_is_new (plaintext)
+        //  20. checkpoint_is_new (plaintext)
+        //  21. charges_is_new (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.user_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_ledger.nonce)
+            .encrypted_state(&ctx.accounts.user_ledger.state())
+            .x25519_pubkey(ctx.accounts.merchant_ledger.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.merchant_ledger.nonce)
+            .encrypted_state(&ctx.accounts.merchant_ledger.state())
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .x25519_pubkey(ctx.accounts.spending_cap.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.spending_cap.nonce)
+            .encrypted_state(&ctx.accounts.spending_cap.state())
+            .x25519_pubkey(ctx.accounts.plan_stats.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.plan_stats.nonce)
+            .encrypted_state(&ctx.accounts.plan_stats.state())
+            .x25519_pubkey(ctx.accounts.user_ledger.encryption_pubkey)
+            .plaintext_u128(encrypted_quantity_nonce)
+            .encrypted::<u32>(&encrypted_quantity);                                     // new_quantity
+        let args = pool_yield_args(args, &ctx.accounts.protocol_pool);
+        let args = yield_checkpoint_args(args, &ctx.accounts.user_ledger);
+        let args = charge_record_args(args, &ctx.accounts.user_subscription)
+            .plaintext_i64(current_timestamp)                                       // current_timestamp
+            .plaintext_u64(plan_price)                                              // plan_price
+            .plaintext_u32(billing_cycle_days)                                      // billing_cycle_days
+            .plaintext_u128(plan_bytes[0])                                          // plan_pubkey_part1
+            .plaintext_u128(plan_bytes[1])                                          // plan_pubkey_part2
+            .plaintext_u32(seats_assigned)                                          // seats_assigned
+            .plaintext_bool(user_is_new)                                            // user_is_new
+            .plaintext_bool(merchant_is_new)                                        // merchant_is_new
+            .plaintext_bool(cap_is_new)                                             // cap_is_new
+            .plaintext_bool(stats_is_new)                                           // stats_is_new
+            .plaintext_bool(yield_is_new)                                           // yield_is_new
+            .plaintext_bool(checkpoint_is_new)                                      // checkpoint_is_new
+            .plaintext_bool(charges_is_new)                                         // charges_is_new
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![UpdateSeatsV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.merchant_ledger.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.spending_cap.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.plan_stats.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.protocol_pool.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Assign one of the subscription's seats to a member wallet
+    pub fn assign_seat(
+        ctx: Context<AssignSeat>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_subscription.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        // Reserve the seat now so concurrent assignments see distinct indices;
+        // the callback releases it again if the subscription has no room
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        let seat_index = user_subscription.seats_assigned;
+        user_subscription.seats_assigned = seat_index
+            .checked_add(1)
+            .ok_or(ErrorCode::NoSeatAvailable)?;
+
+        let seat_assignment = &mut ctx.accounts.seat_assignment;
+        seat_assignment.user_subscription = user_subscription.key();
+        seat_assignment.member = ctx.accounts.member.key();
+        seat_assignment.status = SEAT_STATUS_PENDING;
+        seat_assignment.assigned_at = Clock::get()?.unix_timestamp;
+        seat_assignment.bump = ctx.bumps.seat_assignment;
+
+        let plan_bytes = pubkey_to_u128s(&ctx.accounts.subscription_plan.key());
+
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+
+        // ArgBuilder order must match Arcis circuit's assign_seat parameters:
+        //   1. subscription (Enc<Shared, UserSubscriptionState>)
+        //   2. plan_pubkey (plaintext [u128; 2])
+        //   3. seat_index (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .plaintext_u128(plan_bytes[0])                                          // plan_pubkey_part1
+            .plaintext_u128(plan_bytes[1])                                          // plan_pubkey_part2
+            .plaintext_u32(seat_index)                                              // seat_index
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![AssignSeatV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.user_subscription.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.seat_assignment.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Verify a subscription on behalf of a member holding an assigned seat
+    ///
+    /// Runs the same verify_subscription computation as the payer's check,
+    /// after confirming the member signed and their seat is active.
+    pub fn verify_seat(
+        ctx: Context<VerifySeat>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        require!(
+            ctx.accounts.seat_assignment.status == SEAT_STATUS_ACTIVE,
+            ErrorCode::SeatNotActive
+        );
+        require!(
+            !is_zero_pubkey(&ctx.accounts.user_subscription.encryption_pubkey),
+            ErrorCode::InvalidEncryptionKey
+        );
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+
+        require_current_subscription_layout(&ctx.accounts.user_subscription)?;
+
+        // ArgBuilder order must match Arcis circuit's verify_subscription parameters:
+        //   1. subscription (Enc<Shared, UserSubscriptionState>)
+        //   2. current_timestamp (plaintext)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(ctx.accounts.user_subscription.encryption_pubkey)
+            .plaintext_u128(ctx.accounts.user_subscription.nonce)
+            .encrypted_state(&ctx.accounts.user_subscription.state())
+            .plaintext_i64(current_timestamp)
+            .build();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![VerifySubscriptionV2Callback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Phase 2: Encrypted Instructions - Callback Phase
+    // ========================================================================
+
+    const SHARED_ENCRYPTED_BASE_SIZE: usize = 32 + 16;
+    const SHARED_ENCRYPTED_SIZE_3: usize = SHARED_ENCRYPTED_BASE_SIZE + (32 * 3);
+    const SHARED_ENCRYPTED_SIZE_4: usize = SHARED_ENCRYPTED_BASE_SIZE + (32 * 4);
+    const SHARED_ENCRYPTED_SIZE_6: usize = SHARED_ENCRYPTED_BASE_SIZE + (32 * 6);
+    const SHARED_ENCRYPTED_SIZE_7: usize = SHARED_ENCRYPTED_BASE_SIZE + (32 * 7);
+    const MXE_ENCRYPTED_SIZE_1: usize = 16 + 32;
+    const MXE_ENCRYPTED_SIZE_2: usize = 16 + (32 * 2);
+    const MXE_ENCRYPTED_SIZE_VOUCHER_POOL: usize = 16 + (32 * VOUCHER_POOL_SLOTS);
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct DepositResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: MXEEncryptedStruct<1>,
+        pub field_3: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for DepositResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct WithdrawResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: MXEEncryptedStruct<1>,
+        pub field_3: MXEEncryptedStruct<2>,
+        pub field_4: u64,
+    }
+
+    impl HasSize for WithdrawResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_2 + 8;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct ClaimRevenueResult {
+        pub field_0: SharedEncryptedStruct<3>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: u64,
+    }
+
+    impl HasSize for ClaimRevenueResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_3 + MXE_ENCRYPTED_SIZE_1 + 8;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct SubscribeResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: SharedEncryptedStruct<3>,
+        pub field_2: SharedEncryptedStruct<6>,
+        pub field_3: SharedEncryptedStruct<4>,
+        pub field_4: SharedEncryptedStruct<7>,
+        pub field_5: MXEEncryptedStruct<1>,
+        pub field_6: MXEEncryptedStruct<2>,
+        pub field_7: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for SubscribeResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_3 + SHARED_ENCRYPTED_SIZE_6 + SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_7
+            + MXE_ENCRYPTED_SIZE_1
+            + MXE_ENCRYPTED_SIZE_2
+            + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct UnsubscribeResult {
+        pub field_0: SharedEncryptedStruct<6>,
+        pub field_1: SharedEncryptedStruct<7>,
+    }
+
+    impl HasSize for UnsubscribeResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_6 + SHARED_ENCRYPTED_SIZE_7;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct ProcessPaymentResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: SharedEncryptedStruct<3>,
+        pub field_2: SharedEncryptedStruct<6>,
+        pub field_3: SharedEncryptedStruct<4>,
+        pub field_4: SharedEncryptedStruct<7>,
+        pub field_5: MXEEncryptedStruct<1>,
+        pub field_6: MXEEncryptedStruct<2>,
+        pub field_7: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for ProcessPaymentResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_3 + SHARED_ENCRYPTED_SIZE_6 + SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_7
+            + MXE_ENCRYPTED_SIZE_1
+            + MXE_ENCRYPTED_SIZE_2
+            + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct ReactivateSubscriptionResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: SharedEncryptedStruct<3>,
+        pub field_2: SharedEncryptedStruct<6>,
+        pub field_3: SharedEncryptedStruct<4>,
+        pub field_4: SharedEncryptedStruct<7>,
+        pub field_5: MXEEncryptedStruct<1>,
+        pub field_6: MXEEncryptedStruct<2>,
+        pub field_7: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for ReactivateSubscriptionResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_3 + SHARED_ENCRYPTED_SIZE_6 + SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_7
+            + MXE_ENCRYPTED_SIZE_1
+            + MXE_ENCRYPTED_SIZE_2
+            + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct RefundResult {
+        pub field_0: SharedEncryptedStruct<3>,
+        pub field_1: SharedEncryptedStruct<4>,
+        pub field_2: SharedEncryptedStruct<6>,
+        pub field_3: SharedEncryptedStruct<7>,
+        pub field_4: MXEEncryptedStruct<1>,
+        pub field_5: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for RefundResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_3 + SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_6 + SHARED_ENCRYPTED_SIZE_7
+            + MXE_ENCRYPTED_SIZE_1
+            + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct OpenDisputeResult {
+        pub field_0: SharedEncryptedStruct<3>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: bool,
+    }
+
+    impl HasSize for OpenDisputeResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_3 + MXE_ENCRYPTED_SIZE_1 + 1;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct SetSpendingCapResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: bool,
+    }
+
+    impl HasSize for SetSpendingCapResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + 1;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct ResolveDisputeRefundResult {
+        pub field_0: SharedEncryptedStruct<3>,
+        pub field_1: SharedEncryptedStruct<4>,
+        pub field_2: MXEEncryptedStruct<1>,
+        pub field_3: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for ResolveDisputeRefundResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_3 + SHARED_ENCRYPTED_SIZE_4 + MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct TransferResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: MXEEncryptedStruct<2>,
+        pub field_3: MXEEncryptedStruct<1>,
+    }
+
+    impl HasSize for TransferResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_2 + MXE_ENCRYPTED_SIZE_1;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct ClaimTransfersResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: MXEEncryptedStruct<1>,
+        pub field_3: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for ClaimTransfersResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + MXE_ENCRYPTED_SIZE_1 * 2 + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct BuyVoucherResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: SharedEncryptedStruct<3>,
+        pub field_2: MXEEncryptedStruct<VOUCHER_POOL_SLOTS>,
+        pub field_3: MXEEncryptedStruct<1>,
+        pub field_4: MXEEncryptedStruct<2>,
+        pub field_5: bool,
+    }
+
+    impl HasSize for BuyVoucherResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4
+            + SHARED_ENCRYPTED_SIZE_3
+            + MXE_ENCRYPTED_SIZE_VOUCHER_POOL
+            + MXE_ENCRYPTED_SIZE_1
+            + MXE_ENCRYPTED_SIZE_2
+            + 1;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct RedeemVoucherResult {
+        pub field_0: MXEEncryptedStruct<VOUCHER_POOL_SLOTS>,
+        pub field_1: SharedEncryptedStruct<4>,
+        pub field_2: SharedEncryptedStruct<6>,
+        pub field_3: SharedEncryptedStruct<7>,
+        pub field_4: MXEEncryptedStruct<2>,
+        pub field_5: bool,
+    }
+
+    impl HasSize for RedeemVoucherResult {
+        const SIZE: usize = MXE_ENCRYPTED_SIZE_VOUCHER_POOL
+            + SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_6 + SHARED_ENCRYPTED_SIZE_7
+            + MXE_ENCRYPTED_SIZE_2
+            + 1;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct UpdateSeatsResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: SharedEncryptedStruct<3>,
+        pub field_2: SharedEncryptedStruct<6>,
+        pub field_3: SharedEncryptedStruct<4>,
+        pub field_4: SharedEncryptedStruct<7>,
+        pub field_5: MXEEncryptedStruct<1>,
+        pub field_6: MXEEncryptedStruct<2>,
+        pub field_7: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for UpdateSeatsResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_3 + SHARED_ENCRYPTED_SIZE_6 + SHARED_ENCRYPTED_SIZE_4 + SHARED_ENCRYPTED_SIZE_7
+            + MXE_ENCRYPTED_SIZE_1
+            + MXE_ENCRYPTED_SIZE_2
+            + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct ScheduledPayoutResult {
+        pub field_0: SharedEncryptedStruct<3>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: u64,
+    }
+
+    impl HasSize for ScheduledPayoutResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_3 + MXE_ENCRYPTED_SIZE_1 + 8;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct DistributeYieldResult {
+        pub field_0: MXEEncryptedStruct<2>,
+        pub field_1: MXEEncryptedStruct<1>,
+    }
+
+    impl HasSize for DistributeYieldResult {
+        const SIZE: usize = MXE_ENCRYPTED_SIZE_2 + MXE_ENCRYPTED_SIZE_1;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct SettleYieldResult {
+        pub field_0: SharedEncryptedStruct<4>,
+        pub field_1: MXEEncryptedStruct<1>,
+        pub field_2: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for SettleYieldResult {
+        const SIZE: usize = SHARED_ENCRYPTED_SIZE_4 + MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct SeedLiabilitiesResult {
+        pub field_0: MXEEncryptedStruct<1>,
+        pub field_1: MXEEncryptedStruct<2>,
+    }
+
+    impl HasSize for SeedLiabilitiesResult {
+        const SIZE: usize = MXE_ENCRYPTED_SIZE_1 + MXE_ENCRYPTED_SIZE_2;
+    }
+
+    #[arcium_callback(encrypted_ix = "deposit_v2", auto_serialize = false)]
+    pub fn deposit_v2_callback(
+        ctx: Context<DepositV2Callback>,
+        output: SignedComputationOutputs<DepositResult>,
+    ) -> Result<()> {
+        let DepositResult { field_0: o, field_1: liabilities, field_2: checkpoint, field_3: pool_yield } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&o.ciphertexts, o.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.encrypted_total_liabilities = liabilities.ciphertexts[0];
+        protocol_pool.liabilities_nonce = liabilities.nonce;
+        protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "withdraw_v2", auto_serialize = false)]
+    pub fn withdraw_v2_callback(
+        ctx: Context<WithdrawV2Callback>,
+        output: SignedComputationOutputs<WithdrawResult>,
+    ) -> Result<()> {
+        let WithdrawResult { field_0: o, field_1: liabilities, field_2: checkpoint, field_3: pool_yield, field_4: actual_amount } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update user ledger with new encrypted balance
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&o.ciphertexts, o.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.encrypted_total_liabilities = liabilities.ciphertexts[0];
+        protocol_pool.liabilities_nonce = liabilities.nonce;
+        protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        consume_pending_outflow(
+            ctx.accounts.pending_outflow.as_deref_mut(),
+            ctx.accounts.computation_account.key(),
+            actual_amount,
+        );
+
+        // Transfer actual amount from pool to user if approved by MPC
+        if actual_amount > 0 {
+            // Over budget, the whole callback reverts and the ledger keeps its balance
+            let now = Clock::get()?.unix_timestamp;
+            consume_outflow_budget(&mut ctx.accounts.protocol_pool, actual_amount, now)?;
+
+            let protocol_pool = &ctx.accounts.protocol_pool;
+            let signer_seeds: &[&[u8]] = &[
+                PROTOCOL_POOL_SEED,
+                protocol_pool.mint.as_ref(),
+                &[protocol_pool.bump],
+            ];
+            let signer = &[signer_seeds];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.pool_token_account.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.protocol_pool.to_account_info(),
+                },
+                signer,
+            );
+            anchor_spl::token::transfer(cpi_ctx, actual_amount)?;
+        }
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "subscribe_v2", auto_serialize = false)]
+    pub fn subscribe_v2_callback(
+        ctx: Context<SubscribeV2Callback>,
+        output: SignedComputationOutputs<SubscribeResult>,
+    ) -> Result<()> {
+        let SubscribeResult { field_0: user_out, field_1: merchant_out, field_2: sub_out, field_3: cap_out, field_4: stats_out, field_5: checkpoint, field_6: pool_yield, field_7: charges } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update user ledger
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&user_out.ciphertexts, user_out.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        // Update user subscription
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&sub_out.ciphertexts, sub_out.nonce);
+        user_subscription.write_charges(&charges.ciphertexts, charges.nonce);
+
+        // Update spending cap
+        let spending_cap = &mut ctx.accounts.spending_cap;
+        spending_cap.write_state(&cap_out.ciphertexts, cap_out.nonce);
+
+        // Update plan analytics
+        let plan_stats = &mut ctx.accounts.plan_stats;
+        plan_stats.write_state(&stats_out.ciphertexts, stats_out.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "unsubscribe_v2", auto_serialize = false)]
+    pub fn unsubscribe_v2_callback(
+        ctx: Context<UnsubscribeV2Callback>,
+        output: SignedComputationOutputs<UnsubscribeResult>,
+    ) -> Result<()> {
+        let UnsubscribeResult { field_0: o, field_1: stats_out } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&o.ciphertexts, o.nonce);
+
+        // Update plan analytics
+        let plan_stats = &mut ctx.accounts.plan_stats;
+        plan_stats.write_state(&stats_out.ciphertexts, stats_out.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "process_payment_v2", auto_serialize = false)]
+    pub fn process_payment_v2_callback(
+        ctx: Context<ProcessPaymentV2Callback>,
+        output: SignedComputationOutputs<ProcessPaymentResult>,
+    ) -> Result<()> {
+        let ProcessPaymentResult { field_0: user_out, field_1: merchant_out, field_2: sub_out, field_3: cap_out, field_4: stats_out, field_5: checkpoint, field_6: pool_yield, field_7: charges } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update user ledger
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&user_out.ciphertexts, user_out.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        // Update user subscription
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&sub_out.ciphertexts, sub_out.nonce);
+        user_subscription.write_charges(&charges.ciphertexts, charges.nonce);
+
+        // Update spending cap
+        let spending_cap = &mut ctx.accounts.spending_cap;
+        spending_cap.write_state(&cap_out.ciphertexts, cap_out.nonce);
+
+        // Update plan analytics
+        let plan_stats = &mut ctx.accounts.plan_stats;
+        plan_stats.write_state(&stats_out.ciphertexts, stats_out.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "verify_subscription_v2")]
+    pub fn verify_subscription_v2_callback(
+        ctx: Context<VerifySubscriptionV2Callback>,
+        output: SignedComputationOutputs<VerifySubscriptionV2Output>,
+    ) -> Result<()> {
+        // VerifySubscriptionV2Output.field_0 is a revealed bool (not encrypted)
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(VerifySubscriptionV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Emit the revealed boolean result
+        emit!(SubscriptionVerified {
+            is_valid: o,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "claim_revenue_v2", auto_serialize = false)]
+    pub fn claim_revenue_v2_callback(
+        ctx: Context<ClaimRevenueV2Callback>,
+        output: SignedComputationOutputs<ClaimRevenueResult>,
+    ) -> Result<()> {
+        let ClaimRevenueResult { field_0: o, field_1: liabilities, field_2: actual_amount } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&o.ciphertexts, o.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.encrypted_total_liabilities = liabilities.ciphertexts[0];
+        protocol_pool.liabilities_nonce = liabilities.nonce;
+
+        consume_pending_outflow(
+            ctx.accounts.pending_outflow.as_deref_mut(),
+            ctx.accounts.computation_account.key(),
+            actual_amount,
+        );
+
+        // Transfer actual amount from pool to merchant if approved by MPC
+        if actual_amount > 0 {
+            // Over budget, the whole callback reverts and the ledger keeps its balance
+            let now = Clock::get()?.unix_timestamp;
+            consume_outflow_budget(&mut ctx.accounts.protocol_pool, actual_amount, now)?;
+
+            let protocol_pool = &ctx.accounts.protocol_pool;
+            let signer_seeds: &[&[u8]] = &[
+                PROTOCOL_POOL_SEED,
+                protocol_pool.mint.as_ref(),
+                &[protocol_pool.bump],
+            ];
+            let signer = &[signer_seeds];
+
+            // claim_revenue_split appends the revenue split and its recipients
+            if ctx.remaining_accounts.is_empty() {
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.pool_token_account.to_account_info(),
+                        to: ctx.accounts.merchant_token_account.to_account_info(),
+                        authority: ctx.accounts.protocol_pool.to_account_info(),
+                    },
+                    signer,
+                );
+                anchor_spl::token::transfer(cpi_ctx, actual_amount)?;
+            } else {
+                // Remaining accounts carry their own lifetime, so the split transfers take
+                // every account they touch from them: [revenue_split, pool_token_account,
+                // protocol_pool, token_program, recipients...]
+                let remaining = ctx.remaining_accounts;
+                require!(remaining.len() > 4, ErrorCode::InvalidSplitRecipients);
+                let split_info = &remaining[0];
+                require!(split_info.owner == &crate::ID, ErrorCode::InvalidRevenueSplit);
+                let revenue_split = RevenueSplit::try_deserialize(&mut &split_info.try_borrow_data()?[..])?;
+                require!(
+                    revenue_split.merchant == ctx.accounts.merchant_ledger.merchant
+                        && revenue_split.mint == protocol_pool.mint,
+                    ErrorCode::InvalidRevenueSplit
+                );
+
+                let pool_token_info = &remaining[1];
+                let pool_info = &remaining[2];
+                let token_program_info = &remaining[3];
+                require!(
+                    pool_token_info.key() == ctx.accounts.pool_token_account.key()
+                        && pool_info.key() == protocol_pool.key()
+                        && token_program_info.key() == ctx.accounts.token_program.key(),
+                    ErrorCode::InvalidRevenueSplit
+                );
+
+                let recipient_count = revenue_split.recipient_count as usize;
+                let recipients = &remaining[4..];
+                require!(recipients.len() == recipient_count, ErrorCode::InvalidSplitRecipients);
+
+                let payouts = split_by_bps(actual_amount, &revenue_split.shares_bps[..recipient_count]);
+                for (i, recipient) in recipients.iter().enumerate() {
+                    require!(
+                        recipient.key() == revenue_split.recipients[i],
+                        ErrorCode::InvalidSplitRecipients
+                    );
+                    if payouts[i] == 0 {
+                        continue;
+                    }
+                    let cpi_ctx = CpiContext::new_with_signer(
+                        token_program_info.clone(),
+                        anchor_spl::token::Transfer {
+                            from: pool_token_info.clone(),
+                            to: recipient.clone(),
+                            authority: pool_info.clone(),
+                        },
+                        signer,
+                    );
+                    anchor_spl::token::transfer(cpi_ctx, payouts[i])?;
+                }
+            }
+        }
+
+        emit!(RevenueClaimed {
+            merchant: ctx.accounts.merchant_ledger.merchant,
+            mint: ctx.accounts.protocol_pool.mint,
+            amount: actual_amount,
+            split: !ctx.remaining_accounts.is_empty(),
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reactivate_subscription_v2", auto_serialize = false)]
+    pub fn reactivate_subscription_v2_callback(
+        ctx: Context<ReactivateSubscriptionV2Callback>,
+        output: SignedComputationOutputs<ReactivateSubscriptionResult>,
+    ) -> Result<()> {
+        let ReactivateSubscriptionResult { field_0: user_out, field_1: merchant_out, field_2: sub_out, field_3: cap_out, field_4: stats_out, field_5: checkpoint, field_6: pool_yield, field_7: charges } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update user ledger
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&user_out.ciphertexts, user_out.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        // Update user subscription
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&sub_out.ciphertexts, sub_out.nonce);
+        user_subscription.write_charges(&charges.ciphertexts, charges.nonce);
+
+        // Update spending cap
+        let spending_cap = &mut ctx.accounts.spending_cap;
+        spending_cap.write_state(&cap_out.ciphertexts, cap_out.nonce);
+
+        // Update plan analytics
+        let plan_stats = &mut ctx.accounts.plan_stats;
+        plan_stats.write_state(&stats_out.ciphertexts, stats_out.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "cancel_at_period_end_v2")]
+    pub fn cancel_at_period_end_v2_callback(
+        ctx: Context<CancelAtPeriodEndV2Callback>,
+        output: SignedComputationOutputs<CancelAtPeriodEndV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CancelAtPeriodEndV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "resume_subscription_v2")]
+    pub fn resume_subscription_v2_callback(
+        ctx: Context<ResumeSubscriptionV2Callback>,
+        output: SignedComputationOutputs<ResumeSubscriptionV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ResumeSubscriptionV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "refund_v2", auto_serialize = false)]
+    pub fn refund_v2_callback(
+        ctx: Context<RefundV2Callback>,
+        output: SignedComputationOutputs<RefundResult>,
+    ) -> Result<()> {
+        let RefundResult { field_0: merchant_out, field_1: user_out, field_2: sub_out, field_3: stats_out, field_4: checkpoint, field_5: pool_yield } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        // Update user ledger
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&user_out.ciphertexts, user_out.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        // Update user subscription
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&sub_out.ciphertexts, sub_out.nonce);
+
+        // Update plan analytics
+        let plan_stats = &mut ctx.accounts.plan_stats;
+        plan_stats.write_state(&stats_out.ciphertexts, stats_out.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "open_dispute_v2", auto_serialize = false)]
+    pub fn open_dispute_v2_callback(
+        ctx: Context<OpenDisputeV2Callback>,
+        output: SignedComputationOutputs<OpenDisputeResult>,
+    ) -> Result<()> {
+        let OpenDisputeResult { field_0: merchant_out, field_1: amount_out, field_2: frozen } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        // Record the frozen amount on the dispute. A dispute that froze nothing
+        // (wrong plan, uncharged period, amount over the charge or already claimed)
+        // is rejected and no longer counts towards the subscription's cap.
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.encrypted_amount = amount_out.ciphertexts[0];
+        dispute.nonce = amount_out.nonce;
+        if frozen {
+            dispute.status = DISPUTE_STATUS_OPEN;
+        } else {
+            dispute.status = DISPUTE_STATUS_REJECTED;
+            dispute.resolved_at = Clock::get()?.unix_timestamp;
+            let user_subscription = &mut ctx.accounts.user_subscription;
+            user_subscription.open_disputes = user_subscription.open_disputes.saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "resolve_dispute_refund_v2", auto_serialize = false)]
+    pub fn resolve_dispute_refund_v2_callback(
+        ctx: Context<ResolveDisputeRefundV2Callback>,
+        output: SignedComputationOutputs<ResolveDisputeRefundResult>,
+    ) -> Result<()> {
+        let ResolveDisputeRefundResult { field_0: merchant_out, field_1: user_out, field_2: checkpoint, field_3: pool_yield } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let dispute = &mut ctx.accounts.dispute;
+        require!(dispute.status == DISPUTE_STATUS_OPEN, ErrorCode::DisputeNotOpen);
+        dispute.status = DISPUTE_STATUS_REFUNDED;
+        dispute.resolved_at = Clock::get()?.unix_timestamp;
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.open_disputes = user_subscription.open_disputes.saturating_sub(1);
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        // Update user ledger
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&user_out.ciphertexts, user_out.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "resolve_dispute_release_v2")]
+    pub fn resolve_dispute_release_v2_callback(
+        ctx: Context<ResolveDisputeReleaseV2Callback>,
+        output: SignedComputationOutputs<ResolveDisputeReleaseV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ResolveDisputeReleaseV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let dispute = &mut ctx.accounts.dispute;
+        require!(dispute.status == DISPUTE_STATUS_OPEN, ErrorCode::DisputeNotOpen);
+        dispute.status = DISPUTE_STATUS_RELEASED;
+        dispute.resolved_at = Clock::get()?.unix_timestamp;
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.open_disputes = user_subscription.open_disputes.saturating_sub(1);
+
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "set_spending_cap_v2", auto_serialize = false)]
+    pub fn set_spending_cap_v2_callback(
+        ctx: Context<SetSpendingCapV2Callback>,
+        output: SignedComputationOutputs<SetSpendingCapResult>,
+    ) -> Result<()> {
+        let SetSpendingCapResult { field_0: o, field_1: is_valid_window } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // The circuit leaves the cap untouched; surface the rejection
+        require!(is_valid_window, ErrorCode::InvalidSpendingWindow);
+
+        let spending_cap = &mut ctx.accounts.spending_cap;
+        spending_cap.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "balance_runway_v2")]
+    pub fn balance_runway_v2_callback(
+        ctx: Context<BalanceRunwayV2Callback>,
+        output: SignedComputationOutputs<BalanceRunwayV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(BalanceRunwayV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Only the user can decrypt the result, so it is emitted rather than stored
+        let runway = BalanceRunwayCiphertexts::from_ciphertexts(&o.ciphertexts);
+        emit!(BalanceRunwayComputed {
+            user: ctx.accounts.user_ledger.user,
+            encrypted_monthly_spend: runway.monthly_spend,
+            encrypted_first_failure_date: runway.first_failure_date,
+            nonce: o.nonce,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_solvency_v2")]
+    pub fn check_solvency_v2_callback(
+        ctx: Context<CheckSolvencyV2Callback>,
+        output: SignedComputationOutputs<CheckSolvencyV2Output>,
+    ) -> Result<()> {
+        // CheckSolvencyV2Output.field_0 is a revealed bool (not encrypted)
+        let is_solvent = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckSolvencyV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let attestation = &mut ctx.accounts.solvency_attestation;
+        if attestation.pending_computation != ctx.accounts.computation_account.key() {
+            return Ok(());
+        }
+
+        attestation.is_solvent = is_solvent;
+        attestation.token_balance = attestation.pending_token_balance;
+        attestation.attested_at = Clock::get()?.unix_timestamp;
+        attestation.pending_computation = Pubkey::default();
+
+        emit!(SolvencyChecked {
+            pool: attestation.pool,
+            is_solvent,
+            token_balance: attestation.token_balance,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "transfer_v2", auto_serialize = false)]
+    pub fn transfer_v2_callback(
+        ctx: Context<TransferV2Callback>,
+        output: SignedComputationOutputs<TransferResult>,
+    ) -> Result<()> {
+        let TransferResult { field_0: sender_out, field_1: sender_checkpoint, field_2: pool_yield, field_3: recipient_pending } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let sender_ledger = &mut ctx.accounts.sender_ledger;
+        sender_ledger.write_state(&sender_out.ciphertexts, sender_out.nonce);
+        sender_ledger.write_yield_checkpoint(&sender_checkpoint);
+
+        ctx.accounts.recipient_ledger.write_pending_transfers(&recipient_pending);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "claim_transfers_v2", auto_serialize = false)]
+    pub fn claim_transfers_v2_callback(
+        ctx: Context<ClaimTransfersV2Callback>,
+        output: SignedComputationOutputs<ClaimTransfersResult>,
+    ) -> Result<()> {
+        let ClaimTransfersResult { field_0: o, field_1: pending, field_2: checkpoint, field_3: pool_yield } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&o.ciphertexts, o.nonce);
+        user_ledger.write_pending_transfers(&pending);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "buy_voucher_v2", auto_serialize = false)]
+    pub fn buy_voucher_v2_callback(
+        ctx: Context<BuyVoucherV2Callback>,
+        output: SignedComputationOutputs<BuyVoucherResult>,
+    ) -> Result<()> {
+        let BuyVoucherResult {
+            field_0: buyer_out,
+            field_1: merchant_out,
+            field_2: slots_out,
+            field_3: checkpoint,
+            field_4: pool_yield,
+            field_5: issued,
+        } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let voucher_pool = &mut ctx.accounts.voucher_pool;
+        unlock_voucher_pool(voucher_pool, ctx.accounts.computation_account.key())?;
+        voucher_pool.encrypted_slots = slots_out.ciphertexts;
+        voucher_pool.nonce = slots_out.nonce;
+        if issued {
+            voucher_pool.outstanding += 1;
+        }
+
+        let buyer_ledger = &mut ctx.accounts.buyer_ledger;
+        buyer_ledger.write_state(&buyer_out.ciphertexts, buyer_out.nonce);
+        buyer_ledger.write_yield_checkpoint(&checkpoint);
+
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        emit!(VoucherIssued {
+            voucher_pool: ctx.accounts.voucher_pool.key(),
+            issued,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "redeem_voucher_v2", auto_serialize = false)]
+    pub fn redeem_voucher_v2_callback(
+        ctx: Context<RedeemVoucherV2Callback>,
+        output: SignedComputationOutputs<RedeemVoucherResult>,
+    ) -> Result<()> {
+        let RedeemVoucherResult {
+            field_0: slots_out,
+            field_1: ledger_out,
+            field_2: sub_out,
+            field_3: stats_out,
+            field_4: charges,
+            field_5: redeemed,
+        } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let voucher_pool = &mut ctx.accounts.voucher_pool;
+        unlock_voucher_pool(voucher_pool, ctx.accounts.computation_account.key())?;
+        voucher_pool.encrypted_slots = slots_out.ciphertexts;
+        voucher_pool.nonce = slots_out.nonce;
+        if redeemed {
+            voucher_pool.outstanding -= 1;
+        }
+
+        let recipient_ledger = &mut ctx.accounts.recipient_ledger;
+        recipient_ledger.write_state(&ledger_out.ciphertexts, ledger_out.nonce);
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&sub_out.ciphertexts, sub_out.nonce);
+        user_subscription.write_charges(&charges.ciphertexts, charges.nonce);
+
+        let plan_stats = &mut ctx.accounts.plan_stats;
+        plan_stats.write_state(&stats_out.ciphertexts, stats_out.nonce);
+
+        emit!(VoucherRedeemed {
+            voucher_pool: ctx.accounts.voucher_pool.key(),
+            redeemed,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "update_seats_v2", auto_serialize = false)]
+    pub fn update_seats_v2_callback(
+        ctx: Context<UpdateSeatsV2Callback>,
+        output: SignedComputationOutputs<UpdateSeatsResult>,
+    ) -> Result<()> {
+        let UpdateSeatsResult { field_0: user_out, field_1: merchant_out, field_2: sub_out, field_3: cap_out, field_4: stats_out, field_5: checkpoint, field_6: pool_yield, field_7: charges } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&user_out.ciphertexts, user_out.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&merchant_out.ciphertexts, merchant_out.nonce);
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&sub_out.ciphertexts, sub_out.nonce);
+        user_subscription.write_charges(&charges.ciphertexts, charges.nonce);
+
+        let spending_cap = &mut ctx.accounts.spending_cap;
+        spending_cap.write_state(&cap_out.ciphertexts, cap_out.nonce);
+
+        // Update plan analytics
+        let plan_stats = &mut ctx.accounts.plan_stats;
+        plan_stats.write_state(&stats_out.ciphertexts, stats_out.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "assign_seat_v2")]
+    pub fn assign_seat_v2_callback(
+        ctx: Context<AssignSeatV2Callback>,
+        output: SignedComputationOutputs<AssignSeatV2Output>,
+    ) -> Result<()> {
+        // AssignSeatV2Output.field_0 is a revealed bool (not encrypted)
+        let has_seat = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(AssignSeatV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let seat_assignment = &mut ctx.accounts.seat_assignment;
+        if has_seat {
+            seat_assignment.status = SEAT_STATUS_ACTIVE;
+        } else {
+            seat_assignment.status = SEAT_STATUS_REJECTED;
+            // Give back the seat reserved when the assignment was queued
+            let user_subscription = &mut ctx.accounts.user_subscription;
+            user_subscription.seats_assigned = user_subscription.seats_assigned.saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "scheduled_payout_v2", auto_serialize = false)]
+    pub fn scheduled_payout_v2_callback(
+        ctx: Context<ScheduledPayoutV2Callback>,
+        output: SignedComputationOutputs<ScheduledPayoutResult>,
+    ) -> Result<()> {
+        let ScheduledPayoutResult { field_0: o, field_1: liabilities, field_2: actual_amount } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        // Update merchant ledger
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&o.ciphertexts, o.nonce);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.encrypted_total_liabilities = liabilities.ciphertexts[0];
+        protocol_pool.liabilities_nonce = liabilities.nonce;
+
+        // Below the threshold the balance stays put and nothing is transferred
+        if actual_amount > 0 {
+            // Over budget, the whole callback reverts and the ledger keeps its balance
+            let now = Clock::get()?.unix_timestamp;
+            consume_outflow_budget(&mut ctx.accounts.protocol_pool, actual_amount, now)?;
+
+            let protocol_pool = &ctx.accounts.protocol_pool;
+            let signer_seeds: &[&[u8]] = &[
+                PROTOCOL_POOL_SEED,
+                protocol_pool.mint.as_ref(),
+                &[protocol_pool.bump],
+            ];
+            let signer = &[signer_seeds];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.pool_token_account.to_account_info(),
+                    to: ctx.accounts.destination_token_account.to_account_info(),
+                    authority: ctx.accounts.protocol_pool.to_account_info(),
+                },
+                signer,
+            );
+            anchor_spl::token::transfer(cpi_ctx, actual_amount)?;
+
+            let payout_schedule = &mut ctx.accounts.payout_schedule;
+            payout_schedule.last_payout_at = now;
+            payout_schedule.last_payout_amount = actual_amount;
+        }
+
+        emit!(ScheduledPayoutExecuted {
+            merchant: ctx.accounts.payout_schedule.merchant,
+            mint: ctx.accounts.payout_schedule.mint,
+            amount: actual_amount,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "publish_plan_stats_v2")]
+    pub fn publish_plan_stats_v2_callback(
+        ctx: Context<PublishPlanStatsV2Callback>,
+        output: SignedComputationOutputs<PublishPlanStatsV2Output>,
+    ) -> Result<()> {
+        // PublishPlanStatsV2Output.field_0 is a revealed u64 (0 = fewer than k)
+        let subscriber_count = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PublishPlanStatsV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let public_stats = &mut ctx.accounts.plan_public_stats;
+        if public_stats.pending_computation != ctx.accounts.computation_account.key() {
+            return Ok(());
+        }
+
+        public_stats.subscriber_count = subscriber_count;
+        public_stats.is_below_threshold = subscriber_count == 0;
+        public_stats.updated_at = Clock::get()?.unix_timestamp;
+        public_stats.pending_computation = Pubkey::default();
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "disclose_user_ledger_v2")]
+    pub fn disclose_user_ledger_v2_callback(
+        ctx: Context<DiscloseUserLedgerV2Callback>,
+        output: SignedComputationOutputs<DiscloseUserLedgerV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(DiscloseUserLedgerV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let audit_grant = &ctx.accounts.audit_grant;
+        emit!(AuditDisclosed {
+            audit_grant: audit_grant.key(),
+            target: audit_grant.target,
+            auditor_encryption_pubkey: audit_grant.auditor_encryption_pubkey,
+            ciphertexts: o.ciphertexts.to_vec(),
+            nonce: o.nonce,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "disclose_merchant_ledger_v2")]
+    pub fn disclose_merchant_ledger_v2_callback(
+        ctx: Context<DiscloseMerchantLedgerV2Callback>,
+        output: SignedComputationOutputs<DiscloseMerchantLedgerV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(DiscloseMerchantLedgerV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let audit_grant = &ctx.accounts.audit_grant;
+        emit!(AuditDisclosed {
+            audit_grant: audit_grant.key(),
+            target: audit_grant.target,
+            auditor_encryption_pubkey: audit_grant.auditor_encryption_pubkey,
+            ciphertexts: o.ciphertexts.to_vec(),
+            nonce: o.nonce,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "disclose_subscription_v2")]
+    pub fn disclose_subscription_v2_callback(
+        ctx: Context<DiscloseSubscriptionV2Callback>,
+        output: SignedComputationOutputs<DiscloseSubscriptionV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(DiscloseSubscriptionV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let audit_grant = &ctx.accounts.audit_grant;
+        emit!(AuditDisclosed {
+            audit_grant: audit_grant.key(),
+            target: audit_grant.target,
+            auditor_encryption_pubkey: audit_grant.auditor_encryption_pubkey,
+            ciphertexts: o.ciphertexts.to_vec(),
+            nonce: o.nonce,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "distribute_yield_v2", auto_serialize = false)]
+    pub fn distribute_yield_v2_callback(
+        ctx: Context<DistributeYieldV2Callback>,
+        output: SignedComputationOutputs<DistributeYieldResult>,
+    ) -> Result<()> {
+        let DistributeYieldResult { field_0: pool_yield, field_1: liabilities } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+        protocol_pool.encrypted_total_liabilities = liabilities.ciphertexts[0];
+        protocol_pool.liabilities_nonce = liabilities.nonce;
+        protocol_pool.yield_epoch += 1;
+
+        emit!(YieldDistributed {
+            pool: protocol_pool.key(),
+            yield_epoch: protocol_pool.yield_epoch,
+        });
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "settle_yield_v2", auto_serialize = false)]
+    pub fn settle_yield_v2_callback(
+        ctx: Context<SettleYieldV2Callback>,
+        output: SignedComputationOutputs<SettleYieldResult>,
+    ) -> Result<()> {
+        let SettleYieldResult { field_0: o, field_1: checkpoint, field_2: pool_yield } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&o.ciphertexts, o.nonce);
+        user_ledger.write_yield_checkpoint(&checkpoint);
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        ctx.accounts.protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "set_principal_lock_v2")]
+    pub fn set_principal_lock_v2_callback(
+        ctx: Context<SetPrincipalLockV2Callback>,
+        output: SignedComputationOutputs<SetPrincipalLockV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SetPrincipalLockV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "migrate_user_ledger_v2")]
+    pub fn migrate_user_ledger_v2_callback(
+        ctx: Context<MigrateUserLedgerV2Callback>,
+        output: SignedComputationOutputs<MigrateUserLedgerV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MigrateUserLedgerV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_ledger = &mut ctx.accounts.user_ledger;
+        user_ledger.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "migrate_user_subscription_v2")]
+    pub fn migrate_user_subscription_v2_callback(
+        ctx: Context<MigrateUserSubscriptionV2Callback>,
+        output: SignedComputationOutputs<MigrateUserSubscriptionV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MigrateUserSubscriptionV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let user_subscription = &mut ctx.accounts.user_subscription;
+        user_subscription.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "migrate_merchant_ledger_v2")]
+    pub fn migrate_merchant_ledger_v2_callback(
+        ctx: Context<MigrateMerchantLedgerV2Callback>,
+        output: SignedComputationOutputs<MigrateMerchantLedgerV2Output>,
+    ) -> Result<()> {
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(MigrateMerchantLedgerV2Output { field_0 }) => field_0,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let merchant_ledger = &mut ctx.accounts.merchant_ledger;
+        merchant_ledger.write_state(&o.ciphertexts, o.nonce);
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "seed_liabilities_v2", auto_serialize = false)]
+    pub fn seed_liabilities_v2_callback(
+        ctx: Context<SeedLiabilitiesV2Callback>,
+        output: SignedComputationOutputs<SeedLiabilitiesResult>,
+    ) -> Result<()> {
+        let SeedLiabilitiesResult { field_0: liabilities, field_1: pool_yield } = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(result) => result,
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        unlock_pool_liabilities(
+            &mut ctx.accounts.protocol_pool,
+            ctx.accounts.computation_account.key(),
+        )?;
+        let protocol_pool = &mut ctx.accounts.protocol_pool;
+        protocol_pool.encrypted_total_liabilities = liabilities.ciphertexts[0];
+        protocol_pool.liabilities_nonce = liabilities.nonce;
+        protocol_pool.write_yield_state(&pool_yield.ciphertexts, pool_yield.nonce);
+        protocol_pool.liabilities_seeded = true;
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+/// Protocol configuration account
+/// PDA Seeds: ["protocol_config"]
+#[account]
+pub struct ProtocolConfig {
+    /// Protocol administrator
+    pub authority: Pubkey,
+    /// Fee rate in basis points (100 = 1%)
+    pub fee_rate_bps: u16,
+    /// Protocol pause flag
+    pub is_paused: bool,
+    /// PDA bump
+    pub bump: u8,
+    /// Arbiter appointed to resolve disputes
+    pub arbiter: Pubkey,
+    /// Authorities allowed to issue screening attestations
+    pub screening_authorities: [Pubkey; MAX_SCREENING_AUTHORITIES],
+    /// Number of listed screening authorities
+    pub screening_authority_count: u8,
+    /// Layout version of this account (0 = created before versioning)
+    pub account_version: u8,
+}
+
+impl ProtocolConfig {
+    pub const SIZE: usize = 8 + 32 + 2 + 1 + 1 + 32 + (32 * MAX_SCREENING_AUTHORITIES) + 1 + 1;
+    pub const VERSION: u8 = 1;
+}
+
+/// Protocol token pool account
+/// PDA Seeds: ["protocol_pool", mint]
+#[account]
+pub struct ProtocolPool {
+    /// Token mint
+    pub mint: Pubkey,
+    /// Pool's token account
+    pub token_account: Pubkey,
+    /// PDA bump
+    pub bump: u8,
+    /// Encrypted sum of all user and merchant balances for this mint (Enc<Mxe, u64>)
+    pub encrypted_total_liabilities: [u8; 32],
+    /// Nonce for the liabilities ciphertext (0 = not yet initialized)
+    pub liabilities_nonce: u128,
+    /// Whether deposits and withdrawals require a screening attestation
+    pub compliance_mode: bool,
+    /// Length of an outflow budget epoch in seconds
+    pub outflow_epoch_seconds: i64,
+    /// Maximum amount paid out per epoch (0 = unlimited)
+    pub outflow_epoch_budget: u64,
+    /// Start of the current outflow epoch
+    pub outflow_epoch_start: i64,
+    /// Amount paid out in the current epoch
+    pub outflow_epoch_spent: u64,
+    /// Withdrawals and claims above this amount need a time-locked request (0 = disabled)
+    pub large_outflow_threshold: u64,
+    /// Delay before a large outflow request can be executed
+    pub large_outflow_delay_seconds: i64,
+    /// Lending program idle liquidity is allocated to (default = none)
+    pub strategy_program: Pubkey,
+    /// Principal currently allocated to the lending strategy
+    pub strategy_allocated: u64,
+    /// Encrypted yield index and user-owed liabilities (Enc<Mxe, PoolYieldState>)
+    pub encrypted_yield_state: [[u8; 32]; PoolYieldStateCiphertexts::LEN],
+    /// Nonce for the yield state ciphertexts (0 = no yield distributed yet)
+    pub yield_nonce: u128,
+    /// Number of completed yield distributions
+    pub yield_epoch: u64,
+    /// When yield was last harvested from the strategy
+    pub last_yield_distribution_at: i64,
+    /// Whether the liabilities ciphertext tracks every balance held by the pool
+    pub liabilities_seeded: bool,
+    /// Computation currently allowed to write the liabilities (default = none)
+    pub liabilities_lock: Pubkey,
+    /// When the liabilities lock was taken
+    pub liabilities_locked_at: i64,
+    /// Layout version of this account (0 = created before versioning)
+    pub account_version: u8,
+}
+
+impl ProtocolPool {
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 32 + 16 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + (32 * PoolYieldStateCiphertexts::LEN) + 16 + 8 + 8 + 1 + 32 + 8 + 1;
+    pub const VERSION: u8 = 1;
+
+    /// Ciphertexts of the pool's PoolYieldState
+    pub fn yield_state(&self) -> PoolYieldStateCiphertexts {
+        PoolYieldStateCiphertexts::from_ciphertexts(&self.encrypted_yield_state)
+    }
+
+    /// Store a PoolYieldState computation output
+    pub fn write_yield_state(
+        &mut self,
+        ciphertexts: &[[u8; 32]; PoolYieldStateCiphertexts::LEN],
+        nonce: u128,
+    ) {
+        self.encrypted_yield_state = *ciphertexts;
+        self.yield_nonce = nonce;
+    }
+}
+
+/// Merchant account
+/// PDA Seeds: ["merchant", wallet]
+#[account]
+pub struct Merchant {
+    /// Merchant wallet address
+    pub wallet: Pubkey,
+    /// Merchant name (fixed-size)
+    pub name: [u8; MAX_NAME_LENGTH],
+    /// Active flag
+    pub is_active: bool,
+    /// Registration timestamp
+    pub registered_at: i64,
+    /// PDA bump
+    pub bump: u8,
+    /// Layout version of this account (0 = created before versioning)
+    pub account_version: u8,
+}
+
+impl Merchant {
+    pub const SIZE: usize = 8 + 32 + MAX_NAME_LENGTH + 1 + 8 + 1 + 1;
+    pub const VERSION: u8 = 1;
+}
+
+/// Merchant ledger for encrypted balance tracking
+/// PDA Seeds: ["merchant_ledger", merchant, mint]
+#[account]
+pub struct MerchantLedger {
+    /// Associated merchant
+    pub merchant: Pubkey,
+    /// Token mint
+    pub mint: Pubkey,
+    /// X25519 encryption public key (used for Enc<Shared, T>)
+    pub encryption_pubkey: [u8; 32],
+    /// Encrypted balance (Enc<Shared, u64>)
+    pub encrypted_balance: [u8; 32],
+    /// Encrypted total claimed (Enc<Shared, u64>)
+    pub encrypted_total_claimed: [u8; 32],
+    /// Nonce for encryption
+    pub nonce: u128,
+    /// PDA bump
+    pub bump: u8,
+    /// Encrypted amount frozen by open disputes (Enc<Shared, u64>)
+    pub encrypted_frozen: [u8; 32],
+    /// MerchantLedgerState layout of the ciphertexts (0 = written before disputes, layout 1)
+    pub state_version: u8,
+    /// Layout version of this account (0 = created before versioning)
+    pub account_version: u8,
+}
+
+impl MerchantLedger {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 16 + 1 + 32 + 1 + 1;
+    pub const VERSION: u8 = 1;
+
+    /// Ciphertexts of the ledger's MerchantLedgerState
+    pub fn state(&self) -> MerchantLedgerStateCiphertexts {
+        MerchantLedgerStateCiphertexts {
+            balance: self.encrypted_balance,
+            total_claimed: self.encrypted_total_claimed,
+            frozen: self.encrypted_frozen,
+        }
+    }
+
+    /// Ciphertexts of a ledger still written in MerchantLedgerState layout 1
+    pub fn state_v1(&self) -> MerchantLedgerStateV1Ciphertexts {
+        MerchantLedgerStateV1Ciphertexts {
+            balance: self.encrypted_balance,
+            total_claimed: self.encrypted_total_claimed,
+        }
+    }
+
+    /// Store a MerchantLedgerState computation output
+    pub fn write_state(
+        &mut self,
+        ciphertexts: &[[u8; 32]; MerchantLedgerStateCiphertexts::LEN],
+        nonce: u128,
+    ) {
+        let state = MerchantLedgerStateCiphertexts::from_ciphertexts(ciphertexts);
+        self.encrypted_balance = state.balance;
+        self.encrypted_total_claimed = state.total_claimed;
+        self.encrypted_frozen = state.frozen;
+        self.nonce = nonce;
+        self.state_version = MERCHANT_LEDGER_STATE_VERSION;
+    }
+}
+
+/// Subscription plan account
+/// PDA Seeds: ["subscription_plan", merchant, plan_id.to_le_bytes()]
+#[account]
+pub struct SubscriptionPlan {
+    /// Associated merchant
+    pub merchant: Pubkey,
+    /// Plan ID (unique per merchant)
+    pub plan_id: u64,
+    /// Plan name (fixed-size)
+    pub name: [u8; MAX_PLAN_NAME_LENGTH],
+    /// Payment token mint
+    pub mint: Pubkey,
+    /// Subscription price per seat, in `price_currency`
+    pub price: u64,
+    /// Billing cycle in days
+    pub billing_cycle_days: u32,
+    /// Active flag
+    pub is_active: bool,
+    /// Creation timestamp
+    pub created_at: i64,
+    /// PDA bump
+    pub bump: u8,
+    /// Reference currency code the price is quoted in (MINT_PRICE_CURRENCY = the plan's mint)
+    pub price_currency: [u8; 8],
+    /// Layout version of this account (0 = created before versioning)
+    pub account_version: u8,
+}
+
+impl SubscriptionPlan {
+    pub const SIZE: usize = 8 + 32 + 8 + MAX_PLAN_NAME_LENGTH + 32 + 8 + 4 + 1 + 8 + 1 + 8 + 1;
+    pub const VERSION: u8 = 1;
+}
+
+/// User ledger for encrypted balance tracking
+/// PDA Seeds: ["user_ledger", user, mint]
+#[account]
+pub struct UserLedger {
+    /// User wallet
+    pub user: Pubkey,
+    /// Token mint
+    pub mint: Pubkey,
+    /// X25519 encryption public key (used for Enc<Shared, T>)
+    pub encryption_pubkey: [u8; 32],
+    /// Encrypted principal (Enc<Shared, u64>)
+    pub encrypted_balance: [u8; 32],
+    /// Encrypted subscription count (Enc<Shared, u64>)
+    pub encrypted_subscription_count: [u8; 32],
+    /// Nonce for encryption
+    pub nonce: u128,
+    /// Last update timestamp
+    pub last_updated: i64,
+    /// PDA bump
+    pub bump: u8,
+    /// Pool yield index at the last settlement (Enc<Mxe, u128>)
+    pub encrypted_yield_checkpoint: [u8; 32],
+    /// Nonce for the yield checkpoint (0 = never settled)
+    pub yield_checkpoint_nonce: u128,
+    /// Pool yield epoch the ledger was last settled in
+    pub yield_epoch: u64,
+    /// Encrypted accrued yield, spent before principal (Enc<Shared, u64>, shares `nonce`)
+    pub encrypted_accrued_yield: [u8; 32],
+    /// Encrypted flag forbidding subscriptions to spend principal (Enc<Shared, bool>, shares `nonce`)
+    pub encrypted_principal_locked: [u8; 32],
+    /// UserLedgerState layout of the ciphertexts (0 = written before versioning, layout 1)
+    pub state_version: u8,
+    /// Layout version of this account (0 = created before versioning)
+    pub account_version: u8,
+    /// Transfers received and not yet claimed into the balance (Enc<Mxe, u64>)
+    pub encrypted_pending_transfers: [u8; 32],
+    /// Nonce for the pending transfers (0 = none received)
+    pub pending_transfers_nonce: u128,
+}
+
+impl UserLedger {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 16 + 8 + 1 + 32 + 16 + 8 + 32 + 32 + 1 + 1 + 32 + 16;
+    pub const VERSION: u8 = 2;
+
+    /// Ciphertexts of the ledger's UserLedgerState
+    pub fn state(&self) -> UserLedgerStateCiphertexts {
+        UserLedgerStateCiphertexts {
+            principal: self.encrypted_balance,
+            subscription_count: self.encrypted_subscription_count,
+            accrued_yield: self.encrypted_accrued_yield,
+            principal_locked: self.encrypted_principal_locked,
+        }
+    }
+
+    /// Ciphertexts of a ledger still written in UserLedgerState layout 1
+    pub fn state_v1(&self) -> UserLedgerStateV1Ciphertexts {
+        UserLedgerStateV1Ciphertexts {
+            balance: self.encrypted_balance,
+            subscription_count: self.encrypted_subscription_count,
+        }
+    }
+
+    /// Store a UserLedgerState computation output
+    pub fn write_state(
+        &mut self,
+        ciphertexts: &[[u8; 32]; UserLedgerStateCiphertexts::LEN],
+        nonce: u128,
+    ) {
+        let state = UserLedgerStateCiphertexts::from_ciphertexts(ciphertexts);
+        self.encrypted_balance = state.principal;
+        self.encrypted_subscription_count = state.subscription_count;
+        self.encrypted_accrued_yield = state.accrued_yield;
+        self.encrypted_principal_locked = state.principal_locked;
+        self.nonce = nonce;
+        self.state_version = USER_LEDGER_STATE_VERSION;
+    }
+
+    /// Store a yield checkpoint (Enc<Mxe, u128>) computation output
+    pub fn write_yield_checkpoint(&mut self, checkpoint: &MXEEncryptedStruct<1>) {
+        self.encrypted_yield_checkpoint = checkpoint.ciphertexts[0];
+        self.yield_checkpoint_nonce = checkpoint.nonce;
+    }
+
+    /// Store a pending transfers (Enc<Mxe, u64>) computation output
+    pub fn write_pending_transfers(&mut self, pending: &MXEEncryptedStruct<1>) {
+        self.encrypted_pending_transfers = pending.ciphertexts[0];
+        self.pending_transfers_nonce = pending.nonce;
+    }
+}
+
+/// User subscription account
+/// PDA Seeds: ["user_subscription", user, subscription_index.to_le_bytes()]
+#[account]
+pub struct UserSubscription {
+    /// User wallet
+    pub user: Pubkey,
+    /// Subscription index (unique per user)
+    pub subscription_index: u64,
+    /// X25519 encryption public key (used for Enc<Shared, T>)
+    pub encryption_pubkey: [u8; 32],
+    /// Encrypted plan public key (Enc<Shared, [u128; 2]>)
+    pub encrypted_plan: [[u8; 32]; 2],
+    /// Encrypted status (Enc<Shared, u8>)
+    pub encrypted_status: [u8; 32],
+    /// Encrypted next payment date (Enc<Shared, i64>)
+    pub encrypted_next_payment_date: [u8; 32],
+    /// Encrypted start date (Enc<Shared, i64>)
+    pub encrypted_start_date: [u8; 32],
+    /// Nonce for encryption
+    pub nonce: u128,
+    /// PDA bump
+    pub bump: u8,
+    /// Encrypted seat count (Enc<Shared, u32>)
+    pub encrypted_quantity: [u8; 32],
+    /// Disputes pending or open against this subscription
+    pub open_disputes: u8,
+    /// Seats assigned to member wallets (including pending assignments)
+    pub seats_assigned: u32,
+    /// Mint the subscription is billed in
+    pub payment_mint: Pubkey,
+    /// UserSubscriptionState layout of the ciphertexts (0 = written before versioning, layout 1)
+    pub state_version: u8,
+    /// Layout version of this account (0 = created before versioning)
+    pub account_version: u8,
+    /// Charges recorded for the subscription's billing periods (Enc<Mxe, ChargeRecordState>)
+    pub encrypted_charges: [[u8; 32]; ChargeRecordStateCiphertexts::LEN],
+    /// Nonce for the charge record (0 = nothing recorded)
+    pub charges_nonce: u128,
+    /// Billing periods prepaid by the voucher the subscription was redeemed from
+    pub prepaid_cycles: u32,
+}
+
+impl UserSubscription {
+    pub const SIZE: usize = 8 + 32 + 8 + 32 + (32 * 2) + 32 + 32 + 32 + 16 + 1 + 32 + 1 + 4 + 32 + 1 + 1
+        + (32 * ChargeRecordStateCiphertexts::LEN) + 16 + 4;
+    pub const VERSION: u8 = 2;
+
+    /// Ciphertexts of the subscription's UserSubscriptionState
+    pub fn state(&self) -> UserSubscriptionStateCiphertexts {
+        UserSubscriptionStateCiphertexts {
+            plan: self.encrypted_plan,
+            status: self.encrypted_status,
+            next_payment_date: self.encrypted_next_payment_date,
+            start_date: self.encrypted_start_date,
+            quantity: self.encrypted_quantity,
+        }
+    }
+
+    /// Ciphertexts of a subscription still written in UserSubscriptionState layout 1
+    pub fn state_v1(&self) -> UserSubscriptionStateV1Ciphertexts {
+        UserSubscriptionStateV1Ciphertexts {
+            plan: self.encrypted_plan,
+            status: self.encrypted_status,
+            next_payment_date: self.encrypted_next_payment_date,
+            start_date: self.encrypted_start_date,
+        }
+    }
+
+    /// Store a UserSubscriptionState computation output
+    pub fn write_state(
+        &mut self,
+        ciphertexts: &[[u8; 32]; UserSubscriptionStateCiphertexts::LEN],
+        nonce: u128,
+    ) {
+        let state = UserSubscriptionStateCiphertexts::from_ciphertexts(ciphertexts);
+        self.encrypted_plan = state.plan;
+        self.encrypted_status = state.status;
+        self.encrypted_next_payment_date = state.next_payment_date;
+        self.encrypted_start_date = state.start_date;
+        self.encrypted_quantity = state.quantity;
+        self.nonce = nonce;
+        self.state_version = USER_SUBSCRIPTION_STATE_VERSION;
+    }
+
+    /// Ciphertexts of the subscription's ChargeRecordState
+    pub fn charges(&self) -> ChargeRecordStateCiphertexts {
+        ChargeRecordStateCiphertexts::from_ciphertexts(&self.encrypted_charges)
+    }
+
+    /// Store a ChargeRecordState computation output
+    pub fn write_charges(
+        &mut self,
+        ciphertexts: &[[u8; 32]; ChargeRecordStateCiphertexts::LEN],
+        nonce: u128,
+    ) {
+        self.encrypted_charges = *ciphertexts;
+        self.charges_nonce = nonce;
+    }
+}
+
+/// Dispute raised by a user against one billing period of a subscription
+/// PDA Seeds: ["dispute", user_subscription, billing_period.to_le_bytes()]
+#[account]
+pub struct Dispute {
+    /// Disputed subscription
+    pub user_subscription: Pubkey,
+    /// User wallet that opened the dispute
+    pub user: Pubkey,
+    /// Merchant whose ledger holds the frozen amount
+    pub merchant: Pubkey,
+    /// Plan the charge was made for
+    pub subscription_plan: Pubkey,
+    /// Token mint
+    pub mint: Pubkey,
+    /// Disputed billing period (0 = the first charged cycle)
+    pub billing_period: u64,
+    /// Dispute status (0=Pending, 1=Open, 2=Refunded, 3=Released, 4=Rejected)
+    pub status: u8,
+    /// Encrypted frozen amount (Enc<Mxe, u64>)
+    pub encrypted_amount: [u8; 32],
+    /// Nonce for encryption
+    pub nonce: u128,
+    /// Open timestamp
+    pub opened_at: i64,
+    /// Resolution timestamp
+    pub resolved_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl Dispute {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 8 + 1 + 32 + 16 + 8 + 8 + 1;
+}
+
+/// Per-merchant spending cap set by a user
+/// PDA Seeds: ["spending_cap", user, merchant, mint]
+#[account]
+pub struct SpendingCap {
+    /// User wallet
+    pub user: Pubkey,
+    /// Merchant the cap applies to
+    pub merchant: Pubkey,
+    /// Token mint
+    pub mint: Pubkey,
+    /// X25519 encryption public key (used for Enc<Shared, T>)
+    pub encryption_pubkey: [u8; 32],
+    /// Encrypted cap per window (Enc<Shared, u64>)
+    pub encrypted_cap: [u8; 32],
+    /// Encrypted window length in seconds (Enc<Shared, i64>)
+    pub encrypted_window_seconds: [u8; 32],
+    /// Encrypted start of the current window (Enc<Shared, i64>)
+    pub encrypted_window_start: [u8; 32],
+    /// Encrypted amount spent in the current window (Enc<Shared, u64>)
+    pub encrypted_spent: [u8; 32],
+    /// Nonce for encryption
+    pub nonce: u128,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SpendingCap {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 32 + 32 + 16 + 1;
+
+    /// Ciphertexts of the cap's SpendingCapState
+    pub fn state(&self) -> SpendingCapStateCiphertexts {
+        SpendingCapStateCiphertexts {
+            cap: self.encrypted_cap,
+            window_seconds: self.encrypted_window_seconds,
+            window_start: self.encrypted_window_start,
+            spent: self.encrypted_spent,
+        }
+    }
+
+    /// Store a SpendingCapState computation output
+    pub fn write_state(
+        &mut self,
+        ciphertexts: &[[u8; 32]; SpendingCapStateCiphertexts::LEN],
+        nonce: u128,
+    ) {
+        let state = SpendingCapStateCiphertexts::from_ciphertexts(ciphertexts);
+        self.encrypted_cap = state.cap;
+        self.encrypted_window_seconds = state.window_seconds;
+        self.encrypted_window_start = state.window_start;
+        self.encrypted_spent = state.spent;
+        self.nonce = nonce;
+    }
+}
+
+/// Latest proof-of-reserves result for a pool
+/// PDA Seeds: ["solvency_attestation", protocol_pool]
+#[account]
+pub struct SolvencyAttestation {
+    /// Protocol pool being attested
+    pub pool: Pubkey,
+    /// Whether total liabilities were at most the pool token balance
+    pub is_solvent: bool,
+    /// Pool token balance, plus strategy allocations, the liabilities were compared against
+    pub token_balance: u64,
+    /// Attestation timestamp
+    pub attested_at: i64,
+    /// Computation account of the check in flight (default when idle)
+    pub pending_computation: Pubkey,
+    /// Pool token balance captured when the check in flight was queued
+    pub pending_token_balance: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SolvencyAttestation {
+    pub const SIZE: usize = 8 + 32 + 1 + 8 + 8 + 32 + 8 + 1;
+}
+
+/// Outstanding gift vouchers for one plan and set of terms
+/// PDA Seeds: ["voucher_pool", subscription_plan, cycles, billing_cycle_days]
+///
+/// Vouchers with the same terms share a pool, and a redemption only names the
+/// pool, so it cannot be tied to the purchase it redeems.
+#[account]
+pub struct VoucherPool {
+    /// Subscription plan the vouchers prepay
+    pub subscription_plan: Pubkey,
+    /// Billing cycles each voucher prepays
+    pub cycles: u32,
+    /// Plan billing cycle length the vouchers were bought at
+    pub billing_cycle_days: u32,
+    /// Encrypted secret hashes, zero for a free slot (Enc<Mxe, [u128; VOUCHER_POOL_SLOTS]>)
+    pub encrypted_slots: [[u8; 32]; VOUCHER_POOL_SLOTS],
+    /// Nonce for encryption
+    pub nonce: u128,
+    /// Vouchers issued and not yet redeemed
+    pub outstanding: u32,
+    /// Computation account of the queued purchase or redemption
+    pub lock: Pubkey,
+    /// When `lock` was taken
+    pub locked_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl VoucherPool {
+    pub const SIZE: usize = 8 + 32 + 4 + 4 + (32 * VOUCHER_POOL_SLOTS) + 16 + 4 + 32 + 8 + 1;
+}
+
+/// Seat on a subscription assigned to a member wallet
+/// PDA Seeds: ["seat_assignment", user_subscription, member]
+#[account]
+pub struct SeatAssignment {
+    /// Subscription the seat belongs to
+    pub user_subscription: Pubkey,
+    /// Member wallet holding the seat
+    pub member: Pubkey,
+    /// Assignment status (0=Pending, 1=Active, 2=Rejected)
+    pub status: u8,
+    /// Assignment timestamp
+    pub assigned_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SeatAssignment {
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 8 + 1;
+}
+
+/// Merchant revenue split configuration
+/// PDA Seeds: ["revenue_split", merchant, mint]
+#[account]
+pub struct RevenueSplit {
+    /// Associated merchant
+    pub merchant: Pubkey,
+    /// Token mint
+    pub mint: Pubkey,
+    /// Recipient token accounts (first `recipient_count` entries are used)
+    pub recipients: [Pubkey; MAX_SPLIT_RECIPIENTS],
+    /// Recipient shares in basis points (sum to SPLIT_TOTAL_BPS)
+    pub shares_bps: [u16; MAX_SPLIT_RECIPIENTS],
+    /// Number of configured recipients
+    pub recipient_count: u8,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl RevenueSplit {
+    pub const SIZE: usize = 8 + 32 + 32 + (32 * MAX_SPLIT_RECIPIENTS) + (2 * MAX_SPLIT_RECIPIENTS) + 1 + 1;
+}
+
+/// Automatic payout schedule for a merchant's balance in one mint
+/// PDA Seeds: ["payout_schedule", merchant, mint]
+#[account]
+pub struct PayoutSchedule {
+    /// Associated merchant
+    pub merchant: Pubkey,
+    /// Token mint
+    pub mint: Pubkey,
+    /// Token account receiving payouts
+    pub destination: Pubkey,
+    /// Days between payouts
+    pub cadence_days: u32,
+    /// Minimum balance before a payout is made
+    pub min_threshold: u64,
+    /// Active flag
+    pub is_active: bool,
+    /// Earliest timestamp the next payout can be executed
+    pub next_payout_at: i64,
+    /// Timestamp of the last non-empty payout
+    pub last_payout_at: i64,
+    /// Amount of the last non-empty payout
+    pub last_payout_amount: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PayoutSchedule {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 4 + 8 + 1 + 8 + 8 + 8 + 1;
+}
+
+/// Per-plan encrypted analytics, readable with the merchant ledger key
+/// PDA Seeds: ["plan_stats", subscription_plan]
+#[account]
+pub struct PlanStats {
+    /// Associated subscription plan
+    pub subscription_plan: Pubkey,
+    /// Associated merchant
+    pub merchant: Pubkey,
+    /// X25519 encryption public key (merchant ledger key, used for Enc<Shared, T>)
+    pub encryption_pubkey: [u8; 32],
+    /// Encrypted active subscriber count (Enc<Shared, u64>)
+    pub encrypted_active_subscribers: [u8; 32],
+    /// Encrypted monthly recurring revenue (Enc<Shared, u64>)
+    pub encrypted_mrr: [u8; 32],
+    /// Encrypted start of the current period (Enc<Shared, i64>)
+    pub encrypted_period_start: [u8; 32],
+    /// Encrypted new subscribers this period (Enc<Shared, u64>)
+    pub encrypted_new_subscribers: [u8; 32],
+    /// Encrypted churned subscribers this period (Enc<Shared, u64>)
+    pub encrypted_churned_subscribers: [u8; 32],
+    /// Encrypted new subscribers last period (Enc<Shared, u64>)
+    pub encrypted_previous_new_subscribers: [u8; 32],
+    /// Encrypted churned subscribers last period (Enc<Shared, u64>)
+    pub encrypted_previous_churned_subscribers: [u8; 32],
+    /// Nonce for encryption
+    pub nonce: u128,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PlanStats {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + (32 * 7) + 16 + 1;
+
+    /// Ciphertexts of the plan's PlanStatsState
+    pub fn state(&self) -> PlanStatsStateCiphertexts {
+        PlanStatsStateCiphertexts {
+            active_subscribers: self.encrypted_active_subscribers,
+            mrr: self.encrypted_mrr,
+            period_start: self.encrypted_period_start,
+            new_subscribers: self.encrypted_new_subscribers,
+            churned_subscribers: self.encrypted_churned_subscribers,
+            previous_new_subscribers: self.encrypted_previous_new_subscribers,
+            previous_churned_subscribers: self.encrypted_previous_churned_subscribers,
+        }
+    }
+
+    /// Store a PlanStatsState computation output
+    pub fn write_state(
+        &mut self,
+        ciphertexts: &[[u8; 32]; PlanStatsStateCiphertexts::LEN],
+        nonce: u128,
+    ) {
+        let state = PlanStatsStateCiphertexts::from_ciphertexts(ciphertexts);
+        self.encrypted_active_subscribers = state.active_subscribers;
+        self.encrypted_mrr = state.mrr;
+        self.encrypted_period_start = state.period_start;
+        self.encrypted_new_subscribers = state.new_subscribers;
+        self.encrypted_churned_subscribers = state.churned_subscribers;
+        self.encrypted_previous_new_subscribers = state.previous_new_subscribers;
+        self.encrypted_previous_churned_subscribers = state.previous_churned_subscribers;
+        self.nonce = nonce;
+    }
+}
+
+/// Public, k-anonymous statistics for a subscription plan
+/// PDA Seeds: ["plan_public_stats", subscription_plan]
+#[account]
+pub struct PlanPublicStats {
+    /// Associated subscription plan
+    pub subscription_plan: Pubkey,
+    /// Threshold below which the count is reported as "fewer than k"
+    pub k: u64,
+    /// Active subscriber count (0 while below the threshold)
+    pub subscriber_count: u64,
+    /// Whether the count is in the "fewer than k" bucket
+    pub is_below_threshold: bool,
+    /// Timestamp of the last published result
+    pub updated_at: i64,
+    /// Timestamp of the last refresh request (rate limit)
+    pub last_requested_at: i64,
+    /// Computation account of the refresh in flight (default when idle)
+    pub pending_computation: Pubkey,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PlanPublicStats {
+    pub const SIZE: usize = 8 + 32 + 8 + 8 + 1 + 8 + 8 + 32 + 1;
+}
+
+/// Auditor read access to one encrypted account
+/// PDA Seeds: ["audit_grant", owner, auditor, target]
+#[account]
+pub struct AuditGrant {
+    /// Wallet that owns the disclosed account
+    pub owner: Pubkey,
+    /// Auditor wallet
+    pub auditor: Pubkey,
+    /// Disclosed account (UserLedger, MerchantLedger or UserSubscription)
+    pub target: Pubkey,
+    /// Auditor X25519 public key that disclosures are encrypted to
+    pub auditor_encryption_pubkey: [u8; 32],
+    /// Grant timestamp
+    pub granted_at: i64,
+    /// Timestamp after which disclosures are refused
+    pub expires_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl AuditGrant {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Time-limited sanctions-screening attestation for a wallet
+/// PDA Seeds: ["screening_attestation", wallet]
+#[account]
+pub struct ScreeningAttestation {
+    /// Screened wallet
+    pub wallet: Pubkey,
+    /// Screening authority that issued, or last revoked, the attestation
+    pub issuer: Pubkey,
+    /// Issue timestamp
+    pub issued_at: i64,
+    /// Expiry timestamp
+    pub expires_at: i64,
+    /// Revoked flag (blocks deposits and incoming transfers, not withdrawals)
+    pub is_revoked: bool,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl ScreeningAttestation {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 1 + 1;
+}
+
+/// Time-locked request for a withdrawal or claim above the pool's threshold
+/// PDA Seeds: ["pending_outflow", owner, mint, kind]
+#[account]
+pub struct PendingOutflow {
+    /// Wallet that will withdraw or claim
+    pub owner: Pubkey,
+    /// Token mint
+    pub mint: Pubkey,
+    /// Outflow kind (0=Withdraw, 1=Claim)
+    pub kind: u8,
+    /// Requested amount (0 once paid out)
+    pub amount: u64,
+    /// Request timestamp
+    pub requested_at: i64,
+    /// Earliest execution timestamp
+    pub executable_at: i64,
+    /// PDA bump
+    pub bump: u8,
+    /// Computation queued against the request (default when none)
+    pub pending_computation: Pubkey,
+}
+
+impl PendingOutflow {
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 8 + 8 + 8 + 1 + 32;
+}
+
+/// Oracle price account approved for converting reference-currency plan prices into a mint
+/// PDA Seeds: ["price_feed", mint, currency]
+#[account]
+pub struct PriceFeedConfig {
+    /// Settlement token mint
+    pub mint: Pubkey,
+    /// Reference currency code (e.g. b"USD\0\0\0\0\0")
+    pub currency: [u8; 8],
+    /// Oracle program owning the price account
+    pub oracle_program: Pubkey,
+    /// Price of one whole `mint` token in `currency`
+    pub price_account: Pubkey,
+    /// Pyth feed id `price_account` must publish (unused by other oracles)
+    pub feed_id: [u8; 32],
+    /// Oldest accepted price, in seconds before the billing time
+    pub max_staleness_seconds: u32,
+    /// Widest accepted confidence interval, in basis points of the price
+    pub max_confidence_bps: u16,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PriceFeedConfig {
+    pub const SIZE: usize = 8 + 32 + 8 + 32 + 32 + 32 + 4 + 2 + 1;
+}
+
+/// Where the circuits of one circuit version are published
+/// PDA Seeds: ["circuit_version", version]
+#[account]
+pub struct CircuitVersion {
+    /// Circuit version (matches the `_v<N>` circuit suffix)
+    pub version: u8,
+    /// Base URL serving `<circuit>.arcis` files (fixed-size, zero padded, no trailing slash)
+    pub source_base_url: [u8; MAX_CIRCUIT_SOURCE_URL_LENGTH],
+    /// Last update timestamp
+    pub updated_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl CircuitVersion {
+    pub const SIZE: usize = 8 + 1 + MAX_CIRCUIT_SOURCE_URL_LENGTH + 8 + 1;
+}
+
+// ============================================================================
+// Context Structures - Phase 1: Non-Encrypted
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeProtocol<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = ProtocolConfig::SIZE,
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        space = ProtocolPool::SIZE,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = protocol_pool,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterMerchant<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = wallet,
+        space = Merchant::SIZE,
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        init,
+        payer = wallet,
+        space = MerchantLedger::SIZE,
+        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenMerchantLedger<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        init,
+        payer = wallet,
+        space = MerchantLedger::SIZE,
+        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: u64)]
+pub struct CreateSubscriptionPlan<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = wallet,
+        space = SubscriptionPlan::SIZE,
+        seeds = [SUBSCRIPTION_PLAN_SEED, merchant.key().as_ref(), &plan_id.to_le_bytes()],
+        bump,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSubscriptionPlan<'info> {
+    pub wallet: Signer<'info>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_PLAN_SEED, merchant.key().as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+}
+
+#[derive(Accounts)]
+#[instruction(price_currency: [u8; 8])]
+pub struct SetPlanPriceCurrency<'info> {
+    pub wallet: Signer<'info>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_PLAN_SEED, merchant.key().as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    /// Approved oracle configuration for the new currency in the plan's mint
+    /// (reference currencies only)
+    #[account(
+        seeds = [PRICE_FEED_SEED, subscription_plan.mint.as_ref(), price_currency.as_ref()],
+        bump = price_feed.bump,
+    )]
+    pub price_feed: Option<Box<Account<'info, PriceFeedConfig>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetArbiter<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UnassignSeat<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [SEAT_ASSIGNMENT_SEED, user_subscription.key().as_ref(), seat_assignment.member.as_ref()],
+        bump = seat_assignment.bump,
+    )]
+    pub seat_assignment: Account<'info, SeatAssignment>,
+}
+
+#[derive(Accounts)]
+pub struct SetRevenueSplit<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = RevenueSplit::SIZE,
+        seeds = [REVENUE_SPLIT_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub revenue_split: Account<'info, RevenueSplit>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutSchedule<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        constraint = destination_token_account.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = PayoutSchedule::SIZE,
+        seeds = [PAYOUT_SCHEDULE_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub payout_schedule: Account<'info, PayoutSchedule>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GrantAuditAccess<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: auditor wallet receiving read access
+    pub auditor: UncheckedAccount<'info>,
+    /// CHECK: disclosed account; ownership is checked when a disclosure is queued
+    pub target: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = AuditGrant::SIZE,
+        seeds = [AUDIT_GRANT_SEED, owner.key().as_ref(), auditor.key().as_ref(), target.key().as_ref()],
+        bump,
+    )]
+    pub audit_grant: Account<'info, AuditGrant>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAuditAccess<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [AUDIT_GRANT_SEED, owner.key().as_ref(), audit_grant.auditor.as_ref(), audit_grant.target.as_ref()],
+        bump = audit_grant.bump,
+    )]
+    pub audit_grant: Account<'info, AuditGrant>,
+}
+
+#[derive(Accounts)]
+pub struct ManageScreeningAuthorities<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetComplianceMode<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, protocol_pool.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+}
+
+#[derive(Accounts)]
+pub struct IssueScreeningAttestation<'info> {
+    #[account(mut)]
+    pub screening_authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    /// CHECK: screened wallet
+    pub wallet: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = screening_authority,
+        space = ScreeningAttestation::SIZE,
+        seeds = [SCREENING_ATTESTATION_SEED, wallet.key().as_ref()],
+        bump,
+    )]
+    pub screening_attestation: Account<'info, ScreeningAttestation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeScreeningAttestation<'info> {
+    pub screening_authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [SCREENING_ATTESTATION_SEED, screening_attestation.wallet.as_ref()],
+        bump = screening_attestation.bump,
+    )]
+    pub screening_attestation: Account<'info, ScreeningAttestation>,
+}
+
+#[derive(Accounts)]
+pub struct SetOutflowLimits<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, protocol_pool.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: u8)]
+pub struct RequestLargeOutflow<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = PendingOutflow::SIZE,
+        seeds = [PENDING_OUTFLOW_SEED, owner.key().as_ref(), mint.key().as_ref(), &[kind]],
+        bump,
+    )]
+    pub pending_outflow: Account<'info, PendingOutflow>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLargeOutflow<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PENDING_OUTFLOW_SEED, owner.key().as_ref(), pending_outflow.mint.as_ref(), &[pending_outflow.kind]],
+        bump = pending_outflow.bump,
+    )]
+    pub pending_outflow: Account<'info, PendingOutflow>,
+}
+
+#[derive(Accounts)]
+pub struct SetLendingStrategy<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, protocol_pool.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    /// CHECK: lending program, checked against the supported strategies
+    pub lending_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageStrategyAllocation<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, protocol_pool.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        mut,
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    /// CHECK: lending program chosen for this pool
+    #[account(address = protocol_pool.strategy_program @ ErrorCode::NoLendingStrategy)]
+    pub lending_program: UncheckedAccount<'info>,
+    /// CHECK: lending reserve, validated by the lending program
+    #[account(mut)]
+    pub lending_reserve: UncheckedAccount<'info>,
+    /// CHECK: lending reserve vault, validated by the lending program
+    #[account(mut)]
+    pub lending_vault: UncheckedAccount<'info>,
+    /// CHECK: the pool's lending position, validated by the lending program
+    #[account(mut)]
+    pub lending_position: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(currency: [u8; 8])]
+pub struct SetPriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PriceFeedConfig::SIZE,
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref(), currency.as_ref()],
+        bump,
+    )]
+    pub price_feed: Account<'info, PriceFeedConfig>,
+    /// CHECK: oracle program, checked against the supported oracles
+    pub oracle_program: UncheckedAccount<'info>,
+    /// CHECK: price account, checked to be owned by the oracle program
+    pub price_account: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(version: u8)]
+pub struct RegisterCircuitVersion<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = CircuitVersion::SIZE,
+        seeds = [CIRCUIT_VERSION_SEED, &[version]],
+        bump,
+    )]
+    pub circuit_version: Account<'info, CircuitVersion>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: any account of this program; its type is read from the discriminator
+    #[account(mut, owner = crate::ID @ ErrorCode::UnsupportedAccountMigration)]
+    pub account: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// Context Structures - Phase 2: Computation Definition Initialization
+// ============================================================================
+
+#[init_computation_definition_accounts("deposit_v2", payer)]
+#[derive(Accounts)]
+pub struct InitDepositCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("withdraw_v2", payer)]
+#[derive(Accounts)]
+pub struct InitWithdrawCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("subscribe_v2", payer)]
+#[derive(Accounts)]
+pub struct InitSubscribeCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("unsubscribe_v2", payer)]
+#[derive(Accounts)]
+pub struct InitUnsubscribeCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("process_payment_v2", payer)]
+#[derive(Accounts)]
+pub struct InitProcessPaymentCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("verify_subscription_v2", payer)]
+#[derive(Accounts)]
+pub struct InitVerifySubscriptionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("claim_revenue_v2", payer)]
+#[derive(Accounts)]
+pub struct InitClaimRevenueCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reactivate_subscription_v2", payer)]
+#[derive(Accounts)]
+pub struct InitReactivateSubscriptionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("cancel_at_period_end_v2", payer)]
+#[derive(Accounts)]
+pub struct InitCancelAtPeriodEndCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("resume_subscription_v2", payer)]
+#[derive(Accounts)]
+pub struct InitResumeSubscriptionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("refund_v2", payer)]
+#[derive(Accounts)]
+pub struct InitRefundCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("open_dispute_v2", payer)]
+#[derive(Accounts)]
+pub struct InitOpenDisputeCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("resolve_dispute_refund_v2", payer)]
+#[derive(Accounts)]
+pub struct InitResolveDisputeRefundCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("resolve_dispute_release_v2", payer)]
+#[derive(Accounts)]
+pub struct InitResolveDisputeReleaseCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("set_spending_cap_v2", payer)]
+#[derive(Accounts)]
+pub struct InitSetSpendingCapCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("balance_runway_v2", payer)]
+#[derive(Accounts)]
+pub struct InitBalanceRunwayCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("check_solvency_v2", payer)]
+#[derive(Accounts)]
+pub struct InitCheckSolvencyCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("transfer_v2", payer)]
+#[derive(Accounts)]
+pub struct InitTransferCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("claim_transfers_v2", payer)]
+#[derive(Accounts)]
+pub struct InitClaimTransfersCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("buy_voucher_v2", payer)]
+#[derive(Accounts)]
+pub struct InitBuyVoucherCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+
+#[init_computation_definition_accounts("redeem_voucher_v2", payer)]
+#[derive(Accounts)]
+pub struct InitRedeemVoucherCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("update_seats_v2", payer)]
+#[derive(Accounts)]
+pub struct InitUpdateSeatsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+
+#[init_computation_definition_accounts("assign_seat_v2", payer)]
+#[derive(Accounts)]
+pub struct InitAssignSeatCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("scheduled_payout_v2", payer)]
+#[derive(Accounts)]
+pub struct InitScheduledPayoutCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("publish_plan_stats_v2", payer)]
+#[derive(Accounts)]
+pub struct InitPublishPlanStatsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("disclose_user_ledger_v2", payer)]
+#[derive(Accounts)]
+pub struct InitDiscloseUserLedgerCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("disclose_merchant_ledger_v2", payer)]
+#[derive(Accounts)]
+pub struct InitDiscloseMerchantLedgerCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("disclose_subscription_v2", payer)]
+#[derive(Accounts)]
+pub struct InitDiscloseSubscriptionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("distribute_yield_v2", payer)]
+#[derive(Accounts)]
+pub struct InitDistributeYieldCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("settle_yield_v2", payer)]
+#[derive(Accounts)]
+pub struct InitSettleYieldCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("set_principal_lock_v2", payer)]
+#[derive(Accounts)]
+pub struct InitSetPrincipalLockCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("migrate_user_ledger_v2", payer)]
+#[derive(Accounts)]
+pub struct InitMigrateUserLedgerCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("migrate_user_subscription_v2", payer)]
+#[derive(Accounts)]
+pub struct InitMigrateUserSubscriptionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("migrate_merchant_ledger_v2", payer)]
+#[derive(Accounts)]
+pub struct InitMigrateMerchantLedgerCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("seed_liabilities_v2", payer)]
+#[derive(Accounts)]
+pub struct InitSeedLiabilitiesCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CIRCUIT_VERSION_SEED, &[CIRCUIT_VERSION]],
+        bump = circuit_version.bump,
+    )]
+    pub circuit_version: Box<Account<'info, CircuitVersion>>,
+    #[account(mut, address = derive_mxe_lut_pda!())]
+    /// CHECK: address lookup table for the MXE program
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
+    /// CHECK: address lookup table program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// Context Structures - Phase 2: Queue Computation
+// ============================================================================
+
+#[queue_computation_accounts("deposit_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    #[account(
+        seeds = [SCREENING_ATTESTATION_SEED, user.key().as_ref()],
+        bump = screening_attestation.bump,
+    )]
+    pub screening_attestation: Option<Account<'info, ScreeningAttestation>>,
+    #[account(
+        mut,
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ ErrorCode::Unauthorized,
+        constraint = user_token_account.mint == mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserLedger::SIZE,
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("withdraw_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        mut,
+        seeds = [PENDING_OUTFLOW_SEED, user.key().as_ref(), mint.key().as_ref(), &[OUTFLOW_KIND_WITHDRAW]],
+        bump = pending_outflow.bump,
+    )]
+    pub pending_outflow: Option<Account<'info, PendingOutflow>>,
+    #[account(
+        mut,
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ ErrorCode::Unauthorized,
+        constraint = user_token_account.mint == mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_WITHDRAW))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("subscribe_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, subscription_index: u64)]
+pub struct Subscribe<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_plan.mint == mint.key()
+            || subscription_plan.price_currency != MINT_PRICE_CURRENCY @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init,
+        payer = user,
+        space = UserSubscription::SIZE,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &subscription_index.to_le_bytes()],
+        bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = SpendingCap::SIZE,
+        seeds = [SPENDING_CAP_SEED, user.key().as_ref(), subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PlanStats::SIZE,
+        seeds = [PLAN_STATS_SEED, subscription_plan.key().as_ref()],
+        bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    /// Oracle configuration for the plan's reference currency (reference-priced plans only)
+    #[account(
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref(), subscription_plan.price_currency.as_ref()],
+        bump = price_feed.bump,
+    )]
+    pub price_feed: Option<Box<Account<'info, PriceFeedConfig>>>,
+    /// CHECK: oracle price account, checked against `price_feed`
+    pub price_account: Option<UncheckedAccount<'info>>,
+    /// Settled against for yield; holds the liabilities lock until the callback
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBSCRIBE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("unsubscribe_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct Unsubscribe<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), subscription_plan.mint.as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PlanStats::SIZE,
+        seeds = [PLAN_STATS_SEED, subscription_plan.key().as_ref()],
+        bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UNSUBSCRIBE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("process_payment_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ProcessPayment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_payment_mint(&subscription_plan, &user_subscription) == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user_subscription.user.as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user_subscription.user.as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    /// Created and paid for by the user in subscribe, redeem_voucher or set_spending_cap
+    #[account(
+        seeds = [SPENDING_CAP_SEED, user_subscription.user.as_ref(), subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = spending_cap.bump,
+    )]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PlanStats::SIZE,
+        seeds = [PLAN_STATS_SEED, subscription_plan.key().as_ref()],
+        bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    /// Oracle configuration for the plan's reference currency (reference-priced plans only)
+    #[account(
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref(), subscription_plan.price_currency.as_ref()],
+        bump = price_feed.bump,
+    )]
+    pub price_feed: Option<Box<Account<'info, PriceFeedConfig>>>,
+    /// CHECK: oracle price account, checked against `price_feed`
+    pub price_account: Option<UncheckedAccount<'info>>,
+    /// Settled against for yield; holds the liabilities lock until the callback
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_PAYMENT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("verify_subscription_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct VerifySubscription<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [USER_SUBSCRIPTION_SEED, user_subscription.user.as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VERIFY_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("claim_revenue_v2", wallet)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClaimRevenue<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        mut,
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [PENDING_OUTFLOW_SEED, wallet.key().as_ref(), mint.key().as_ref(), &[OUTFLOW_KIND_CLAIM]],
+        bump = pending_outflow.bump,
+    )]
+    pub pending_outflow: Option<Account<'info, PendingOutflow>>,
+    #[account(
+        mut,
+        constraint = merchant_token_account.owner == wallet.key() @ ErrorCode::Unauthorized,
+        constraint = merchant_token_account.mint == mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub merchant_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = wallet,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_REVENUE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("claim_revenue_v2", wallet)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClaimRevenueSplit<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        mut,
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [PENDING_OUTFLOW_SEED, wallet.key().as_ref(), mint.key().as_ref(), &[OUTFLOW_KIND_CLAIM]],
+        bump = pending_outflow.bump,
+    )]
+    pub pending_outflow: Option<Account<'info, PendingOutflow>>,
+    #[account(
+        seeds = [REVENUE_SPLIT_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump = revenue_split.bump,
+    )]
+    pub revenue_split: Account<'info, RevenueSplit>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = wallet,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_REVENUE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("reactivate_subscription_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ReactivateSubscription<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    /// Created and paid for by the user in subscribe, redeem_voucher or set_spending_cap
+    #[account(
+        seeds = [SPENDING_CAP_SEED, user.key().as_ref(), subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = spending_cap.bump,
+    )]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PlanStats::SIZE,
+        seeds = [PLAN_STATS_SEED, subscription_plan.key().as_ref()],
+        bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    /// Settled against for yield; holds the liabilities lock until the callback
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REACTIVATE_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("cancel_at_period_end_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CancelAtPeriodEnd<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_AT_PERIOD_END))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("resume_subscription_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ResumeSubscription<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RESUME_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("refund_v2", wallet)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct Refund<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, merchant.key().as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user_subscription.user.as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user_subscription.user.as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = PlanStats::SIZE,
+        seeds = [PLAN_STATS_SEED, subscription_plan.key().as_ref()],
+        bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    /// Settled against for yield; holds the liabilities lock until the callback
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = wallet,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REFUND))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("open_dispute_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, billing_period: u64)]
+pub struct OpenDispute<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init,
+        payer = user,
+        space = Dispute::SIZE,
+        seeds = [DISPUTE_SEED, user_subscription.key().as_ref(), &billing_period.to_le_bytes()],
+        bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_OPEN_DISPUTE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("resolve_dispute_refund_v2", arbiter)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ResolveDisputeRefund<'info> {
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = arbiter @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        seeds = [DISPUTE_SEED, dispute.user_subscription.as_ref(), &dispute.billing_period.to_le_bytes()],
+        bump = dispute.bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        mut,
+        address = dispute.user_subscription @ ErrorCode::InvalidSubscription,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, dispute.merchant.as_ref(), dispute.mint.as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, dispute.user.as_ref(), dispute.mint.as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    /// Settled against for yield; holds the liabilities lock until the callback
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, dispute.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = arbiter,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RESOLVE_DISPUTE_REFUND))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("resolve_dispute_release_v2", arbiter)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ResolveDisputeRelease<'info> {
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = arbiter @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        seeds = [DISPUTE_SEED, dispute.user_subscription.as_ref(), &dispute.billing_period.to_le_bytes()],
+        bump = dispute.bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        mut,
+        address = dispute.user_subscription @ ErrorCode::InvalidSubscription,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, dispute.merchant.as_ref(), dispute.mint.as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = arbiter,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RESOLVE_DISPUTE_RELEASE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("set_spending_cap_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SetSpendingCap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub merchant: Account<'info, Merchant>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = SpendingCap::SIZE,
+        seeds = [SPENDING_CAP_SEED, user.key().as_ref(), merchant.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_SPENDING_CAP))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("balance_runway_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueryBalanceRunway<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), user_ledger.mint.as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BALANCE_RUNWAY))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("check_solvency_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CheckSolvency<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_POOL_SEED, protocol_pool.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = SolvencyAttestation::SIZE,
+        seeds = [SOLVENCY_ATTESTATION_SEED, protocol_pool.key().as_ref()],
+        bump,
+    )]
+    pub solvency_attestation: Account<'info, SolvencyAttestation>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_SOLVENCY))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("transfer_v2", sender)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct Transfer<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: recipient wallet, only used to derive the recipient ledger
+    pub recipient: UncheckedAccount<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, sender.key().as_ref(), mint.key().as_ref()],
+        bump = sender_ledger.bump,
+    )]
+    pub sender_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = UserLedger::SIZE,
+        seeds = [USER_LEDGER_SEED, recipient.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub recipient_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    #[account(
+        seeds = [SCREENING_ATTESTATION_SEED, recipient.key().as_ref()],
+        bump = recipient_screening_attestation.bump,
+    )]
+    pub recipient_screening_attestation: Option<Account<'info, ScreeningAttestation>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = sender,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_TRANSFER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("claim_transfers_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClaimTransfers<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_TRANSFERS))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("buy_voucher_v2", buyer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, cycles: u32)]
+pub struct BuyVoucher<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, buyer.key().as_ref(), mint.key().as_ref()],
+        bump = buyer_ledger.bump,
+    )]
+    pub buyer_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = VoucherPool::SIZE,
+        seeds = [
+            VOUCHER_POOL_SEED,
+            subscription_plan.key().as_ref(),
+            &cycles.to_le_bytes(),
+            &subscription_plan.billing_cycle_days.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub voucher_pool: Box<Account<'info, VoucherPool>>,
+    /// Settled against for yield; holds the liabilities lock until the callback
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = buyer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BUY_VOUCHER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("redeem_voucher_v2", recipient)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, subscription_index: u64)]
+pub struct RedeemVoucher<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [
+            VOUCHER_POOL_SEED,
+            subscription_plan.key().as_ref(),
+            &voucher_pool.cycles.to_le_bytes(),
+            &voucher_pool.billing_cycle_days.to_le_bytes(),
+        ],
+        bump = voucher_pool.bump,
+    )]
+    pub voucher_pool: Box<Account<'info, VoucherPool>>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, recipient.key().as_ref(), mint.key().as_ref()],
+        bump = recipient_ledger.bump,
+    )]
+    pub recipient_ledger: Account<'info, UserLedger>,
+    #[account(
+        init,
+        payer = recipient,
+        space = UserSubscription::SIZE,
+        seeds = [USER_SUBSCRIPTION_SEED, recipient.key().as_ref(), &subscription_index.to_le_bytes()],
+        bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = SpendingCap::SIZE,
+        seeds = [SPENDING_CAP_SEED, recipient.key().as_ref(), subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    /// Read for the merchant key the plan analytics are encrypted to
+    #[account(
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = PlanStats::SIZE,
+        seeds = [PLAN_STATS_SEED, subscription_plan.key().as_ref()],
+        bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = recipient,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REDEEM_VOUCHER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("update_seats_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct UpdateSeats<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        seeds = [SPENDING_CAP_SEED, user.key().as_ref(), subscription_plan.merchant.as_ref(), mint.key().as_ref()],
+        bump = spending_cap.bump,
+    )]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PlanStats::SIZE,
+        seeds = [PLAN_STATS_SEED, subscription_plan.key().as_ref()],
+        bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    /// Settled against for yield; holds the liabilities lock until the callback
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UPDATE_SEATS))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("assign_seat_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AssignSeat<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: member wallet receiving the seat
+    pub member: UncheckedAccount<'info>,
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
+        bump = subscription_plan.bump,
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init,
+        payer = user,
+        space = SeatAssignment::SIZE,
+        seeds = [SEAT_ASSIGNMENT_SEED, user_subscription.key().as_ref(), member.key().as_ref()],
+        bump,
+    )]
+    pub seat_assignment: Account<'info, SeatAssignment>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ASSIGN_SEAT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("verify_subscription_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct VerifySeat<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Member wallet whose seat is being verified; must sign so a seat
+    /// cannot be verified by anyone who merely knows the member's address
+    pub member: Signer<'info>,
+    #[account(
+        seeds = [USER_SUBSCRIPTION_SEED, user_subscription.user.as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        seeds = [SEAT_ASSIGNMENT_SEED, user_subscription.key().as_ref(), member.key().as_ref()],
+        bump = seat_assignment.bump,
+    )]
+    pub seat_assignment: Account<'info, SeatAssignment>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VERIFY_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("scheduled_payout_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteScheduledPayout<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [MERCHANT_SEED, merchant.wallet.as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        mut,
+        seeds = [PAYOUT_SCHEDULE_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump = payout_schedule.bump,
+    )]
+    pub payout_schedule: Account<'info, PayoutSchedule>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        mut,
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = payout_schedule.destination @ ErrorCode::Unauthorized,
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCHEDULED_PAYOUT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("publish_plan_stats_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PublishPlanStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [PLAN_STATS_SEED, plan_stats.subscription_plan.as_ref()],
+        bump = plan_stats.bump,
+    )]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PlanPublicStats::SIZE,
+        seeds = [PLAN_PUBLIC_STATS_SEED, plan_stats.subscription_plan.as_ref()],
+        bump,
+    )]
+    pub plan_public_stats: Account<'info, PlanPublicStats>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PUBLISH_PLAN_STATS))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("disclose_user_ledger_v2", requester)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DiscloseUserLedger<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+    #[account(
+        seeds = [AUDIT_GRANT_SEED, audit_grant.owner.as_ref(), audit_grant.auditor.as_ref(), audit_grant.target.as_ref()],
+        bump = audit_grant.bump,
+    )]
+    pub audit_grant: Account<'info, AuditGrant>,
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = requester,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_USER_LEDGER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("disclose_merchant_ledger_v2", requester)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DiscloseMerchantLedger<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+    #[account(
+        seeds = [AUDIT_GRANT_SEED, audit_grant.owner.as_ref(), audit_grant.auditor.as_ref(), audit_grant.target.as_ref()],
+        bump = audit_grant.bump,
+    )]
+    pub audit_grant: Account<'info, AuditGrant>,
+    #[account(
+        seeds = [MERCHANT_SEED, audit_grant.owner.as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    #[account(
+        constraint = merchant_ledger.merchant == merchant.key() @ ErrorCode::Unauthorized,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = requester,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_MERCHANT_LEDGER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("disclose_subscription_v2", requester)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DiscloseSubscription<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+    #[account(
+        seeds = [AUDIT_GRANT_SEED, audit_grant.owner.as_ref(), audit_grant.auditor.as_ref(), audit_grant.target.as_ref()],
+        bump = audit_grant.bump,
+    )]
+    pub audit_grant: Account<'info, AuditGrant>,
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = requester,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("distribute_yield_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DistributeYield<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, protocol_pool.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+    #[account(
+        mut,
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: lending program chosen for this pool
+    #[account(address = protocol_pool.strategy_program @ ErrorCode::NoLendingStrategy)]
+    pub lending_program: UncheckedAccount<'info>,
+    /// CHECK: lending reserve, validated by the lending program
+    #[account(mut)]
+    pub lending_reserve: UncheckedAccount<'info>,
+    /// CHECK: lending reserve vault, validated by the lending program
+    #[account(mut)]
+    pub lending_vault: UncheckedAccount<'info>,
+    /// CHECK: the pool's lending position, validated by the strategy adapter
+    #[account(mut)]
+    pub lending_position: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISTRIBUTE_YIELD))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("settle_yield_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SettleYield<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, user_ledger.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user_ledger.user.as_ref(), user_ledger.mint.as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SETTLE_YIELD))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("set_principal_lock_v2", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SetPrincipalLock<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_PRINCIPAL_LOCK))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("migrate_user_ledger_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MigrateUserLedger<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [USER_LEDGER_SEED, user_ledger.user.as_ref(), user_ledger.mint.as_ref()],
+        bump = user_ledger.bump,
+    )]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_USER_LEDGER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("migrate_user_subscription_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MigrateUserSubscription<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [USER_SUBSCRIPTION_SEED, user_subscription.user.as_ref(), &user_subscription.subscription_index.to_le_bytes()],
+        bump = user_subscription.bump,
+    )]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_USER_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("migrate_merchant_ledger_v2", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MigrateMerchantLedger<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [MERCHANT_LEDGER_SEED, merchant_ledger.merchant.as_ref(), merchant_ledger.mint.as_ref()],
+        bump = merchant_ledger.bump,
+    )]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_MERCHANT_LEDGER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("seed_liabilities_v2", authority)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SeedPoolLiabilities<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_POOL_SEED, protocol_pool.mint.as_ref()],
+        bump = protocol_pool.bump,
+    )]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(
+        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = authority,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SEED_LIABILITIES))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+// ============================================================================
+// Context Structures - Phase 2: Callback
+// ============================================================================
+
+#[callback_accounts("deposit_v2")]
+#[derive(Accounts)]
+pub struct DepositV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+}
+
+#[callback_accounts("withdraw_v2")]
+#[derive(Accounts)]
+pub struct WithdrawV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_WITHDRAW))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(mut)]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut)]
+    pub pending_outflow: Option<Account<'info, PendingOutflow>>,
+}
+
+#[callback_accounts("subscribe_v2")]
+#[derive(Accounts)]
+pub struct SubscribeV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBSCRIBE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(mut)]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(mut)]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+}
+
+#[callback_accounts("unsubscribe_v2")]
+#[derive(Accounts)]
+pub struct UnsubscribeV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UNSUBSCRIBE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(mut)]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+}
+
+#[callback_accounts("process_payment_v2")]
+#[derive(Accounts)]
+pub struct ProcessPaymentV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_PAYMENT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(mut)]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(mut)]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
 }
 
-// ============================================================================
-// Context Structures - Phase 1: Non-Encrypted
-// ============================================================================
-
+#[callback_accounts("verify_subscription_v2")]
 #[derive(Accounts)]
-pub struct InitializeProtocol<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(
-        init,
-        payer = authority,
-        space = ProtocolConfig::SIZE,
-        seeds = [PROTOCOL_CONFIG_SEED],
-        bump,
-    )]
-    pub protocol_config: Account<'info, ProtocolConfig>,
-    pub system_program: Program<'info, System>,
+pub struct VerifySubscriptionV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VERIFY_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
 }
 
+#[callback_accounts("claim_revenue_v2")]
 #[derive(Accounts)]
-pub struct InitializePool<'info> {
+pub struct ClaimRevenueV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_REVENUE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
     #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(
-        seeds = [PROTOCOL_CONFIG_SEED],
-        bump = protocol_config.bump,
-        has_one = authority @ ErrorCode::Unauthorized,
-    )]
-    pub protocol_config: Account<'info, ProtocolConfig>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        init,
-        payer = authority,
-        space = ProtocolPool::SIZE,
-        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
-        bump,
-    )]
     pub protocol_pool: Account<'info, ProtocolPool>,
-    #[account(
-        init,
-        payer = authority,
-        token::mint = mint,
-        token::authority = protocol_pool,
-    )]
+    #[account(mut)]
     pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub merchant_token_account: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    #[account(mut)]
+    pub pending_outflow: Option<Account<'info, PendingOutflow>>,
 }
 
+#[callback_accounts("reactivate_subscription_v2")]
 #[derive(Accounts)]
-pub struct RegisterMerchant<'info> {
+pub struct ReactivateSubscriptionV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REACTIVATE_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
     #[account(mut)]
-    pub wallet: Signer<'info>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        init,
-        payer = wallet,
-        space = Merchant::SIZE,
-        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
-        bump,
-    )]
-    pub merchant: Account<'info, Merchant>,
-    #[account(
-        init,
-        payer = wallet,
-        space = MerchantLedger::SIZE,
-        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
-        bump,
-    )]
     pub merchant_ledger: Account<'info, MerchantLedger>,
-    pub system_program: Program<'info, System>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(mut)]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(mut)]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
 }
 
+#[callback_accounts("cancel_at_period_end_v2")]
 #[derive(Accounts)]
-#[instruction(plan_id: u64)]
-pub struct CreateSubscriptionPlan<'info> {
+pub struct CancelAtPeriodEndV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CANCEL_AT_PERIOD_END))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    pub wallet: Signer<'info>,
-    #[account(
-        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
-        bump = merchant.bump,
-    )]
-    pub merchant: Account<'info, Merchant>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        init,
-        payer = wallet,
-        space = SubscriptionPlan::SIZE,
-        seeds = [SUBSCRIPTION_PLAN_SEED, merchant.key().as_ref(), &plan_id.to_le_bytes()],
-        bump,
-    )]
-    pub subscription_plan: Account<'info, SubscriptionPlan>,
-    pub system_program: Program<'info, System>,
+    pub user_subscription: Account<'info, UserSubscription>,
 }
 
+#[callback_accounts("resume_subscription_v2")]
 #[derive(Accounts)]
-pub struct UpdateSubscriptionPlan<'info> {
-    pub wallet: Signer<'info>,
-    #[account(
-        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
-        bump = merchant.bump,
-    )]
-    pub merchant: Account<'info, Merchant>,
-    #[account(
-        mut,
-        seeds = [SUBSCRIPTION_PLAN_SEED, merchant.key().as_ref(), &subscription_plan.plan_id.to_le_bytes()],
-        bump = subscription_plan.bump,
-    )]
-    pub subscription_plan: Account<'info, SubscriptionPlan>,
+pub struct ResumeSubscriptionV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RESUME_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
 }
 
-// ============================================================================
-// Context Structures - Phase 2: Computation Definition Initialization
-// ============================================================================
-
-#[init_computation_definition_accounts("deposit_v2", payer)]
+#[callback_accounts("refund_v2")]
 #[derive(Accounts)]
-pub struct InitDepositCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
+pub struct RefundV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REFUND))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!())]
-    /// CHECK: address lookup table for the MXE program
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
-    /// CHECK: address lookup table program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(mut)]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
 }
 
-#[init_computation_definition_accounts("withdraw_v2", payer)]
+#[callback_accounts("open_dispute_v2")]
 #[derive(Accounts)]
-pub struct InitWithdrawCompDef<'info> {
+pub struct OpenDisputeV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_OPEN_DISPUTE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+}
+
+#[callback_accounts("resolve_dispute_refund_v2")]
+#[derive(Accounts)]
+pub struct ResolveDisputeRefundV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RESOLVE_DISPUTE_REFUND))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
     #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!())]
-    /// CHECK: address lookup table for the MXE program
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
-    /// CHECK: address lookup table program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
 }
 
-#[init_computation_definition_accounts("subscribe_v2", payer)]
+#[callback_accounts("resolve_dispute_release_v2")]
 #[derive(Accounts)]
-pub struct InitSubscribeCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
+pub struct ResolveDisputeReleaseV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RESOLVE_DISPUTE_RELEASE))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!())]
-    /// CHECK: address lookup table for the MXE program
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
-    /// CHECK: address lookup table program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
 }
 
-#[init_computation_definition_accounts("unsubscribe_v2", payer)]
+#[callback_accounts("set_spending_cap_v2")]
 #[derive(Accounts)]
-pub struct InitUnsubscribeCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
+pub struct SetSpendingCapV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_SPENDING_CAP))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!())]
-    /// CHECK: address lookup table for the MXE program
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
-    /// CHECK: address lookup table program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
 }
 
-#[init_computation_definition_accounts("process_payment_v2", payer)]
+#[callback_accounts("balance_runway_v2")]
 #[derive(Accounts)]
-pub struct InitProcessPaymentCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!())]
-    /// CHECK: address lookup table for the MXE program
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
-    /// CHECK: address lookup table program
-    pub lut_program: UncheckedAccount<'info>,
+pub struct BalanceRunwayV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BALANCE_RUNWAY))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub user_ledger: Account<'info, UserLedger>,
 }
 
-#[init_computation_definition_accounts("verify_subscription_v2", payer)]
+#[callback_accounts("check_solvency_v2")]
 #[derive(Accounts)]
-pub struct InitVerifySubscriptionCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
+pub struct CheckSolvencyV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_SOLVENCY))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!())]
-    /// CHECK: address lookup table for the MXE program
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
-    /// CHECK: address lookup table program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    pub solvency_attestation: Account<'info, SolvencyAttestation>,
 }
 
-#[init_computation_definition_accounts("claim_revenue_v2", payer)]
+#[callback_accounts("transfer_v2")]
 #[derive(Accounts)]
-pub struct InitClaimRevenueCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
+pub struct TransferV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_TRANSFER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!())]
-    /// CHECK: address lookup table for the MXE program
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = ::arcium_anchor::solana_address_lookup_table_interface::program::ID)]
-    /// CHECK: address lookup table program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    pub sender_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub recipient_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
 }
 
-// ============================================================================
-// Context Structures - Phase 2: Queue Computation
-// ============================================================================
-
-#[queue_computation_accounts("deposit_v2", user)]
+#[callback_accounts("claim_transfers_v2")]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct Deposit<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
-        bump = protocol_pool.bump,
-    )]
-    pub protocol_pool: Account<'info, ProtocolPool>,
-    #[account(
-        mut,
-        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
-    )]
-    pub pool_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        constraint = user_token_account.owner == user.key() @ ErrorCode::Unauthorized,
-        constraint = user_token_account.mint == mint.key() @ ErrorCode::Unauthorized,
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = UserLedger::SIZE,
-        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
-        bump,
-    )]
-    pub user_ledger: Account<'info, UserLedger>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = user,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+pub struct ClaimTransfersV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_TRANSFERS))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Box<Account<'info, Cluster>>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
 }
 
-#[queue_computation_accounts("withdraw_v2", user)]
+#[callback_accounts("buy_voucher_v2")]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct Withdraw<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
-        bump = protocol_pool.bump,
-    )]
-    pub protocol_pool: Account<'info, ProtocolPool>,
-    #[account(
-        mut,
-        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
-    )]
-    pub pool_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        constraint = user_token_account.owner == user.key() @ ErrorCode::Unauthorized,
-        constraint = user_token_account.mint == mint.key() @ ErrorCode::Unauthorized,
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
-        bump = user_ledger.bump,
-    )]
-    pub user_ledger: Account<'info, UserLedger>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = user,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+pub struct BuyVoucherV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_BUY_VOUCHER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub buyer_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub voucher_pool: Box<Account<'info, VoucherPool>>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+}
+
+#[callback_accounts("redeem_voucher_v2")]
+#[derive(Accounts)]
+pub struct RedeemVoucherV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REDEEM_VOUCHER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_WITHDRAW))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Box<Account<'info, Cluster>>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub voucher_pool: Box<Account<'info, VoucherPool>>,
+    #[account(mut)]
+    pub recipient_ledger: Account<'info, UserLedger>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(mut)]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
 }
 
-#[queue_computation_accounts("subscribe_v2", user)]
+#[callback_accounts("update_seats_v2")]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64, subscription_index: u64)]
-pub struct Subscribe<'info> {
+pub struct UpdateSeatsV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UPDATE_SEATS))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    pub user: Signer<'info>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
-        bump = subscription_plan.bump,
-        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
-    )]
-    pub subscription_plan: Account<'info, SubscriptionPlan>,
-    #[account(
-        mut,
-        seeds = [USER_LEDGER_SEED, user.key().as_ref(), mint.key().as_ref()],
-        bump = user_ledger.bump,
-    )]
     pub user_ledger: Account<'info, UserLedger>,
-    #[account(
-        mut,
-        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
-        bump = merchant_ledger.bump,
-    )]
+    #[account(mut)]
     pub merchant_ledger: Account<'info, MerchantLedger>,
-    #[account(
-        init,
-        payer = user,
-        space = UserSubscription::SIZE,
-        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &subscription_index.to_le_bytes()],
-        bump,
-    )]
+    #[account(mut)]
     pub user_subscription: Account<'info, UserSubscription>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = user,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(mut)]
+    pub spending_cap: Box<Account<'info, SpendingCap>>,
+    #[account(mut)]
+    pub plan_stats: Box<Account<'info, PlanStats>>,
+    #[account(mut)]
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
+}
+
+#[callback_accounts("assign_seat_v2")]
+#[derive(Accounts)]
+pub struct AssignSeatV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ASSIGN_SEAT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBSCRIBE))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Box<Account<'info, Cluster>>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_subscription: Account<'info, UserSubscription>,
+    #[account(mut)]
+    pub seat_assignment: Account<'info, SeatAssignment>,
 }
 
-#[queue_computation_accounts("unsubscribe_v2", user)]
+#[callback_accounts("scheduled_payout_v2")]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct Unsubscribe<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [USER_SUBSCRIPTION_SEED, user.key().as_ref(), &user_subscription.subscription_index.to_le_bytes()],
-        bump = user_subscription.bump,
-    )]
-    pub user_subscription: Account<'info, UserSubscription>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = user,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+pub struct ScheduledPayoutV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCHEDULED_PAYOUT))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UNSUBSCRIBE))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Box<Account<'info, Cluster>>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
+    #[account(mut)]
+    pub protocol_pool: Account<'info, ProtocolPool>,
+    #[account(mut)]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub destination_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payout_schedule: Account<'info, PayoutSchedule>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[queue_computation_accounts("process_payment_v2", payer)]
+#[callback_accounts("publish_plan_stats_v2")]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct ProcessPayment<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.merchant.as_ref(), &subscription_plan.plan_id.to_le_bytes()],
-        bump = subscription_plan.bump,
-        constraint = subscription_plan.mint == mint.key() @ ErrorCode::InvalidMint,
-    )]
-    pub subscription_plan: Account<'info, SubscriptionPlan>,
-    #[account(
-        mut,
-        seeds = [USER_LEDGER_SEED, user_subscription.user.as_ref(), mint.key().as_ref()],
-        bump = user_ledger.bump,
-    )]
-    pub user_ledger: Account<'info, UserLedger>,
-    #[account(
-        mut,
-        seeds = [MERCHANT_LEDGER_SEED, subscription_plan.merchant.as_ref(), mint.key().as_ref()],
-        bump = merchant_ledger.bump,
-    )]
-    pub merchant_ledger: Account<'info, MerchantLedger>,
-    #[account(
-        mut,
-        seeds = [USER_SUBSCRIPTION_SEED, user_subscription.user.as_ref(), &user_subscription.subscription_index.to_le_bytes()],
-        bump = user_subscription.bump,
-    )]
-    pub user_subscription: Account<'info, UserSubscription>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+pub struct PublishPlanStatsV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PUBLISH_PLAN_STATS))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_PAYMENT))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Box<Account<'info, Cluster>>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-}
-
-#[queue_computation_accounts("verify_subscription_v2", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct VerifySubscription<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        seeds = [USER_SUBSCRIPTION_SEED, user_subscription.user.as_ref(), &user_subscription.subscription_index.to_le_bytes()],
-        bump = user_subscription.bump,
-    )]
-    pub user_subscription: Account<'info, UserSubscription>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub plan_public_stats: Account<'info, PlanPublicStats>,
+}
+
+#[callback_accounts("disclose_user_ledger_v2")]
+#[derive(Accounts)]
+pub struct DiscloseUserLedgerV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_USER_LEDGER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VERIFY_SUBSCRIPTION))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Box<Account<'info, Cluster>>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub audit_grant: Account<'info, AuditGrant>,
 }
 
-#[queue_computation_accounts("claim_revenue_v2", wallet)]
+#[callback_accounts("disclose_merchant_ledger_v2")]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct ClaimRevenue<'info> {
-    #[account(mut)]
-    pub wallet: Signer<'info>,
-    pub mint: Account<'info, Mint>,
-    #[account(
-        seeds = [MERCHANT_SEED, wallet.key().as_ref()],
-        bump = merchant.bump,
-    )]
-    pub merchant: Account<'info, Merchant>,
-    #[account(
-        seeds = [PROTOCOL_POOL_SEED, mint.key().as_ref()],
-        bump = protocol_pool.bump,
-    )]
-    pub protocol_pool: Account<'info, ProtocolPool>,
-    #[account(
-        mut,
-        constraint = pool_token_account.key() == protocol_pool.token_account @ ErrorCode::Unauthorized,
-    )]
-    pub pool_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        constraint = merchant_token_account.owner == wallet.key() @ ErrorCode::Unauthorized,
-        constraint = merchant_token_account.mint == mint.key() @ ErrorCode::Unauthorized,
-    )]
-    pub merchant_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        seeds = [MERCHANT_LEDGER_SEED, merchant.key().as_ref(), mint.key().as_ref()],
-        bump = merchant_ledger.bump,
-    )]
-    pub merchant_ledger: Account<'info, MerchantLedger>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = wallet,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+pub struct DiscloseMerchantLedgerV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_MERCHANT_LEDGER))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
     /// CHECK: computation_account
     pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_REVENUE))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
     pub cluster_account: Box<Account<'info, Cluster>>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub audit_grant: Account<'info, AuditGrant>,
 }
 
-// ============================================================================
-// Context Structures - Phase 2: Callback
-// ============================================================================
-
-#[callback_accounts("deposit_v2")]
+#[callback_accounts("disclose_subscription_v2")]
 #[derive(Accounts)]
-pub struct DepositV2Callback<'info> {
+pub struct DiscloseSubscriptionV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEPOSIT))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISCLOSE_SUBSCRIPTION))]
     pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
@@ -1948,15 +11802,14 @@ pub struct DepositV2Callback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
-    #[account(mut)]
-    pub user_ledger: Account<'info, UserLedger>,
+    pub audit_grant: Account<'info, AuditGrant>,
 }
 
-#[callback_accounts("withdraw_v2")]
+#[callback_accounts("distribute_yield_v2")]
 #[derive(Accounts)]
-pub struct WithdrawV2Callback<'info> {
+pub struct DistributeYieldV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_WITHDRAW))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_DISTRIBUTE_YIELD))]
     pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
@@ -1968,20 +11821,14 @@ pub struct WithdrawV2Callback<'info> {
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    pub user_ledger: Account<'info, UserLedger>,
     pub protocol_pool: Account<'info, ProtocolPool>,
-    #[account(mut)]
-    pub pool_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
 }
 
-#[callback_accounts("subscribe_v2")]
+#[callback_accounts("settle_yield_v2")]
 #[derive(Accounts)]
-pub struct SubscribeV2Callback<'info> {
+pub struct SettleYieldV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBSCRIBE))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SETTLE_YIELD))]
     pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
@@ -1995,16 +11842,14 @@ pub struct SubscribeV2Callback<'info> {
     #[account(mut)]
     pub user_ledger: Account<'info, UserLedger>,
     #[account(mut)]
-    pub merchant_ledger: Account<'info, MerchantLedger>,
-    #[account(mut)]
-    pub user_subscription: Account<'info, UserSubscription>,
+    pub protocol_pool: Box<Account<'info, ProtocolPool>>,
 }
 
-#[callback_accounts("unsubscribe_v2")]
+#[callback_accounts("set_principal_lock_v2")]
 #[derive(Accounts)]
-pub struct UnsubscribeV2Callback<'info> {
+pub struct SetPrincipalLockV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UNSUBSCRIBE))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_PRINCIPAL_LOCK))]
     pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
@@ -2016,14 +11861,14 @@ pub struct UnsubscribeV2Callback<'info> {
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    pub user_subscription: Account<'info, UserSubscription>,
+    pub user_ledger: Account<'info, UserLedger>,
 }
 
-#[callback_accounts("process_payment_v2")]
+#[callback_accounts("migrate_user_ledger_v2")]
 #[derive(Accounts)]
-pub struct ProcessPaymentV2Callback<'info> {
+pub struct MigrateUserLedgerV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_PAYMENT))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_USER_LEDGER))]
     pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
@@ -2036,17 +11881,32 @@ pub struct ProcessPaymentV2Callback<'info> {
     pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
     pub user_ledger: Account<'info, UserLedger>,
-    #[account(mut)]
-    pub merchant_ledger: Account<'info, MerchantLedger>,
+}
+
+#[callback_accounts("migrate_user_subscription_v2")]
+#[derive(Accounts)]
+pub struct MigrateUserSubscriptionV2Callback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_USER_SUBSCRIPTION))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: computation_account
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
     pub user_subscription: Account<'info, UserSubscription>,
 }
 
-#[callback_accounts("verify_subscription_v2")]
+#[callback_accounts("migrate_merchant_ledger_v2")]
 #[derive(Accounts)]
-pub struct VerifySubscriptionV2Callback<'info> {
+pub struct MigrateMerchantLedgerV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VERIFY_SUBSCRIPTION))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MIGRATE_MERCHANT_LEDGER))]
     pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
@@ -2057,13 +11917,15 @@ pub struct VerifySubscriptionV2Callback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub merchant_ledger: Account<'info, MerchantLedger>,
 }
 
-#[callback_accounts("claim_revenue_v2")]
+#[callback_accounts("seed_liabilities_v2")]
 #[derive(Accounts)]
-pub struct ClaimRevenueV2Callback<'info> {
+pub struct SeedLiabilitiesV2Callback<'info> {
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLAIM_REVENUE))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SEED_LIABILITIES))]
     pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
     #[account(address = derive_mxe_pda!())]
     pub mxe_account: Box<Account<'info, MXEAccount>>,
@@ -2075,13 +11937,7 @@ pub struct ClaimRevenueV2Callback<'info> {
     /// CHECK: instructions_sysvar
     pub instructions_sysvar: AccountInfo<'info>,
     #[account(mut)]
-    pub merchant_ledger: Account<'info, MerchantLedger>,
     pub protocol_pool: Account<'info, ProtocolPool>,
-    #[account(mut)]
-    pub pool_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub merchant_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
 }
 
 // ============================================================================
@@ -2093,6 +11949,78 @@ pub struct SubscriptionVerified {
     pub is_valid: bool,
 }
 
+/// Balance runway result, decryptable only with the user's key
+#[event]
+pub struct BalanceRunwayComputed {
+    pub user: Pubkey,
+    pub encrypted_monthly_spend: [u8; 32],
+    pub encrypted_first_failure_date: [u8; 32],
+    pub nonce: u128,
+}
+
+#[event]
+pub struct SolvencyChecked {
+    pub pool: Pubkey,
+    pub is_solvent: bool,
+    pub token_balance: u64,
+}
+
+/// Voucher purchase result; `issued` is false when the buyer's balance was short or the pool was full
+#[event]
+pub struct VoucherIssued {
+    pub voucher_pool: Pubkey,
+    pub issued: bool,
+}
+
+/// Voucher redemption result; `redeemed` is false when the secret did not match
+#[event]
+pub struct VoucherRedeemed {
+    pub voucher_pool: Pubkey,
+    pub redeemed: bool,
+}
+
+/// Amount a claim paid out; zero when the MPC rejected it.
+/// `split` claims paid the revenue split's recipients instead of the merchant.
+#[event]
+pub struct RevenueClaimed {
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub split: bool,
+}
+
+/// Scheduled payout result; amount is zero when the balance was below the threshold
+#[event]
+pub struct ScheduledPayoutExecuted {
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Account state re-encrypted to an auditor key under an audit grant
+#[event]
+pub struct AuditDisclosed {
+    pub audit_grant: Pubkey,
+    pub target: Pubkey,
+    pub auditor_encryption_pubkey: [u8; 32],
+    pub ciphertexts: Vec<[u8; 32]>,
+    pub nonce: u128,
+}
+
+/// Lending strategy allocation changed; amounts are pool-level and already public on chain
+#[event]
+pub struct StrategyAllocationChanged {
+    pub pool: Pubkey,
+    pub strategy_allocated: u64,
+}
+
+/// Harvested yield was folded into the pool's encrypted yield index
+#[event]
+pub struct YieldDistributed {
+    pub pool: Pubkey,
+    pub yield_epoch: u64,
+}
+
 // ============================================================================
 // Errors
 // ============================================================================
@@ -2147,4 +12075,211 @@ pub enum ErrorCode {
 
     #[msg("Subscription not active")]
     SubscriptionNotActive,
+
+    #[msg("Dispute not open")]
+    DisputeNotOpen,
+
+    #[msg("Too many disputes open on this subscription")]
+    TooManyOpenDisputes,
+
+    #[msg("Invalid billing period")]
+    InvalidBillingPeriod,
+
+    #[msg("Subscription does not match")]
+    InvalidSubscription,
+
+    #[msg("Spending cap window must be 1 to 365 days")]
+    InvalidSpendingWindow,
+
+    #[msg("Pool liabilities have not been seeded")]
+    LiabilitiesNotSeeded,
+
+    #[msg("Pool liabilities have already been seeded")]
+    LiabilitiesAlreadySeeded,
+
+    #[msg("Another computation is updating the pool liabilities")]
+    LiabilitiesBusy,
+
+    #[msg("Pool liabilities were updated after this computation was queued")]
+    StaleLiabilitiesUpdate,
+
+    #[msg("Runway query expects up to 4 (subscription, plan) account pairs")]
+    InvalidRunwayAccounts,
+
+    #[msg("Invalid recipient")]
+    InvalidRecipient,
+
+    #[msg("Invalid voucher cycles (must be 1-24)")]
+    InvalidVoucherCycles,
+
+    #[msg("Voucher pool has no free slot")]
+    VoucherPoolFull,
+
+    #[msg("Voucher pool has no outstanding vouchers")]
+    NoOutstandingVouchers,
+
+    #[msg("A purchase or redemption on this voucher pool is already queued")]
+    VoucherPoolBusy,
+
+    #[msg("No seat available")]
+    NoSeatAvailable,
+
+    #[msg("Seat assignment is not active")]
+    SeatNotActive,
+
+    #[msg("Seat assignment is still pending")]
+    SeatPending,
+
+    #[msg("Invalid revenue split configuration")]
+    InvalidRevenueSplit,
+
+    #[msg("Revenue split recipients do not match the configuration")]
+    InvalidSplitRecipients,
+
+    #[msg("Invalid payout cadence")]
+    InvalidPayoutCadence,
+
+    #[msg("Payout schedule is not active")]
+    PayoutScheduleInactive,
+
+    #[msg("Scheduled payout is not due yet")]
+    PayoutNotDue,
+
+    #[msg("Plan statistics are not available yet")]
+    PlanStatsUnavailable,
+
+    #[msg("Refresh requested too soon")]
+    RefreshTooSoon,
+
+    #[msg("Invalid expiry")]
+    InvalidExpiry,
+
+    #[msg("Audit grant has expired")]
+    AuditGrantExpired,
+
+    #[msg("Screening authority is already listed")]
+    ScreeningAuthorityExists,
+
+    #[msg("Too many screening authorities")]
+    TooManyScreeningAuthorities,
+
+    #[msg("Unknown screening authority")]
+    UnknownScreeningAuthority,
+
+    #[msg("A screening attestation is required")]
+    ScreeningRequired,
+
+    #[msg("Screening attestation has been revoked")]
+    ScreeningRevoked,
+
+    #[msg("Screening attestation has expired")]
+    ScreeningExpired,
+
+    #[msg("Screening attestation is held by another screening authority")]
+    ScreeningAttestationHeld,
+
+    #[msg("Invalid outflow limits")]
+    InvalidOutflowLimits,
+
+    #[msg("Invalid outflow kind")]
+    InvalidOutflowKind,
+
+    #[msg("Pool outflow budget for this epoch is exhausted")]
+    OutflowBudgetExceeded,
+
+    #[msg("Amount requires a time-locked outflow request")]
+    OutflowRequestRequired,
+
+    #[msg("Amount does not match the outflow request")]
+    OutflowRequestMismatch,
+
+    #[msg("Outflow request is still time-locked")]
+    OutflowRequestLocked,
+
+    #[msg("An outflow request is already pending")]
+    OutflowRequestPending,
+
+    #[msg("Lending program is not a supported strategy")]
+    UnsupportedLendingStrategy,
+
+    #[msg("No lending strategy is set for this pool")]
+    NoLendingStrategy,
+
+    #[msg("Strategy still holds allocated liquidity")]
+    StrategyInUse,
+
+    #[msg("Strategy accounts do not belong to this pool")]
+    InvalidStrategyAccounts,
+
+    #[msg("Pool token account does not hold enough liquidity")]
+    InsufficientPoolLiquidity,
+
+    #[msg("Amount exceeds the liquidity allocated to the strategy")]
+    ExceedsStrategyAllocation,
+
+    #[msg("No yield has accrued since the last distribution")]
+    NoYieldAccrued,
+
+    #[msg("Yield was distributed too recently")]
+    YieldDistributionTooSoon,
+
+    #[msg("Ledger has already settled the current yield epoch")]
+    YieldAlreadySettled,
+
+    #[msg("Price oracle program is not supported")]
+    UnsupportedPriceOracle,
+
+    #[msg("Price account does not match the approved price feed")]
+    InvalidPriceAccount,
+
+    #[msg("Oracle price update is not fully verified")]
+    UnverifiedOraclePrice,
+
+    #[msg("Oracle price update is for a different feed")]
+    PriceFeedIdMismatch,
+
+    #[msg("Price currency code is invalid")]
+    InvalidPriceCurrency,
+
+    #[msg("Price feed staleness or confidence limits are invalid")]
+    InvalidPriceFeedConfig,
+
+    #[msg("Plan is priced in a reference currency and needs a price feed for this mint")]
+    MissingPriceFeed,
+
+    #[msg("Oracle price must be positive")]
+    InvalidOraclePrice,
+
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+
+    #[msg("Oracle price confidence interval is too wide")]
+    OraclePriceTooUncertain,
+
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+
+    #[msg("Not supported for plans priced in a reference currency")]
+    ReferencePricedPlan,
+
+    #[msg("Circuit source URL is empty or too long")]
+    InvalidCircuitSource,
+
+    #[msg("Encrypted state uses an older layout; migrate it first")]
+    StaleStateVersion,
+
+    #[msg("Encrypted state already uses the current layout")]
+    StateAlreadyCurrent,
+
+    #[msg("Account type cannot be migrated")]
+    UnsupportedAccountMigration,
+
+    #[msg("Account already uses the current layout")]
+    AccountAlreadyCurrent,
+
+    #[msg("Billing period was prepaid by a voucher and cannot be disputed")]
+    VoucherPrepaidPeriod,
+
+    #[msg("Voucher pool was updated after this computation was queued")]
+    StaleVoucherPoolUpdate,
 }
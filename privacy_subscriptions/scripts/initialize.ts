@@ -26,9 +26,17 @@ const USDC_MINT = new PublicKey(TOKEN_MINT_ADDRESS);
 // Protocol fee rate in basis points (100 = 1%)
 const FEE_RATE_BPS = 0;
 
+// Circuit version the program's computation definitions are built from (must match the program)
+const CIRCUIT_VERSION = 2;
+// Where that version's .arcis files are published (override with CIRCUIT_SOURCE_BASE_URL)
+const CIRCUIT_SOURCE_BASE_URL =
+  process.env.CIRCUIT_SOURCE_BASE_URL ||
+  "https://raw.githubusercontent.com/SublyFi/circuits/main";
+
 // PDA Seeds (must match the program)
 const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
 const PROTOCOL_POOL_SEED = Buffer.from("protocol_pool");
+const CIRCUIT_VERSION_SEED = Buffer.from("circuit_version");
 
 // Computation definition names
 const COMP_DEF_NAMES = [
@@ -39,6 +47,34 @@ const COMP_DEF_NAMES = [
   "process_payment_v2",
   "verify_subscription_v2",
   "claim_revenue_v2",
+  "reactivate_subscription_v2",
+  "cancel_at_period_end_v2",
+  "resume_subscription_v2",
+  "refund_v2",
+  "open_dispute_v2",
+  "resolve_dispute_refund_v2",
+  "resolve_dispute_release_v2",
+  "set_spending_cap_v2",
+  "balance_runway_v2",
+  "check_solvency_v2",
+  "transfer_v2",
+  "claim_transfers_v2",
+  "buy_voucher_v2",
+  "redeem_voucher_v2",
+  "update_seats_v2",
+  "assign_seat_v2",
+  "scheduled_payout_v2",
+  "publish_plan_stats_v2",
+  "disclose_user_ledger_v2",
+  "disclose_merchant_ledger_v2",
+  "disclose_subscription_v2",
+  "distribute_yield_v2",
+  "settle_yield_v2",
+  "set_principal_lock_v2",
+  "migrate_user_ledger_v2",
+  "migrate_user_subscription_v2",
+  "migrate_merchant_ledger_v2",
+  "seed_liabilities_v2",
 ] as const;
 
 // ============================================================================
@@ -64,6 +100,16 @@ function getProtocolPoolPDA(
   );
 }
 
+function getCircuitVersionPDA(
+  programId: PublicKey,
+  version: number,
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [CIRCUIT_VERSION_SEED, Buffer.from([version])],
+    programId,
+  );
+}
+
 function getCompDefPDA(
   programId: PublicKey,
   compDefName: string,
@@ -121,6 +167,7 @@ async function initializeCompDefs(
 
   const mxeAccount = getMXEAccAddress(program.programId);
   const addressLookupTable = getLookupTableAddress(program.programId);
+  const [circuitVersion] = getCircuitVersionPDA(program.programId, CIRCUIT_VERSION);
   const forceReinit = process.env.FORCE_REINIT === "1";
 
   for (const compDefName of COMP_DEF_NAMES) {
@@ -145,6 +192,7 @@ async function initializeCompDefs(
           compDefAccount: compDefPDA,
           addressLookupTable,
           lutProgram: AddressLookupTableProgram.programId,
+          circuitVersion,
         };
 
         switch (compDefName) {
@@ -172,6 +220,118 @@ async function initializeCompDefs(
             return program.methods
               .initClaimRevenueCompDef()
               .accounts(baseAccounts);
+          case "reactivate_subscription_v2":
+            return program.methods
+              .initReactivateSubscriptionCompDef()
+              .accounts(baseAccounts);
+          case "cancel_at_period_end_v2":
+            return program.methods
+              .initCancelAtPeriodEndCompDef()
+              .accounts(baseAccounts);
+          case "resume_subscription_v2":
+            return program.methods
+              .initResumeSubscriptionCompDef()
+              .accounts(baseAccounts);
+          case "refund_v2":
+            return program.methods
+              .initRefundCompDef()
+              .accounts(baseAccounts);
+          case "open_dispute_v2":
+            return program.methods
+              .initOpenDisputeCompDef()
+              .accounts(baseAccounts);
+          case "resolve_dispute_refund_v2":
+            return program.methods
+              .initResolveDisputeRefundCompDef()
+              .accounts(baseAccounts);
+          case "resolve_dispute_release_v2":
+            return program.methods
+              .initResolveDisputeReleaseCompDef()
+              .accounts(baseAccounts);
+          case "set_spending_cap_v2":
+            return program.methods
+              .initSetSpendingCapCompDef()
+              .accounts(baseAccounts);
+          case "balance_runway_v2":
+            return program.methods
+              .initBalanceRunwayCompDef()
+              .accounts(baseAccounts);
+          case "check_solvency_v2":
+            return program.methods
+              .initCheckSolvencyCompDef()
+              .accounts(baseAccounts);
+          case "transfer_v2":
+            return program.methods
+              .initTransferCompDef()
+              .accounts(baseAccounts);
+          case "claim_transfers_v2":
+            return program.methods
+              .initClaimTransfersCompDef()
+              .accounts(baseAccounts);
+          case "buy_voucher_v2":
+            return program.methods
+              .initBuyVoucherCompDef()
+              .accounts(baseAccounts);
+          case "redeem_voucher_v2":
+            return program.methods
+              .initRedeemVoucherCompDef()
+              .accounts(baseAccounts);
+          case "update_seats_v2":
+            return program.methods
+              .initUpdateSeatsCompDef()
+              .accounts(baseAccounts);
+          case "assign_seat_v2":
+            return program.methods
+              .initAssignSeatCompDef()
+              .accounts(baseAccounts);
+          case "scheduled_payout_v2":
+            return program.methods
+              .initScheduledPayoutCompDef()
+              .accounts(baseAccounts);
+          case "publish_plan_stats_v2":
+            return program.methods
+              .initPublishPlanStatsCompDef()
+              .accounts(baseAccounts);
+          case "disclose_user_ledger_v2":
+            return program.methods
+              .initDiscloseUserLedgerCompDef()
+              .accounts(baseAccounts);
+          case "disclose_merchant_ledger_v2":
+            return program.methods
+              .initDiscloseMerchantLedgerCompDef()
+              .accounts(baseAccounts);
+          case "disclose_subscription_v2":
+            return program.methods
+              .initDiscloseSubscriptionCompDef()
+              .accounts(baseAccounts);
+          case "distribute_yield_v2":
+            return program.methods
+              .initDistributeYieldCompDef()
+              .accounts(baseAccounts);
+          case "settle_yield_v2":
+            return program.methods
+              .initSettleYieldCompDef()
+              .accounts(baseAccounts);
+          case "set_principal_lock_v2":
+            return program.methods
+              .initSetPrincipalLockCompDef()
+              .accounts(baseAccounts);
+          case "migrate_user_ledger_v2":
+            return program.methods
+              .initMigrateUserLedgerCompDef()
+              .accounts(baseAccounts);
+          case "migrate_user_subscription_v2":
+            return program.methods
+              .initMigrateUserSubscriptionCompDef()
+              .accounts(baseAccounts);
+          case "migrate_merchant_ledger_v2":
+            return program.methods
+              .initMigrateMerchantLedgerCompDef()
+              .accounts(baseAccounts);
+          case "seed_liabilities_v2":
+            return program.methods
+              .initSeedLiabilitiesCompDef()
+              .accounts(baseAccounts);
           default:
             throw new Error(`Unknown comp def: ${compDefName}`);
         }
@@ -200,6 +360,38 @@ async function initializeCompDefs(
   }
 }
 
+async function registerCircuitVersion(
+  program: Program<PrivacySubscriptions>,
+  authority: Keypair,
+): Promise<void> {
+  console.log("\n=== Registering Circuit Version ===\n");
+
+  const [circuitVersionPDA] = getCircuitVersionPDA(
+    program.programId,
+    CIRCUIT_VERSION,
+  );
+  console.log(`[INIT] Circuit version ${CIRCUIT_VERSION} -> ${CIRCUIT_SOURCE_BASE_URL}`);
+  console.log(`       PDA: ${circuitVersionPDA.toBase58()}`);
+
+  try {
+    // Re-registering only updates the source, so this always runs
+    const sig = await program.methods
+      .registerCircuitVersion(CIRCUIT_VERSION, CIRCUIT_SOURCE_BASE_URL)
+      .accountsPartial({
+        authority: authority.publicKey,
+        circuitVersion: circuitVersionPDA,
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+
+    console.log(`       Tx: ${sig}`);
+    console.log(`[DONE] Circuit version registered\n`);
+  } catch (error) {
+    console.error("[ERROR] Failed to register circuit version:", error);
+    throw error;
+  }
+}
+
 async function initializeProtocol(
   program: Program<PrivacySubscriptions>,
   authority: Keypair,
@@ -339,13 +531,16 @@ async function main() {
 
   // Run initialization steps
   try {
-    // Step 1: Initialize Computation Definitions
-    await initializeCompDefs(program, authority, provider);
-
-    // Step 2: Initialize Protocol
+    // Step 1: Initialize Protocol
     await initializeProtocol(program, authority, provider);
 
-    // Step 3: Initialize USDC Pool
+    // Step 2: Register where the circuits are published
+    await registerCircuitVersion(program, authority);
+
+    // Step 3: Initialize Computation Definitions
+    await initializeCompDefs(program, authority, provider);
+
+    // Step 4: Initialize USDC Pool
     await initializePool(program, authority, USDC_MINT, provider);
 
     console.log(
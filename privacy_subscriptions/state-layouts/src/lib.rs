@@ -0,0 +1,379 @@
+//! Ciphertext layouts of the encrypted state structs.
+//!
+//! Arcis serializes an encrypted struct as one 32-byte ciphertext per scalar,
+//! in field declaration order. Each layout is declared once here and generates
+//! a plaintext mirror (`UserLedgerState`, ...) the circuits are checked against
+//! with [`assert_layout!`], and a ciphertext struct (`UserLedgerStateCiphertexts`,
+//! ...) that the program decodes callback outputs into and, with the `program`
+//! feature, appends to an `ArgBuilder` in the same order.
+
+#[cfg(feature = "program")]
+use arcium_anchor::prelude::ArgBuilder;
+
+/// Plaintext type of an encrypted field and the ciphertexts it occupies
+pub trait EncryptedField {
+    /// Ciphertexts of one value (`[u8; 32]` for scalars)
+    type Ciphertext: Copy;
+    /// Number of 32-byte ciphertexts one value occupies
+    const CIPHERTEXTS: usize;
+
+    /// Read a value's ciphertexts starting at `ciphertexts[0]`
+    fn read(ciphertexts: &[[u8; 32]]) -> Self::Ciphertext;
+
+    /// Write a value's ciphertexts starting at `out[0]`
+    fn write(ciphertext: &Self::Ciphertext, out: &mut [[u8; 32]]);
+
+    /// Rebuild a value from its decrypted field elements, starting at `plaintexts[0]`.
+    /// Signed values must be non-negative.
+    fn from_plaintexts(plaintexts: &[u128]) -> Self;
+
+    /// Append a value's ciphertexts to the circuit arguments
+    #[cfg(feature = "program")]
+    fn push(builder: ArgBuilder, ciphertext: &Self::Ciphertext) -> ArgBuilder;
+}
+
+macro_rules! scalar_fields {
+    ($($ty:ty => $encrypted:ident, |$value:ident| $decode:expr);* $(;)?) => {$(
+        impl EncryptedField for $ty {
+            type Ciphertext = [u8; 32];
+            const CIPHERTEXTS: usize = 1;
+
+            fn read(ciphertexts: &[[u8; 32]]) -> [u8; 32] {
+                ciphertexts[0]
+            }
+
+            fn write(ciphertext: &[u8; 32], out: &mut [[u8; 32]]) {
+                out[0] = *ciphertext;
+            }
+
+            fn from_plaintexts(plaintexts: &[u128]) -> Self {
+                let $value = plaintexts[0];
+                $decode
+            }
+
+            #[cfg(feature = "program")]
+            fn push(builder: ArgBuilder, ciphertext: &[u8; 32]) -> ArgBuilder {
+                builder.$encrypted(*ciphertext)
+            }
+        }
+    )*};
+}
+
+scalar_fields!(
+    bool => encrypted_bool, |value| value != 0;
+    u8 => encrypted_u8, |value| value as u8;
+    u32 => encrypted_u32, |value| value as u32;
+    u64 => encrypted_u64, |value| value as u64;
+    i64 => encrypted_i64, |value| value as u64 as i64;
+    u128 => encrypted_u128, |value| value;
+);
+
+impl<T: EncryptedField<Ciphertext = [u8; 32]>, const N: usize> EncryptedField for [T; N] {
+    type Ciphertext = [[u8; 32]; N];
+    const CIPHERTEXTS: usize = N;
+
+    fn read(ciphertexts: &[[u8; 32]]) -> [[u8; 32]; N] {
+        core::array::from_fn(|i| ciphertexts[i])
+    }
+
+    fn write(ciphertext: &[[u8; 32]; N], out: &mut [[u8; 32]]) {
+        out[..N].copy_from_slice(ciphertext);
+    }
+
+    fn from_plaintexts(plaintexts: &[u128]) -> Self {
+        core::array::from_fn(|i| T::from_plaintexts(&plaintexts[i..]))
+    }
+
+    #[cfg(feature = "program")]
+    fn push(builder: ArgBuilder, ciphertext: &[[u8; 32]; N]) -> ArgBuilder {
+        ciphertext.iter().fold(builder, T::push)
+    }
+}
+
+/// Ciphertexts of one encrypted state struct that can be passed to a circuit
+#[cfg(feature = "program")]
+pub trait StateCiphertexts {
+    /// Append every field's ciphertexts in circuit field order
+    fn push_args(&self, builder: ArgBuilder) -> ArgBuilder;
+}
+
+/// `ArgBuilder` support for state structs and single encrypted values
+#[cfg(feature = "program")]
+pub trait ArgBuilderExt {
+    /// Append a state struct's ciphertexts, after its `Enc` pubkey and nonce
+    fn encrypted_state<S: StateCiphertexts>(self, state: &S) -> Self;
+
+    /// Append one encrypted value of plaintext type `T`
+    fn encrypted<T: EncryptedField>(self, ciphertext: &T::Ciphertext) -> Self;
+}
+
+#[cfg(feature = "program")]
+impl ArgBuilderExt for ArgBuilder {
+    fn encrypted_state<S: StateCiphertexts>(self, state: &S) -> Self {
+        state.push_args(self)
+    }
+
+    fn encrypted<T: EncryptedField>(self, ciphertext: &T::Ciphertext) -> Self {
+        T::push(self, ciphertext)
+    }
+}
+
+macro_rules! state_layouts {
+    ($(
+        $(#[$meta:meta])*
+        $name:ident => $ciphertexts:ident {
+            $($(#[$field_meta:meta])* $field:ident: $ty:ty),+ $(,)?
+        }
+    )*) => {$(
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: $ty,)+
+        }
+
+        impl $name {
+            /// Rebuild the state from its decrypted field elements, in layout order
+            pub fn from_plaintexts(plaintexts: &[u128; $ciphertexts::LEN]) -> Self {
+                let mut offset = 0;
+                $(
+                    let $field = <$ty as EncryptedField>::from_plaintexts(&plaintexts[offset..]);
+                    offset += <$ty as EncryptedField>::CIPHERTEXTS;
+                )+
+                debug_assert_eq!(offset, $ciphertexts::LEN);
+                Self { $($field,)+ }
+            }
+        }
+
+        #[doc = concat!("Ciphertexts of [`", stringify!($name), "`], one entry per field")]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct $ciphertexts {
+            $(pub $field: <$ty as EncryptedField>::Ciphertext,)+
+        }
+
+        impl $ciphertexts {
+            /// Number of 32-byte ciphertexts in the layout
+            pub const LEN: usize = 0 $(+ <$ty as EncryptedField>::CIPHERTEXTS)+;
+
+            /// Split a computation output's ciphertexts into fields
+            pub fn from_ciphertexts(ciphertexts: &[[u8; 32]; $ciphertexts::LEN]) -> Self {
+                let mut offset = 0;
+                $(
+                    let $field = <$ty as EncryptedField>::read(&ciphertexts[offset..]);
+                    offset += <$ty as EncryptedField>::CIPHERTEXTS;
+                )+
+                debug_assert_eq!(offset, $ciphertexts::LEN);
+                Self { $($field,)+ }
+            }
+
+            /// Flatten the fields back into ciphertexts, in layout order
+            pub fn to_ciphertexts(&self) -> [[u8; 32]; $ciphertexts::LEN] {
+                let mut ciphertexts = [[0u8; 32]; $ciphertexts::LEN];
+                let mut offset = 0;
+                $(
+                    <$ty as EncryptedField>::write(&self.$field, &mut ciphertexts[offset..]);
+                    offset += <$ty as EncryptedField>::CIPHERTEXTS;
+                )+
+                debug_assert_eq!(offset, $ciphertexts::LEN);
+                ciphertexts
+            }
+        }
+
+        #[cfg(feature = "program")]
+        impl StateCiphertexts for $ciphertexts {
+            fn push_args(&self, builder: ArgBuilder) -> ArgBuilder {
+                $(let builder = <$ty as EncryptedField>::push(builder, &self.$field);)+
+                builder
+            }
+        }
+    )*};
+}
+
+state_layouts! {
+    /// User ledger state (Enc<Shared, UserLedgerState>, layout 2)
+    UserLedgerState => UserLedgerStateCiphertexts {
+        /// Deposited and credited funds
+        principal: u64,
+        subscription_count: u64,
+        /// Yield credited by settlements; spent before principal
+        accrued_yield: u64,
+        /// When set, subscription charges may only be paid from accrued yield
+        principal_locked: bool,
+    }
+
+    /// Merchant ledger state (Enc<Shared, MerchantLedgerState>, layout 2)
+    MerchantLedgerState => MerchantLedgerStateCiphertexts {
+        balance: u64,
+        total_claimed: u64,
+        /// Amount frozen by open disputes
+        frozen: u64,
+    }
+
+    /// User subscription state (Enc<Shared, UserSubscriptionState>, layout 2)
+    UserSubscriptionState => UserSubscriptionStateCiphertexts {
+        /// Plan public key as two u128 values
+        plan: [u128; 2],
+        /// Subscription status (0=Active, 1=Cancelled, 2=Expired, 3=Cancelling, 4=CapExceeded)
+        status: u8,
+        next_payment_date: i64,
+        start_date: i64,
+        /// Number of seats paid for
+        quantity: u32,
+    }
+
+    /// Per-merchant spending cap state (Enc<Shared, SpendingCapState>)
+    SpendingCapState => SpendingCapStateCiphertexts {
+        /// Maximum spend per window
+        cap: u64,
+        window_seconds: i64,
+        /// Start of the current window
+        window_start: i64,
+        /// Amount charged in the current window
+        spent: u64,
+    }
+
+    /// Per-plan analytics (Enc<Shared, PlanStatsState>, merchant key)
+    PlanStatsState => PlanStatsStateCiphertexts {
+        active_subscribers: u64,
+        /// Monthly (30-day) recurring revenue
+        mrr: u64,
+        period_start: i64,
+        new_subscribers: u64,
+        churned_subscribers: u64,
+        previous_new_subscribers: u64,
+        previous_churned_subscribers: u64,
+    }
+
+    /// Pool yield accounting (Enc<Mxe, PoolYieldState>)
+    PoolYieldState => PoolYieldStateCiphertexts {
+        /// Cumulative yield per unit of settled user balance, fixed-point
+        index: u128,
+        /// Sum of user balances as of their last settlement
+        user_liabilities: u64,
+    }
+
+    /// Charges recorded on a subscription (Enc<Mxe, ChargeRecordState>)
+    ChargeRecordState => ChargeRecordStateCiphertexts {
+        /// Charged for the latest paid billing period, seat changes included
+        last_charge: u64,
+        /// Smallest charge of any paid billing period
+        min_charge: u64,
+    }
+
+    /// Balance runway summary (Enc<Shared, BalanceRunway>)
+    BalanceRunway => BalanceRunwayCiphertexts {
+        monthly_spend: u64,
+        /// First payment the balance cannot cover (0 = none)
+        first_failure_date: i64,
+    }
+
+    /// User ledger state layout 1, before yield accounting
+    UserLedgerStateV1 => UserLedgerStateV1Ciphertexts {
+        balance: u64,
+        subscription_count: u64,
+    }
+
+    /// Merchant ledger state layout 1, before disputes
+    MerchantLedgerStateV1 => MerchantLedgerStateV1Ciphertexts {
+        balance: u64,
+        total_claimed: u64,
+    }
+
+    /// User subscription state layout 1, before seats
+    UserSubscriptionStateV1 => UserSubscriptionStateV1Ciphertexts {
+        plan: [u128; 2],
+        status: u8,
+        next_payment_date: i64,
+        start_date: i64,
+    }
+}
+
+/// Fail to compile unless a circuit struct has exactly the fields, with the
+/// same types and in the same order, as its shared layout.
+///
+/// Arcis serializes a struct in declaration order, so the circuit struct must
+/// be `#[repr(C)]`: its field offsets then follow declaration order, and the
+/// listed fields are checked to have strictly increasing offsets.
+///
+/// ```ignore
+/// state_layouts::assert_layout!(circuits::MerchantLedgerState => state_layouts::MerchantLedgerState {
+///     balance, total_claimed, frozen,
+/// });
+/// ```
+#[macro_export]
+macro_rules! assert_layout {
+    ($circuit:path => $layout:path { $first:ident $(, $field:ident)* $(,)? }) => {
+        const _: fn($circuit) -> $layout = |state| {
+            let $circuit { $first, $($field),* } = state;
+            $layout { $first, $($field),* }
+        };
+
+        const _: () = $crate::assert_layout!(@order $circuit; $first $(, $field)*);
+    };
+    (@order $circuit:path; $last:ident) => { () };
+    (@order $circuit:path; $previous:ident, $next:ident $(, $rest:ident)*) => {{
+        assert!(
+            ::core::mem::offset_of!($circuit, $previous) < ::core::mem::offset_of!($circuit, $next),
+            concat!(
+                "`", stringify!($next), "` is declared before `", stringify!($previous),
+                "` in the circuit struct",
+            ),
+        );
+        $crate::assert_layout!(@order $circuit; $next $(, $rest)*)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ciphertext(tag: u8) -> [u8; 32] {
+        [tag; 32]
+    }
+
+    #[test]
+    fn layout_lengths() {
+        assert_eq!(UserLedgerStateCiphertexts::LEN, 4);
+        assert_eq!(MerchantLedgerStateCiphertexts::LEN, 3);
+        assert_eq!(UserSubscriptionStateCiphertexts::LEN, 6);
+        assert_eq!(UserLedgerStateV1Ciphertexts::LEN, 2);
+        assert_eq!(MerchantLedgerStateV1Ciphertexts::LEN, 2);
+        assert_eq!(UserSubscriptionStateV1Ciphertexts::LEN, 5);
+        assert_eq!(SpendingCapStateCiphertexts::LEN, 4);
+        assert_eq!(PlanStatsStateCiphertexts::LEN, 7);
+        assert_eq!(PoolYieldStateCiphertexts::LEN, 2);
+        assert_eq!(ChargeRecordStateCiphertexts::LEN, 2);
+        assert_eq!(BalanceRunwayCiphertexts::LEN, 2);
+    }
+
+    #[test]
+    fn ciphertexts_round_trip_in_layout_order() {
+        let flat = core::array::from_fn(|i| ciphertext(i as u8));
+        let state = UserSubscriptionStateCiphertexts::from_ciphertexts(&flat);
+        assert_eq!(state.plan, [ciphertext(0), ciphertext(1)]);
+        assert_eq!(state.status, ciphertext(2));
+        assert_eq!(state.next_payment_date, ciphertext(3));
+        assert_eq!(state.start_date, ciphertext(4));
+        assert_eq!(state.quantity, ciphertext(5));
+        assert_eq!(state.to_ciphertexts(), flat);
+    }
+
+    #[test]
+    fn plaintexts_decode_in_layout_order() {
+        let state = UserLedgerState::from_plaintexts(&[1_000, 2, 50, 1]);
+        assert_eq!(
+            state,
+            UserLedgerState {
+                principal: 1_000,
+                subscription_count: 2,
+                accrued_yield: 50,
+                principal_locked: true,
+            }
+        );
+
+        let subscription = UserSubscriptionState::from_plaintexts(&[7, 8, 4, 1_700_000_000, 0, 3]);
+        assert_eq!(subscription.plan, [7, 8]);
+        assert_eq!(subscription.status, 4);
+        assert_eq!(subscription.next_payment_date, 1_700_000_000);
+        assert_eq!(subscription.quantity, 3);
+    }
+}
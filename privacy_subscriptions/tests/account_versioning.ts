@@ -0,0 +1,148 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { createMint } from "@solana/spl-token";
+import { PrivacySubscriptions } from "../target/types/privacy_subscriptions";
+import { expect } from "chai";
+
+const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
+const MERCHANT_SEED = Buffer.from("merchant");
+const MERCHANT_LEDGER_SEED = Buffer.from("merchant_ledger");
+
+// Fixtures loaded by Anchor.toml, in the layout shipped before account versioning
+const LEGACY_USER_SUBSCRIPTION = new PublicKey("CzaXaeFqZAXuVPDTabUGoy6t6nwBNS6RgqqFdspXBKD4");
+const LEGACY_SUBSCRIPTION_USER = new PublicKey("8VgeNdB647phF7G2BdsBGRDfqRYyokeXq9CpeyRMg17v");
+const LEGACY_PROTOCOL_POOL = new PublicKey("FkxS3DSucrb1NtR92GR2MNTFgjchqUTLcfn9HFjHtbVg");
+const LEGACY_POOL_MINT = new PublicKey("98twdboxPLFhYm1k8nAiFi5mHySswtikWDcyHRDDagyw");
+
+describe("Account versioning", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace
+    .PrivacySubscriptions as Program<PrivacySubscriptions>;
+
+  let protocolConfig: PublicKey;
+
+  before(async () => {
+    // The protocol config may already exist from another suite
+    [protocolConfig] = PublicKey.findProgramAddressSync(
+      [PROTOCOL_CONFIG_SEED],
+      program.programId,
+    );
+    if (!(await provider.connection.getAccountInfo(protocolConfig))) {
+      await program.methods
+        .initializeProtocol(0)
+        .accounts({ authority: payer.publicKey })
+        .rpc({ commitment: "confirmed" });
+    }
+  });
+
+  const expectMigrationError = async (account: PublicKey, error: string) => {
+    try {
+      await program.methods
+        .migrateAccount()
+        .accounts({ payer: payer.publicKey, account })
+        .rpc({ commitment: "confirmed" });
+      expect.fail(`expected ${error}`);
+    } catch (err) {
+      expect(String(err)).to.include(error);
+    }
+  };
+
+  it("creates accounts at the current version", async () => {
+    const config = await program.account.protocolConfig.fetch(protocolConfig);
+    expect(config.accountVersion).to.equal(1);
+
+    const wallet = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      wallet.publicKey,
+      LAMPORTS_PER_SOL,
+    );
+    await provider.connection.confirmTransaction(sig, "confirmed");
+
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    await program.methods
+      .registerMerchant("Versioned Merchant", Array.from(Keypair.generate().publicKey.toBytes()))
+      .accounts({ wallet: wallet.publicKey, mint })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+
+    const [merchant] = PublicKey.findProgramAddressSync(
+      [MERCHANT_SEED, wallet.publicKey.toBuffer()],
+      program.programId,
+    );
+    const [merchantLedger] = PublicKey.findProgramAddressSync(
+      [MERCHANT_LEDGER_SEED, merchant.toBuffer(), mint.toBuffer()],
+      program.programId,
+    );
+    expect((await program.account.merchant.fetch(merchant)).accountVersion).to.equal(1);
+    expect(
+      (await program.account.merchantLedger.fetch(merchantLedger)).accountVersion,
+    ).to.equal(1);
+
+    await expectMigrationError(merchant, "AccountAlreadyCurrent");
+    await expectMigrationError(merchantLedger, "AccountAlreadyCurrent");
+  });
+
+  const migrate = (account: PublicKey) =>
+    program.methods
+      .migrateAccount()
+      .accounts({ payer: payer.publicKey, account })
+      .rpc({ commitment: "confirmed" });
+
+  it("grows a legacy subscription and keeps its fields in place", async () => {
+    const before = await provider.connection.getAccountInfo(LEGACY_USER_SUBSCRIPTION);
+    expect(before.data.length).to.equal(257);
+
+    await migrate(LEGACY_USER_SUBSCRIPTION);
+
+    const after = await provider.connection.getAccountInfo(LEGACY_USER_SUBSCRIPTION);
+    expect(after.data.length).to.equal(
+      program.account.userSubscription.size,
+    );
+    expect(after.data.subarray(0, 257)).to.deep.equal(before.data);
+    expect(after.lamports).to.equal(
+      await provider.connection.getMinimumBalanceForRentExemption(after.data.length),
+    );
+
+    const subscription = await program.account.userSubscription.fetch(
+      LEGACY_USER_SUBSCRIPTION,
+    );
+    expect(subscription.user.equals(LEGACY_SUBSCRIPTION_USER)).to.be.true;
+    expect(subscription.subscriptionIndex.toNumber()).to.equal(7);
+    expect(subscription.encryptedStartDate).to.deep.equal(new Array(32).fill(0x55));
+    expect(subscription.nonce.toNumber()).to.equal(9);
+    expect(subscription.bump).to.equal(254);
+    expect(subscription.encryptedQuantity).to.deep.equal(new Array(32).fill(0));
+    expect(subscription.openDisputes).to.equal(0);
+    expect(subscription.paymentMint.equals(PublicKey.default)).to.be.true;
+    expect(subscription.stateVersion).to.equal(0);
+    expect(subscription.accountVersion).to.equal(2);
+    expect(subscription.chargesNonce.toNumber()).to.equal(0);
+    expect(subscription.prepaidCycles).to.equal(0);
+
+    await expectMigrationError(LEGACY_USER_SUBSCRIPTION, "AccountAlreadyCurrent");
+  });
+
+  it("grows a legacy pool with unseeded liabilities", async () => {
+    await migrate(LEGACY_PROTOCOL_POOL);
+
+    const pool = await program.account.protocolPool.fetch(LEGACY_PROTOCOL_POOL);
+    expect(pool.mint.equals(LEGACY_POOL_MINT)).to.be.true;
+    expect(pool.bump).to.equal(253);
+    expect(pool.liabilitiesSeeded).to.be.false;
+    expect(pool.liabilitiesNonce.toNumber()).to.equal(0);
+    expect(pool.accountVersion).to.equal(1);
+
+    await expectMigrationError(LEGACY_PROTOCOL_POOL, "AccountAlreadyCurrent");
+  });
+
+  it("refuses to migrate current accounts", async () => {
+    await expectMigrationError(protocolConfig, "AccountAlreadyCurrent");
+  });
+
+  it("refuses accounts the program does not own", async () => {
+    await expectMigrationError(payer.publicKey, "UnsupportedAccountMigration");
+  });
+});
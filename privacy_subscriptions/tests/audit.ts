@@ -0,0 +1,229 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { deserializeLE } from "@arcium-hq/client";
+import { randomBytes } from "crypto";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  subscribe,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const AUDIT_GRANT_SEED = Buffer.from("audit_grant");
+const DAY = 86400;
+
+describe("Audit access", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  type Event = anchor.IdlEvents<Subly["idl"]>;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let user: Keypair;
+  let userLedger: PublicKey;
+  let userSubscription: PublicKey;
+  let auditor: Keypair;
+  let auditorKey: EncryptionKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "subscribe_v2",
+      "disclose_user_ledger_v2",
+      "disclose_merchant_ledger_v2",
+      "disclose_subscription_v2",
+    ]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    const plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    const userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    userSubscription = await subscribe(program, user, userKey, mint, plan, 0);
+
+    auditor = await fundedWallet(provider);
+    auditorKey = await EncryptionKey.create(program);
+  });
+
+  const chainTime = async () =>
+    provider.connection.getBlockTime(await provider.connection.getSlot("confirmed"));
+
+  const auditGrant = (owner: Keypair, target: PublicKey) =>
+    pda(program, [
+      AUDIT_GRANT_SEED,
+      owner.publicKey.toBuffer(),
+      auditor.publicKey.toBuffer(),
+      target.toBuffer(),
+    ]);
+
+  const grant = async (owner: Keypair, target: PublicKey, expiresAt: number) => {
+    await program.methods
+      .grantAuditAccess(Array.from(auditorKey.publicKey), new anchor.BN(expiresAt))
+      .accountsPartial({
+        owner: owner.publicKey,
+        auditor: auditor.publicKey,
+        target,
+        auditGrant: auditGrant(owner, target),
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+    return auditGrant(owner, target);
+  };
+
+  const revoke = (owner: Keypair, grantAccount: PublicKey) =>
+    program.methods
+      .revokeAuditAccess()
+      .accountsPartial({ owner: owner.publicKey, auditGrant: grantAccount })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
+  /// Queue a disclosure and return the values it re-encrypts to the auditor
+  const disclose = async (
+    queue: (computationOffset: anchor.BN, auditorNonce: anchor.BN) => Promise<string>,
+  ) => {
+    const computationOffset = newComputationOffset();
+    const auditorNonce = new anchor.BN(deserializeLE(randomBytes(16)).toString());
+
+    let listenerId: number;
+    const event = new Promise<Event["auditDisclosed"]>((resolve) => {
+      listenerId = program.addEventListener("auditDisclosed", resolve);
+    });
+    try {
+      await queue(computationOffset, auditorNonce);
+      await awaitComputation(program, computationOffset);
+      const { ciphertexts, nonce } = await event;
+      return auditorKey.decrypt(ciphertexts, nonce);
+    } finally {
+      await program.removeEventListener(listenerId);
+    }
+  };
+
+  const discloseUserLedger = (requester: Keypair, grantAccount: PublicKey) =>
+    disclose((computationOffset, auditorNonce) =>
+      program.methods
+        .discloseUserLedger(computationOffset, auditorNonce)
+        .accountsPartial({
+          requester: requester.publicKey,
+          auditGrant: grantAccount,
+          userLedger,
+          ...computationAccounts(program, "disclose_user_ledger_v2", computationOffset),
+        })
+        .signers([requester])
+        .rpc({ commitment: "confirmed" }),
+    );
+
+  const discloseMerchantLedger = (requester: Keypair, grantAccount: PublicKey) =>
+    disclose((computationOffset, auditorNonce) =>
+      program.methods
+        .discloseMerchantLedger(computationOffset, auditorNonce)
+        .accountsPartial({
+          requester: requester.publicKey,
+          auditGrant: grantAccount,
+          merchant: merchant.merchant,
+          merchantLedger: merchant.merchantLedger,
+          ...computationAccounts(program, "disclose_merchant_ledger_v2", computationOffset),
+        })
+        .signers([requester])
+        .rpc({ commitment: "confirmed" }),
+    );
+
+  const discloseSubscription = (requester: Keypair, grantAccount: PublicKey) =>
+    disclose((computationOffset, auditorNonce) =>
+      program.methods
+        .discloseSubscription(computationOffset, auditorNonce)
+        .accountsPartial({
+          requester: requester.publicKey,
+          auditGrant: grantAccount,
+          userSubscription,
+          ...computationAccounts(program, "disclose_subscription_v2", computationOffset),
+        })
+        .signers([requester])
+        .rpc({ commitment: "confirmed" }),
+    );
+
+  let ledgerGrant: PublicKey;
+
+  it("rejects grants that have already expired", async () => {
+    await expectError(() => grant(user, userLedger, (await chainTime()) - 1), "InvalidExpiry");
+  });
+
+  it("records who may read which account until when", async () => {
+    const expiresAt = (await chainTime()) + DAY;
+    ledgerGrant = await grant(user, userLedger, expiresAt);
+
+    const account = await program.account.auditGrant.fetch(ledgerGrant);
+    expect(account.owner.equals(user.publicKey)).to.be.true;
+    expect(account.auditor.equals(auditor.publicKey)).to.be.true;
+    expect(account.target.equals(userLedger)).to.be.true;
+    expect(account.auditorEncryptionPubkey).to.deep.equal(Array.from(auditorKey.publicKey));
+    expect(account.expiresAt.toNumber()).to.equal(expiresAt);
+  });
+
+  it("discloses a user ledger to its auditor only", async () => {
+    const stranger = await fundedWallet(provider);
+    await expectError(() => discloseUserLedger(stranger, ledgerGrant), "Unauthorized");
+
+    const [principal, subscriptionCount, accruedYield, principalLocked] =
+      await discloseUserLedger(auditor, ledgerGrant);
+    expect(principal).to.equal(BigInt(900));
+    expect(subscriptionCount).to.equal(BigInt(1));
+    expect(accruedYield).to.equal(BigInt(0));
+    expect(principalLocked).to.equal(BigInt(0));
+  });
+
+  it("discloses only the account a grant names", async () => {
+    await expectError(() => discloseSubscription(auditor, ledgerGrant), "Unauthorized");
+
+    const subscriptionGrant = await grant(user, userSubscription, (await chainTime()) + DAY);
+    const [, , status, , , quantity] = await discloseSubscription(auditor, subscriptionGrant);
+    expect(status).to.equal(BigInt(0));
+    expect(quantity).to.equal(BigInt(1));
+  });
+
+  it("discloses a merchant ledger until the grant expires", async () => {
+    const expiresAt = (await chainTime()) + 5;
+    const merchantGrant = await grant(merchant.wallet, merchant.merchantLedger, expiresAt);
+
+    const [balance, totalClaimed, frozen] = await discloseMerchantLedger(auditor, merchantGrant);
+    expect(balance).to.equal(BigInt(100));
+    expect(totalClaimed).to.equal(BigInt(0));
+    expect(frozen).to.equal(BigInt(0));
+
+    while ((await chainTime()) <= expiresAt) {
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+    }
+    await expectError(
+      () => discloseMerchantLedger(auditor, merchantGrant),
+      "AuditGrantExpired",
+    );
+  });
+
+  it("lets only the owner revoke a grant", async () => {
+    await expectError(() => revoke(auditor, ledgerGrant), "ConstraintSeeds");
+
+    await revoke(user, ledgerGrant);
+    expect(await provider.connection.getAccountInfo(ledgerGrant)).to.be.null;
+    await expectError(() => discloseUserLedger(auditor, ledgerGrant), "AccountNotInitialized");
+  });
+});
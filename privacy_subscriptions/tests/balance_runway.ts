@@ -0,0 +1,101 @@
+import * as anchor from "@coral-xyz/anchor";
+import { AccountMeta, Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  registerMerchant,
+  subscribe,
+  subscriptionState,
+  Subly,
+} from "./helpers";
+
+describe("Balance runway", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  type Event = anchor.IdlEvents<Subly["idl"]>;
+
+  let plan: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userLedger: PublicKey;
+  let userSubscription: PublicKey;
+  let otherSubscription: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "subscribe_v2", "balance_runway_v2"]);
+    const { mint, poolTokenAccount } = await createPool(program, payer);
+
+    const merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+
+    // 350 pays the first charge and two renewals; the third renewal fails
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 350);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 350);
+    userSubscription = await subscribe(program, user, userKey, mint, plan, 0);
+
+    const other = await fundedWallet(provider);
+    const otherKey = await EncryptionKey.create(program);
+    const otherTokens = await fundTokens(provider, payer, mint, other.publicKey, 100);
+    await deposit(program, other, otherKey, mint, poolTokenAccount, otherTokens, 100);
+    otherSubscription = await subscribe(program, other, otherKey, mint, plan, 0);
+  });
+
+  const queryRunway = async (subscriptions: PublicKey[]) => {
+    const computationOffset = newComputationOffset();
+    const slots: AccountMeta[] = subscriptions.flatMap((pubkey) => [
+      { pubkey, isSigner: false, isWritable: false },
+      { pubkey: plan, isSigner: false, isWritable: false },
+    ]);
+    await program.methods
+      .queryBalanceRunway(computationOffset)
+      .accountsPartial({
+        user: user.publicKey,
+        userLedger,
+        ...computationAccounts(program, "balance_runway_v2", computationOffset),
+      })
+      .remainingAccounts(slots)
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects subscriptions that belong to another wallet", async () => {
+    await expectError(() => queryRunway([otherSubscription]), "Unauthorized");
+  });
+
+  it("emits the monthly spend and the first renewal that would fail", async () => {
+    let listenerId: number;
+    const event = new Promise<Event["balanceRunwayComputed"]>((resolve) => {
+      listenerId = program.addEventListener("balanceRunwayComputed", resolve);
+    });
+
+    await awaitComputation(program, await queryRunway([userSubscription]));
+    const { encryptedMonthlySpend, encryptedFirstFailureDate, nonce } = await event;
+    await program.removeEventListener(listenerId);
+
+    const [monthlySpend, firstFailureDate] = userKey.decrypt(
+      [encryptedMonthlySpend, encryptedFirstFailureDate],
+      nonce,
+    );
+    const { nextPaymentDate } = await subscriptionState(program, userKey, userSubscription);
+    expect(monthlySpend).to.equal(BigInt(100));
+    expect(firstFailureDate).to.equal(nextPaymentDate + BigInt(2 * 30 * 86_400));
+  });
+});
@@ -0,0 +1,226 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  newComputationOffset,
+  pda,
+  PROTOCOL_CONFIG_SEED,
+  registerMerchant,
+  subscribe,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const DISPUTE_SEED = Buffer.from("dispute");
+const DISPUTE_STATUS_OPEN = 1;
+const DISPUTE_STATUS_REFUNDED = 2;
+const DISPUTE_STATUS_RELEASED = 3;
+const DISPUTE_STATUS_REJECTED = 4;
+
+describe("Disputes", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let plan: PublicKey;
+  let arbiter: Keypair;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userLedger: PublicKey;
+  let subscriptions: PublicKey[];
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "subscribe_v2",
+      "open_dispute_v2",
+      "resolve_dispute_refund_v2",
+      "resolve_dispute_release_v2",
+    ]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    arbiter = await fundedWallet(provider);
+    await program.methods
+      .setArbiter(arbiter.publicKey)
+      .accounts({ authority: payer.publicKey })
+      .rpc({ commitment: "confirmed" });
+
+    // Two subscriptions, each charged once: the merchant holds 200
+    merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    subscriptions = [
+      await subscribe(program, user, userKey, mint, plan, 0),
+      await subscribe(program, user, userKey, mint, plan, 1),
+    ];
+  });
+
+  after(async () => {
+    await program.methods
+      .setArbiter(payer.publicKey)
+      .accounts({ authority: payer.publicKey })
+      .rpc({ commitment: "confirmed" });
+  });
+
+  const dispute = (userSubscription: PublicKey, billingPeriod: number) =>
+    pda(program, [
+      DISPUTE_SEED,
+      userSubscription.toBuffer(),
+      new anchor.BN(billingPeriod).toArrayLike(Buffer, "le", 8),
+    ]);
+
+  /// Balance and frozen amount of the merchant ledger
+  const merchantFunds = async () => {
+    const ledger = await program.account.merchantLedger.fetch(merchant.merchantLedger);
+    const [balance, , frozen] = merchant.key.decrypt(
+      [ledger.encryptedBalance, ledger.encryptedTotalClaimed, ledger.encryptedFrozen],
+      ledger.nonce,
+    );
+    return { balance, frozen };
+  };
+
+  const openDispute = async (
+    userSubscription: PublicKey,
+    billingPeriod: number,
+    amount: number,
+  ) => {
+    const computationOffset = newComputationOffset();
+    // The amount is encrypted to the subscription's key
+    const { ciphertext, nonce } = userKey.encrypt(BigInt(amount));
+    await program.methods
+      .openDispute(computationOffset, new anchor.BN(billingPeriod), ciphertext, nonce)
+      .accountsPartial({
+        user: user.publicKey,
+        mint,
+        subscriptionPlan: plan,
+        merchantLedger: merchant.merchantLedger,
+        userSubscription,
+        dispute: dispute(userSubscription, billingPeriod),
+        ...computationAccounts(program, "open_dispute_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+    return dispute(userSubscription, billingPeriod);
+  };
+
+  const resolve = async (
+    method: "resolveDisputeRefund" | "resolveDisputeRelease",
+    compDefName: string,
+    signer: Keypair,
+    userSubscription: PublicKey,
+  ) => {
+    const computationOffset = newComputationOffset();
+    await program.methods[method](computationOffset)
+      .accountsPartial({
+        arbiter: signer.publicKey,
+        dispute: dispute(userSubscription, 0),
+        userSubscription,
+        merchantLedger: merchant.merchantLedger,
+        ...(method === "resolveDisputeRefund" ? { userLedger } : {}),
+        ...computationAccounts(program, compDefName, computationOffset),
+      })
+      .signers([signer])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const openDisputes = async (userSubscription: PublicKey) =>
+    (await program.account.userSubscription.fetch(userSubscription)).openDisputes;
+
+  it("lets only the protocol authority appoint the arbiter", async () => {
+    await expectError(
+      () =>
+        program.methods
+          .setArbiter(user.publicKey)
+          .accounts({ authority: user.publicKey })
+          .signers([user])
+          .rpc({ commitment: "confirmed" }),
+      "Unauthorized",
+    );
+    const config = await program.account.protocolConfig.fetch(
+      pda(program, [PROTOCOL_CONFIG_SEED]),
+    );
+    expect(config.arbiter.equals(arbiter.publicKey)).to.be.true;
+  });
+
+  it("rejects a dispute over a period that was never charged", async () => {
+    const rejected = await openDispute(subscriptions[0], 1, 50);
+
+    expect((await program.account.dispute.fetch(rejected)).status).to.equal(
+      DISPUTE_STATUS_REJECTED,
+    );
+    expect(await openDisputes(subscriptions[0])).to.equal(0);
+    expect(await merchantFunds()).to.deep.equal({ balance: BigInt(200), frozen: BigInt(0) });
+  });
+
+  it("freezes the disputed part of a charge", async () => {
+    const opened = await openDispute(subscriptions[0], 0, 40);
+
+    expect((await program.account.dispute.fetch(opened)).status).to.equal(DISPUTE_STATUS_OPEN);
+    expect(await openDisputes(subscriptions[0])).to.equal(1);
+    expect(await merchantFunds()).to.deep.equal({ balance: BigInt(160), frozen: BigInt(40) });
+  });
+
+  it("lets only the arbiter resolve a dispute", async () => {
+    await expectError(
+      () => resolve("resolveDisputeRefund", "resolve_dispute_refund_v2", user, subscriptions[0]),
+      "Unauthorized",
+    );
+  });
+
+  it("refunds the frozen amount to the user", async () => {
+    await awaitComputation(
+      program,
+      await resolve("resolveDisputeRefund", "resolve_dispute_refund_v2", arbiter, subscriptions[0]),
+    );
+
+    expect((await program.account.dispute.fetch(dispute(subscriptions[0], 0))).status).to.equal(
+      DISPUTE_STATUS_REFUNDED,
+    );
+    expect(await openDisputes(subscriptions[0])).to.equal(0);
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(840));
+    expect(await merchantFunds()).to.deep.equal({ balance: BigInt(160), frozen: BigInt(0) });
+    await expectError(
+      () => resolve("resolveDisputeRefund", "resolve_dispute_refund_v2", arbiter, subscriptions[0]),
+      "DisputeNotOpen",
+    );
+  });
+
+  it("releases the frozen amount back to the merchant", async () => {
+    const release = (signer: Keypair) =>
+      resolve("resolveDisputeRelease", "resolve_dispute_release_v2", signer, subscriptions[1]);
+    await openDispute(subscriptions[1], 0, 30);
+    expect(await merchantFunds()).to.deep.equal({ balance: BigInt(130), frozen: BigInt(30) });
+    await expectError(() => release(merchant.wallet), "Unauthorized");
+
+    await awaitComputation(program, await release(arbiter));
+
+    expect((await program.account.dispute.fetch(dispute(subscriptions[1], 0))).status).to.equal(
+      DISPUTE_STATUS_RELEASED,
+    );
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(840));
+    expect(await merchantFunds()).to.deep.equal({ balance: BigInt(160), frozen: BigInt(0) });
+    await expectError(() => release(arbiter), "DisputeNotOpen");
+  });
+});
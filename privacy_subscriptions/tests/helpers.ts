@@ -0,0 +1,483 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  AddressLookupTableProgram,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+} from "@solana/web3.js";
+import {
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import {
+  awaitComputationFinalization,
+  deserializeLE,
+  getArciumAccountBaseSeed,
+  getArciumEnv,
+  getArciumProgramId,
+  getClusterAccAddress,
+  getCompDefAccAddress,
+  getCompDefAccOffset,
+  getComputationAccAddress,
+  getExecutingPoolAccAddress,
+  getLookupTableAddress,
+  getMempoolAccAddress,
+  getMXEAccAddress,
+  getMXEPublicKey,
+  RescueCipher,
+  x25519,
+} from "@arcium-hq/client";
+import { randomBytes } from "crypto";
+import { expect } from "chai";
+import { PrivacySubscriptions } from "../target/types/privacy_subscriptions";
+
+// Shared setup for the suites that queue MPC computations. Circuits are
+// fetched from CIRCUIT_SOURCE_BASE_URL, as in scripts/initialize.ts.
+
+export const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
+export const PROTOCOL_POOL_SEED = Buffer.from("protocol_pool");
+export const USER_LEDGER_SEED = Buffer.from("user_ledger");
+export const MERCHANT_SEED = Buffer.from("merchant");
+export const MERCHANT_LEDGER_SEED = Buffer.from("merchant_ledger");
+export const SUBSCRIPTION_PLAN_SEED = Buffer.from("subscription_plan");
+export const USER_SUBSCRIPTION_SEED = Buffer.from("user_subscription");
+export const CIRCUIT_VERSION_SEED = Buffer.from("circuit_version");
+
+const CIRCUIT_VERSION = 2;
+const CIRCUIT_SOURCE_BASE_URL =
+  process.env.CIRCUIT_SOURCE_BASE_URL ||
+  "https://raw.githubusercontent.com/SublyFi/circuits/main";
+
+export type Subly = Program<PrivacySubscriptions>;
+
+export function pda(program: Subly, seeds: Buffer[]): PublicKey {
+  return PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+}
+
+export async function fundedWallet(
+  provider: anchor.AnchorProvider,
+): Promise<Keypair> {
+  const wallet = Keypair.generate();
+  const sig = await provider.connection.requestAirdrop(
+    wallet.publicKey,
+    2 * LAMPORTS_PER_SOL,
+  );
+  await provider.connection.confirmTransaction(sig, "confirmed");
+  return wallet;
+}
+
+/// Run `fn` and check it fails with `error` (an error code name or message)
+export async function expectError(
+  fn: () => Promise<unknown>,
+  error: string,
+): Promise<void> {
+  try {
+    await fn();
+  } catch (err) {
+    expect(String(err)).to.include(error);
+    return;
+  }
+  expect.fail(`expected ${error}`);
+}
+
+export async function ensureProtocol(
+  program: Subly,
+  authority: Keypair,
+): Promise<PublicKey> {
+  const provider = program.provider as anchor.AnchorProvider;
+  // The protocol config may already exist from another suite
+  const protocolConfig = pda(program, [PROTOCOL_CONFIG_SEED]);
+  if (!(await provider.connection.getAccountInfo(protocolConfig))) {
+    await program.methods
+      .initializeProtocol(0)
+      .accounts({ authority: authority.publicKey })
+      .rpc({ commitment: "confirmed" });
+  }
+  const circuitVersion = pda(program, [
+    CIRCUIT_VERSION_SEED,
+    Buffer.from([CIRCUIT_VERSION]),
+  ]);
+  if (!(await provider.connection.getAccountInfo(circuitVersion))) {
+    await program.methods
+      .registerCircuitVersion(CIRCUIT_VERSION, CIRCUIT_SOURCE_BASE_URL)
+      .accountsPartial({ authority: authority.publicKey, circuitVersion })
+      .rpc({ commitment: "confirmed" });
+  }
+  return protocolConfig;
+}
+
+/// Initialize the computation definitions a suite needs, skipping existing ones
+export async function ensureCompDefs(
+  program: Subly,
+  payer: Keypair,
+  names: string[],
+): Promise<void> {
+  const provider = program.provider as anchor.AnchorProvider;
+  for (const name of names) {
+    const compDefAccount = PublicKey.findProgramAddressSync(
+      [
+        getArciumAccountBaseSeed("ComputationDefinitionAccount"),
+        program.programId.toBuffer(),
+        getCompDefAccOffset(name),
+      ],
+      getArciumProgramId(),
+    )[0];
+    if (await provider.connection.getAccountInfo(compDefAccount)) {
+      continue;
+    }
+    // "set_spending_cap_v2" -> initSetSpendingCapCompDef
+    const method =
+      "init" +
+      name
+        .replace(/_v2$/, "")
+        .split("_")
+        .map((part) => part[0].toUpperCase() + part.slice(1))
+        .join("") +
+      "CompDef";
+    await (program.methods as any)
+      [method]()
+      .accounts({
+        payer: payer.publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        compDefAccount,
+        addressLookupTable: getLookupTableAddress(program.programId),
+        lutProgram: AddressLookupTableProgram.programId,
+        circuitVersion: pda(program, [
+          CIRCUIT_VERSION_SEED,
+          Buffer.from([CIRCUIT_VERSION]),
+        ]),
+      })
+      .rpc({ commitment: "confirmed" });
+  }
+}
+
+/// A mint with an initialized pool
+export async function createPool(
+  program: Subly,
+  authority: Keypair,
+): Promise<{ mint: PublicKey; protocolPool: PublicKey; poolTokenAccount: PublicKey }> {
+  const provider = program.provider as anchor.AnchorProvider;
+  const mint = await createMint(
+    provider.connection,
+    authority,
+    authority.publicKey,
+    null,
+    6,
+  );
+  const poolTokenAccount = Keypair.generate();
+  await program.methods
+    .initializePool()
+    .accounts({
+      authority: authority.publicKey,
+      mint,
+      poolTokenAccount: poolTokenAccount.publicKey,
+    })
+    .signers([poolTokenAccount])
+    .rpc({ commitment: "confirmed" });
+  return {
+    mint,
+    protocolPool: pda(program, [PROTOCOL_POOL_SEED, mint.toBuffer()]),
+    poolTokenAccount: poolTokenAccount.publicKey,
+  };
+}
+
+export async function fundTokens(
+  provider: anchor.AnchorProvider,
+  mintAuthority: Keypair,
+  mint: PublicKey,
+  owner: PublicKey,
+  amount: number,
+): Promise<PublicKey> {
+  const account = await getOrCreateAssociatedTokenAccount(
+    provider.connection,
+    mintAuthority,
+    mint,
+    owner,
+  );
+  await mintTo(
+    provider.connection,
+    mintAuthority,
+    mint,
+    account.address,
+    mintAuthority,
+    amount,
+  );
+  return account.address;
+}
+
+/// Accounts every queue instruction takes for `compDefName`
+export function computationAccounts(
+  program: Subly,
+  compDefName: string,
+  computationOffset: anchor.BN,
+) {
+  const arciumEnv = getArciumEnv();
+  return {
+    computationAccount: getComputationAccAddress(
+      arciumEnv.arciumClusterOffset,
+      computationOffset,
+    ),
+    clusterAccount: getClusterAccAddress(arciumEnv.arciumClusterOffset),
+    mxeAccount: getMXEAccAddress(program.programId),
+    mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+    executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+    compDefAccount: getCompDefAccAddress(
+      program.programId,
+      Buffer.from(getCompDefAccOffset(compDefName)).readUInt32LE(),
+    ),
+  };
+}
+
+export function newComputationOffset(): anchor.BN {
+  return new anchor.BN(randomBytes(8), "hex");
+}
+
+export async function awaitComputation(
+  program: Subly,
+  computationOffset: anchor.BN,
+): Promise<string> {
+  return awaitComputationFinalization(
+    program.provider as anchor.AnchorProvider,
+    computationOffset,
+    program.programId,
+    "confirmed",
+  );
+}
+
+/// An x25519 key pair and the Rescue cipher it shares with the MXE
+export class EncryptionKey {
+  readonly publicKey: Uint8Array;
+  cipher: RescueCipher;
+
+  private constructor(readonly privateKey: Uint8Array) {
+    this.publicKey = x25519.getPublicKey(privateKey);
+  }
+
+  /// A fresh key, or the key of `privateKey` (e.g. one a fixture was written with)
+  static async create(program: Subly, privateKey?: Uint8Array): Promise<EncryptionKey> {
+    const key = new EncryptionKey(privateKey ?? x25519.utils.randomSecretKey());
+    const mxePublicKey = await getMXEPublicKey(
+      program.provider as anchor.AnchorProvider,
+      program.programId,
+    );
+    key.cipher = new RescueCipher(
+      x25519.getSharedSecret(key.privateKey, mxePublicKey),
+    );
+    return key;
+  }
+
+  /// Encrypt one value; returns the ciphertext and its nonce as program arguments
+  encrypt(value: bigint): { ciphertext: number[]; nonce: anchor.BN } {
+    const { ciphertexts, nonce } = this.encryptAll([value]);
+    return { ciphertext: ciphertexts[0], nonce };
+  }
+
+  /// Encrypt several values under one nonce (e.g. a pubkey as two u128s)
+  encryptAll(values: bigint[]): { ciphertexts: number[][]; nonce: anchor.BN } {
+    const nonce = randomBytes(16);
+    return {
+      ciphertexts: this.cipher.encrypt(values, nonce).map((c) => Array.from(c)),
+      nonce: new anchor.BN(deserializeLE(nonce).toString()),
+    };
+  }
+
+  decrypt(ciphertexts: number[][], nonce: anchor.BN): bigint[] {
+    return this.cipher.decrypt(
+      ciphertexts,
+      Uint8Array.from(nonce.toArrayLike(Buffer, "le", 16)),
+    );
+  }
+}
+
+/// Deposit `amount` into `user`'s ledger and wait for the callback
+export async function deposit(
+  program: Subly,
+  user: Keypair,
+  key: EncryptionKey,
+  mint: PublicKey,
+  poolTokenAccount: PublicKey,
+  userTokenAccount: PublicKey,
+  amount: number,
+): Promise<PublicKey> {
+  const computationOffset = newComputationOffset();
+  const { ciphertext, nonce } = key.encrypt(BigInt(amount));
+  await program.methods
+    .deposit(
+      computationOffset,
+      new anchor.BN(amount),
+      Array.from(key.publicKey),
+      ciphertext,
+      nonce,
+    )
+    .accountsPartial({
+      user: user.publicKey,
+      mint,
+      poolTokenAccount,
+      userTokenAccount,
+      screeningAttestation: null,
+      ...computationAccounts(program, "deposit_v2", computationOffset),
+    })
+    .signers([user])
+    .rpc({ skipPreflight: true, commitment: "confirmed" });
+  await awaitComputation(program, computationOffset);
+  return pda(program, [USER_LEDGER_SEED, user.publicKey.toBuffer(), mint.toBuffer()]);
+}
+
+/// Decrypted principal of a user ledger
+export async function ledgerBalance(
+  program: Subly,
+  key: EncryptionKey,
+  userLedger: PublicKey,
+): Promise<bigint> {
+  const ledger = await program.account.userLedger.fetch(userLedger);
+  return key.decrypt([ledger.encryptedBalance], ledger.nonce)[0];
+}
+
+/// Decrypted balance of a merchant ledger
+export async function merchantBalance(
+  program: Subly,
+  key: EncryptionKey,
+  merchantLedger: PublicKey,
+): Promise<bigint> {
+  const ledger = await program.account.merchantLedger.fetch(merchantLedger);
+  return key.decrypt([ledger.encryptedBalance], ledger.nonce)[0];
+}
+
+/// Decrypted state of a user subscription. Every field is decrypted together,
+/// since each ciphertext's keystream depends on its position under the nonce.
+export async function subscriptionState(
+  program: Subly,
+  key: EncryptionKey,
+  userSubscription: PublicKey,
+): Promise<{ status: number; nextPaymentDate: bigint; startDate: bigint; quantity: number }> {
+  const subscription = await program.account.userSubscription.fetch(userSubscription);
+  const [, , status, nextPaymentDate, startDate, quantity] = key.decrypt(
+    [
+      ...subscription.encryptedPlan,
+      subscription.encryptedStatus,
+      subscription.encryptedNextPaymentDate,
+      subscription.encryptedStartDate,
+      subscription.encryptedQuantity,
+    ],
+    subscription.nonce,
+  );
+  return {
+    status: Number(status),
+    nextPaymentDate,
+    startDate,
+    quantity: Number(quantity),
+  };
+}
+
+/// A pubkey as the two little-endian u128 halves the circuits compare
+export function pubkeyToU128s(pubkey: PublicKey): [bigint, bigint] {
+  const bytes = pubkey.toBuffer();
+  return [
+    deserializeLE(bytes.subarray(0, 16)),
+    deserializeLE(bytes.subarray(16, 32)),
+  ];
+}
+
+export interface TestMerchant {
+  wallet: Keypair;
+  key: EncryptionKey;
+  merchant: PublicKey;
+  merchantLedger: PublicKey;
+}
+
+export async function registerMerchant(
+  program: Subly,
+  mint: PublicKey,
+): Promise<TestMerchant> {
+  const provider = program.provider as anchor.AnchorProvider;
+  const wallet = await fundedWallet(provider);
+  const key = await EncryptionKey.create(program);
+  await program.methods
+    .registerMerchant("Test Merchant", Array.from(key.publicKey))
+    .accounts({ wallet: wallet.publicKey, mint })
+    .signers([wallet])
+    .rpc({ commitment: "confirmed" });
+  const merchant = pda(program, [MERCHANT_SEED, wallet.publicKey.toBuffer()]);
+  return {
+    wallet,
+    key,
+    merchant,
+    merchantLedger: pda(program, [MERCHANT_LEDGER_SEED, merchant.toBuffer(), mint.toBuffer()]),
+  };
+}
+
+export async function createPlan(
+  program: Subly,
+  merchant: TestMerchant,
+  mint: PublicKey,
+  planId: number,
+  price: number,
+  billingCycleDays: number,
+): Promise<PublicKey> {
+  const id = new anchor.BN(planId);
+  await program.methods
+    .createSubscriptionPlan(id, "Test Plan", new anchor.BN(price), billingCycleDays)
+    .accounts({ wallet: merchant.wallet.publicKey, mint })
+    .signers([merchant.wallet])
+    .rpc({ commitment: "confirmed" });
+  return pda(program, [
+    SUBSCRIPTION_PLAN_SEED,
+    merchant.merchant.toBuffer(),
+    id.toArrayLike(Buffer, "le", 8),
+  ]);
+}
+
+/// Subscribe `user` (who already has a ledger) to `plan` and wait for the callback
+export async function subscribe(
+  program: Subly,
+  user: Keypair,
+  key: EncryptionKey,
+  mint: PublicKey,
+  plan: PublicKey,
+  subscriptionIndex: number,
+  quantity: number = 1,
+): Promise<PublicKey> {
+  const planAccount = await program.account.subscriptionPlan.fetch(plan);
+  const encryptedPlan = key.encryptAll(pubkeyToU128s(plan));
+  const price = key.encrypt(BigInt(planAccount.price.toString()));
+  const cycle = key.encrypt(BigInt(planAccount.billingCycleDays));
+  const seats = key.encrypt(BigInt(quantity));
+  const computationOffset = newComputationOffset();
+  const index = new anchor.BN(subscriptionIndex);
+  await program.methods
+    .subscribe(
+      computationOffset,
+      index,
+      encryptedPlan.ciphertexts,
+      encryptedPlan.nonce,
+      price.ciphertext,
+      price.nonce,
+      cycle.ciphertext,
+      cycle.nonce,
+      seats.ciphertext,
+      seats.nonce,
+    )
+    .accountsPartial({
+      user: user.publicKey,
+      mint,
+      subscriptionPlan: plan,
+      merchantLedger: pda(program, [
+        MERCHANT_LEDGER_SEED,
+        planAccount.merchant.toBuffer(),
+        mint.toBuffer(),
+      ]),
+      priceFeed: null,
+      priceAccount: null,
+      ...computationAccounts(program, "subscribe_v2", computationOffset),
+    })
+    .signers([user])
+    .rpc({ skipPreflight: true, commitment: "confirmed" });
+  await awaitComputation(program, computationOffset);
+  return pda(program, [
+    USER_SUBSCRIPTION_SEED,
+    user.publicKey.toBuffer(),
+    index.toArrayLike(Buffer, "le", 8),
+  ]);
+}
@@ -0,0 +1,187 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import {
+  createMint,
+  getAccount,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { MockLending } from "../target/types/mock_lending";
+import { PrivacySubscriptions } from "../target/types/privacy_subscriptions";
+import { expect } from "chai";
+
+const RESERVE_SEED = Buffer.from("reserve");
+const POSITION_SEED = Buffer.from("position");
+const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
+const PROTOCOL_POOL_SEED = Buffer.from("protocol_pool");
+
+describe("Lending strategy (mock lending)", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const lending = anchor.workspace.MockLending as Program<MockLending>;
+  const program = anchor.workspace
+    .PrivacySubscriptions as Program<PrivacySubscriptions>;
+
+  let mint: PublicKey;
+  let reserve: PublicKey;
+  let vault: anchor.web3.Keypair;
+  let protocolPool: PublicKey;
+  let poolTokenAccount: anchor.web3.Keypair;
+  let poolPosition: PublicKey;
+
+  before(async () => {
+    mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    [reserve] = PublicKey.findProgramAddressSync(
+      [RESERVE_SEED, mint.toBuffer()],
+      lending.programId,
+    );
+    vault = anchor.web3.Keypair.generate();
+    await lending.methods
+      .initializeReserve()
+      .accounts({ payer: payer.publicKey, mint, vault: vault.publicKey })
+      .signers([vault])
+      .rpc({ commitment: "confirmed" });
+
+    // The protocol config may already exist from another suite
+    const [protocolConfig] = PublicKey.findProgramAddressSync(
+      [PROTOCOL_CONFIG_SEED],
+      program.programId,
+    );
+    if (!(await provider.connection.getAccountInfo(protocolConfig))) {
+      await program.methods
+        .initializeProtocol(0)
+        .accounts({ authority: payer.publicKey })
+        .rpc({ commitment: "confirmed" });
+    }
+
+    [protocolPool] = PublicKey.findProgramAddressSync(
+      [PROTOCOL_POOL_SEED, mint.toBuffer()],
+      program.programId,
+    );
+    poolTokenAccount = anchor.web3.Keypair.generate();
+    await program.methods
+      .initializePool()
+      .accounts({
+        authority: payer.publicKey,
+        mint,
+        poolTokenAccount: poolTokenAccount.publicKey,
+      })
+      .signers([poolTokenAccount])
+      .rpc({ commitment: "confirmed" });
+    await mintTo(
+      provider.connection,
+      payer,
+      mint,
+      poolTokenAccount.publicKey,
+      payer,
+      1_000_000,
+    );
+
+    [poolPosition] = PublicKey.findProgramAddressSync(
+      [POSITION_SEED, reserve.toBuffer(), protocolPool.toBuffer()],
+      lending.programId,
+    );
+  });
+
+  it("rejects lending programs without an adapter", async () => {
+    try {
+      await program.methods
+        .setLendingStrategy()
+        .accountsPartial({
+          authority: payer.publicKey,
+          protocolPool,
+          lendingProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected UnsupportedLendingStrategy");
+    } catch (err) {
+      expect(String(err)).to.include("UnsupportedLendingStrategy");
+    }
+  });
+
+  it("allocates pool liquidity and returns it", async () => {
+    await program.methods
+      .setLendingStrategy()
+      .accountsPartial({
+        authority: payer.publicKey,
+        protocolPool,
+        lendingProgram: lending.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const strategyAccounts = {
+      authority: payer.publicKey,
+      protocolPool,
+      poolTokenAccount: poolTokenAccount.publicKey,
+      lendingProgram: lending.programId,
+      lendingReserve: reserve,
+      lendingVault: vault.publicKey,
+      lendingPosition: poolPosition,
+    };
+
+    try {
+      await program.methods
+        .allocateToStrategy(new anchor.BN(1_000_001))
+        .accountsPartial(strategyAccounts)
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected InsufficientPoolLiquidity");
+    } catch (err) {
+      expect(String(err)).to.include("InsufficientPoolLiquidity");
+    }
+
+    await program.methods
+      .allocateToStrategy(new anchor.BN(600_000))
+      .accountsPartial(strategyAccounts)
+      .rpc({ commitment: "confirmed" });
+
+    let pool = await program.account.protocolPool.fetch(protocolPool);
+    expect(pool.strategyAllocated.toNumber()).to.equal(600_000);
+    let position = await lending.account.position.fetch(poolPosition);
+    expect(position.amount.toNumber()).to.equal(600_000);
+    let poolTokens = await getAccount(provider.connection, poolTokenAccount.publicKey);
+    expect(Number(poolTokens.amount)).to.equal(400_000);
+
+    // Interest belongs to the pool but is not principal, so it cannot be deallocated directly
+    const funder = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      payer,
+      mint,
+      payer.publicKey,
+    );
+    await mintTo(provider.connection, payer, mint, funder.address, payer, 5_000);
+    await lending.methods
+      .accrueInterest(new anchor.BN(5_000))
+      .accountsPartial({
+        funder: payer.publicKey,
+        reserve,
+        vault: vault.publicKey,
+        position: poolPosition,
+        funderTokenAccount: funder.address,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await program.methods
+        .deallocateFromStrategy(new anchor.BN(605_000))
+        .accountsPartial(strategyAccounts)
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected ExceedsStrategyAllocation");
+    } catch (err) {
+      expect(String(err)).to.include("ExceedsStrategyAllocation");
+    }
+
+    await program.methods
+      .deallocateFromStrategy(new anchor.BN(600_000))
+      .accountsPartial(strategyAccounts)
+      .rpc({ commitment: "confirmed" });
+
+    pool = await program.account.protocolPool.fetch(protocolPool);
+    expect(pool.strategyAllocated.toNumber()).to.equal(0);
+    position = await lending.account.position.fetch(poolPosition);
+    expect(position.amount.toNumber()).to.equal(5_000);
+    poolTokens = await getAccount(provider.connection, poolTokenAccount.publicKey);
+    expect(Number(poolTokens.amount)).to.equal(1_000_000);
+  });
+});
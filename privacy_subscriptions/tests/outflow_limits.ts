@@ -0,0 +1,150 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { getAccount, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  pda,
+  Subly,
+} from "./helpers";
+
+const PENDING_OUTFLOW_SEED = Buffer.from("pending_outflow");
+const OUTFLOW_KIND_WITHDRAW = 0;
+
+describe("Outflow limits", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let protocolPool: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userTokens: PublicKey;
+  let pendingOutflow: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "withdraw_v2"]);
+    ({ mint, protocolPool, poolTokenAccount } = await createPool(program, payer));
+
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    pendingOutflow = pda(program, [
+      PENDING_OUTFLOW_SEED,
+      user.publicKey.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from([OUTFLOW_KIND_WITHDRAW]),
+    ]);
+  });
+
+  const setLimits = (
+    budget: number,
+    threshold: number,
+    delaySeconds: number,
+    epochSeconds = 86_400,
+  ) =>
+    program.methods
+      .setOutflowLimits(
+        new anchor.BN(epochSeconds),
+        new anchor.BN(budget),
+        new anchor.BN(threshold),
+        new anchor.BN(delaySeconds),
+      )
+      .accountsPartial({ authority: payer.publicKey, protocolPool })
+      .rpc({ commitment: "confirmed" });
+
+  const requestOutflow = (amount: number, kind = OUTFLOW_KIND_WITHDRAW) =>
+    program.methods
+      .requestLargeOutflow(kind, new anchor.BN(amount))
+      .accounts({ owner: user.publicKey, mint })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+
+  const cancelOutflow = (owner = user) =>
+    program.methods
+      .cancelLargeOutflow()
+      .accountsPartial({ owner: owner.publicKey, pendingOutflow })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
+  const withdraw = async (amount: number, pending: boolean) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .withdraw(computationOffset, new anchor.BN(amount), Array.from(userKey.publicKey))
+      .accountsPartial({
+        user: user.publicKey,
+        mint,
+        poolTokenAccount,
+        userTokenAccount: userTokens,
+        pendingOutflow: pending ? pendingOutflow : null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        ...computationAccounts(program, "withdraw_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const tokenBalance = async () =>
+    Number((await getAccount(provider.connection, userTokens)).amount);
+
+  it("rejects limits without an epoch", async () => {
+    await expectError(() => setLimits(100, 0, 0, 0), "InvalidOutflowLimits");
+  });
+
+  it("rejects withdrawals over the epoch budget", async () => {
+    await setLimits(100, 0, 0);
+    await expectError(() => withdraw(101, false), "OutflowBudgetExceeded");
+  });
+
+  it("requires a request for large withdrawals", async () => {
+    await setLimits(0, 100, 0);
+    await expectError(() => withdraw(200, false), "OutflowRequestRequired");
+  });
+
+  it("rejects requests that have not matured", async () => {
+    await setLimits(0, 100, 3_600);
+    await expectError(() => requestOutflow(200, 7), "InvalidOutflowKind");
+    await requestOutflow(200);
+    await expectError(() => requestOutflow(300), "OutflowRequestPending");
+    await expectError(() => withdraw(200, true), "OutflowRequestLocked");
+
+    // Only the requester can withdraw the request
+    await expectError(async () => cancelOutflow(await fundedWallet(provider)), "ConstraintSeeds");
+    await cancelOutflow();
+    expect(await provider.connection.getAccountInfo(pendingOutflow)).to.be.null;
+  });
+
+  it("uses a matured request up only once the callback pays out", async () => {
+    await setLimits(0, 100, 0);
+    await requestOutflow(200);
+    const before = await tokenBalance();
+
+    await awaitComputation(program, await withdraw(200, true));
+
+    expect(await tokenBalance()).to.equal(before + 200);
+    const request = await program.account.pendingOutflow.fetch(pendingOutflow);
+    expect(request.amount.toNumber()).to.equal(0);
+    expect(request.pendingComputation.toBase58()).to.equal(PublicKey.default.toBase58());
+    await expectError(() => withdraw(200, true), "OutflowRequestMismatch");
+  });
+
+  after(async () => {
+    await setLimits(0, 0, 0);
+  });
+});
@@ -0,0 +1,128 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  computationAccounts,
+  createPool,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundTokens,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const PAYOUT_SCHEDULE_SEED = Buffer.from("payout_schedule");
+const DAY = 86400;
+
+describe("Scheduled payouts", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let merchant: TestMerchant;
+  let destination: PublicKey;
+  let payoutSchedule: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["scheduled_payout_v2"]);
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    destination = await fundTokens(provider, payer, mint, merchant.wallet.publicKey, 0);
+    payoutSchedule = pda(program, [
+      PAYOUT_SCHEDULE_SEED,
+      merchant.merchant.toBuffer(),
+      mint.toBuffer(),
+    ]);
+  });
+
+  const setSchedule = (cadenceDays: number, minThreshold: number, isActive: boolean) =>
+    program.methods
+      .setPayoutSchedule(cadenceDays, new anchor.BN(minThreshold), isActive)
+      .accountsPartial({
+        wallet: merchant.wallet.publicKey,
+        merchant: merchant.merchant,
+        mint,
+        destinationTokenAccount: destination,
+        payoutSchedule,
+      })
+      .signers([merchant.wallet])
+      .rpc({ commitment: "confirmed" });
+
+  const executePayout = (destinationTokenAccount: PublicKey) => {
+    const computationOffset = newComputationOffset();
+    return program.methods
+      .executeScheduledPayout(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        mint,
+        merchant: merchant.merchant,
+        payoutSchedule,
+        poolTokenAccount,
+        destinationTokenAccount,
+        merchantLedger: merchant.merchantLedger,
+        ...computationAccounts(program, "scheduled_payout_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+  };
+
+  const chainTime = async () =>
+    provider.connection.getBlockTime(await provider.connection.getSlot("confirmed"));
+
+  it("rejects cadences outside the allowed range", async () => {
+    await expectError(() => setSchedule(0, 50, true), "InvalidPayoutCadence");
+    await expectError(() => setSchedule(366, 50, true), "InvalidPayoutCadence");
+  });
+
+  it("schedules the first payout one cadence out", async () => {
+    await setSchedule(7, 50, true);
+
+    const schedule = await program.account.payoutSchedule.fetch(payoutSchedule);
+    expect(schedule.merchant.equals(merchant.merchant)).to.be.true;
+    expect(schedule.destination.equals(destination)).to.be.true;
+    expect(schedule.cadenceDays).to.equal(7);
+    expect(schedule.minThreshold.toNumber()).to.equal(50);
+    expect(schedule.isActive).to.be.true;
+    expect(schedule.lastPayoutAt.toNumber()).to.equal(0);
+    expect(Math.abs(schedule.nextPayoutAt.toNumber() - ((await chainTime()) + 7 * DAY))).to.be
+      .lessThan(60);
+  });
+
+  it("keeps the next payout date when the schedule changes", async () => {
+    const before = await program.account.payoutSchedule.fetch(payoutSchedule);
+
+    await setSchedule(14, 75, true);
+
+    const schedule = await program.account.payoutSchedule.fetch(payoutSchedule);
+    expect(schedule.cadenceDays).to.equal(14);
+    expect(schedule.minThreshold.toNumber()).to.equal(75);
+    expect(schedule.nextPayoutAt.eq(before.nextPayoutAt)).to.be.true;
+  });
+
+  it("pays out only to the scheduled destination", async () => {
+    const other = await fundTokens(provider, payer, mint, payer.publicKey, 0);
+    await expectError(() => executePayout(other), "Unauthorized");
+  });
+
+  it("refuses payouts before they are due", async () => {
+    // The first payout is a full cadence away, which a local validator cannot fast-forward to
+    await expectError(() => executePayout(destination), "PayoutNotDue");
+
+    const schedule = await program.account.payoutSchedule.fetch(payoutSchedule);
+    expect(schedule.lastPayoutAt.toNumber()).to.equal(0);
+  });
+
+  it("refuses payouts while the schedule is paused", async () => {
+    await setSchedule(14, 75, false);
+
+    await expectError(() => executePayout(destination), "PayoutScheduleInactive");
+  });
+});
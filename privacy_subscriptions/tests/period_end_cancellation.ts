@@ -0,0 +1,111 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  registerMerchant,
+  subscribe,
+  subscriptionState,
+  Subly,
+} from "./helpers";
+
+const STATUS_ACTIVE = 0;
+const STATUS_CANCELLING = 3;
+
+describe("Cancel at period end", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userSubscription: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "subscribe_v2",
+      "cancel_at_period_end_v2",
+      "resume_subscription_v2",
+    ]);
+    const { mint, poolTokenAccount } = await createPool(program, payer);
+
+    const merchant = await registerMerchant(program, mint);
+    const plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    userSubscription = await subscribe(program, user, userKey, mint, plan, 0);
+  });
+
+  const queue = async (
+    method: "cancelAtPeriodEnd" | "resumeSubscription",
+    compDefName: string,
+    signer: Keypair,
+  ) => {
+    const computationOffset = newComputationOffset();
+    await program.methods[method](computationOffset)
+      .accountsPartial({
+        user: signer.publicKey,
+        userSubscription,
+        ...computationAccounts(program, compDefName, computationOffset),
+      })
+      .signers([signer])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects cancellation by another wallet", async () => {
+    const other = await fundedWallet(provider);
+    await expectError(
+      () => queue("cancelAtPeriodEnd", "cancel_at_period_end_v2", other),
+      "ConstraintSeeds",
+    );
+  });
+
+  it("keeps the paid-through date when cancelling at period end", async () => {
+    const before = await subscriptionState(program, userKey, userSubscription);
+
+    await awaitComputation(
+      program,
+      await queue("cancelAtPeriodEnd", "cancel_at_period_end_v2", user),
+    );
+
+    const after = await subscriptionState(program, userKey, userSubscription);
+    expect(after.status).to.equal(STATUS_CANCELLING);
+    expect(after.nextPaymentDate).to.equal(before.nextPaymentDate);
+  });
+
+  it("rejects resumption by another wallet", async () => {
+    const other = await fundedWallet(provider);
+    await expectError(
+      () => queue("resumeSubscription", "resume_subscription_v2", other),
+      "ConstraintSeeds",
+    );
+  });
+
+  it("resumes a pending cancellation before the paid-through date", async () => {
+    await awaitComputation(
+      program,
+      await queue("resumeSubscription", "resume_subscription_v2", user),
+    );
+
+    expect((await subscriptionState(program, userKey, userSubscription)).status).to.equal(
+      STATUS_ACTIVE,
+    );
+  });
+});
@@ -0,0 +1,121 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  subscribe,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const PLAN_STATS_SEED = Buffer.from("plan_stats");
+
+describe("Plan analytics", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let merchant: TestMerchant;
+  let plan: PublicKey;
+  let planStats: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "subscribe_v2", "unsubscribe_v2"]);
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    // A 15-day cycle bills twice per 30 days, so each subscription adds 200 to MRR
+    plan = await createPlan(program, merchant, mint, 1, 100, 15);
+    planStats = pda(program, [PLAN_STATS_SEED, plan.toBuffer()]);
+  });
+
+  const subscriber = async (amount: number) => {
+    const user = await fundedWallet(provider);
+    const key = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, amount);
+    await deposit(program, user, key, mint, poolTokenAccount, userTokens, amount);
+    return { user, key, userSubscription: await subscribe(program, user, key, mint, plan, 0) };
+  };
+
+  /// Decrypted plan analytics, read with the merchant ledger key
+  const stats = async () => {
+    const account = await program.account.planStats.fetch(planStats);
+    const [activeSubscribers, mrr, , newSubscribers, churnedSubscribers] = merchant.key.decrypt(
+      [
+        account.encryptedActiveSubscribers,
+        account.encryptedMrr,
+        account.encryptedPeriodStart,
+        account.encryptedNewSubscribers,
+        account.encryptedChurnedSubscribers,
+        account.encryptedPreviousNewSubscribers,
+        account.encryptedPreviousChurnedSubscribers,
+      ],
+      account.nonce,
+    );
+    return { activeSubscribers, mrr, newSubscribers, churnedSubscribers };
+  };
+
+  let first: { user: Keypair; userSubscription: PublicKey };
+
+  it("counts paying subscriptions and their monthly revenue", async () => {
+    first = await subscriber(1_000);
+    await subscriber(1_000);
+
+    expect(await stats()).to.deep.equal({
+      activeSubscribers: BigInt(2),
+      mrr: BigInt(400),
+      newSubscribers: BigInt(2),
+      churnedSubscribers: BigInt(0),
+    });
+  });
+
+  it("leaves the totals alone when the first charge fails", async () => {
+    await subscriber(50);
+
+    expect(await stats()).to.deep.equal({
+      activeSubscribers: BigInt(2),
+      mrr: BigInt(400),
+      newSubscribers: BigInt(2),
+      churnedSubscribers: BigInt(0),
+    });
+  });
+
+  it("moves cancelled subscriptions to churn", async () => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .unsubscribe(computationOffset)
+      .accountsPartial({
+        user: first.user.publicKey,
+        userSubscription: first.userSubscription,
+        subscriptionPlan: plan,
+        merchantLedger: merchant.merchantLedger,
+        ...computationAccounts(program, "unsubscribe_v2", computationOffset),
+      })
+      .signers([first.user])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+
+    expect(await stats()).to.deep.equal({
+      activeSubscribers: BigInt(1),
+      mrr: BigInt(200),
+      newSubscribers: BigInt(2),
+      churnedSubscribers: BigInt(1),
+    });
+  });
+});
@@ -0,0 +1,91 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  subscribe,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const PLAN_STATS_SEED = Buffer.from("plan_stats");
+const PLAN_PUBLIC_STATS_SEED = Buffer.from("plan_public_stats");
+const MIN_SUBSCRIBERS = 10;
+
+describe("Public plan statistics", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let plan: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "subscribe_v2", "publish_plan_stats_v2"]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    for (let i = 0; i < 2; i++) {
+      const user = await fundedWallet(provider);
+      const key = await EncryptionKey.create(program);
+      const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 100);
+      await deposit(program, user, key, mint, poolTokenAccount, userTokens, 100);
+      await subscribe(program, user, key, mint, plan, 0);
+    }
+  });
+
+  const publish = async (subscriptionPlan: PublicKey) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .publishPlanStats(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        planStats: pda(program, [PLAN_STATS_SEED, subscriptionPlan.toBuffer()]),
+        planPublicStats: pda(program, [PLAN_PUBLIC_STATS_SEED, subscriptionPlan.toBuffer()]),
+        ...computationAccounts(program, "publish_plan_stats_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects plans nobody has subscribed to", async () => {
+    const emptyPlan = await createPlan(program, merchant, mint, 2, 100, 30);
+    await expectError(() => publish(emptyPlan), "AccountNotInitialized");
+  });
+
+  it("reports plans below k subscribers without a count", async () => {
+    await awaitComputation(program, await publish(plan));
+
+    const stats = await program.account.planPublicStats.fetch(
+      pda(program, [PLAN_PUBLIC_STATS_SEED, plan.toBuffer()]),
+    );
+    expect(stats.subscriptionPlan.equals(plan)).to.be.true;
+    expect(stats.k.toNumber()).to.equal(MIN_SUBSCRIBERS);
+    expect(stats.isBelowThreshold).to.be.true;
+    expect(stats.subscriberCount.toNumber()).to.equal(0);
+    expect(stats.updatedAt.toNumber()).to.be.greaterThan(0);
+    expect(stats.pendingComputation.equals(PublicKey.default)).to.be.true;
+  });
+
+  it("rate-limits refreshes", async () => {
+    await expectError(() => publish(plan), "RefreshTooSoon");
+  });
+});
@@ -0,0 +1,246 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { createMint } from "@solana/spl-token";
+import { MockOracle } from "../target/types/mock_oracle";
+import { PrivacySubscriptions } from "../target/types/privacy_subscriptions";
+import { expect } from "chai";
+
+const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
+const PRICE_FEED_SEED = Buffer.from("price_feed");
+const MERCHANT_SEED = Buffer.from("merchant");
+const MERCHANT_LEDGER_SEED = Buffer.from("merchant_ledger");
+const SUBSCRIPTION_PLAN_SEED = Buffer.from("subscription_plan");
+
+const USD = [..."USD"].map((c) => c.charCodeAt(0)).concat([0, 0, 0, 0, 0]);
+// The mock oracle serves a single price per account and has no feed ids
+const FEED_ID = new Array(32).fill(0);
+
+describe("Reference-currency pricing (mock oracle)", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const oracle = anchor.workspace.MockOracle as Program<MockOracle>;
+  const program = anchor.workspace
+    .PrivacySubscriptions as Program<PrivacySubscriptions>;
+
+  let mint: PublicKey;
+  let feed: Keypair;
+  let priceFeed: PublicKey;
+
+  before(async () => {
+    mint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+
+    // 150.00 USD per token, +/- 0.10
+    feed = Keypair.generate();
+    await oracle.methods
+      .initializeFeed(new anchor.BN(15_000), new anchor.BN(10), -2)
+      .accounts({ authority: payer.publicKey, feed: feed.publicKey })
+      .signers([feed])
+      .rpc({ commitment: "confirmed" });
+
+    // The protocol config may already exist from another suite
+    const [protocolConfig] = PublicKey.findProgramAddressSync(
+      [PROTOCOL_CONFIG_SEED],
+      program.programId,
+    );
+    if (!(await provider.connection.getAccountInfo(protocolConfig))) {
+      await program.methods
+        .initializeProtocol(0)
+        .accounts({ authority: payer.publicKey })
+        .rpc({ commitment: "confirmed" });
+    }
+
+    [priceFeed] = PublicKey.findProgramAddressSync(
+      [PRICE_FEED_SEED, mint.toBuffer(), Buffer.from(USD)],
+      program.programId,
+    );
+  });
+
+  it("rejects oracle programs without an adapter", async () => {
+    try {
+      await program.methods
+        .setPriceFeed(USD, FEED_ID, 60, 100)
+        .accountsPartial({
+          authority: payer.publicKey,
+          mint,
+          priceFeed,
+          oracleProgram: anchor.web3.SystemProgram.programId,
+          priceAccount: feed.publicKey,
+        })
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected UnsupportedPriceOracle");
+    } catch (err) {
+      expect(String(err)).to.include("UnsupportedPriceOracle");
+    }
+  });
+
+  it("rejects price accounts the oracle does not own", async () => {
+    try {
+      await program.methods
+        .setPriceFeed(USD, FEED_ID, 60, 100)
+        .accountsPartial({
+          authority: payer.publicKey,
+          mint,
+          priceFeed,
+          oracleProgram: oracle.programId,
+          priceAccount: payer.publicKey,
+        })
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected InvalidPriceAccount");
+    } catch (err) {
+      expect(String(err)).to.include("InvalidPriceAccount");
+    }
+  });
+
+  it("rejects a confidence limit above 100%", async () => {
+    try {
+      await program.methods
+        .setPriceFeed(USD, FEED_ID, 60, 10_001)
+        .accountsPartial({
+          authority: payer.publicKey,
+          mint,
+          priceFeed,
+          oracleProgram: oracle.programId,
+          priceAccount: feed.publicKey,
+        })
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected InvalidPriceFeedConfig");
+    } catch (err) {
+      expect(String(err)).to.include("InvalidPriceFeedConfig");
+    }
+  });
+
+  it("approves a feed and quotes a plan in USD", async () => {
+    await program.methods
+      .setPriceFeed(USD, FEED_ID, 60, 100)
+      .accountsPartial({
+        authority: payer.publicKey,
+        mint,
+        priceFeed,
+        oracleProgram: oracle.programId,
+        priceAccount: feed.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const config = await program.account.priceFeedConfig.fetch(priceFeed);
+    expect(config.priceAccount.toBase58()).to.equal(feed.publicKey.toBase58());
+    expect(config.maxStalenessSeconds).to.equal(60);
+    expect(config.maxConfidenceBps).to.equal(100);
+
+    const wallet = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      wallet.publicKey,
+      LAMPORTS_PER_SOL,
+    );
+    await provider.connection.confirmTransaction(sig, "confirmed");
+
+    await program.methods
+      .registerMerchant("Oracle Merchant", Array.from(Keypair.generate().publicKey.toBytes()))
+      .accounts({ wallet: wallet.publicKey, mint })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+
+    const [merchant] = PublicKey.findProgramAddressSync(
+      [MERCHANT_SEED, wallet.publicKey.toBuffer()],
+      program.programId,
+    );
+    const planId = new anchor.BN(1);
+    const [subscriptionPlan] = PublicKey.findProgramAddressSync(
+      [SUBSCRIPTION_PLAN_SEED, merchant.toBuffer(), planId.toArrayLike(Buffer, "le", 8)],
+      program.programId,
+    );
+
+    // One token per month, repriced to 9.99 USD below
+    await program.methods
+      .createSubscriptionPlan(planId, "Pro", new anchor.BN(1_000_000_000), 30)
+      .accounts({ wallet: wallet.publicKey, mint })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+
+    let plan = await program.account.subscriptionPlan.fetch(subscriptionPlan);
+    expect(plan.priceCurrency).to.deep.equal([0, 0, 0, 0, 0, 0, 0, 0]);
+
+    // Only the plan's merchant can reprice it
+    try {
+      await program.methods
+        .setPlanPriceCurrency(USD, new anchor.BN(9_990_000))
+        .accountsPartial({ wallet: payer.publicKey, merchant, subscriptionPlan, priceFeed })
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected ConstraintSeeds");
+    } catch (err) {
+      expect(String(err)).to.include("ConstraintSeeds");
+    }
+
+    // A reference currency needs an approved feed for the plan's mint
+    try {
+      await program.methods
+        .setPlanPriceCurrency(USD, new anchor.BN(9_990_000))
+        .accountsPartial({ wallet: wallet.publicKey, merchant, subscriptionPlan, priceFeed: null })
+        .signers([wallet])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected MissingPriceFeed");
+    } catch (err) {
+      expect(String(err)).to.include("MissingPriceFeed");
+    }
+
+    try {
+      await program.methods
+        .setPlanPriceCurrency(USD, new anchor.BN(0))
+        .accountsPartial({ wallet: wallet.publicKey, merchant, subscriptionPlan, priceFeed })
+        .signers([wallet])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected InvalidPrice");
+    } catch (err) {
+      expect(String(err)).to.include("InvalidPrice");
+    }
+
+    await program.methods
+      .setPlanPriceCurrency(USD, new anchor.BN(9_990_000))
+      .accountsPartial({ wallet: wallet.publicKey, merchant, subscriptionPlan, priceFeed })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+
+    plan = await program.account.subscriptionPlan.fetch(subscriptionPlan);
+    expect(plan.priceCurrency).to.deep.equal(USD);
+    expect(plan.price.toNumber()).to.equal(9_990_000);
+
+    // The merchant can settle the USD plan in a second mint once it has a ledger there
+    const secondMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    try {
+      await program.methods
+        .openMerchantLedger(new Array(32).fill(0))
+        .accounts({ wallet: wallet.publicKey, mint: secondMint })
+        .signers([wallet])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expected InvalidEncryptionKey");
+    } catch (err) {
+      expect(String(err)).to.include("InvalidEncryptionKey");
+    }
+
+    await program.methods
+      .openMerchantLedger(Array.from(Keypair.generate().publicKey.toBytes()))
+      .accounts({ wallet: wallet.publicKey, mint: secondMint })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+
+    const [secondLedger] = PublicKey.findProgramAddressSync(
+      [MERCHANT_LEDGER_SEED, merchant.toBuffer(), secondMint.toBuffer()],
+      program.programId,
+    );
+    const ledger = await program.account.merchantLedger.fetch(secondLedger);
+    expect(ledger.merchant.toBase58()).to.equal(merchant.toBase58());
+    expect(ledger.mint.toBase58()).to.equal(secondMint.toBase58());
+  });
+
+  it("publishes prices with an explicit publish time", async () => {
+    await oracle.methods
+      .setPrice(new anchor.BN(14_000), new anchor.BN(5), new anchor.BN(1))
+      .accounts({ authority: payer.publicKey, feed: feed.publicKey })
+      .rpc({ commitment: "confirmed" });
+
+    const account = await oracle.account.priceFeed.fetch(feed.publicKey);
+    expect(account.price.toNumber()).to.equal(14_000);
+    expect(account.publishTime.toNumber()).to.equal(1);
+  });
+});
@@ -0,0 +1,117 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  registerMerchant,
+  subscribe,
+  subscriptionState,
+  Subly,
+} from "./helpers";
+
+const STATUS_ACTIVE = 0;
+const STATUS_CANCELLED = 1;
+
+describe("Principal lock", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let plan: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userLedger: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "subscribe_v2", "set_principal_lock_v2"]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    const merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+  });
+
+  const setLock = async (wallet: Keypair, key: EncryptionKey, locked: boolean) => {
+    const computationOffset = newComputationOffset();
+    const { ciphertext, nonce } = key.encrypt(BigInt(locked));
+    await program.methods
+      .setPrincipalLock(computationOffset, ciphertext, nonce)
+      .accountsPartial({
+        user: wallet.publicKey,
+        mint,
+        ...computationAccounts(program, "set_principal_lock_v2", computationOffset),
+      })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  /// Decrypted user ledger state, in layout order
+  const ledgerState = async () => {
+    const ledger = await program.account.userLedger.fetch(userLedger);
+    const [principal, , accruedYield, principalLocked] = userKey.decrypt(
+      [
+        ledger.encryptedBalance,
+        ledger.encryptedSubscriptionCount,
+        ledger.encryptedAccruedYield,
+        ledger.encryptedPrincipalLocked,
+      ],
+      ledger.nonce,
+    );
+    return { principal, accruedYield, principalLocked: principalLocked !== BigInt(0) };
+  };
+
+  it("rejects wallets without a ledger", async () => {
+    const stranger = await fundedWallet(provider);
+    await expectError(
+      async () => setLock(stranger, await EncryptionKey.create(program), true),
+      "AccountNotInitialized",
+    );
+  });
+
+  it("keeps subscriptions off a locked principal", async () => {
+    await awaitComputation(program, await setLock(user, userKey, true));
+    expect(await ledgerState()).to.deep.equal({
+      principal: BigInt(1_000),
+      accruedYield: BigInt(0),
+      principalLocked: true,
+    });
+
+    // Nothing has accrued, so the charge has no funds to draw on
+    const refused = await subscribe(program, user, userKey, mint, plan, 0);
+    expect((await subscriptionState(program, userKey, refused)).status).to.equal(
+      STATUS_CANCELLED,
+    );
+    expect((await ledgerState()).principal).to.equal(BigInt(1_000));
+  });
+
+  it("charges principal again once unlocked", async () => {
+    await awaitComputation(program, await setLock(user, userKey, false));
+
+    const accepted = await subscribe(program, user, userKey, mint, plan, 1);
+    expect((await subscriptionState(program, userKey, accepted)).status).to.equal(STATUS_ACTIVE);
+    expect(await ledgerState()).to.deep.equal({
+      principal: BigInt(900),
+      accruedYield: BigInt(0),
+      principalLocked: false,
+    });
+  });
+});
@@ -0,0 +1,122 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  merchantBalance,
+  newComputationOffset,
+  registerMerchant,
+  subscribe,
+  subscriptionState,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const STATUS_ACTIVE = 0;
+const STATUS_CANCELLED = 1;
+
+describe("Reactivation", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let plan: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userLedger: PublicKey;
+  let userSubscription: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "subscribe_v2",
+      "unsubscribe_v2",
+      "reactivate_subscription_v2",
+    ]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    userSubscription = await subscribe(program, user, userKey, mint, plan, 0);
+
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .unsubscribe(computationOffset)
+      .accountsPartial({
+        user: user.publicKey,
+        userSubscription,
+        subscriptionPlan: plan,
+        merchantLedger: merchant.merchantLedger,
+        ...computationAccounts(program, "unsubscribe_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+  });
+
+  const setPlanActive = (isActive: boolean) =>
+    program.methods
+      .updateSubscriptionPlan(null, null, null, isActive)
+      .accountsPartial({ wallet: merchant.wallet.publicKey, subscriptionPlan: plan })
+      .signers([merchant.wallet])
+      .rpc({ commitment: "confirmed" });
+
+  const reactivate = async () => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .reactivateSubscription(computationOffset)
+      .accountsPartial({
+        user: user.publicKey,
+        mint,
+        subscriptionPlan: plan,
+        merchantLedger: merchant.merchantLedger,
+        userSubscription,
+        ...computationAccounts(program, "reactivate_subscription_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects reactivation while the plan is inactive", async () => {
+    expect((await subscriptionState(program, userKey, userSubscription)).status).to.equal(
+      STATUS_CANCELLED,
+    );
+    await setPlanActive(false);
+    await expectError(reactivate, "PlanNotActive");
+    await setPlanActive(true);
+  });
+
+  it("charges the plan price and starts a new cycle in place", async () => {
+    const startedAt = BigInt(Math.floor(Date.now() / 1000));
+    await awaitComputation(program, await reactivate());
+
+    const state = await subscriptionState(program, userKey, userSubscription);
+    expect(state.status).to.equal(STATUS_ACTIVE);
+    expect(state.nextPaymentDate > startedAt + BigInt(29 * 86_400)).to.be.true;
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(800));
+    expect(await merchantBalance(program, merchant.key, merchant.merchantLedger)).to.equal(
+      BigInt(200),
+    );
+  });
+});
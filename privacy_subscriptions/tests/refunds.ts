@@ -0,0 +1,124 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  merchantBalance,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  subscribe,
+  subscriptionState,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const STATUS_CANCELLED = 1;
+const PLAN_STATS_SEED = Buffer.from("plan_stats");
+
+describe("Refunds", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let otherMerchant: TestMerchant;
+  let plan: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userLedger: PublicKey;
+  let userSubscription: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "subscribe_v2", "refund_v2"]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    otherMerchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    userSubscription = await subscribe(program, user, userKey, mint, plan, 0);
+  });
+
+  const refund = async (from: TestMerchant, amount: number, cancel: boolean) => {
+    const computationOffset = newComputationOffset();
+    // The amount is encrypted to the refunding merchant's key
+    const { ciphertext, nonce } = from.key.encrypt(BigInt(amount));
+    await program.methods
+      .refund(computationOffset, ciphertext, nonce, cancel)
+      .accountsPartial({
+        wallet: from.wallet.publicKey,
+        mint,
+        subscriptionPlan: plan,
+        merchantLedger: from.merchantLedger,
+        userLedger,
+        userSubscription,
+        ...computationAccounts(program, "refund_v2", computationOffset),
+      })
+      .signers([from.wallet])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects refunds by a merchant that does not own the plan", async () => {
+    await expectError(() => refund(otherMerchant, 50, false), "ConstraintSeeds");
+  });
+
+  it("leaves both ledgers untouched when the merchant balance is short", async () => {
+    await awaitComputation(program, await refund(merchant, 101, false));
+
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(900));
+    expect(await merchantBalance(program, merchant.key, merchant.merchantLedger)).to.equal(
+      BigInt(100),
+    );
+  });
+
+  it("moves the refund between ledgers and can cancel the subscription", async () => {
+    await awaitComputation(program, await refund(merchant, 60, true));
+
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(960));
+    expect(await merchantBalance(program, merchant.key, merchant.merchantLedger)).to.equal(
+      BigInt(40),
+    );
+    expect((await subscriptionState(program, userKey, userSubscription)).status).to.equal(
+      STATUS_CANCELLED,
+    );
+
+    // The cancellation is churn in the plan analytics
+    const stats = await program.account.planStats.fetch(
+      pda(program, [PLAN_STATS_SEED, plan.toBuffer()]),
+    );
+    const [activeSubscribers, , , , churnedSubscribers] = merchant.key.decrypt(
+      [
+        stats.encryptedActiveSubscribers,
+        stats.encryptedMrr,
+        stats.encryptedPeriodStart,
+        stats.encryptedNewSubscribers,
+        stats.encryptedChurnedSubscribers,
+        stats.encryptedPreviousNewSubscribers,
+        stats.encryptedPreviousChurnedSubscribers,
+      ],
+      stats.nonce,
+    );
+    expect(activeSubscribers).to.equal(BigInt(0));
+    expect(churnedSubscribers).to.equal(BigInt(1));
+  });
+});
@@ -0,0 +1,110 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { getAccount, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  merchantBalance,
+  newComputationOffset,
+  registerMerchant,
+  subscribe,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+describe("Revenue splits", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let merchant: TestMerchant;
+  let payees: PublicKey[];
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "subscribe_v2", "claim_revenue_v2"]);
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    // One subscription charge of 1_000 gives the merchant something to claim
+    merchant = await registerMerchant(program, mint);
+    const plan = await createPlan(program, merchant, mint, 1, 1_000, 30);
+    const user = await fundedWallet(provider);
+    const userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 5_000);
+    await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 5_000);
+    await subscribe(program, user, userKey, mint, plan, 0);
+
+    payees = [];
+    for (let i = 0; i < 2; i++) {
+      payees.push(await fundTokens(provider, payer, mint, Keypair.generate().publicKey, 0));
+    }
+    await setSplit([7_000, 3_000]);
+  });
+
+  const setSplit = (sharesBps: number[]) =>
+    program.methods
+      .setRevenueSplit(sharesBps)
+      .accounts({ wallet: merchant.wallet.publicKey, mint })
+      .remainingAccounts(
+        payees.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })),
+      )
+      .signers([merchant.wallet])
+      .rpc({ commitment: "confirmed" });
+
+  const claimSplit = async (recipients: PublicKey[], amount: number) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .claimRevenueSplit(computationOffset, new anchor.BN(amount))
+      .accountsPartial({
+        wallet: merchant.wallet.publicKey,
+        mint,
+        poolTokenAccount,
+        merchantLedger: merchant.merchantLedger,
+        pendingOutflow: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        ...computationAccounts(program, "claim_revenue_v2", computationOffset),
+      })
+      .remainingAccounts(
+        recipients.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+      )
+      .signers([merchant.wallet])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects shares that do not add up to the whole claim", async () => {
+    await expectError(() => setSplit([7_000, 2_000]), "InvalidRevenueSplit");
+    await expectError(() => setSplit([10_000, 0]), "InvalidRevenueSplit");
+  });
+
+  it("rejects recipients that differ from the split", async () => {
+    await expectError(() => claimSplit([...payees].reverse(), 1_000), "InvalidSplitRecipients");
+  });
+
+  it("pays every recipient its share in the callback", async () => {
+    expect(await merchantBalance(program, merchant.key, merchant.merchantLedger)).to.equal(
+      BigInt(1_000),
+    );
+
+    await awaitComputation(program, await claimSplit(payees, 1_000));
+
+    expect(Number((await getAccount(provider.connection, payees[0])).amount)).to.equal(700);
+    expect(Number((await getAccount(provider.connection, payees[1])).amount)).to.equal(300);
+    expect(await merchantBalance(program, merchant.key, merchant.merchantLedger)).to.equal(
+      BigInt(0),
+    );
+  });
+});
@@ -0,0 +1,194 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPool,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  newComputationOffset,
+  pda,
+  Subly,
+  USER_LEDGER_SEED,
+} from "./helpers";
+
+const SCREENING_ATTESTATION_SEED = Buffer.from("screening_attestation");
+
+describe("Screening", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let protocolPool: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let issuer: Keypair;
+  let otherAuthority: Keypair;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userTokens: PublicKey;
+
+  const attestation = (wallet: PublicKey) =>
+    pda(program, [SCREENING_ATTESTATION_SEED, wallet.toBuffer()]);
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2"]);
+    ({ mint, protocolPool, poolTokenAccount } = await createPool(program, payer));
+
+    issuer = await fundedWallet(provider);
+    otherAuthority = await fundedWallet(provider);
+    for (const authority of [issuer, otherAuthority]) {
+      await program.methods
+        .addScreeningAuthority(authority.publicKey)
+        .accounts({ authority: payer.publicKey })
+        .rpc({ commitment: "confirmed" });
+    }
+    await program.methods
+      .setComplianceMode(true)
+      .accountsPartial({ authority: payer.publicKey, protocolPool })
+      .rpc({ commitment: "confirmed" });
+
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+  });
+
+  after(async () => {
+    // Free the authority slots for other suites
+    for (const authority of [issuer, otherAuthority]) {
+      await program.methods
+        .removeScreeningAuthority(authority.publicKey)
+        .accounts({ authority: payer.publicKey })
+        .rpc({ commitment: "confirmed" });
+    }
+  });
+
+  const issue = (authority: Keypair, wallet: PublicKey) =>
+    program.methods
+      .issueScreeningAttestation(new anchor.BN(Math.floor(Date.now() / 1000) + 3_600))
+      .accountsPartial({
+        screeningAuthority: authority.publicKey,
+        wallet,
+        screeningAttestation: attestation(wallet),
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+
+  const deposit = async (screened: boolean, amount: number) => {
+    const computationOffset = newComputationOffset();
+    const { ciphertext, nonce } = userKey.encrypt(BigInt(amount));
+    await program.methods
+      .deposit(
+        computationOffset,
+        new anchor.BN(amount),
+        Array.from(userKey.publicKey),
+        ciphertext,
+        nonce,
+      )
+      .accountsPartial({
+        user: user.publicKey,
+        mint,
+        poolTokenAccount,
+        userTokenAccount: userTokens,
+        screeningAttestation: screened ? attestation(user.publicKey) : null,
+        ...computationAccounts(program, "deposit_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects deposits without an attestation in compliance mode", async () => {
+    await expectError(() => deposit(false, 100), "ScreeningRequired");
+  });
+
+  it("lets only the protocol authority manage screening", async () => {
+    await expectError(
+      () =>
+        program.methods
+          .addScreeningAuthority(user.publicKey)
+          .accounts({ authority: user.publicKey })
+          .signers([user])
+          .rpc({ commitment: "confirmed" }),
+      "Unauthorized",
+    );
+    await expectError(
+      () =>
+        program.methods
+          .setComplianceMode(false)
+          .accountsPartial({ authority: user.publicKey, protocolPool })
+          .signers([user])
+          .rpc({ commitment: "confirmed" }),
+      "Unauthorized",
+    );
+    await expectError(
+      () =>
+        program.methods
+          .addScreeningAuthority(issuer.publicKey)
+          .accounts({ authority: payer.publicKey })
+          .rpc({ commitment: "confirmed" }),
+      "ScreeningAuthorityExists",
+    );
+    await expectError(
+      () =>
+        program.methods
+          .removeScreeningAuthority(user.publicKey)
+          .accounts({ authority: payer.publicKey })
+          .rpc({ commitment: "confirmed" }),
+      "UnknownScreeningAuthority",
+    );
+  });
+
+  it("admits deposits from attested wallets", async () => {
+    await issue(issuer, user.publicKey);
+    await awaitComputation(program, await deposit(true, 100));
+
+    const ledger = pda(program, [USER_LEDGER_SEED, user.publicKey.toBuffer(), mint.toBuffer()]);
+    expect(await ledgerBalance(program, userKey, ledger)).to.equal(BigInt(100));
+  });
+
+  it("keeps an attestation with the authority holding it", async () => {
+    await expectError(() => issue(otherAuthority, user.publicKey), "ScreeningAttestationHeld");
+  });
+
+  it("lets only listed authorities revoke attestations", async () => {
+    await expectError(
+      () =>
+        program.methods
+          .revokeScreeningAttestation()
+          .accountsPartial({
+            screeningAuthority: user.publicKey,
+            screeningAttestation: attestation(user.publicKey),
+          })
+          .signers([user])
+          .rpc({ commitment: "confirmed" }),
+      "UnknownScreeningAuthority",
+    );
+  });
+
+  it("lets only the revoking authority lift a revocation", async () => {
+    await program.methods
+      .revokeScreeningAttestation()
+      .accountsPartial({
+        screeningAuthority: otherAuthority.publicKey,
+        screeningAttestation: attestation(user.publicKey),
+      })
+      .signers([otherAuthority])
+      .rpc({ commitment: "confirmed" });
+    await expectError(() => deposit(true, 100), "ScreeningRevoked");
+    await expectError(() => issue(issuer, user.publicKey), "ScreeningAttestationHeld");
+
+    await issue(otherAuthority, user.publicKey);
+    const account = await program.account.screeningAttestation.fetch(attestation(user.publicKey));
+    expect(account.isRevoked).to.equal(false);
+    expect(account.issuer.toBase58()).to.equal(otherAuthority.publicKey.toBase58());
+  });
+});
@@ -0,0 +1,186 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  subscribe,
+  subscriptionState,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const SEAT_ASSIGNMENT_SEED = Buffer.from("seat_assignment");
+const SEAT_STATUS_ACTIVE = 1;
+const SEAT_STATUS_REJECTED = 2;
+
+describe("Seats", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  type Event = anchor.IdlEvents<Subly["idl"]>;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let plan: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userLedger: PublicKey;
+  let userSubscription: PublicKey;
+  let members: Keypair[];
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "subscribe_v2",
+      "update_seats_v2",
+      "assign_seat_v2",
+      "verify_subscription_v2",
+    ]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    userSubscription = await subscribe(program, user, userKey, mint, plan, 0, 1);
+    members = [await fundedWallet(provider), await fundedWallet(provider)];
+  });
+
+  const seat = (member: Keypair) =>
+    pda(program, [SEAT_ASSIGNMENT_SEED, userSubscription.toBuffer(), member.publicKey.toBuffer()]);
+
+  const assignSeat = async (member: Keypair) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .assignSeat(computationOffset)
+      .accountsPartial({
+        user: user.publicKey,
+        member: member.publicKey,
+        subscriptionPlan: plan,
+        userSubscription,
+        seatAssignment: seat(member),
+        ...computationAccounts(program, "assign_seat_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+    return (await program.account.seatAssignment.fetch(seat(member))).status;
+  };
+
+  const verifySeat = async (member: Keypair, seatOf: Keypair) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .verifySeat(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        member: member.publicKey,
+        userSubscription,
+        seatAssignment: seat(seatOf),
+        ...computationAccounts(program, "verify_subscription_v2", computationOffset),
+      })
+      .signers([member])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const updateSeats = async (quantity: number) => {
+    const computationOffset = newComputationOffset();
+    const { ciphertext, nonce } = userKey.encrypt(BigInt(quantity));
+    await program.methods
+      .updateSeats(computationOffset, ciphertext, nonce)
+      .accountsPartial({
+        user: user.publicKey,
+        mint,
+        subscriptionPlan: plan,
+        merchantLedger: merchant.merchantLedger,
+        userSubscription,
+        ...computationAccounts(program, "update_seats_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+  };
+
+  const unassignSeat = (signer: Keypair, member: Keypair) =>
+    program.methods
+      .unassignSeat()
+      .accountsPartial({
+        user: signer.publicKey,
+        userSubscription,
+        seatAssignment: seat(member),
+      })
+      .signers([signer])
+      .rpc({ commitment: "confirmed" });
+
+  const seatsAssigned = async () =>
+    (await program.account.userSubscription.fetch(userSubscription)).seatsAssigned;
+
+  it("assigns a seat while the subscription has room", async () => {
+    expect(await assignSeat(members[0])).to.equal(SEAT_STATUS_ACTIVE);
+    expect(await seatsAssigned()).to.equal(1);
+  });
+
+  it("rejects assignments beyond the paid seats", async () => {
+    expect(await assignSeat(members[1])).to.equal(SEAT_STATUS_REJECTED);
+    expect(await seatsAssigned()).to.equal(1);
+    await expectError(() => verifySeat(members[1], members[1]), "SeatNotActive");
+
+    // Only the subscriber frees seats, not the member holding one
+    await expectError(() => unassignSeat(members[1], members[1]), "ConstraintSeeds");
+    await unassignSeat(user, members[1]);
+    expect(await provider.connection.getAccountInfo(seat(members[1]))).to.be.null;
+    expect(await seatsAssigned()).to.equal(1);
+  });
+
+  it("verifies a seat only for the member holding it", async () => {
+    await expectError(() => verifySeat(members[1], members[0]), "ConstraintSeeds");
+
+    let listenerId: number;
+    const event = new Promise<Event["subscriptionVerified"]>((resolve) => {
+      listenerId = program.addEventListener("subscriptionVerified", resolve);
+    });
+    await awaitComputation(program, await verifySeat(members[0], members[0]));
+    const { isValid } = await event;
+    await program.removeEventListener(listenerId);
+    expect(isValid).to.be.true;
+  });
+
+  it("charges added seats for the rest of the period", async () => {
+    await updateSeats(2);
+
+    expect((await subscriptionState(program, userKey, userSubscription)).quantity).to.equal(2);
+    // Nearly the whole period is left, so close to a full seat's price
+    const balance = await ledgerBalance(program, userKey, userLedger);
+    expect(balance >= BigInt(800) && balance < BigInt(900)).to.be.true;
+    expect(await assignSeat(members[1])).to.equal(SEAT_STATUS_ACTIVE);
+  });
+
+  it("keeps seats that are handed to members", async () => {
+    const balance = await ledgerBalance(program, userKey, userLedger);
+
+    await updateSeats(1);
+
+    expect((await subscriptionState(program, userKey, userSubscription)).quantity).to.equal(2);
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(balance);
+  });
+});
@@ -0,0 +1,134 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  pda,
+  Subly,
+} from "./helpers";
+
+const SOLVENCY_ATTESTATION_SEED = Buffer.from("solvency_attestation");
+
+// Fixtures loaded by Anchor.toml: a pool from before liabilities were tracked, holding 5000
+const UNSEEDED_PROTOCOL_POOL = new PublicKey("9J3EMkTqeRCrXHY41BgTmuv2WgaGcqJ4QYTykUiRJ719");
+const UNSEEDED_POOL_TOKEN_ACCOUNT = new PublicKey("Eiz9C2KhcfqRzDYJVqL99vBbx25tfUeJSZwkwtyc4Tje");
+
+describe("Solvency", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let protocolPool: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let userTokens: PublicKey;
+  let attestation: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "check_solvency_v2",
+      "seed_liabilities_v2",
+    ]);
+    let mint: PublicKey;
+    ({ mint, protocolPool, poolTokenAccount } = await createPool(program, payer));
+    attestation = pda(program, [SOLVENCY_ATTESTATION_SEED, protocolPool.toBuffer()]);
+
+    const user = await fundedWallet(provider);
+    const userKey = await EncryptionKey.create(program);
+    userTokens = await fundTokens(provider, payer, mint, user.publicKey, 500);
+    await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 500);
+  });
+
+  const checkSolvency = async (tokenAccount: PublicKey, pool = protocolPool) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .checkSolvency(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        protocolPool: pool,
+        poolTokenAccount: tokenAccount,
+        ...computationAccounts(program, "check_solvency_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const seedLiabilities = async (authority: Keypair, pool: PublicKey, tokenAccount: PublicKey) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .seedPoolLiabilities(computationOffset)
+      .accountsPartial({
+        authority: authority.publicKey,
+        protocolPool: pool,
+        poolTokenAccount: tokenAccount,
+        ...computationAccounts(program, "seed_liabilities_v2", computationOffset),
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects a token account other than the pool's", async () => {
+    await expectError(() => checkSolvency(userTokens), "Unauthorized");
+  });
+
+  it("attests that the pool covers the encrypted liabilities", async () => {
+    await awaitComputation(program, await checkSolvency(poolTokenAccount));
+
+    const account = await program.account.solvencyAttestation.fetch(attestation);
+    expect(account.isSolvent).to.be.true;
+    expect(account.tokenBalance.toNumber()).to.equal(500);
+    expect(account.attestedAt.toNumber()).to.be.greaterThan(0);
+    expect(account.pendingComputation.toBase58()).to.equal(PublicKey.default.toBase58());
+  });
+
+  it("refuses to seed pools that already track liabilities", async () => {
+    await expectError(
+      () => seedLiabilities(payer, protocolPool, poolTokenAccount),
+      "LiabilitiesAlreadySeeded",
+    );
+  });
+
+  it("seeds an older pool's liabilities from what it holds", async () => {
+    await expectError(
+      () => checkSolvency(UNSEEDED_POOL_TOKEN_ACCOUNT, UNSEEDED_PROTOCOL_POOL),
+      "LiabilitiesNotSeeded",
+    );
+    const stranger = await fundedWallet(provider);
+    await expectError(
+      () => seedLiabilities(stranger, UNSEEDED_PROTOCOL_POOL, UNSEEDED_POOL_TOKEN_ACCOUNT),
+      "Unauthorized",
+    );
+
+    await awaitComputation(
+      program,
+      await seedLiabilities(payer, UNSEEDED_PROTOCOL_POOL, UNSEEDED_POOL_TOKEN_ACCOUNT),
+    );
+    const pool = await program.account.protocolPool.fetch(UNSEEDED_PROTOCOL_POOL);
+    expect(pool.liabilitiesSeeded).to.be.true;
+    expect(pool.liabilitiesLock.toBase58()).to.equal(PublicKey.default.toBase58());
+
+    // Everything the pool holds is owed, so it is exactly solvent
+    await awaitComputation(
+      program,
+      await checkSolvency(UNSEEDED_POOL_TOKEN_ACCOUNT, UNSEEDED_PROTOCOL_POOL),
+    );
+    const account = await program.account.solvencyAttestation.fetch(
+      pda(program, [SOLVENCY_ATTESTATION_SEED, UNSEEDED_PROTOCOL_POOL.toBuffer()]),
+    );
+    expect(account.isSolvent).to.be.true;
+    expect(account.tokenBalance.toNumber()).to.equal(5_000);
+  });
+});
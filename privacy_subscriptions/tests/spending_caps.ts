@@ -0,0 +1,129 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  newComputationOffset,
+  registerMerchant,
+  subscribe,
+  subscriptionState,
+  Subly,
+  TestMerchant,
+} from "./helpers";
+
+const STATUS_ACTIVE = 0;
+const STATUS_CAP_EXCEEDED = 4;
+
+describe("Spending caps", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let plan: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userLedger: PublicKey;
+  let cappedSubscription: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "subscribe_v2",
+      "set_spending_cap_v2",
+      "reactivate_subscription_v2",
+    ]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+  });
+
+  const setCap = async (wallet: Keypair, key: EncryptionKey, cap: number, windowDays: number) => {
+    const computationOffset = newComputationOffset();
+    const encryptedCap = key.encrypt(BigInt(cap));
+    const encryptedWindow = key.encrypt(BigInt(windowDays));
+    await program.methods
+      .setSpendingCap(
+        computationOffset,
+        encryptedCap.ciphertext,
+        encryptedCap.nonce,
+        encryptedWindow.ciphertext,
+        encryptedWindow.nonce,
+      )
+      .accountsPartial({
+        user: wallet.publicKey,
+        merchant: merchant.merchant,
+        mint,
+        ...computationAccounts(program, "set_spending_cap_v2", computationOffset),
+      })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("rejects caps from wallets without a ledger", async () => {
+    // The cap is encrypted to the ledger's key, so the ledger must exist first
+    const stranger = await fundedWallet(provider);
+    await expectError(
+      async () => setCap(stranger, await EncryptionKey.create(program), 100, 30),
+      "AccountNotInitialized",
+    );
+  });
+
+  it("refuses charges to the merchant beyond the cap", async () => {
+    await awaitComputation(program, await setCap(user, userKey, 150, 30));
+
+    const first = await subscribe(program, user, userKey, mint, plan, 0);
+    expect((await subscriptionState(program, userKey, first)).status).to.equal(STATUS_ACTIVE);
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(900));
+
+    // A second charge of 100 would bring the window's spend to 200
+    cappedSubscription = await subscribe(program, user, userKey, mint, plan, 1);
+    expect((await subscriptionState(program, userKey, cappedSubscription)).status).to.equal(
+      STATUS_CAP_EXCEEDED,
+    );
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(900));
+  });
+
+  it("refuses reactivations beyond the cap", async () => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .reactivateSubscription(computationOffset)
+      .accountsPartial({
+        user: user.publicKey,
+        mint,
+        subscriptionPlan: plan,
+        merchantLedger: merchant.merchantLedger,
+        userSubscription: cappedSubscription,
+        ...computationAccounts(program, "reactivate_subscription_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+
+    expect((await subscriptionState(program, userKey, cappedSubscription)).status).to.equal(
+      STATUS_CAP_EXCEEDED,
+    );
+    expect(await ledgerBalance(program, userKey, userLedger)).to.equal(BigInt(900));
+  });
+});
@@ -0,0 +1,222 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  CIRCUIT_VERSION_SEED,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  subscribe,
+  Subly,
+} from "./helpers";
+
+// Fixtures loaded by Anchor.toml, in state layout 1 and encrypted to these keys
+const LAYOUT1_USER_LEDGER = new PublicKey("FXoanMbnwGKtj48WJuCBNnqUbMBjb6tbmQRLD7QCvKRS");
+const LAYOUT1_MERCHANT_LEDGER = new PublicKey("58BkJ6WzkypPBGRWMmTiNG1oXP8gbDTPXMbFLRxnbQ7c");
+const LAYOUT1_USER_SUBSCRIPTION = new PublicKey("98LQsU1ctn9uhhv6zKcjKPP3UVvCe2ZuTRLWvxeJpg9E");
+const LAYOUT1_USER_KEY = Buffer.from(
+  "442851d9a85bc8e8437263da47eb59802bda64e3832584ce862cc54900797f01",
+  "hex",
+);
+const LAYOUT1_MERCHANT_KEY = Buffer.from(
+  "32f8504e3b475166d0bca59bdb0de5831407f7e8423969125529f39185f40e52",
+  "hex",
+);
+const CURRENT_STATE_VERSION = 2;
+
+describe("State migrations", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let userLedger: PublicKey;
+  let merchantLedger: PublicKey;
+  let userSubscription: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "subscribe_v2",
+      "migrate_user_ledger_v2",
+      "migrate_merchant_ledger_v2",
+      "migrate_user_subscription_v2",
+    ]);
+    const { mint, poolTokenAccount } = await createPool(program, payer);
+
+    const merchant = await registerMerchant(program, mint);
+    merchantLedger = merchant.merchantLedger;
+    const plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    const user = await fundedWallet(provider);
+    const userKey = await EncryptionKey.create(program);
+    const userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    userSubscription = await subscribe(program, user, userKey, mint, plan, 0);
+  });
+
+  const migrateUserLedger = async (account: PublicKey) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .migrateUserLedger(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        userLedger: account,
+        ...computationAccounts(program, "migrate_user_ledger_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const migrateMerchantLedger = async (account: PublicKey) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .migrateMerchantLedger(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        merchantLedger: account,
+        ...computationAccounts(program, "migrate_merchant_ledger_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const migrateUserSubscription = async (account: PublicKey) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .migrateUserSubscription(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        userSubscription: account,
+        ...computationAccounts(program, "migrate_user_subscription_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  // Fixtures predating the current account layout have to grow before their state can move
+  const migrateAccount = (account: PublicKey) =>
+    program.methods
+      .migrateAccount()
+      .accounts({ payer: payer.publicKey, account })
+      .rpc({ commitment: "confirmed" });
+
+  const registerCircuitVersion = (authority: Keypair, version: number, url: string) =>
+    program.methods
+      .registerCircuitVersion(version, url)
+      .accountsPartial({
+        authority: authority.publicKey,
+        circuitVersion: pda(program, [CIRCUIT_VERSION_SEED, Buffer.from([version])]),
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+
+  describe("circuit versions", () => {
+    // Far above CIRCUIT_VERSION, so the sources other suites rely on stay put
+    const version = 200;
+
+    it("accepts sources from the protocol authority only", async () => {
+      const stranger = await fundedWallet(provider);
+      await expectError(
+        () => registerCircuitVersion(stranger, version, "https://example.com/v200"),
+        "Unauthorized",
+      );
+      await expectError(() => registerCircuitVersion(payer, version, "/"), "InvalidCircuitSource");
+      await expectError(
+        () => registerCircuitVersion(payer, version, `https://example.com/${"a".repeat(128)}`),
+        "InvalidCircuitSource",
+      );
+    });
+
+    it("stores the source without a trailing slash", async () => {
+      await registerCircuitVersion(payer, version, "https://example.com/circuits/v200/");
+
+      const circuitVersion = await program.account.circuitVersion.fetch(
+        pda(program, [CIRCUIT_VERSION_SEED, Buffer.from([version])]),
+      );
+      const url = Buffer.from(circuitVersion.sourceBaseUrl).toString().replace(/\0+$/, "");
+      expect(circuitVersion.version).to.equal(version);
+      expect(url).to.equal("https://example.com/circuits/v200");
+      expect(circuitVersion.updatedAt.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("state layouts", () => {
+    it("refuses to migrate state that is already current", async () => {
+      await expectError(() => migrateUserLedger(userLedger), "StateAlreadyCurrent");
+      await expectError(() => migrateMerchantLedger(merchantLedger), "StateAlreadyCurrent");
+      await expectError(() => migrateUserSubscription(userSubscription), "StateAlreadyCurrent");
+    });
+
+    it("moves a layout 1 user ledger to the current layout", async () => {
+      await migrateAccount(LAYOUT1_USER_LEDGER);
+      await awaitComputation(program, await migrateUserLedger(LAYOUT1_USER_LEDGER));
+
+      const ledger = await program.account.userLedger.fetch(LAYOUT1_USER_LEDGER);
+      expect(ledger.stateVersion).to.equal(CURRENT_STATE_VERSION);
+      // Layout 1 had no yield or lock, so both start cleared
+      const key = await EncryptionKey.create(program, LAYOUT1_USER_KEY);
+      const [, , accruedYield, principalLocked] = key.decrypt(
+        [
+          ledger.encryptedBalance,
+          ledger.encryptedSubscriptionCount,
+          ledger.encryptedAccruedYield,
+          ledger.encryptedPrincipalLocked,
+        ],
+        ledger.nonce,
+      );
+      expect(accruedYield).to.equal(BigInt(0));
+      expect(principalLocked).to.equal(BigInt(0));
+
+      await expectError(() => migrateUserLedger(LAYOUT1_USER_LEDGER), "StateAlreadyCurrent");
+    });
+
+    it("moves a layout 1 merchant ledger to the current layout", async () => {
+      await awaitComputation(program, await migrateMerchantLedger(LAYOUT1_MERCHANT_LEDGER));
+
+      const ledger = await program.account.merchantLedger.fetch(LAYOUT1_MERCHANT_LEDGER);
+      expect(ledger.stateVersion).to.equal(CURRENT_STATE_VERSION);
+      // Nothing could be disputed before layout 2, so nothing is frozen
+      const key = await EncryptionKey.create(program, LAYOUT1_MERCHANT_KEY);
+      const [, , frozen] = key.decrypt(
+        [ledger.encryptedBalance, ledger.encryptedTotalClaimed, ledger.encryptedFrozen],
+        ledger.nonce,
+      );
+      expect(frozen).to.equal(BigInt(0));
+    });
+
+    it("moves a layout 1 subscription to the current layout", async () => {
+      await migrateAccount(LAYOUT1_USER_SUBSCRIPTION);
+      await awaitComputation(program, await migrateUserSubscription(LAYOUT1_USER_SUBSCRIPTION));
+
+      const subscription = await program.account.userSubscription.fetch(
+        LAYOUT1_USER_SUBSCRIPTION,
+      );
+      expect(subscription.stateVersion).to.equal(CURRENT_STATE_VERSION);
+      // Subscriptions from before seats pay for exactly one
+      const key = await EncryptionKey.create(program, LAYOUT1_USER_KEY);
+      const [, , , , , quantity] = key.decrypt(
+        [
+          ...subscription.encryptedPlan,
+          subscription.encryptedStatus,
+          subscription.encryptedNextPaymentDate,
+          subscription.encryptedStartDate,
+          subscription.encryptedQuantity,
+        ],
+        subscription.nonce,
+      );
+      expect(quantity).to.equal(BigInt(1));
+    });
+  });
+});
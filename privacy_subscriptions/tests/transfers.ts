@@ -0,0 +1,134 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  newComputationOffset,
+  pda,
+  Subly,
+  USER_LEDGER_SEED,
+} from "./helpers";
+
+describe("Transfers", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let sender: Keypair;
+  let senderKey: EncryptionKey;
+  let senderLedger: PublicKey;
+  let recipient: Keypair;
+  let recipientKey: EncryptionKey;
+  let recipientLedger: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, ["deposit_v2", "transfer_v2", "claim_transfers_v2"]);
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    sender = await fundedWallet(provider);
+    senderKey = await EncryptionKey.create(program);
+    const senderTokens = await fundTokens(provider, payer, mint, sender.publicKey, 1_000);
+    senderLedger = await deposit(
+      program, sender, senderKey, mint, poolTokenAccount, senderTokens, 1_000,
+    );
+
+    recipient = await fundedWallet(provider);
+    recipientKey = await EncryptionKey.create(program);
+    const recipientTokens = await fundTokens(provider, payer, mint, recipient.publicKey, 10);
+    recipientLedger = await deposit(
+      program, recipient, recipientKey, mint, poolTokenAccount, recipientTokens, 10,
+    );
+  });
+
+  const transfer = async (to: PublicKey, amount: bigint) => {
+    const computationOffset = newComputationOffset();
+    const { ciphertext, nonce } = senderKey.encrypt(amount);
+    await program.methods
+      .transfer(computationOffset, ciphertext, nonce)
+      .accountsPartial({
+        sender: sender.publicKey,
+        recipient: to,
+        mint,
+        recipientLedger: pda(program, [USER_LEDGER_SEED, to.toBuffer(), mint.toBuffer()]),
+        recipientScreeningAttestation: null,
+        ...computationAccounts(program, "transfer_v2", computationOffset),
+      })
+      .signers([sender])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const claim = async (owner: Keypair, key: EncryptionKey) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .claimTransfers(computationOffset, Array.from(key.publicKey))
+      .accountsPartial({
+        user: owner.publicKey,
+        mint,
+        ...computationAccounts(program, "claim_transfers_v2", computationOffset),
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  it("holds a transfer in the recipient's pending transfers", async () => {
+    await awaitComputation(program, await transfer(recipient.publicKey, BigInt(250)));
+
+    expect(await ledgerBalance(program, senderKey, senderLedger)).to.equal(BigInt(750));
+    expect(await ledgerBalance(program, recipientKey, recipientLedger)).to.equal(BigInt(10));
+    const ledger = await program.account.userLedger.fetch(recipientLedger);
+    expect(ledger.pendingTransfersNonce.isZero()).to.be.false;
+  });
+
+  it("credits pending transfers once the recipient claims them", async () => {
+    await awaitComputation(program, await claim(recipient, recipientKey));
+    expect(await ledgerBalance(program, recipientKey, recipientLedger)).to.equal(BigInt(260));
+
+    // A second claim finds nothing left
+    await awaitComputation(program, await claim(recipient, recipientKey));
+    expect(await ledgerBalance(program, recipientKey, recipientLedger)).to.equal(BigInt(260));
+  });
+
+  it("creates a missing ledger and leaves its key to the recipient", async () => {
+    const newcomer = await fundedWallet(provider);
+    const newcomerLedger = pda(program, [
+      USER_LEDGER_SEED,
+      newcomer.publicKey.toBuffer(),
+      mint.toBuffer(),
+    ]);
+    await awaitComputation(program, await transfer(newcomer.publicKey, BigInt(100)));
+
+    let ledger = await program.account.userLedger.fetch(newcomerLedger);
+    expect(ledger.user.equals(newcomer.publicKey)).to.be.true;
+    expect(ledger.encryptionPubkey).to.deep.equal(new Array(32).fill(0));
+    expect(await ledgerBalance(program, senderKey, senderLedger)).to.equal(BigInt(650));
+
+    const newcomerKey = await EncryptionKey.create(program);
+    await awaitComputation(program, await claim(newcomer, newcomerKey));
+
+    ledger = await program.account.userLedger.fetch(newcomerLedger);
+    expect(Buffer.from(ledger.encryptionPubkey)).to.deep.equal(
+      Buffer.from(newcomerKey.publicKey),
+    );
+    expect(await ledgerBalance(program, newcomerKey, newcomerLedger)).to.equal(BigInt(100));
+    await expectError(
+      async () => claim(newcomer, await EncryptionKey.create(program)),
+      "EncryptionKeyMismatch",
+    );
+  });
+});
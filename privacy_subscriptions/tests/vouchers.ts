@@ -0,0 +1,231 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { deserializeLE } from "@arcium-hq/client";
+import { createHash, randomBytes } from "crypto";
+import { expect } from "chai";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPlan,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  ledgerBalance,
+  merchantBalance,
+  newComputationOffset,
+  pda,
+  registerMerchant,
+  subscriptionState,
+  Subly,
+  TestMerchant,
+  USER_SUBSCRIPTION_SEED,
+} from "./helpers";
+
+const VOUCHER_POOL_SEED = Buffer.from("voucher_pool");
+const DISPUTE_SEED = Buffer.from("dispute");
+const STATUS_ACTIVE = 0;
+const STATUS_CANCELLED = 1;
+
+/// The first 128 bits of the voucher secret's SHA-256, little-endian, as the circuits compare it
+function secretHash(secret: Buffer): bigint {
+  const hash = createHash("sha256").update(secret).digest();
+  return deserializeLE(hash.subarray(0, 16));
+}
+
+function u32Bytes(value: number): Buffer {
+  const bytes = Buffer.alloc(4);
+  bytes.writeUInt32LE(value);
+  return bytes;
+}
+
+describe("Gift vouchers", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+
+  let mint: PublicKey;
+  let merchant: TestMerchant;
+  let plan: PublicKey;
+  let buyer: Keypair;
+  let buyerKey: EncryptionKey;
+  let buyerLedger: PublicKey;
+  let recipient: Keypair;
+  let recipientKey: EncryptionKey;
+  const secret = randomBytes(32);
+  let voucherPool: PublicKey;
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "buy_voucher_v2",
+      "redeem_voucher_v2",
+      "open_dispute_v2",
+    ]);
+    let poolTokenAccount: PublicKey;
+    ({ mint, poolTokenAccount } = await createPool(program, payer));
+
+    merchant = await registerMerchant(program, mint);
+    plan = await createPlan(program, merchant, mint, 1, 100, 30);
+    voucherPool = pda(program, [VOUCHER_POOL_SEED, plan.toBuffer(), u32Bytes(3), u32Bytes(30)]);
+
+    buyer = await fundedWallet(provider);
+    buyerKey = await EncryptionKey.create(program);
+    const buyerTokens = await fundTokens(provider, payer, mint, buyer.publicKey, 1_000);
+    buyerLedger = await deposit(program, buyer, buyerKey, mint, poolTokenAccount, buyerTokens, 1_000);
+
+    // The recipient's ledger holds the key the subscription is encrypted to
+    recipient = await fundedWallet(provider);
+    recipientKey = await EncryptionKey.create(program);
+    const recipientTokens = await fundTokens(provider, payer, mint, recipient.publicKey, 10);
+    await deposit(program, recipient, recipientKey, mint, poolTokenAccount, recipientTokens, 10);
+  });
+
+  const buyVoucher = async (cycles: number, voucherSecret: Buffer = secret) => {
+    const computationOffset = newComputationOffset();
+    const { ciphertext, nonce } = buyerKey.encrypt(secretHash(voucherSecret));
+    await program.methods
+      .buyVoucher(computationOffset, cycles, ciphertext, nonce)
+      .accountsPartial({
+        buyer: buyer.publicKey,
+        mint,
+        subscriptionPlan: plan,
+        merchantLedger: merchant.merchantLedger,
+        voucherPool: pda(program, [
+          VOUCHER_POOL_SEED,
+          plan.toBuffer(),
+          u32Bytes(cycles),
+          u32Bytes(30),
+        ]),
+        ...computationAccounts(program, "buy_voucher_v2", computationOffset),
+      })
+      .signers([buyer])
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const redeem = async (subscriptionIndex: number, voucherSecret: Buffer) => {
+    const computationOffset = newComputationOffset();
+    const index = new anchor.BN(subscriptionIndex);
+    const userSubscription = pda(program, [
+      USER_SUBSCRIPTION_SEED,
+      recipient.publicKey.toBuffer(),
+      index.toArrayLike(Buffer, "le", 8),
+    ]);
+    const { ciphertext, nonce } = recipientKey.encrypt(secretHash(voucherSecret));
+    await program.methods
+      .redeemVoucher(computationOffset, index, ciphertext, nonce)
+      .accountsPartial({
+        recipient: recipient.publicKey,
+        mint,
+        subscriptionPlan: plan,
+        voucherPool,
+        userSubscription,
+        merchantLedger: merchant.merchantLedger,
+        ...computationAccounts(program, "redeem_voucher_v2", computationOffset),
+      })
+      .signers([recipient])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+    return userSubscription;
+  };
+
+  it("rejects vouchers for no cycles", async () => {
+    await expectError(() => buyVoucher(0), "InvalidVoucherCycles");
+  });
+
+  it("charges the buyer for every prepaid cycle up front", async () => {
+    await awaitComputation(program, await buyVoucher(3));
+
+    const account = await program.account.voucherPool.fetch(voucherPool);
+    expect(account.outstanding).to.equal(1);
+    expect(account.cycles).to.equal(3);
+    expect(account.billingCycleDays).to.equal(30);
+    expect(account.lock.equals(PublicKey.default)).to.equal(true);
+    expect(await ledgerBalance(program, buyerKey, buyerLedger)).to.equal(BigInt(700));
+    expect(await merchantBalance(program, merchant.key, merchant.merchantLedger)).to.equal(
+      BigInt(300),
+    );
+  });
+
+  it("keeps the voucher when the secret does not match", async () => {
+    const userSubscription = await redeem(0, randomBytes(32));
+
+    expect((await subscriptionState(program, recipientKey, userSubscription)).status).to.equal(
+      STATUS_CANCELLED,
+    );
+    expect((await program.account.voucherPool.fetch(voucherPool)).outstanding).to.equal(1);
+  });
+
+  it("starts a prepaid subscription for the holder of the secret, once", async () => {
+    const userSubscription = await redeem(1, secret);
+
+    const state = await subscriptionState(program, recipientKey, userSubscription);
+    expect(state.status).to.equal(STATUS_ACTIVE);
+    expect(state.quantity).to.equal(1);
+    expect(state.nextPaymentDate - state.startDate).to.equal(BigInt(3 * 30 * 86_400));
+    expect((await program.account.voucherPool.fetch(voucherPool)).outstanding).to.equal(0);
+
+    await expectError(() => redeem(2, secret), "NoOutstandingVouchers");
+  });
+
+  it("refuses disputes over the prepaid periods", async () => {
+    const userSubscription = pda(program, [
+      USER_SUBSCRIPTION_SEED,
+      recipient.publicKey.toBuffer(),
+      new anchor.BN(1).toArrayLike(Buffer, "le", 8),
+    ]);
+    expect((await program.account.userSubscription.fetch(userSubscription)).prepaidCycles).to.equal(
+      3,
+    );
+
+    const computationOffset = newComputationOffset();
+    const billingPeriod = new anchor.BN(2);
+    const { ciphertext, nonce } = recipientKey.encrypt(BigInt(100));
+    await expectError(
+      () =>
+        program.methods
+          .openDispute(computationOffset, billingPeriod, ciphertext, nonce)
+          .accountsPartial({
+            user: recipient.publicKey,
+            mint,
+            subscriptionPlan: plan,
+            merchantLedger: merchant.merchantLedger,
+            userSubscription,
+            dispute: pda(program, [
+              DISPUTE_SEED,
+              userSubscription.toBuffer(),
+              billingPeriod.toArrayLike(Buffer, "le", 8),
+            ]),
+            ...computationAccounts(program, "open_dispute_v2", computationOffset),
+          })
+          .signers([recipient])
+          .rpc({ commitment: "confirmed" }),
+      "VoucherPrepaidPeriod",
+    );
+  });
+
+  it("pools vouchers bought on the same terms", async () => {
+    const otherSecret = randomBytes(32);
+    await awaitComputation(program, await buyVoucher(3, otherSecret));
+    expect((await program.account.voucherPool.fetch(voucherPool)).outstanding).to.equal(1);
+
+    // The redeemed secret stays spent while the pool holds other vouchers
+    const spent = await redeem(2, secret);
+    expect((await subscriptionState(program, recipientKey, spent)).status).to.equal(
+      STATUS_CANCELLED,
+    );
+
+    const userSubscription = await redeem(3, otherSecret);
+    expect((await subscriptionState(program, recipientKey, userSubscription)).status).to.equal(
+      STATUS_ACTIVE,
+    );
+    expect((await program.account.voucherPool.fetch(voucherPool)).outstanding).to.equal(0);
+  });
+});
@@ -0,0 +1,215 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import { MockLending } from "../target/types/mock_lending";
+import {
+  awaitComputation,
+  computationAccounts,
+  createPool,
+  deposit,
+  EncryptionKey,
+  ensureCompDefs,
+  ensureProtocol,
+  expectError,
+  fundedWallet,
+  fundTokens,
+  newComputationOffset,
+  Subly,
+} from "./helpers";
+
+const RESERVE_SEED = Buffer.from("reserve");
+const POSITION_SEED = Buffer.from("position");
+
+describe("Yield settlement", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const program = anchor.workspace.PrivacySubscriptions as Subly;
+  const lending = anchor.workspace.MockLending as Program<MockLending>;
+
+  let mint: PublicKey;
+  let protocolPool: PublicKey;
+  let poolTokenAccount: PublicKey;
+  let user: Keypair;
+  let userKey: EncryptionKey;
+  let userTokens: PublicKey;
+  let userLedger: PublicKey;
+  let saverKey: EncryptionKey;
+  let saverLedger: PublicKey;
+  let strategyAccounts: {
+    protocolPool: PublicKey;
+    poolTokenAccount: PublicKey;
+    lendingProgram: PublicKey;
+    lendingReserve: PublicKey;
+    lendingVault: PublicKey;
+    lendingPosition: PublicKey;
+  };
+
+  before(async () => {
+    await ensureProtocol(program, payer);
+    await ensureCompDefs(program, payer, [
+      "deposit_v2",
+      "withdraw_v2",
+      "distribute_yield_v2",
+      "settle_yield_v2",
+    ]);
+    ({ mint, protocolPool, poolTokenAccount } = await createPool(program, payer));
+
+    user = await fundedWallet(provider);
+    userKey = await EncryptionKey.create(program);
+    userTokens = await fundTokens(provider, payer, mint, user.publicKey, 1_000);
+    userLedger = await deposit(program, user, userKey, mint, poolTokenAccount, userTokens, 1_000);
+    // A second depositor who only settles on request
+    const saver = await fundedWallet(provider);
+    saverKey = await EncryptionKey.create(program);
+    const saverTokens = await fundTokens(provider, payer, mint, saver.publicKey, 1_000);
+    saverLedger = await deposit(
+      program,
+      saver,
+      saverKey,
+      mint,
+      poolTokenAccount,
+      saverTokens,
+      1_000,
+    );
+
+    // Park half the pool in the mock lending program and let it earn 100
+    const [reserve] = PublicKey.findProgramAddressSync(
+      [RESERVE_SEED, mint.toBuffer()],
+      lending.programId,
+    );
+    const vault = Keypair.generate();
+    await lending.methods
+      .initializeReserve()
+      .accounts({ payer: payer.publicKey, mint, vault: vault.publicKey })
+      .signers([vault])
+      .rpc({ commitment: "confirmed" });
+    const [position] = PublicKey.findProgramAddressSync(
+      [POSITION_SEED, reserve.toBuffer(), protocolPool.toBuffer()],
+      lending.programId,
+    );
+    await program.methods
+      .setLendingStrategy()
+      .accountsPartial({
+        authority: payer.publicKey,
+        protocolPool,
+        lendingProgram: lending.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+    strategyAccounts = {
+      protocolPool,
+      poolTokenAccount,
+      lendingProgram: lending.programId,
+      lendingReserve: reserve,
+      lendingVault: vault.publicKey,
+      lendingPosition: position,
+    };
+    await program.methods
+      .allocateToStrategy(new anchor.BN(500))
+      .accountsPartial({ authority: payer.publicKey, ...strategyAccounts })
+      .rpc({ commitment: "confirmed" });
+    const funder = await fundTokens(provider, payer, mint, payer.publicKey, 100);
+    await lending.methods
+      .accrueInterest(new anchor.BN(100))
+      .accountsPartial({
+        funder: payer.publicKey,
+        reserve,
+        vault: vault.publicKey,
+        position,
+        funderTokenAccount: funder,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    await awaitComputation(program, await distributeYield());
+  });
+
+  const distributeYield = async () => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .distributeYield(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        ...strategyAccounts,
+        ...computationAccounts(program, "distribute_yield_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const settleYield = async (ledger: PublicKey) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .settleYield(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        protocolPool,
+        userLedger: ledger,
+        ...computationAccounts(program, "settle_yield_v2", computationOffset),
+      })
+      .rpc({ commitment: "confirmed" });
+    return computationOffset;
+  };
+
+  const withdraw = async (amount: number) => {
+    const computationOffset = newComputationOffset();
+    await program.methods
+      .withdraw(computationOffset, new anchor.BN(amount), Array.from(userKey.publicKey))
+      .accountsPartial({
+        user: user.publicKey,
+        mint,
+        poolTokenAccount,
+        userTokenAccount: userTokens,
+        pendingOutflow: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        ...computationAccounts(program, "withdraw_v2", computationOffset),
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputation(program, computationOffset);
+  };
+
+  /// Principal plus accrued yield of a ledger
+  const totalBalance = async (key: EncryptionKey, ledgerAccount: PublicKey) => {
+    const ledger = await program.account.userLedger.fetch(ledgerAccount);
+    const [principal, , accrued] = key.decrypt(
+      [
+        ledger.encryptedBalance,
+        ledger.encryptedSubscriptionCount,
+        ledger.encryptedAccruedYield,
+        ledger.encryptedPrincipalLocked,
+      ],
+      ledger.nonce,
+    );
+    return principal + accrued;
+  };
+
+  it("settles distributed yield as part of a withdrawal", async () => {
+    await withdraw(50);
+
+    // The two depositors hold equal balances, so each earns 50 of the 100 of interest
+    expect(await totalBalance(userKey, userLedger)).to.equal(BigInt(1_000));
+    const ledger = await program.account.userLedger.fetch(userLedger);
+    const pool = await program.account.protocolPool.fetch(protocolPool);
+    expect(ledger.yieldEpoch.toNumber()).to.equal(pool.yieldEpoch.toNumber());
+  });
+
+  it("settles yield on request for ledgers that have not moved", async () => {
+    await awaitComputation(program, await settleYield(saverLedger));
+
+    expect(await totalBalance(saverKey, saverLedger)).to.equal(BigInt(1_050));
+    const ledger = await program.account.userLedger.fetch(saverLedger);
+    const pool = await program.account.protocolPool.fetch(protocolPool);
+    expect(ledger.yieldEpoch.toNumber()).to.equal(pool.yieldEpoch.toNumber());
+  });
+
+  it("rejects a second settlement in the same epoch", async () => {
+    await expectError(() => settleYield(userLedger), "YieldAlreadySettled");
+    await expectError(() => settleYield(saverLedger), "YieldAlreadySettled");
+  });
+
+  it("rejects distributions before the interval has passed", async () => {
+    await expectError(distributeYield, "YieldDistributionTooSoon");
+  });
+});